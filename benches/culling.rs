@@ -0,0 +1,65 @@
+// Culling 10 million particles for a few views, going through every number against only the ones
+// in the band of radii that can be in frame. Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use polar_oxides::particles::{Particle, Particles};
+use polar_oxides::view::{Configs, Settings};
+
+const MAX_NUMBER: u64 = 10_000_000;
+const RUNS: u32 = 5;
+const WIDTH: f32 = 1280.0;
+const HEIGHT: f32 = 800.0;
+const POINT_SIZE: f32 = 2.0;
+
+fn linear(particles: &Particles, configs: Configs, settings: Settings) -> usize {
+    particles.numbers_within(0.0, std::f32::INFINITY)
+        .map(|number| Particle::position_of(number, configs.angle_factor))
+        .filter(|&position| configs.is_in_frame(settings, position, POINT_SIZE, WIDTH, HEIGHT))
+        .count()
+}
+
+fn banded(particles: &Particles, configs: Configs, settings: Settings) -> usize {
+    let (inner, outer) = configs.radii_in_frame(settings, POINT_SIZE, WIDTH, HEIGHT);
+    particles.numbers_within(inner, outer)
+        .map(|number| Particle::position_of(number, configs.angle_factor))
+        .filter(|&position| configs.is_in_frame(settings, position, POINT_SIZE, WIDTH, HEIGHT))
+        .count()
+}
+
+// The best of a few runs, and what it counted
+fn time<F: Fn() -> usize>(cull: F) -> (Duration, usize) {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let count = cull();
+            (start.elapsed(), count)
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let particles = Particles::find(MAX_NUMBER);
+    let settings = Settings::default();
+    let views = [
+        ("whole spiral", Configs { zoom_level: settings.zoom_level_for(0.0001), ..Configs::default() }),
+        ("around the origin", Configs::default()),
+        ("halfway out", Configs { offset: (-5_000_000.0, 0.0), ..Configs::default() }),
+        ("halfway out, zoomed out", Configs { offset: (-5_000_000.0, 0.0), zoom_level: 300.0, ..Configs::default() }),
+    ];
+
+    println!("{:<26} {:>10} {:>12} {:>12}", "view", "in frame", "linear", "banded");
+    for &(name, configs) in &views {
+        let (linear_time, linear_count) = time(|| linear(&particles, configs, settings));
+        let (banded_time, banded_count) = time(|| banded(&particles, configs, settings));
+        assert_eq!(linear_count, banded_count, "both ways keep the same particles");
+        println!("{:<26} {:>10} {:>10.1}ms {:>10.1}ms", name, banded_count, millis(linear_time), millis(banded_time));
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
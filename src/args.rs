@@ -0,0 +1,865 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::warn;
+
+use polar_oxides::cache::CacheMode;
+use polar_oxides::camera_path::CameraPath;
+use polar_oxides::classes::Classification;
+use polar_oxides::dataset::DatasetKind;
+use polar_oxides::digits;
+use polar_oxides::overlays::{self, NumberList, Progression, Quadratic};
+use polar_oxides::palette::{self, CustomPalette, Palette};
+use polar_oxides::predicate::Predicate;
+use polar_oxides::particles::Particle;
+use polar_oxides::primality::Primality;
+use polar_oxides::view::{Coloring, Configs, DisplayMode, LastSession, SavedView, Settings};
+
+use crate::config_file::ConfigFile;
+use crate::data_export::Format;
+use crate::screenshot;
+use crate::svg;
+
+pub const USAGE: &str = "\
+Usage: polar-oxides [OPTIONS] [MAX]
+       polar-oxides export [OPTIONS] [MAX] [--max-composites <number>] -o <file>
+       polar-oxides export-data [OPTIONS] [MAX] [--json [--pretty]] [-o <file>]
+       polar-oxides animate [OPTIONS] [MAX] --to-zoom <level> --frames <count> -o <directory>
+       polar-oxides animate [OPTIONS] [MAX] --to-zoom <level> --gif <file> [--seconds <s>] [--fps <fps>]
+       polar-oxides animate [OPTIONS] [MAX] --play <file> (-o <directory> | --gif <file>) [--fps <fps>]
+
+Draws the numbers below MAX (50,000 by default) in polar coordinates, the primes standing out.
+Defaults for some of the options can be kept in polar-oxides.toml, in the working directory or the
+config directory. The options given here go on top.
+
+`export` writes the picture the window would open on to a PNG file instead, at --size, without
+opening a window, or to an SVG file when -o ends in .svg. The last run's view isn't picked up.
+`export-data` writes every number shown to a CSV file the same way, named after the current time
+unless given -o, or with --json to a JSON file that also says what it was made with. `animate`
+zooms from --from-zoom (--zoom by default) to --to-zoom, writing each frame to a numbered PNG in
+the -o directory, and leaves the frames that are already there alone. With --gif it zooms there
+and back instead, as a looping GIF. With --play it follows the camera path in the file instead.
+
+Options:
+  --max <number>            The same as MAX
+  --min <number>            Leave out the numbers below this one, only finding the primes from there
+  --rebase                  Draw the numbers from --min on as if that one were at the origin
+  --mode <points>           numbers (the default), or gaussian for the Gaussian integers a + bi with a
+                            norm a² + b² below MAX, at (a, b), the Gaussian primes standing out
+  --zoom <level>            The zoom level to start at, negative zooms in
+  --angle-factor <factor>   How much each number turns, in radians (1 by default)
+  --palette <name or file>  One of the palettes C cycles through (cb1 and cb2 are colorblind-safe), or
+                            a file with hex colors like prime = \"#5ED1B0\" for background, prime,
+                            composite and highlight
+  --highlight <kinds>       Color some primes on their own: twins, cousins or sexy, several joined with
+                            commas like twins,cousins, sophie-germain with the safe primes they make,
+                            palindromic, or none (the default)
+  --base <base>             The base --highlight palindromic reads the digits in, 2 to 36 (10 by default)
+  --highlighted-only        Only draw the primes --highlight picks out, the composites still going by
+                            --no-nonprimes
+  --mod <modulus>           Color every number by its residue mod this, from 2 to 12
+  --gap-colors              Color every prime by the gap to the next prime, the wide ones standing out
+  --color-by <coloring>     kinds (the default) for the palette's colors, omega for how many prime
+                            factors each number has, counted with multiplicity, smallest-factor
+                            for the smallest prime factor of each composite, mobius for μ(n),
+                            semiprimes to pick out the products of two primes, digital-root for
+                            the digits of each number added up until one is left, or
+                            prime-digital-root for the primes' only
+  --digit-base <base>       The base --color-by digital-root adds the digits up in, 2 to 13 (10 by
+                            default)
+  --powers <k>              Mark the perfect k-th powers, from 2 for the squares to 63, on top of the
+                            rest (Shift+Z cycles through the squares, cubes and 4th powers)
+  --fibonacci               Mark the Fibonacci numbers, labeled once zoomed in, the prime ones standing
+                            out (Ctrl+Z shows/hides them)
+  --lucas                   Mark the Lucas numbers 2, 1, 3, 4, 7, 11... the same way (Ctrl+Shift+Z)
+  --polygonal <sides>       Mark the polygonal numbers with 3 (triangular) to 8 sides, several joined
+                            with commas like 3,5,6, each in a color of its own
+  --poly <a,b,c>            Mark the values of an² + bn + c for n = 0, 1, 2..., the prime ones in a
+                            color of their own, or euler for n² + n + 41
+  --highlight-ap <a,m>      Mark the numbers ≡ a (mod m), like 3,10 for the ones ending in 3, the primes
+                            among them in a brighter shade. Given again, it marks another progression
+                            in the next color, up to 12
+  --expr <predicate>        Mark the numbers a predicate in n picks, like \"isprime(n) && n % 6 == 1\",
+                            out of isprime(x), gcd(a, b), mod(a, m), digitsum(x), whole numbers,
+                            arithmetic, comparisons, !, && and ||
+  --highlight-file <file>   Mark the numbers in a file, one a line or n and a(n) like the OEIS's b-files,
+                            - for the standard input
+  --no-nonprimes            Only draw the primes
+  --primes-only             The same as --no-nonprimes
+  --size <width>x<height>   The size of the window (1280x800 by default)
+  --fullscreen              Start in fullscreen
+  --view <string>           Open a view copied with Ctrl+Shift+C, the other options go on top of it
+  --zoom-base <base>        How much each zoom level scales the view by (1.02 by default)
+  --zoom-step <step>        How many levels a press of W/S moves (1 by default)
+  --primality <method>      sieve (the default) or miller-rabin
+  --no-cache                Don't read or write the cached primes
+  --refresh-cache           Find the primes again and overwrite the cached ones
+  --record <file>           Write the input to a file
+  --replay <file>           Play the input back from a file
+  --fresh                   Don't pick up the view and the window from where the last run left them
+  --tour                    Start slowly zooming and drifting around until something is pressed
+  --play <file>             Fly along the keyframes in a TOML or JSON file, see the README. Space pauses.
+  -o, --output <file>       Where `export` writes the PNG or SVG, or `export-data` the CSV
+  --max-composites <number> Leave out composites evenly in an exported SVG past this many (200000 by default)
+  --from-zoom <level>       The zoom level `animate` starts at
+  --to-zoom <level>         The zoom level `animate` ends at
+  --frames <count>          How many frames `animate` writes
+  --gif <file>              Have `animate` write a GIF going there and back instead of frames
+  --seconds <seconds>       How long the GIF takes to go there and back (6 by default)
+  --fps <fps>               How many frames a second the GIF, or a --play animation, has (25 by default,
+                            up to 50 for a GIF)
+  --json                    Have `export-data` write JSON
+  --pretty                  Indent the JSON
+  -v, -vv                   Print what's being done to stderr, with -vv also how long each step took
+  --dump-config             Print the settings polar-oxides.toml can hold, as they end up, and quit
+  --help                    Show this and quit
+  --version                 Show the version and quit
+
+Environment:
+  These take the place of the options of the same name, for when passing flags is a hassle. They
+  go on top of polar-oxides.toml and the options go on top of them.
+
+  POLAR_OXIDES_MAX, POLAR_OXIDES_MIN, POLAR_OXIDES_ZOOM, POLAR_OXIDES_ANGLE_FACTOR,
+  POLAR_OXIDES_PALETTE, POLAR_OXIDES_SIZE, POLAR_OXIDES_VIEW, POLAR_OXIDES_ZOOM_BASE,
+  POLAR_OXIDES_ZOOM_STEP, POLAR_OXIDES_PRIMALITY
+";
+
+// What the subcommands write, and where
+#[derive(Clone, PartialEq, Debug)]
+pub enum Export {
+    Image(PathBuf),
+    Data(PathBuf, Format),
+    // A directory to write the frames to
+    Animation(PathBuf, Animation),
+    // At so many frames a second
+    Gif(PathBuf, Animation, f32),
+}
+
+// How the camera moves in an animation
+#[derive(Clone, PartialEq, Debug)]
+pub enum Camera {
+    // From one zoom level to another, easing in and out, and back again in a GIF. The starting zoom
+    // level when `from_zoom` is `None`.
+    Zoom { from_zoom: Option<f32>, to_zoom: f32 },
+    // Along a --play file, once
+    Path(CameraPath),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Animation {
+    pub camera: Camera,
+    pub frames: u32,
+}
+
+// What the particles are found for, handed to the task that loads them
+#[derive(Clone, Copy, Debug)]
+pub struct ParticlesConfig {
+    // A multiple of 64 like the sieve wants, up to 63 below `--min`
+    pub start: u64,
+    pub max_number: u64,
+    pub radius_base: u64,
+    pub primality: Primality,
+    pub cache_mode: CacheMode,
+    pub dataset: DatasetKind,
+}
+
+// Everything the command line says
+pub struct Args {
+    pub particles: ParticlesConfig,
+    pub configs: Configs,
+    pub settings: Settings,
+    pub tour: bool,
+    pub window_size: (u32, u32),
+    pub fullscreen: bool,
+    // The files the input is written to or played back from
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    // From the config file, checked but left to be bound on top of keybindings.toml
+    pub keybindings: toml::value::Table,
+    // Whether the view and the window are saved for the next run on the way out. Recordings and
+    // replays leave it alone, they need the same start every time, and so do exports.
+    pub keeps_session: bool,
+    pub help: bool,
+    pub version: bool,
+    pub dump_config: bool,
+    // Set by the subcommands, which write a file instead of opening the window
+    pub export: Option<Export>,
+    // How many composites an SVG gets at most
+    pub max_composites: usize,
+    // 1 for -v, 2 for -vv
+    pub verbosity: u8,
+    // The camera path to fly along in the window
+    pub play: Option<CameraPath>,
+    // Kept to pick up edits to it while running, along with the palette file
+    pub config_file: Option<ConfigFile>,
+    pub palette_path: Option<PathBuf>,
+    // The k of the perfect k-th powers to mark to start with
+    pub powers: Option<u32>,
+    pub fibonacci: bool,
+    pub lucas: bool,
+    // The sides of the polygonal numbers marked, smallest first
+    pub polygonal: Vec<u32>,
+    // The quadratic whose values are marked
+    pub poly: Option<Quadratic>,
+    // The arithmetic progressions marked, in the order they were given
+    pub progressions: Vec<Progression>,
+    // The predicate picking out the numbers to mark
+    pub expr: Option<Predicate>,
+    // The numbers read from the file given with --highlight-file
+    pub highlight_file: Option<NumberList>,
+}
+
+impl Default for Args {
+    fn default() -> Args {
+        Args {
+            particles: ParticlesConfig {
+                start: 0,
+                max_number: Args::DEFAULT_MAX_NUMBER,
+                // Sieving is much faster, testing every number is there to check it against
+                primality: Primality::Sieve,
+                cache_mode: CacheMode::Use,
+                radius_base: 0,
+                dataset: DatasetKind::Numbers,
+            },
+            configs: Configs::default(),
+            settings: Settings::default(),
+            tour: false,
+            window_size: Args::DEFAULT_WINDOW_SIZE,
+            fullscreen: false,
+            record: None,
+            replay: None,
+            keybindings: toml::value::Table::new(),
+            keeps_session: true,
+            help: false,
+            version: false,
+            dump_config: false,
+            export: None,
+            max_composites: svg::DEFAULT_MAX_COMPOSITES,
+            verbosity: 0,
+            play: None,
+            config_file: None,
+            palette_path: None,
+            powers: None,
+            fibonacci: false,
+            lucas: false,
+            polygonal: Vec::new(),
+            poly: None,
+            progressions: Vec::new(),
+            expr: None,
+            highlight_file: None,
+        }
+    }
+}
+
+impl Args {
+    const DEFAULT_MAX_NUMBER: u64 = 50_000;
+    const DEFAULT_WINDOW_SIZE: (u32, u32) = (1280, 800);
+    const FLAGS_WITH_VALUES: [&'static str; 33] = [
+        "--max", "--min", "--zoom", "--angle-factor", "--size", "--view", "--zoom-base", "--zoom-step",
+        "--primality", "--record", "--replay", "--output", "--max-composites", "--from-zoom", "--to-zoom", "--frames",
+        "--gif", "--seconds", "--fps", "--palette", "--play", "--highlight", "--base",
+        "--mod", "--mode", "--color-by", "--powers", "--polygonal", "--poly", "--highlight-ap", "--expr",
+        "--highlight-file", "--digit-base",
+    ];
+    // The ones of them that can be given more than once, their values joined with spaces
+    const REPEATABLE_FLAGS: [&'static str; 1] = ["--highlight-ap"];
+    const DEFAULT_GIF_SECONDS: f32 = 6.0;
+    const DEFAULT_FPS: f32 = 25.0;
+    // GIFs count the time between frames in hundredths of a second, and browsers slow anything
+    // under two of them down
+    const MAX_GIF_FPS: f32 = 50.0;
+    // The flags that can be set with an environment variable instead, see `environment_variable`.
+    // Those that only go with a subcommand would get in the way of every other run.
+    const FROM_ENVIRONMENT: [&'static str; 10] = [
+        "--max", "--min", "--zoom", "--angle-factor", "--palette", "--size", "--view", "--zoom-base", "--zoom-step",
+        "--primality",
+    ];
+    const SUBCOMMANDS: [&'static str; 3] = ["export", "export-data", "animate"];
+    const SWITCHES: [&'static str; 19] = [
+        "--rebase", "--no-nonprimes", "--primes-only", "--fullscreen", "--no-cache", "--refresh-cache", "--tour",
+        "--fresh", "--help", "--version", "--dump-config", "--json", "--pretty", "-v", "-vv", "--highlighted-only",
+        "--gap-colors", "--fibonacci", "--lucas",
+    ];
+
+    // Anything that isn't a known flag, a value that doesn't parse or one that's out of range is an
+    // error, rather than quietly falling back to the defaults. The same goes for the config file and
+    // the environment variables, which the flags go on top of.
+    pub fn from_env() -> Result<Args, String> {
+        let mut args = env::args().skip(1).peekable();
+        let subcommand = args.peek().cloned().filter(|arg| Self::SUBCOMMANDS.contains(&arg.as_str()));
+        if subcommand.is_some() {
+            args.next();
+        }
+        let mut values: HashMap<String, String> = HashMap::new();
+        let mut switches = Vec::new();
+        let mut positional = None;
+        while let Some(arg) = args.next() {
+            let arg = if arg == "-o" { String::from("--output") } else { arg };
+            if Self::FLAGS_WITH_VALUES.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| format!("{} expects a value", arg))?;
+                if !Self::REPEATABLE_FLAGS.contains(&arg.as_str()) && values.contains_key(&arg) {
+                    return Err(format!("{} is given more than once", arg));
+                }
+                let joined = values.entry(arg).or_insert_with(String::new);
+                if !joined.is_empty() {
+                    joined.push(' ');
+                }
+                joined.push_str(&value);
+            } else if Self::SWITCHES.contains(&arg.as_str()) {
+                switches.push(arg);
+            } else if arg.starts_with('-') {
+                return Err(format!("unknown option `{}`", arg));
+            } else if positional.is_none() {
+                positional = Some(arg);
+            } else {
+                return Err(format!("unexpected argument `{}`", arg));
+            }
+        }
+
+        if switches.iter().any(|arg| arg == "--help" || arg == "--version") {
+            let help = switches.iter().any(|arg| arg == "--help");
+            return Ok(Args { help, version: !help, ..Args::default() });
+        }
+
+        // Checked as if they were flags, but their errors should say where they came from
+        let from_environment = Self::read_environment(&mut values, positional.is_some())?;
+        Self::from_values(subcommand, &values, &switches, positional).map_err(|error| {
+            let flag = from_environment.iter().find(|&&flag| {
+                error.starts_with(flag) && error[flag.len()..].starts_with(|c| c == ' ' || c == ':')
+            });
+            match flag {
+                Some(flag) => { error.replacen(flag, &environment_variable(flag), 1) }
+                None => { error }
+            }
+        })
+    }
+
+    // The flags that weren't given but have their environment variable set, put in `values`
+    fn read_environment(values: &mut HashMap<String, String>, has_positional: bool)
+        -> Result<Vec<&'static str>, String>
+    {
+        let mut taken = Vec::new();
+        for &flag in Self::FROM_ENVIRONMENT.iter() {
+            // MAX without --max is the same as --max
+            if values.contains_key(flag) || (flag == "--max" && has_positional) {
+                continue;
+            }
+
+            let variable = environment_variable(flag);
+            match env::var(&variable) {
+                Ok(value) => {
+                    values.insert(String::from(flag), value);
+                    taken.push(flag);
+                }
+                Err(env::VarError::NotPresent) => {}
+                Err(env::VarError::NotUnicode(_)) => { return Err(format!("{} isn't valid unicode", variable)); }
+            }
+        }
+        Ok(taken)
+    }
+
+    fn from_values(
+        subcommand: Option<String>,
+        values: &HashMap<String, String>,
+        switches: &[String],
+        positional: Option<String>,
+    ) -> Result<Args, String> {
+        let has = |switch: &str| switches.iter().any(|arg| arg == switch);
+        let mut parsed = Args::default();
+        if let Some(config_file) = ConfigFile::load()? {
+            config_file.apply(&mut parsed)?;
+            parsed.config_file = Some(config_file);
+        }
+        parsed.dump_config = has("--dump-config");
+        parsed.verbosity = if has("-vv") { 2 } else if has("-v") { 1 } else { 0 };
+
+        let subcommand = subcommand.as_ref().map(String::as_str);
+        parsed.export = Self::export(subcommand, values, &has)?;
+        if let Some(value) = values.get("--max-composites") {
+            if subcommand != Some("export") {
+                return Err(String::from("--max-composites only goes with export"));
+            }
+            parsed.max_composites = parse::<usize>("--max-composites", value, "a whole number")?;
+        }
+        parsed.record = values.get("--record").map(PathBuf::from);
+        parsed.replay = values.get("--replay").map(PathBuf::from);
+
+        if let Some(value) = values.get("--mode") {
+            parsed.particles.dataset = parse_mode(value, subcommand, values)?;
+        }
+
+        // Where the last run left off goes on top of the config file, and the flags on top of that.
+        // It's a view of the numbers, there's nothing to pick up for the Gaussian integers.
+        let records = parsed.record.is_some() || parsed.replay.is_some();
+        let numbers = parsed.particles.dataset == DatasetKind::Numbers;
+        parsed.keeps_session = subcommand.is_none() && !records && numbers;
+        if parsed.keeps_session && !has("--fresh") {
+            match LastSession::load() {
+                Ok(Some(session)) => {
+                    parsed.configs = session.restore(parsed.configs);
+                    parsed.window_size = session.window_size.unwrap_or(parsed.window_size);
+                    parsed.fullscreen = session.fullscreen;
+                }
+                Ok(None) => {}
+                Err(error) => { warn!("starting fresh: {}", error); }
+            }
+        }
+
+        // A view brings its own max number and configs, the rest of the flags go on top
+        if let Some(text) = values.get("--view") {
+            let view = SavedView::decode(text, Configs::default()).map_err(|error| format!("--view: {}", error))?;
+            parsed.particles.max_number = view.max_number;
+            parsed.configs = view.configs.sanitized();
+        }
+
+        let max_number = match (&positional, values.get("--max")) {
+            (Some(_), Some(_)) => { return Err(String::from("the max number is given both with --max and without")); }
+            (Some(value), None) => { Some(("the max number", value)) }
+            (None, Some(value)) => { Some(("--max", value)) }
+            (None, None) => { None }
+        };
+        if let Some((name, value)) = max_number {
+            parsed.particles.max_number = parse::<u64>(name, value, "a whole number")?;
+        }
+
+        if let Some(value) = values.get("--min") {
+            let min_number = parse::<u64>("--min", value, "a whole number")?;
+            if min_number >= parsed.particles.max_number {
+                return Err(format!(
+                    "--min has to be below the max number {}, got {}",
+                    parsed.particles.max_number, min_number,
+                ));
+            }
+            parsed.configs.min_number = min_number;
+            parsed.particles.start = min_number / 64 * 64;
+            if has("--rebase") {
+                parsed.particles.radius_base = min_number;
+            }
+        } else if has("--rebase") {
+            return Err(String::from("--rebase only goes with --min"));
+        }
+
+        if let Some(value) = values.get("--zoom") {
+            parsed.configs.zoom_level = Configs::clamp_zoom_level(parse_finite("--zoom", value)?);
+        }
+
+        if let Some(value) = values.get("--angle-factor") {
+            parsed.configs.angle_factor = parse_finite("--angle-factor", value)?;
+        }
+
+        // Unless told where to look, start where the slice does
+        if values.contains_key("--min") && !values.contains_key("--view") {
+            let min_number = parsed.configs.min_number;
+            let position = Particle::rebased_position_of(min_number, parsed.particles.radius_base, parsed.configs.angle_factor);
+            parsed.configs.offset = (-position.x, -position.y);
+        }
+
+        if let Some(value) = values.get("--palette") {
+            match Palette::find(value) {
+                Some(palette) => { parsed.configs.palette = palette; }
+                None => {
+                    let path = Path::new(value);
+                    if !path.exists() {
+                        let names: Vec<&str> = Palette::ALL.iter().map(|palette| palette.name).collect();
+                        let names = names.join(", ");
+                        return Err(format!("--palette has to be one of {} or a palette file, got `{}`", names, value));
+                    }
+                    let custom = CustomPalette::load(path).map_err(|error| format!("--palette: {}", error))?;
+                    parsed.configs.custom_palette = Some(custom);
+                    parsed.configs.palette = Palette::CUSTOM;
+                    parsed.palette_path = Some(path.to_path_buf());
+                }
+            }
+        }
+
+        Self::highlight(&mut parsed.configs, values, &has)?;
+
+        Self::overlays(&mut parsed, subcommand, values, &has)?;
+
+        if has("--no-nonprimes") || has("--primes-only") {
+            parsed.configs.display_mode = DisplayMode::PrimesOnly;
+        }
+
+        if let Some(value) = values.get("--size") {
+            parsed.window_size = parse_size(value)
+                .ok_or_else(|| format!("--size has to look like 1280x800, got `{}`", value))?;
+        }
+
+        // With animate it's written out instead
+        if let Some(value) = values.get("--play") {
+            match subcommand {
+                None => { parsed.play = Some(load_camera_path(value)?); }
+                Some("animate") => {}
+                Some(_) => { return Err(String::from("--play only goes with animate, or without a subcommand")); }
+            }
+        }
+
+        parsed.fullscreen |= has("--fullscreen");
+        parsed.tour = has("--tour");
+        if parsed.tour && parsed.play.is_some() {
+            return Err(String::from("--tour and --play can't go together"));
+        }
+        if let Some(value) = values.get("--zoom-base") {
+            let zoom_base = parse::<f32>("--zoom-base", value, "a number")?;
+            parsed.settings = parsed.settings.with_zoom_base(zoom_base)
+                .map_err(|error| format!("--zoom-base {}", error))?;
+        }
+
+        if let Some(value) = values.get("--zoom-step") {
+            let zoom_step = parse::<f32>("--zoom-step", value, "a number")?;
+            parsed.settings = parsed.settings.with_zoom_step(zoom_step)
+                .map_err(|error| format!("--zoom-step {}", error))?;
+        }
+
+        if let Some(value) = values.get("--primality") {
+            parsed.particles.primality = Primality::from_name(value)
+                .ok_or_else(|| format!("--primality has to be sieve or miller-rabin, got `{}`", value))?;
+        }
+        parsed.particles.cache_mode = match (has("--no-cache"), has("--refresh-cache")) {
+            (true, true) => { return Err(String::from("--no-cache and --refresh-cache can't go together")); }
+            (true, false) => { CacheMode::Off }
+            (false, true) => { CacheMode::Refresh }
+            (false, false) => { CacheMode::Use }
+        };
+        Ok(parsed)
+    }
+
+    // Which primes get a color of their own, or which residue or gap everything gets colored by
+    fn highlight(configs: &mut Configs, values: &HashMap<String, String>, has: &dyn Fn(&str) -> bool)
+        -> Result<(), String>
+    {
+        if let Some(classification) = parse_highlight(values)? {
+            configs.classification = classification;
+        }
+        if has("--highlighted-only") {
+            configs.highlighted_only = true;
+        }
+        if let Some(value) = values.get("--mod") {
+            configs.residues = true;
+            configs.modulus = parse_modulus(value)?;
+        }
+        if has("--gap-colors") {
+            if values.contains_key("--mod") {
+                return Err(String::from("--gap-colors and --mod can't go together"));
+            }
+            configs.gap_colors = true;
+            configs.residues = false;
+        }
+        if let Some(value) = values.get("--color-by") {
+            configs.coloring = parse_coloring(value)?;
+            // Either would cover it up
+            if configs.coloring != Coloring::Kinds {
+                if values.contains_key("--mod") || has("--gap-colors") {
+                    return Err(format!("--color-by {} can't go with --mod or --gap-colors", value));
+                }
+                configs.residues = false;
+                configs.gap_colors = false;
+            }
+        }
+        if let Some(value) = values.get("--digit-base") {
+            if !configs.coloring.needs_digits() {
+                return Err(String::from("--digit-base only goes with --color-by digital-root or prime-digital-root"));
+            }
+            configs.digit_base = parse_digit_base(value)?;
+        }
+        Ok(())
+    }
+
+    // The sets of numbers marked on top of the points
+    fn overlays(
+        args: &mut Args,
+        subcommand: Option<&str>,
+        values: &HashMap<String, String>,
+        has: &dyn Fn(&str) -> bool,
+    ) -> Result<(), String> {
+        // The overlays are drawn in the window, on top of the points the subcommands write
+        let overlay_flags = [
+            "--powers", "--fibonacci", "--lucas", "--polygonal", "--poly", "--highlight-ap", "--expr",
+            "--highlight-file",
+        ];
+        let overlay_flag = overlay_flags.iter().find(|&&flag| has(flag) || values.contains_key(flag));
+        if let (Some(flag), Some(_)) = (overlay_flag, subcommand) {
+            return Err(format!("{} only goes without a subcommand", flag));
+        }
+        if let Some(value) = values.get("--powers") {
+            let exponent = parse::<u32>("--powers", value, "a whole number")?;
+            let most = overlays::MAX_POWER_EXPONENT;
+            if exponent < 2 || exponent > most {
+                return Err(format!("--powers has to be between 2 and {}, got `{}`", most, value));
+            }
+            args.powers = Some(exponent);
+        }
+        args.fibonacci = has("--fibonacci");
+        args.lucas = has("--lucas");
+        if let Some(value) = values.get("--polygonal") {
+            args.polygonal = overlays::parse_sides(value).map_err(|error| format!("--polygonal: {}", error))?;
+        }
+        if let Some(value) = values.get("--poly") {
+            args.poly = Some(Quadratic::parse(value).map_err(|error| format!("--poly: {}", error))?);
+        }
+        if let Some(value) = values.get("--highlight-ap") {
+            args.progressions = overlays::parse_progressions(value)
+                .map_err(|error| format!("--highlight-ap: {}", error))?;
+            for warning in args.progressions.iter().filter_map(|progression| progression.warning()) {
+                warn!("--highlight-ap: {}", warning);
+            }
+        }
+        if let Some(value) = values.get("--expr") {
+            let predicate = Predicate::parse(value).map_err(|error| format!("--expr: {}", error.pointing_into(value)))?;
+            args.expr = Some(predicate);
+        }
+        if let Some(value) = values.get("--highlight-file") {
+            let list = read_number_list(value).map_err(|error| format!("--highlight-file: {}", error))?;
+            let outside = list.outside(args.particles.start, args.particles.max_number.saturating_add(1));
+            if outside > 0 {
+                warn!(
+                    "{}: {} of its {} numbers are outside {} to {}, and aren't marked",
+                    list.name, outside, list.len(), args.particles.start, args.particles.max_number,
+                );
+            }
+            args.highlight_file = Some(list);
+        }
+        Ok(())
+    }
+
+    // What the subcommand, if any, writes, along with the flags that only go with one of them
+    fn export(subcommand: Option<&str>, values: &HashMap<String, String>, has: &dyn Fn(&str) -> bool)
+        -> Result<Option<Export>, String>
+    {
+        let only_with = |flag: &str, wanted: &str| {
+            if subcommand != Some(wanted) && (has(flag) || values.contains_key(flag)) {
+                return Err(format!("{} only goes with {}", flag, wanted));
+            }
+            Ok(())
+        };
+        only_with("--json", "export-data")?;
+        for flag in &["--from-zoom", "--to-zoom", "--frames", "--gif", "--seconds", "--fps"] {
+            only_with(flag, "animate")?;
+        }
+        if has("--pretty") && !has("--json") {
+            return Err(String::from("--pretty only goes with --json"));
+        }
+
+        let output = values.get("--output").map(PathBuf::from);
+        if subcommand == Some("animate") {
+            return Self::animation(values, output).map(Some);
+        }
+        let subcommand = match subcommand {
+            Some(subcommand) => { subcommand }
+            None if output.is_some() => { return Err(String::from("--output only goes with a subcommand")); }
+            None => { return Ok(None); }
+        };
+        if subcommand == "export-data" {
+            let format = if has("--json") { Format::Json { pretty: has("--pretty") } } else { Format::Csv };
+            let path = output.unwrap_or_else(|| PathBuf::from(screenshot::file_name(format.extension())));
+            return Ok(Some(Export::Data(path, format)));
+        }
+
+        let path = output.ok_or_else(|| format!("{} needs a file to write to, given with -o", subcommand))?;
+        Ok(Some(Export::Image(path)))
+    }
+
+    // Frames in the -o directory, or a GIF
+    fn animation(values: &HashMap<String, String>, output: Option<PathBuf>) -> Result<Export, String> {
+        let required = |flag: &str| values.get(flag).ok_or_else(|| format!("animate needs {} or --play", flag));
+        let camera = match values.get("--play") {
+            Some(value) => {
+                for flag in &["--from-zoom", "--to-zoom", "--frames", "--seconds"] {
+                    if values.contains_key(*flag) {
+                        return Err(format!("{} doesn't go with --play, the file says where the camera goes", flag));
+                    }
+                }
+                Camera::Path(load_camera_path(value)?)
+            }
+            None => {
+                let from_zoom = match values.get("--from-zoom") {
+                    Some(value) => { Some(Configs::clamp_zoom_level(parse_finite("--from-zoom", value)?)) }
+                    None => { None }
+                };
+                let to_zoom = Configs::clamp_zoom_level(parse_finite("--to-zoom", required("--to-zoom")?)?);
+                Camera::Zoom { from_zoom, to_zoom }
+            }
+        };
+
+        let positive = |flag: &str, default: f32| match values.get(flag) {
+            Some(value) => {
+                let number = parse_finite(flag, value)?;
+                if number <= 0.0 {
+                    return Err(format!("{} has to be greater than 0, got {}", flag, number));
+                }
+                Ok(number)
+            }
+            None => { Ok(default) }
+        };
+        let gif = match (output, values.get("--gif")) {
+            (Some(_), Some(_)) => { return Err(String::from("animate writes either frames to -o or a --gif")); }
+            (Some(directory), None) => {
+                let frames = match &camera {
+                    // Both ends included
+                    Camera::Path(path) => {
+                        let fps = positive("--fps", Self::DEFAULT_FPS)?;
+                        (path.duration() * fps).round() as u32 + 1
+                    }
+                    Camera::Zoom { .. } => {
+                        for flag in &["--seconds", "--fps"] {
+                            if values.contains_key(*flag) {
+                                return Err(format!("{} only goes with --gif or --play", flag));
+                            }
+                        }
+                        parse::<u32>("--frames", required("--frames")?, "a whole number")?
+                    }
+                };
+                if frames == 0 {
+                    return Err(String::from("--frames has to be at least 1"));
+                }
+                return Ok(Export::Animation(directory, Animation { camera, frames }));
+            }
+            (None, Some(gif)) => { PathBuf::from(gif) }
+            (None, None) => {
+                return Err(String::from("animate needs a directory for the frames with -o, or a --gif"));
+            }
+        };
+
+        if values.contains_key("--frames") {
+            return Err(String::from("--frames doesn't go with --gif, it has --seconds and --fps"));
+        }
+        let fps = positive("--fps", Self::DEFAULT_FPS)?;
+        if fps > Self::MAX_GIF_FPS {
+            return Err(format!("--fps can't be more than {} for a GIF, got {}", Self::MAX_GIF_FPS, fps));
+        }
+        // The last frame leads back into the first, so the end isn't a frame of its own
+        let seconds = match &camera {
+            Camera::Path(path) => { path.duration() }
+            Camera::Zoom { .. } => { positive("--seconds", Self::DEFAULT_GIF_SECONDS)? }
+        };
+        let frames = (seconds * fps).round().max(2.0) as u32;
+        Ok(Export::Gif(gif, Animation { camera, frames }, fps))
+    }
+}
+
+// The lines that aren't numbers are skipped with a warning each, up to a point, but a file without
+// any numbers in it is an error
+fn read_number_list(value: &str) -> Result<NumberList, String> {
+    const REPORTED_LINES: usize = 10;
+    let (name, text) = if value == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).map_err(|error| format!("couldn't read the standard input: {}", error))?;
+        (String::from("stdin"), text)
+    } else {
+        let path = Path::new(value);
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let name = path.file_name().map_or_else(|| String::from(value), |name| name.to_string_lossy().into_owned());
+        (name, text)
+    };
+    let (list, bad_lines) = NumberList::parse(&name, &text);
+    for (line, content) in bad_lines.iter().take(REPORTED_LINES) {
+        warn!("{}:{}: skipped `{}`, which isn't a whole number", name, line, content);
+    }
+    if bad_lines.len() > REPORTED_LINES {
+        warn!("{}: skipped {} more lines that aren't whole numbers", name, bad_lines.len() - REPORTED_LINES);
+    }
+    if list.is_empty() {
+        return Err(format!("there are no numbers in {}", name));
+    }
+    Ok(list)
+}
+
+fn load_camera_path(value: &str) -> Result<CameraPath, String> {
+    CameraPath::load(Path::new(value)).map_err(|error| format!("--play: {}", error))
+}
+
+// e.g. POLAR_OXIDES_ANGLE_FACTOR for --angle-factor
+fn environment_variable(flag: &str) -> String {
+    format!("POLAR_OXIDES_{}", flag.trim_start_matches('-').to_uppercase().replace('-', "_"))
+}
+
+fn parse<T: FromStr>(name: &str, value: &str, expected: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("{} has to be {}, got `{}`", name, expected, value))
+}
+
+// --highlight, with the --base it can take
+fn parse_highlight(values: &HashMap<String, String>) -> Result<Option<Classification>, String> {
+    let only_palindromic = || String::from("--base only goes with --highlight palindromic");
+    let value = match values.get("--highlight") {
+        Some(value) => { value }
+        None if values.contains_key("--base") => { return Err(only_palindromic()); }
+        None => { return Ok(None); }
+    };
+    let classification = Classification::from_name(value).ok_or_else(|| {
+        let kinds = "none, sophie-germain, palindromic or some of twins, cousins and sexy";
+        format!("--highlight has to be {}, got `{}`", kinds, value)
+    })?;
+    match (classification, values.get("--base")) {
+        (_, None) => { Ok(Some(classification)) }
+        (Classification::Palindromic(_), Some(base)) => {
+            let base = parse::<u32>("--base", base, "a whole number")?;
+            if !Classification::BASES.contains(&base) {
+                return Err(format!("--base has to be between 2 and 36, got `{}`", base));
+            }
+            Ok(Some(Classification::Palindromic(base)))
+        }
+        (_, Some(_)) => { Err(only_palindromic()) }
+    }
+}
+
+// --digit-base, from 2 up to `digits::MAX_DIGIT_BASE`
+fn parse_digit_base(value: &str) -> Result<u32, String> {
+    let base = parse::<u32>("--digit-base", value, "a whole number")?;
+    if base >= 2 && base <= digits::MAX_DIGIT_BASE {
+        Ok(base)
+    } else {
+        Err(format!("--digit-base has to be between 2 and {}, got `{}`", digits::MAX_DIGIT_BASE, value))
+    }
+}
+
+// Only the numbers can be sliced or exported
+fn parse_mode(value: &str, subcommand: Option<&str>, values: &HashMap<String, String>) -> Result<DatasetKind, String> {
+    let dataset = DatasetKind::from_name(value)
+        .ok_or_else(|| format!("--mode has to be numbers or gaussian, got `{}`", value))?;
+    if dataset == DatasetKind::Gaussian {
+        if subcommand.is_some() {
+            return Err(String::from("--mode gaussian only goes without a subcommand"));
+        }
+        if values.contains_key("--min") {
+            return Err(String::from("--min only goes with --mode numbers"));
+        }
+    }
+    Ok(dataset)
+}
+
+fn parse_coloring(value: &str) -> Result<Coloring, String> {
+    Coloring::from_name(value).ok_or_else(|| {
+        let names: Vec<&str> = Coloring::ALL.iter().map(|coloring| coloring.name()).collect();
+        let (last, rest) = names.split_last().unwrap_or((&"", &[]));
+        format!("--color-by has to be {} or {}, got `{}`", rest.join(", "), last, value)
+    })
+}
+
+fn parse_modulus(value: &str) -> Result<u64, String> {
+    let modulus = parse::<u64>("--mod", value, "a whole number")?;
+    if Configs::is_modulus(modulus) {
+        Ok(modulus)
+    } else {
+        Err(format!("--mod has to be between 2 and {}, got `{}`", palette::MAX_MODULUS, value))
+    }
+}
+
+fn parse_finite(flag: &str, value: &str) -> Result<f32, String> {
+    let number = parse::<f32>(flag, value, "a number")?;
+    if number.is_finite() { Ok(number) } else { Err(format!("{} has to be a number, got `{}`", flag, value)) }
+}
+
+// `<width>x<height>`, in pixels
+pub fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let mut sides = value.splitn(2, 'x');
+    let width = sides.next()?.parse::<u32>().ok()?;
+    let height = sides.next()?.parse::<u32>().ok()?;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some((width, height))
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use coffee::graphics::Sprite;
+use log::debug;
+use rayon::prelude::*;
+
+use polar_oxides::dataset::Points;
+use polar_oxides::view::{Configs, Settings};
+
+use crate::sprites;
+
+// The sprites of one kind of number, and how many of them are in the window for the HUD
+pub struct BuiltBatch {
+    pub sprites: Vec<Sprite>,
+    pub visible: usize,
+    // How long working out the sprites took
+    pub time: Duration,
+}
+
+// What a finished build hands over to replace the batches with. A kind that wasn't asked for is
+// left as it was.
+pub struct BuiltBatches {
+    pub configs: Configs,
+    pub settings: Settings,
+    pub size: (f32, f32),
+    pub primes: Option<BuiltBatch>,
+    pub composites: Option<BuiltBatch>,
+    // When the build was started, waiting for a thread included
+    pub started: Instant,
+}
+
+// Sprites being built on rayon's threads, so the window can keep drawing the old batches in the
+// meantime. Dropping it, by starting another one or otherwise, throws away what it builds, and
+// skips the second kind of number if it hasn't been started on yet.
+pub struct BatchBuild {
+    configs: Configs,
+    settings: Settings,
+    size: (f32, f32),
+    primes: bool,
+    composites: bool,
+    cancelled: Arc<AtomicBool>,
+    receiver: Receiver<BuiltBatches>,
+}
+
+impl BatchBuild {
+    pub fn start(
+        points: Points,
+        configs: Configs,
+        settings: Settings,
+        size: (f32, f32),
+        primes: bool,
+        composites: bool,
+    ) -> BatchBuild {
+        let started = Instant::now();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        let worker_cancelled = Arc::clone(&cancelled);
+        rayon::spawn(move || {
+            let build = |wanted: bool, kind_is_prime: bool| {
+                if !wanted || worker_cancelled.load(Ordering::Relaxed) {
+                    return None;
+                }
+                Some(build_batch(&points, configs, settings, size, kind_is_prime))
+            };
+            let built_primes = build(primes, true);
+            let built_composites = build(composites, false);
+            if worker_cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            // Nobody listening anymore means it was cancelled while on the last kind
+            let _ = sender.send(BuiltBatches {
+                configs,
+                settings,
+                size,
+                primes: built_primes,
+                composites: built_composites,
+                started,
+            });
+        });
+
+        BatchBuild { configs, settings, size, primes, composites, cancelled, receiver }
+    }
+
+    // Whether what it builds is still worth waiting for: everything but the camera is the same, and
+    // it builds at least the kinds of number asked for. Where the camera went since is made up for
+    // by moving the batches once they are in.
+    pub fn covers(&self, configs: Configs, settings: Settings, size: (f32, f32), primes: bool, composites: bool) -> bool {
+        let without_camera = Configs {
+            zoom_level: self.configs.zoom_level,
+            offset: self.configs.offset,
+            ..configs
+        };
+        without_camera == self.configs
+            && settings == self.settings
+            && size == self.size
+            && (self.primes || !primes)
+            && (self.composites || !composites)
+    }
+
+    // The new batches, once they are there. Never blocks.
+    pub fn poll(&self) -> Option<BuiltBatches> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for BatchBuild {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+fn build_batch(points: &Points, configs: Configs, settings: Settings, size: (f32, f32), primes: bool) -> BuiltBatch {
+    let started = Instant::now();
+    let (width, height) = size;
+    let sprites = sprites::sprites(points, configs, settings, width, height, primes);
+    let (half_width, half_height) = (width / 2.0, height / 2.0);
+    let visible = sprites.par_iter()
+        .filter(|sprite| sprite.position.x.abs() <= half_width && sprite.position.y.abs() <= half_height)
+        .count();
+    let time = started.elapsed();
+    let kind = if primes { "prime" } else { "composite" };
+    debug!("Built {} {} sprites, {} of them visible, in {:.2?}", sprites.len(), kind, visible, time);
+    BuiltBatch { sprites, visible, time }
+}
@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use coffee::input::keyboard::KeyCode;
+use log::warn;
+
+use crate::controls::Controls;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    ZoomIn,
+    ZoomOut,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ResetView,
+    ZoomToFit,
+    GoTo,
+    Search,
+    CommandLine,
+    NextPrime,
+    PreviousPrime,
+    AddNumbers,
+    ToggleFullscreen,
+    Screenshot,
+    ExportData,
+    CopySelection,
+    CopyView,
+    PasteView,
+    SaveView,
+    RestoreView,
+    AddBookmark,
+    Bookmarks,
+    CycleDisplayMode,
+    CycleClassification,
+    ToggleTheme,
+    CyclePalette,
+    ToggleReveal,
+    ToggleTour,
+    GrowPoints,
+    ShrinkPoints,
+    ResetPointSize,
+    Preset,
+    SavePreset,
+    DecreaseAngle,
+    IncreaseAngle,
+    ToggleHelp,
+    ToggleHud,
+    ToggleStatusBar,
+    ToggleStats,
+    TogglePrimeCurve,
+    ToggleGapHistogram,
+    ToggleRose,
+    CycleRoseBins,
+    ToggleLegend,
+    ToggleCrosshair,
+    ToggleGermainLines,
+    ToggleSpecialPrimes,
+    CyclePowers,
+    ToggleFibonacci,
+    ToggleLucas,
+    ToggleResidues,
+    CycleModulus,
+    ToggleGapColors,
+    CycleColoring,
+    Quit,
+}
+
+impl Action {
+    // The keys for the preset slots, one per slot in order
+    const SLOT_KEYS: [KeyCode; 9] = [
+        KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5,
+        KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    ];
+
+    pub const ALL: [Action; 57] = [
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::PanUp,
+        Action::PanDown,
+        Action::ResetView,
+        Action::ZoomToFit,
+        Action::GoTo,
+        Action::Search,
+        Action::CommandLine,
+        Action::NextPrime,
+        Action::PreviousPrime,
+        Action::AddNumbers,
+        Action::ToggleFullscreen,
+        Action::Screenshot,
+        Action::ExportData,
+        Action::CopySelection,
+        Action::CopyView,
+        Action::PasteView,
+        Action::SaveView,
+        Action::RestoreView,
+        Action::AddBookmark,
+        Action::Bookmarks,
+        Action::CycleDisplayMode,
+        Action::CycleClassification,
+        Action::ToggleTheme,
+        Action::CyclePalette,
+        Action::ToggleReveal,
+        Action::ToggleTour,
+        Action::GrowPoints,
+        Action::ShrinkPoints,
+        Action::ResetPointSize,
+        Action::Preset,
+        Action::SavePreset,
+        Action::DecreaseAngle,
+        Action::IncreaseAngle,
+        Action::ToggleHelp,
+        Action::ToggleHud,
+        Action::ToggleStatusBar,
+        Action::ToggleStats,
+        Action::TogglePrimeCurve,
+        Action::ToggleGapHistogram,
+        Action::ToggleRose,
+        Action::CycleRoseBins,
+        Action::ToggleLegend,
+        Action::ToggleCrosshair,
+        Action::ToggleGermainLines,
+        Action::ToggleSpecialPrimes,
+        Action::CyclePowers,
+        Action::ToggleFibonacci,
+        Action::ToggleLucas,
+        Action::ToggleResidues,
+        Action::CycleModulus,
+        Action::ToggleGapColors,
+        Action::CycleColoring,
+        Action::Quit,
+    ];
+
+    // The name used for the action in keybindings.toml
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ZoomIn => { "zoom_in" }
+            Action::ZoomOut => { "zoom_out" }
+            Action::PanLeft => { "pan_left" }
+            Action::PanRight => { "pan_right" }
+            Action::PanUp => { "pan_up" }
+            Action::PanDown => { "pan_down" }
+            Action::ResetView => { "reset_view" }
+            Action::ZoomToFit => { "zoom_to_fit" }
+            Action::GoTo => { "go_to" }
+            Action::Search => { "search" }
+            Action::CommandLine => { "command_line" }
+            Action::NextPrime => { "next_prime" }
+            Action::PreviousPrime => { "previous_prime" }
+            Action::AddNumbers => { "add_numbers" }
+            Action::ToggleFullscreen => { "toggle_fullscreen" }
+            Action::Screenshot => { "screenshot" }
+            Action::ExportData => { "export_data" }
+            Action::CopySelection => { "copy_selection" }
+            Action::CopyView => { "copy_view" }
+            Action::PasteView => { "paste_view" }
+            Action::SaveView => { "save_view" }
+            Action::RestoreView => { "restore_view" }
+            Action::AddBookmark => { "add_bookmark" }
+            Action::Bookmarks => { "bookmarks" }
+            Action::CycleDisplayMode => { "cycle_display_mode" }
+            Action::CycleClassification => { "cycle_highlight" }
+            Action::ToggleTheme => { "toggle_theme" }
+            Action::CyclePalette => { "cycle_palette" }
+            Action::ToggleReveal => { "toggle_reveal" }
+            Action::ToggleTour => { "toggle_tour" }
+            Action::GrowPoints => { "grow_points" }
+            Action::ShrinkPoints => { "shrink_points" }
+            Action::ResetPointSize => { "reset_point_size" }
+            Action::Preset => { "preset" }
+            Action::SavePreset => { "save_preset" }
+            Action::DecreaseAngle => { "decrease_angle" }
+            Action::IncreaseAngle => { "increase_angle" }
+            Action::ToggleHelp => { "toggle_help" }
+            Action::ToggleHud => { "toggle_hud" }
+            Action::ToggleStatusBar => { "toggle_status_bar" }
+            Action::ToggleStats => { "toggle_stats" }
+            Action::TogglePrimeCurve => { "toggle_prime_curve" }
+            Action::ToggleGapHistogram => { "toggle_gap_histogram" }
+            Action::ToggleRose => { "toggle_rose" }
+            Action::CycleRoseBins => { "cycle_rose_bins" }
+            Action::ToggleLegend => { "toggle_legend" }
+            Action::ToggleCrosshair => { "toggle_crosshair" }
+            Action::ToggleGermainLines => { "toggle_germain_lines" }
+            Action::ToggleSpecialPrimes => { "toggle_special_primes" }
+            Action::CyclePowers => { "cycle_powers" }
+            Action::ToggleFibonacci => { "toggle_fibonacci" }
+            Action::ToggleLucas => { "toggle_lucas" }
+            Action::ToggleResidues => { "toggle_residues" }
+            Action::CycleModulus => { "cycle_modulus" }
+            Action::ToggleGapColors => { "toggle_gap_colors" }
+            Action::CycleColoring => { "cycle_coloring" }
+            Action::Quit => { "quit" }
+        }
+    }
+
+    // What the action does, as shown in the help overlay
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::ZoomIn => { "Zoom in" }
+            Action::ZoomOut => { "Zoom out" }
+            Action::PanLeft => { "Pan left" }
+            Action::PanRight => { "Pan right" }
+            Action::PanUp => { "Pan up" }
+            Action::PanDown => { "Pan down" }
+            Action::ResetView => { "Reset the view" }
+            Action::ZoomToFit => { "Zoom to fit the whole spiral" }
+            Action::GoTo => { "Go to a number" }
+            Action::Search => { "Highlight a number without moving" }
+            Action::CommandLine => { "Type a command, like `max 2000000` or `palette cb1`" }
+            Action::NextPrime => { "Go to the next prime" }
+            Action::PreviousPrime => { "Go to the previous prime" }
+            Action::AddNumbers => { "Add 50,000 more numbers (Shift: 500,000)" }
+            Action::ToggleFullscreen => { "Toggle fullscreen" }
+            Action::Screenshot => { "Save a screenshot (Shift: as an SVG)" }
+            Action::ExportData => { "Save the visible numbers to a CSV file (Shift: JSON)" }
+            Action::CopySelection => { "Copy the selected number's info" }
+            Action::CopyView => { "Copy a string others can open this view with" }
+            Action::PasteView => { "Open a copied view string" }
+            Action::SaveView => { "Save the view" }
+            Action::RestoreView => { "Restore the saved view" }
+            Action::AddBookmark => { "Bookmark the view under a name" }
+            Action::Bookmarks => { "Open the bookmarks" }
+            Action::CycleDisplayMode => { "Cycle all numbers, primes only and composites only" }
+            Action::CycleClassification => { "Cycle which kinds of primes get a color of their own" }
+            Action::ToggleTheme => { "Toggle the light theme" }
+            Action::CyclePalette => { "Cycle the color palettes" }
+            Action::ToggleReveal => { "Start/pause revealing the numbers in order, or the camera path" }
+            Action::ToggleTour => { "Start/stop the tour (any input stops it too)" }
+            Action::GrowPoints => { "Bigger points (Shift: primes only)" }
+            Action::ShrinkPoints => { "Smaller points (Shift: primes only)" }
+            Action::ResetPointSize => { "Reset the size of the points" }
+            Action::Preset => { "Switch to the looks in that slot" }
+            Action::SavePreset => { "Save the current looks in that slot" }
+            Action::DecreaseAngle => { "Decrease the angle factor (Shift: bigger steps)" }
+            Action::IncreaseAngle => { "Increase the angle factor (Shift: bigger steps)" }
+            Action::ToggleHelp => { "Show/hide this help" }
+            Action::ToggleHud => { "Show/hide the zoom, point count and FPS display" }
+            Action::ToggleStatusBar => { "Show/hide where the cursor is, in pixels and on the spiral" }
+            Action::ToggleStats => { "Show/hide statistics about the numbers in the window" }
+            Action::TogglePrimeCurve => { "Show/hide a plot of π, the primes up to each radius" }
+            Action::ToggleGapHistogram => { "Show/hide how far apart the primes in the window are" }
+            Action::ToggleRose => { "Show/hide a rose diagram of the angles the numbers sit at" }
+            Action::CycleRoseBins => { "Cycle 180, 360 and 720 slices in the rose diagram" }
+            Action::ToggleLegend => { "Show/hide what the colors mean" }
+            Action::ToggleCrosshair => { "Show/hide a crosshair with the number closest to the middle" }
+            Action::ToggleGermainLines => { "Show/hide lines from Sophie Germain to safe primes" }
+            Action::ToggleSpecialPrimes => { "Show/hide rings around the Mersenne and Fermat primes" }
+            Action::CyclePowers => { "Mark the perfect squares, cubes, 4th powers or none" }
+            Action::ToggleFibonacci => { "Show/hide markers on the Fibonacci numbers" }
+            Action::ToggleLucas => { "Show/hide markers on the Lucas numbers" }
+            Action::ToggleResidues => { "Toggle coloring the numbers by their residues" }
+            Action::CycleModulus => { "Color by the residues mod the next modulus" }
+            Action::ToggleGapColors => { "Toggle coloring the primes by the gap to the next prime" }
+            Action::CycleColoring => { "Cycle what the numbers are colored by" }
+            Action::Quit => { "Dismiss the error, clear the selection, or quit" }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().cloned().find(|action| action.name() == name)
+    }
+
+    fn default_chords(self) -> Vec<Chord> {
+        match self {
+            Action::ZoomIn => { vec![Chord::key(KeyCode::W)] }
+            Action::ZoomOut => { vec![Chord::key(KeyCode::S)] }
+            Action::PanLeft => { vec![Chord::key(KeyCode::Left), Chord::key(KeyCode::J)] }
+            Action::PanRight => { vec![Chord::key(KeyCode::Right), Chord::key(KeyCode::L)] }
+            Action::PanUp => { vec![Chord::key(KeyCode::Up), Chord::key(KeyCode::I)] }
+            Action::PanDown => { vec![Chord::key(KeyCode::Down), Chord::key(KeyCode::K)] }
+            Action::ResetView => { vec![Chord::key(KeyCode::R)] }
+            Action::ZoomToFit => { vec![Chord::key(KeyCode::A)] }
+            Action::GoTo => { vec![Chord::key(KeyCode::G)] }
+            Action::Search => { vec![Chord::key(KeyCode::Slash), Chord::key(KeyCode::Divide)] }
+            Action::CommandLine => { vec![Chord::key(KeyCode::Colon), Chord::key(KeyCode::Semicolon)] }
+            Action::NextPrime => { vec![Chord::key(KeyCode::N), Chord::key(KeyCode::RBracket)] }
+            Action::PreviousPrime => { vec![Chord::key(KeyCode::LBracket), Chord::shift(KeyCode::N)] }
+            Action::AddNumbers => { vec![Chord::key(KeyCode::M)] }
+            Action::ToggleFullscreen => { vec![Chord::key(KeyCode::F)] }
+            Action::Screenshot => { vec![Chord::key(KeyCode::P)] }
+            Action::ExportData => { vec![Chord::key(KeyCode::X)] }
+            Action::CopySelection => { vec![Chord::control(KeyCode::C)] }
+            Action::CopyView => { vec![Chord::control(KeyCode::C).with_shift()] }
+            Action::PasteView => { vec![Chord::control(KeyCode::V).with_shift()] }
+            Action::SaveView => { vec![Chord::key(KeyCode::F5)] }
+            Action::RestoreView => { vec![Chord::key(KeyCode::F9)] }
+            Action::AddBookmark => { vec![Chord::control(KeyCode::B)] }
+            Action::Bookmarks => { vec![Chord::shift(KeyCode::B)] }
+            Action::CycleDisplayMode => { vec![Chord::key(KeyCode::D)] }
+            Action::CycleClassification => { vec![Chord::shift(KeyCode::D)] }
+            Action::ToggleTheme => { vec![Chord::key(KeyCode::B)] }
+            Action::CyclePalette => { vec![Chord::key(KeyCode::C)] }
+            Action::ToggleReveal => { vec![Chord::key(KeyCode::Space)] }
+            Action::ToggleTour => { vec![Chord::key(KeyCode::T)] }
+            Action::GrowPoints => { vec![Chord::key(KeyCode::Equals), Chord::key(KeyCode::Add)] }
+            Action::ShrinkPoints => { vec![Chord::key(KeyCode::Minus), Chord::key(KeyCode::Subtract)] }
+            Action::ResetPointSize => { vec![Chord::key(KeyCode::Key0), Chord::key(KeyCode::Numpad0)] }
+            // One chord per slot, in order
+            Action::Preset => { Self::SLOT_KEYS.iter().map(|&key| Chord::key(key)).collect() }
+            Action::SavePreset => { Self::SLOT_KEYS.iter().map(|&key| Chord::control(key)).collect() }
+            Action::DecreaseAngle => { vec![Chord::key(KeyCode::Q)] }
+            Action::IncreaseAngle => { vec![Chord::key(KeyCode::E)] }
+            Action::ToggleHelp => { vec![Chord::key(KeyCode::H)] }
+            Action::ToggleHud => { vec![Chord::key(KeyCode::Tab)] }
+            Action::ToggleStatusBar => { vec![Chord::shift(KeyCode::Tab)] }
+            Action::ToggleStats => { vec![Chord::shift(KeyCode::I)] }
+            Action::TogglePrimeCurve => { vec![Chord::key(KeyCode::O)] }
+            Action::ToggleGapHistogram => { vec![Chord::shift(KeyCode::O)] }
+            Action::ToggleRose => { vec![Chord::key(KeyCode::U)] }
+            Action::CycleRoseBins => { vec![Chord::shift(KeyCode::U)] }
+            Action::ToggleLegend => { vec![Chord::shift(KeyCode::L)] }
+            Action::ToggleCrosshair => { vec![Chord::key(KeyCode::Period)] }
+            Action::ToggleGermainLines => { vec![Chord::shift(KeyCode::Period)] }
+            Action::ToggleSpecialPrimes => { vec![Chord::key(KeyCode::Z)] }
+            Action::CyclePowers => { vec![Chord::shift(KeyCode::Z)] }
+            Action::ToggleFibonacci => { vec![Chord::control(KeyCode::Z)] }
+            Action::ToggleLucas => { vec![Chord::control(KeyCode::Z).with_shift()] }
+            Action::ToggleResidues => { vec![Chord::key(KeyCode::Y)] }
+            Action::CycleModulus => { vec![Chord::shift(KeyCode::Y)] }
+            Action::ToggleGapColors => { vec![Chord::control(KeyCode::G)] }
+            Action::CycleColoring => { vec![Chord::shift(KeyCode::C)] }
+            Action::Quit => { vec![Chord::key(KeyCode::Escape)] }
+        }
+    }
+}
+
+// A key along with the modifiers that have to be held for it, named like "Ctrl+Shift+C"
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Chord {
+    key: KeyCode,
+    control: bool,
+    alt: bool,
+    shift: bool,
+}
+
+impl Chord {
+    pub fn key(key: KeyCode) -> Chord {
+        Chord { key, control: false, alt: false, shift: false }
+    }
+
+    pub fn shift(key: KeyCode) -> Chord {
+        Chord { shift: true, ..Chord::key(key) }
+    }
+
+    pub fn control(key: KeyCode) -> Chord {
+        Chord { control: true, ..Chord::key(key) }
+    }
+
+    pub fn with_shift(self) -> Chord {
+        Chord { shift: true, ..self }
+    }
+
+    // The modifiers come first, in any order, e.g. "Shift+Ctrl+Z" is the same as "Ctrl+Shift+Z"
+    pub fn from_name(name: &str) -> Option<Chord> {
+        let mut parts: Vec<&str> = name.split('+').map(str::trim).collect();
+        let mut chord = Chord::key(key_from_name(parts.pop()?)?);
+        for modifier in parts {
+            match modifier {
+                "Ctrl" => { chord.control = true; }
+                "Alt" => { chord.alt = true; }
+                "Shift" => { chord.shift = true; }
+                _ => { return None; }
+            }
+        }
+        Some(chord)
+    }
+
+    pub fn name(self) -> Option<String> {
+        let mut name = String::new();
+        for &(held, modifier) in &[(self.control, "Ctrl+"), (self.alt, "Alt+"), (self.shift, "Shift+")] {
+            if held {
+                name.push_str(modifier);
+            }
+        }
+        name.push_str(key_name(self.key)?);
+        Some(name)
+    }
+
+    // Its key along with both keys for each of its modifiers
+    fn keys(self) -> Vec<KeyCode> {
+        let modifiers = [
+            (self.control, [KeyCode::LControl, KeyCode::RControl]),
+            (self.alt, [KeyCode::LAlt, KeyCode::RAlt]),
+            (self.shift, [KeyCode::LShift, KeyCode::RShift]),
+        ];
+        let held = modifiers.iter().filter(|&&(held, _)| held).flat_map(|(_, keys)| keys.iter().cloned());
+        Some(self.key).into_iter().chain(held).collect()
+    }
+
+    fn modifiers_held(self, input: &Controls) -> bool {
+        (!self.control || input.is_control_pressed())
+            && (!self.alt || input.is_alt_pressed())
+            && (!self.shift || input.is_shift_pressed())
+    }
+
+    // Whether it's the same key as `other` with the same modifiers and more
+    fn extends(self, other: Chord) -> bool {
+        self.key == other.key
+            && self != other
+            && (self.control || !other.control)
+            && (self.alt || !other.alt)
+            && (self.shift || !other.shift)
+    }
+}
+
+pub struct Bindings {
+    chords: HashMap<Action, Vec<Chord>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings {
+            chords: Action::ALL.iter()
+                .map(|&action| (action, action.default_chords()))
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    const FILE_NAME: &'static str = "keybindings.toml";
+
+    // Reads keybindings.toml from next to the executable. Anything missing or wrong in it
+    // keeps its default binding, with a warning for the latter.
+    pub fn load() -> Bindings {
+        let mut bindings = Bindings::default();
+        let path = match Self::path() {
+            Some(path) => { path }
+            None => { return bindings; }
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => { contents }
+            Err(_) => { return bindings; }
+        };
+
+        match contents.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => {
+                for (name, value) in table {
+                    if let Err(message) = bindings.bind(&name, &value) {
+                        warn!("{}: {}, using the default binding", path.display(), message);
+                    }
+                }
+            }
+            Ok(_) => {
+                warn!("{}: expected a table of bindings, using the defaults", path.display());
+            }
+            Err(error) => {
+                warn!("{}: {}, using the defaults", path.display(), error);
+            }
+        }
+        bindings
+    }
+
+    fn path() -> Option<PathBuf> {
+        let executable = env::current_exe().ok()?;
+        Some(executable.parent()?.join(Self::FILE_NAME))
+    }
+
+    // Binds an action to either a single key name or a list of them, each with the modifiers to
+    // hold along with it if any, like "Shift+Z"
+    pub fn bind(&mut self, name: &str, value: &toml::Value) -> Result<(), String> {
+        let action = Action::from_name(name)
+            .ok_or_else(|| format!("unknown action `{}`", name))?;
+
+        let chord_names = match value {
+            toml::Value::String(chord) => { vec![chord.as_str()] }
+            toml::Value::Array(chords) => {
+                chords.iter()
+                    .map(|chord| chord.as_str().ok_or_else(|| format!("`{}` expects key names", name)))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            _ => { return Err(format!("`{}` expects a key name or a list of them", name)); }
+        };
+
+        let chords = chord_names.iter()
+            .map(|&chord| Chord::from_name(chord).ok_or_else(|| format!("unknown key `{}` for `{}`", chord, name)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.chords.insert(action, chords);
+        Ok(())
+    }
+
+    // Every action and its keys, laid out like keybindings.toml
+    pub fn to_table(&self) -> toml::value::Table {
+        Action::ALL.iter()
+            .map(|&action| {
+                let mut names: Vec<toml::Value> = self.chords(action).iter()
+                    .filter_map(|&chord| chord.name())
+                    .map(toml::Value::String)
+                    .collect();
+                let value = if names.len() == 1 { names.remove(0) } else { toml::Value::Array(names) };
+                (String::from(action.name()), value)
+            })
+            .collect()
+    }
+
+    pub fn chords(&self, action: Action) -> &[Chord] {
+        self.chords.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // Every key that goes into the chords bound to an action, modifiers included
+    pub fn keys(&self, action: Action) -> Vec<KeyCode> {
+        self.chords(action).iter().flat_map(|&chord| chord.keys()).collect()
+    }
+
+    // The chords bound to an action, e.g. "Left/J" or "Ctrl+Shift+C"
+    pub fn describe(&self, action: Action) -> String {
+        self.chords(action).iter()
+            .map(|&chord| chord.name().unwrap_or_else(|| String::from("?")))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    pub fn is_pressed(&self, input: &Controls, action: Action) -> bool {
+        self.chords(action).iter().any(|&chord| input.is_key_pressed(chord.key) && self.is_meant(input, chord))
+    }
+
+    pub fn was_released(&self, input: &Controls, action: Action) -> bool {
+        self.chords(action).iter().any(|&chord| input.was_key_released(chord.key) && self.is_meant(input, chord))
+    }
+
+    // For actions with a chord per slot: which of the chords bound to it was released, if any
+    pub fn released_index(&self, input: &Controls, action: Action) -> Option<usize> {
+        self.chords(action).iter().position(|&chord| input.was_key_released(chord.key) && self.is_meant(input, chord))
+    }
+
+    // Whether the modifiers held are the ones for `chord`. Holding more is fine, like Shift for coarser
+    // zoom steps, unless another chord on the same key asks for those, the way Shift+D does over D.
+    fn is_meant(&self, input: &Controls, chord: Chord) -> bool {
+        chord.modifiers_held(input)
+            && !self.chords.values()
+                .flatten()
+                .any(|&other| other.extends(chord) && other.modifiers_held(input))
+    }
+}
+
+// Keys are named after their `KeyCode` variant, e.g. "W", "Key1", "Left" or "F5"
+macro_rules! key_names {
+    ($($key:ident),* $(,)*) => {
+        pub fn key_from_name(name: &str) -> Option<KeyCode> {
+            match name {
+                $(stringify!($key) => { Some(KeyCode::$key) })*
+                _ => { None }
+            }
+        }
+
+        pub fn key_name(key: KeyCode) -> Option<&'static str> {
+            match key {
+                $(KeyCode::$key => { Some(stringify!($key)) })*
+                _ => { None }
+            }
+        }
+    };
+}
+
+key_names! {
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Left, Right, Up, Down, Home, End, PageUp, PageDown, Insert, Delete,
+    Escape, Return, Space, Tab, Back,
+    Add, Subtract, Multiply, Divide, Equals, Minus, Comma, Period, Slash, Backslash,
+    Colon, Semicolon, Apostrophe, Grave, LBracket, RBracket,
+    NumpadEnter, NumpadComma, NumpadEquals,
+    LShift, RShift, LControl, RControl, LAlt, RAlt,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use coffee::input::{keyboard, ButtonState, Event, Input};
+
+    fn press(input: &mut Controls, keys: &[KeyCode]) {
+        for &key_code in keys {
+            input.update(Event::Keyboard(keyboard::Event::Input { key_code, state: ButtonState::Pressed }));
+        }
+    }
+
+    fn release(input: &mut Controls, key_code: KeyCode) {
+        input.update(Event::Keyboard(keyboard::Event::Input { key_code, state: ButtonState::Released }));
+    }
+
+    #[test]
+    fn chords_are_named_with_their_modifiers() {
+        assert_eq!(Chord::from_name("Z"), Some(Chord::key(KeyCode::Z)));
+        assert_eq!(Chord::from_name("Shift+Ctrl+Z"), Some(Chord::control(KeyCode::Z).with_shift()));
+        assert_eq!(Chord::control(KeyCode::Z).with_shift().name(), Some(String::from("Ctrl+Shift+Z")));
+        assert_eq!(Chord::from_name("Alt+F4").and_then(Chord::name), Some(String::from("Alt+F4")));
+        assert_eq!(Chord::from_name("Super+Z"), None);
+        assert_eq!(Chord::from_name("Ctrl+"), None);
+        assert_eq!(Chord::from_name("Shift"), None);
+    }
+
+    #[test]
+    fn bindings_round_trip_through_a_table() {
+        let mut bindings = Bindings::default();
+        bindings.bind("toggle_stats", &toml::Value::from("Ctrl+Alt+I")).unwrap();
+        assert!(bindings.bind("toggle_stats", &toml::Value::from("Hyper+I")).is_err());
+        assert_eq!(bindings.describe(Action::ToggleStats), "Ctrl+Alt+I");
+
+        let mut reread = Bindings::default();
+        for (name, value) in &bindings.to_table() {
+            reread.bind(name, value).unwrap();
+        }
+        for &action in Action::ALL.iter() {
+            assert_eq!(reread.chords(action), bindings.chords(action), "{}", action.name());
+        }
+    }
+
+    #[test]
+    fn the_chord_asking_for_the_most_modifiers_held_wins() {
+        let bindings = Bindings::default();
+        let released = |keys: &[KeyCode]| {
+            let mut input = Controls::new();
+            press(&mut input, keys);
+            release(&mut input, KeyCode::Z);
+            Action::ALL.iter().cloned().filter(|&action| bindings.was_released(&input, action)).collect::<Vec<_>>()
+        };
+        assert_eq!(released(&[KeyCode::Z]), vec![Action::ToggleSpecialPrimes]);
+        assert_eq!(released(&[KeyCode::LShift, KeyCode::Z]), vec![Action::CyclePowers]);
+        assert_eq!(released(&[KeyCode::RControl, KeyCode::Z]), vec![Action::ToggleFibonacci]);
+        assert_eq!(released(&[KeyCode::LShift, KeyCode::LControl, KeyCode::Z]), vec![Action::ToggleLucas]);
+        // Nothing asks for Alt with Z, so it's as if it weren't held
+        assert_eq!(released(&[KeyCode::LAlt, KeyCode::Z]), vec![Action::ToggleSpecialPrimes]);
+
+        // Shift makes the zoom coarser, but Shift+I is the statistics rather than panning up
+        let mut input = Controls::new();
+        press(&mut input, &[KeyCode::LShift, KeyCode::W, KeyCode::I]);
+        assert!(bindings.is_pressed(&input, Action::ZoomIn));
+        assert!(!bindings.is_pressed(&input, Action::PanUp));
+        assert_eq!(bindings.keys(Action::ToggleStats), vec![KeyCode::I, KeyCode::LShift, KeyCode::RShift]);
+    }
+}
@@ -0,0 +1,71 @@
+//! Keeping found particles on disk, so starting again with the same amount of them doesn't have to
+//! find them all over again
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::particles::Particles;
+use crate::primality::Primality;
+
+/// Whether the cache is read and written
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CacheMode {
+    /// Read the cache when it has what we need, write it when it doesn't
+    Use,
+    /// Find the particles again and overwrite the cache with them
+    Refresh,
+    /// Leave the cache alone
+    Off,
+}
+
+impl CacheMode {
+    pub fn reads(self) -> bool {
+        self == CacheMode::Use
+    }
+
+    pub fn writes(self) -> bool {
+        self != CacheMode::Off
+    }
+}
+
+// One file per range of numbers and way of finding the primes. Ranges from 0 keep the names from
+// before slices could start anywhere else.
+fn path(start: u64, max_number: u64, primality: Primality) -> Result<PathBuf, String> {
+    let cache_dir = dirs::cache_dir()
+        .ok_or_else(|| String::from("couldn't find the cache directory"))?;
+    let file_name = if start == 0 {
+        format!("particles-{}-{}.bin", max_number, primality.name())
+    } else {
+        format!("particles-{}-{}-{}.bin", start, max_number, primality.name())
+    };
+    Ok(cache_dir.join("polar-oxides").join(file_name))
+}
+
+/// The particles of `start..max_number` cached by an earlier run, `None` when there aren't any. A
+/// damaged or outdated file is an error, to be found again and overwritten.
+pub fn load(start: u64, max_number: u64, primality: Primality) -> Result<Option<Particles>, String> {
+    let path = path(start, max_number, primality)?;
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => { bytes }
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => { return Ok(None); }
+        Err(error) => { return Err(format!("{}: {}", path.display(), error)); }
+    };
+
+    let particles = Particles::from_bytes(&bytes).map_err(|error| format!("{}: {}", path.display(), error))?;
+    if particles.start() != start || particles.last_number() != max_number.saturating_sub(1) {
+        let range = format!("{}..={}", particles.start(), particles.last_number());
+        return Err(format!("{}: holds the numbers in {}", path.display(), range));
+    }
+    Ok(Some(particles))
+}
+
+pub fn store(particles: &Particles, start: u64, max_number: u64, primality: Primality) -> Result<PathBuf, String> {
+    let path = path(start, max_number, primality)?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)
+            .map_err(|error| format!("{}: {}", directory.display(), error))?;
+    }
+    fs::write(&path, particles.to_bytes()).map_err(|error| format!("{}: {}", path.display(), error))?;
+    Ok(path)
+}
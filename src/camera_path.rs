@@ -0,0 +1,198 @@
+//! Scripted camera moves: where the camera is at a few points in time, for the window to fly
+//! through with `--play` or for `animate` to write out frame by frame
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// How the camera speeds up and slows down between two keyframes
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    /// Smoothstep, stopping at every keyframe
+    EaseInOut,
+}
+
+impl Default for Easing {
+    fn default() -> Easing {
+        Easing::Linear
+    }
+}
+
+impl Easing {
+    /// How far along the way to the next keyframe the camera is, `progress` being how far along
+    /// the time to it we are. Both go from 0 to 1.
+    pub fn apply(self, progress: f32) -> f32 {
+        let progress = progress.max(0.0).min(1.0);
+        match self {
+            Easing::Linear => { progress }
+            Easing::EaseIn => { progress * progress }
+            Easing::EaseOut => { 1.0 - (1.0 - progress) * (1.0 - progress) }
+            Easing::EaseInOut => { progress * progress * (3.0 - 2.0 * progress) }
+        }
+    }
+}
+
+/// Where the camera is `time` seconds in, like `Configs::zoom_level` and `Configs::offset`
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframe {
+    pub time: f32,
+    pub zoom: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+/// Keyframes in order of time, with the easing used between each of them. Read from a file like
+///
+/// ```toml
+/// easing = "ease-in-out"
+///
+/// [[keyframes]]
+/// time = 0.0
+/// zoom = 0.0
+/// offset_x = 0.0
+/// offset_y = 0.0
+/// ```
+///
+/// or the same as JSON.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraPath {
+    #[serde(default)]
+    pub easing: Easing,
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    /// JSON for a file ending in .json, TOML otherwise
+    pub fn load(path: &Path) -> Result<CameraPath, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let is_json = path.extension().and_then(|extension| extension.to_str())
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("json"));
+        let parsed = if is_json { Self::parse_json(&text) } else { Self::parse_toml(&text) };
+        parsed.map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    pub fn parse_toml(text: &str) -> Result<CameraPath, String> {
+        toml::from_str::<CameraPath>(text).map_err(|error| error.to_string())?.checked()
+    }
+
+    pub fn parse_json(text: &str) -> Result<CameraPath, String> {
+        serde_json::from_str::<CameraPath>(text).map_err(|error| error.to_string())?.checked()
+    }
+
+    // At least one keyframe, with numbers in them and times that don't go backwards
+    fn checked(self) -> Result<CameraPath, String> {
+        if self.keyframes.is_empty() {
+            return Err(String::from("there has to be at least one keyframe"));
+        }
+        for (index, keyframe) in self.keyframes.iter().enumerate() {
+            let Keyframe { time, zoom, offset_x, offset_y } = *keyframe;
+            if !(time.is_finite() && zoom.is_finite() && offset_x.is_finite() && offset_y.is_finite()) {
+                return Err(format!("keyframe {} has to have numbers in it", index + 1));
+            }
+            if time < 0.0 {
+                return Err(format!("keyframe {} is at {} seconds, before the start", index + 1, time));
+            }
+        }
+        if let Some(index) = self.keyframes.windows(2).position(|pair| pair[1].time < pair[0].time) {
+            return Err(format!("keyframe {} is earlier than the one before it", index + 2));
+        }
+        Ok(self)
+    }
+
+    /// How long it takes, in seconds: the time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// The zoom level and the offset `time` seconds in. Zoom levels are exponents of how much the
+    /// view is scaled by, so going between them evenly zooms at an even pace. Before the first
+    /// keyframe and after the last the camera stays on them.
+    pub fn sample(&self, time: f32) -> (f32, (f32, f32)) {
+        let at = |keyframe: &Keyframe| (keyframe.zoom, (keyframe.offset_x, keyframe.offset_y));
+        let keyframes = &self.keyframes;
+        let next = match keyframes.iter().position(|keyframe| keyframe.time > time) {
+            Some(0) => { return at(&keyframes[0]); }
+            Some(next) => { next }
+            None => { return keyframes.last().map_or((0.0, (0.0, 0.0)), at); }
+        };
+
+        let (from, to) = (&keyframes[next - 1], &keyframes[next]);
+        let progress = self.easing.apply((time - from.time) / (to.time - from.time));
+        let between = |a: f32, b: f32| a + (b - a) * progress;
+        (
+            between(from.zoom, to.zoom),
+            (between(from.offset_x, to.offset_x), between(from.offset_y, to.offset_y)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    const PATH: &str = "
+        easing = \"linear\"
+
+        [[keyframes]]
+        time = 1.0
+        zoom = 0.0
+        offset_x = 0.0
+        offset_y = 0.0
+
+        [[keyframes]]
+        time = 3.0
+        zoom = -100.0
+        offset_x = 10.0
+        offset_y = -20.0
+    ";
+
+    #[test]
+    fn paths_are_sampled_between_keyframes() {
+        let path = CameraPath::parse_toml(PATH).unwrap();
+        assert_close(path.duration(), 3.0);
+        assert_eq!(path.sample(0.0), (0.0, (0.0, 0.0)));
+        assert_eq!(path.sample(5.0), (-100.0, (10.0, -20.0)));
+
+        let (zoom, (x, y)) = path.sample(2.5);
+        assert_close(zoom, -75.0);
+        assert_close(x, 7.5);
+        assert_close(y, -15.0);
+
+        let eased = CameraPath { easing: Easing::EaseInOut, ..path };
+        assert_close(eased.sample(2.0).0, -50.0);
+        assert!(eased.sample(1.5).0 > -25.0);
+    }
+
+    #[test]
+    fn easings_start_and_end_on_the_keyframes() {
+        for &easing in &[Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_close(easing.apply(0.0), 0.0);
+            assert_close(easing.apply(1.0), 1.0);
+            assert!(easing.apply(0.25) < easing.apply(0.75));
+        }
+    }
+
+    #[test]
+    fn paths_can_be_json_and_bad_ones_are_errors() {
+        let json = r#"{"easing": "ease-out", "keyframes": [{"time": 0, "zoom": 5, "offset_x": 1, "offset_y": 2}]}"#;
+        let path = CameraPath::parse_json(json).unwrap();
+        assert_eq!(path.easing, Easing::EaseOut);
+        assert_eq!(path.sample(10.0), (5.0, (1.0, 2.0)));
+
+        assert!(CameraPath::parse_toml("keyframes = []").is_err());
+        assert!(CameraPath::parse_toml("easing = \"bouncy\"\nkeyframes = []").is_err());
+        let backwards = PATH.replace("time = 3.0", "time = 0.5");
+        assert!(CameraPath::parse_toml(&backwards).unwrap_err().contains("keyframe 2"));
+    }
+}
@@ -0,0 +1,407 @@
+//! Kinds of primes that can be picked out in a color of their own, and the modes that pick them
+
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::particles::Particles;
+use crate::primality;
+
+/// A kind of prime drawn in a color of its own, see `Palette::class_source`. A prime can be of
+/// several, and it's drawn as the first of them in `ALL`, the closest pair.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Class {
+    /// A prime with another one 2 away from it, on either side
+    Twin,
+    /// 4 away
+    Cousin,
+    /// 6 away
+    Sexy,
+    /// A prime p with 2p + 1 prime too
+    SophieGermain,
+    /// The 2p + 1 of a Sophie Germain prime p
+    Safe,
+    /// A prime whose digits read the same both ways
+    Palindromic,
+}
+
+impl Class {
+    pub const COUNT: usize = 6;
+    pub const ALL: [Class; Self::COUNT] = [
+        Class::Twin,
+        Class::Cousin,
+        Class::Sexy,
+        Class::SophieGermain,
+        Class::Safe,
+        Class::Palindromic,
+    ];
+    /// The ones `Pairs` picks from
+    pub const PAIRS: [Class; 3] = [Class::Twin, Class::Cousin, Class::Sexy];
+
+    /// Where the class's colors are among each palette's
+    pub fn index(self) -> usize {
+        match self {
+            Class::Twin => { 0 }
+            Class::Cousin => { 1 }
+            Class::Sexy => { 2 }
+            Class::SophieGermain => { 3 }
+            Class::Safe => { 4 }
+            Class::Palindromic => { 5 }
+        }
+    }
+
+    /// How far the other prime of the pair is, for the classes of `PAIRS`
+    pub fn distance(self) -> Option<u64> {
+        match self {
+            Class::Twin => { Some(2) }
+            Class::Cousin => { Some(4) }
+            Class::Sexy => { Some(6) }
+            Class::SophieGermain | Class::Safe | Class::Palindromic => { None }
+        }
+    }
+
+    /// What `--highlight` takes for it
+    pub fn name(self) -> &'static str {
+        match self {
+            Class::Twin => { "twins" }
+            Class::Cousin => { "cousins" }
+            Class::Sexy => { "sexy" }
+            Class::SophieGermain => { "sophie-germain" }
+            Class::Safe => { "safe" }
+            Class::Palindromic => { "palindromic" }
+        }
+    }
+
+    fn adjective(self) -> &'static str {
+        match self {
+            Class::Twin => { "twin" }
+            Class::Cousin => { "cousin" }
+            Class::Sexy => { "sexy" }
+            Class::SophieGermain => { "Sophie Germain" }
+            Class::Safe => { "safe" }
+            Class::Palindromic => { "palindromic" }
+        }
+    }
+
+    /// What the class's color means, for the legend
+    pub fn meaning(self) -> String {
+        match self {
+            Class::SophieGermain => { String::from("Sophie Germain prime p") }
+            Class::Safe => { String::from("safe prime 2p + 1") }
+            _ => { format!("{} prime", self.adjective()) }
+        }
+    }
+}
+
+/// Which of the pairs of primes are picked out
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct Pairs {
+    pub twins: bool,
+    pub cousins: bool,
+    pub sexy: bool,
+}
+
+impl Pairs {
+    pub fn has(self, class: Class) -> bool {
+        match class {
+            Class::Twin => { self.twins }
+            Class::Cousin => { self.cousins }
+            Class::Sexy => { self.sexy }
+            Class::SophieGermain | Class::Safe | Class::Palindromic => { false }
+        }
+    }
+
+    /// Classes that aren't pairs leave it as it is
+    pub fn with(self, class: Class) -> Pairs {
+        match class {
+            Class::Twin => { Pairs { twins: true, ..self } }
+            Class::Cousin => { Pairs { cousins: true, ..self } }
+            Class::Sexy => { Pairs { sexy: true, ..self } }
+            Class::SophieGermain | Class::Safe | Class::Palindromic => { self }
+        }
+    }
+
+    /// The ones picked, closest pair first
+    pub fn classes(self) -> impl Iterator<Item = Class> {
+        Class::PAIRS.iter().cloned().filter(move |&class| self.has(class))
+    }
+}
+
+/// Which kinds of primes are drawn in a color of their own, the rest of the primes keeping theirs
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Classification {
+    None,
+    /// Primes with another one some way away from them, at least one kind of pair picked
+    Pairs(Pairs),
+    /// Sophie Germain primes, and the safe primes they make
+    SophieGermain,
+    /// Primes that are palindromes in the base, from 2 to 36
+    Palindromic(u32),
+}
+
+impl Default for Classification {
+    fn default() -> Classification {
+        Classification::None
+    }
+}
+
+impl Classification {
+    /// What Shift+D goes through: each kind of pair on its own, all of them, then the rest
+    pub const CYCLE: [Classification; 7] = [
+        Classification::None,
+        Classification::Pairs(Pairs { twins: true, cousins: false, sexy: false }),
+        Classification::Pairs(Pairs { twins: false, cousins: true, sexy: false }),
+        Classification::Pairs(Pairs { twins: false, cousins: false, sexy: true }),
+        Classification::Pairs(Pairs { twins: true, cousins: true, sexy: true }),
+        Classification::SophieGermain,
+        Classification::Palindromic(10),
+    ];
+
+    /// The bases `Palindromic` reads the digits in
+    pub const BASES: RangeInclusive<u32> = 2..=36;
+
+    /// Pairs not in `CYCLE` go back to the start of it
+    pub fn next(self) -> Classification {
+        let index = Self::CYCLE.iter().position(|&classification| classification == self);
+        Self::CYCLE[index.map_or(0, |index| (index + 1) % Self::CYCLE.len())]
+    }
+
+    /// What `--highlight` takes: `none`, `sophie-germain`, `palindromic`, or the names of the pairs
+    /// picked joined with commas. The base of a palindromic one is left to `--base`.
+    pub fn name(self) -> String {
+        match self {
+            Classification::None => { String::from("none") }
+            Classification::SophieGermain => { String::from(Class::SophieGermain.name()) }
+            Classification::Palindromic(_) => { String::from(Class::Palindromic.name()) }
+            Classification::Pairs(pairs) => {
+                let names: Vec<&str> = pairs.classes().map(Class::name).collect();
+                names.join(",")
+            }
+        }
+    }
+
+    /// The pairs can come in any order
+    pub fn from_name(name: &str) -> Option<Classification> {
+        if name.trim().eq_ignore_ascii_case("none") {
+            return Some(Classification::None);
+        }
+        if name.trim().eq_ignore_ascii_case(Class::SophieGermain.name()) {
+            return Some(Classification::SophieGermain);
+        }
+        if name.trim().eq_ignore_ascii_case(Class::Palindromic.name()) {
+            return Some(Classification::Palindromic(10));
+        }
+        let mut pairs = Pairs::default();
+        for part in name.split(',') {
+            let class = Class::PAIRS.iter().cloned().find(|class| class.name().eq_ignore_ascii_case(part.trim()))?;
+            pairs = pairs.with(class);
+        }
+        Some(Classification::Pairs(pairs))
+    }
+
+    /// What's picked out, for the window to show. Nothing when nothing is.
+    pub fn title(self) -> Option<String> {
+        if let Classification::Palindromic(base) = self {
+            if base != 10 {
+                return Some(format!("Palindromic primes in base {}", base));
+            }
+        }
+        let adjectives: Vec<&str> = self.classes().into_iter().map(Class::adjective).collect();
+        let listed = match adjectives.split_last() {
+            None => { return None; }
+            Some((last, [])) => { last.to_string() }
+            Some((last, rest)) => { format!("{} and {}", rest.join(", "), last) }
+        };
+        let mut title = format!("{} primes", listed);
+        title[..1].make_ascii_uppercase();
+        Some(title)
+    }
+
+    /// The classes it picks out, in the order a prime of several is colored by them
+    pub fn classes(self) -> Vec<Class> {
+        match self {
+            Classification::None => { Vec::new() }
+            Classification::Pairs(pairs) => { pairs.classes().collect() }
+            Classification::SophieGermain => { vec![Class::SophieGermain, Class::Safe] }
+            Classification::Palindromic(_) => { vec![Class::Palindromic] }
+        }
+    }
+
+    /// The class `number` is drawn in, if it's a prime this picks out. A prime's partners past
+    /// either end of the spiral are tested on their own, so the primes at the ends are classified
+    /// like the rest. For the upper half of the spiral that's every 2p + 1, found with Miller–Rabin
+    /// rather than a sieve twice as long.
+    pub fn class_of(self, particles: &Particles, number: u64) -> Option<Class> {
+        match self {
+            Classification::None => { None }
+            Classification::Pairs(pairs) => {
+                if !particles.is_prime(number) {
+                    return None;
+                }
+                pairs.classes().find(|class| {
+                    let distance = class.distance().unwrap_or(0);
+                    number.checked_sub(distance).map_or(false, |below| is_prime(particles, below))
+                        || number.checked_add(distance).map_or(false, |above| is_prime(particles, above))
+                })
+            }
+            Classification::SophieGermain => {
+                if !particles.is_prime(number) {
+                    None
+                } else if safe_prime_of(number).map_or(false, |safe| is_prime(particles, safe)) {
+                    Some(Class::SophieGermain)
+                } else if number > 2 && is_prime(particles, (number - 1) / 2) {
+                    Some(Class::Safe)
+                } else {
+                    None
+                }
+            }
+            Classification::Palindromic(base) => {
+                if particles.is_prime(number) && is_palindrome(number, base) { Some(Class::Palindromic) } else { None }
+            }
+        }
+    }
+
+    /// What the class's color means in the legend. A prime of several classes is drawn as the
+    /// first of them, so the later ones say which they aren't.
+    pub fn meaning(self, class: Class) -> String {
+        if let Classification::Palindromic(base) = self {
+            if base != 10 {
+                return format!("{} in base {}", class.meaning(), base);
+            }
+        }
+        let before: Vec<&str> = self.classes().into_iter()
+            .take_while(|&other| other != class)
+            .map(Class::adjective)
+            .collect();
+        match before.split_last() {
+            None => { class.meaning() }
+            Some((last, [])) => { format!("{}, not {}", class.meaning(), last) }
+            Some((last, rest)) => { format!("{}, not {} or {}", class.meaning(), rest.join(", "), last) }
+        }
+    }
+}
+
+/// 2p + 1, the safe prime `number` makes if it's a Sophie Germain prime
+pub fn safe_prime_of(number: u64) -> Option<u64> {
+    number.checked_mul(2)?.checked_add(1)
+}
+
+/// Whether `number`'s digits in `base` read the same both ways
+pub fn is_palindrome(number: u64, base: u32) -> bool {
+    let base = u64::from(base);
+    let mut digits = Vec::new();
+    let mut rest = number;
+    loop {
+        digits.push(rest % base);
+        rest /= base;
+        if rest == 0 {
+            break;
+        }
+    }
+    digits.iter().eq(digits.iter().rev())
+}
+
+// Looked up in the spiral when it has the number, worked out otherwise
+fn is_prime(particles: &Particles, number: u64) -> bool {
+    if number >= particles.start() && number <= particles.last_number() {
+        particles.is_prime(number)
+    } else {
+        primality::is_prime(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::primality::Primality;
+
+    fn picked(classification: &str, class: Class, particles: &Particles) -> Vec<u64> {
+        let classification = Classification::from_name(classification).unwrap();
+        (0..100).filter(|&number| classification.class_of(particles, number) == Some(class)).collect()
+    }
+
+    #[test]
+    fn pairs_are_the_primes_that_far_from_another_one() {
+        let particles = Particles::find(100);
+        assert_eq!(
+            picked("twins", Class::Twin, &particles),
+            vec![3, 5, 7, 11, 13, 17, 19, 29, 31, 41, 43, 59, 61, 71, 73],
+        );
+        assert_eq!(
+            picked("cousins", Class::Cousin, &particles),
+            vec![3, 7, 11, 13, 17, 19, 23, 37, 41, 43, 47, 67, 71, 79, 83, 97],
+        );
+        assert_eq!(&picked("sexy", Class::Sexy, &particles)[..6], &[5, 7, 11, 13, 17, 19]);
+        assert_eq!(Classification::None.class_of(&particles, 5), None);
+    }
+
+    #[test]
+    fn the_closest_pair_wins() {
+        let particles = Particles::find(100);
+        assert_eq!(picked("sexy,cousins,twins", Class::Twin, &particles), picked("twins", Class::Twin, &particles));
+        // The cousins that aren't twins, sexy as well or not
+        assert_eq!(&picked("twins,cousins,sexy", Class::Cousin, &particles)[..3], &[23, 37, 47]);
+        let all = Classification::from_name("twins,cousins,sexy").unwrap();
+        assert_eq!(all.class_of(&particles, 53), Some(Class::Sexy));
+        assert_eq!(all.meaning(Class::Twin), "twin prime");
+        assert_eq!(all.meaning(Class::Sexy), "sexy prime, not twin or cousin");
+    }
+
+    #[test]
+    fn pairs_past_the_ends_count() {
+        // 101 and 103 are twins with 103 past the end, 191 and 193 with 191 before the start
+        let twins = Classification::from_name("twins").unwrap();
+        let particles = Particles::find_between(0, 102, Primality::Sieve);
+        assert_eq!(twins.class_of(&particles, 101), Some(Class::Twin));
+        let particles = Particles::find_between(192, 256, Primality::Sieve);
+        assert_eq!(twins.class_of(&particles, 193), Some(Class::Twin));
+        assert_eq!(twins.class_of(&particles, 211), None);
+    }
+
+    #[test]
+    fn sophie_germain_primes_come_before_the_safe_primes_they_make() {
+        // 83's safe prime 167 is past the end of the spiral
+        let particles = Particles::find(100);
+        let sophie_germain = picked("sophie-germain", Class::SophieGermain, &particles);
+        assert_eq!(sophie_germain, vec![2, 3, 5, 11, 23, 29, 41, 53, 83, 89]);
+        assert_eq!(picked("sophie-germain", Class::Safe, &particles), vec![7, 47, 59]);
+        assert_eq!(safe_prime_of(u64::max_value()), None);
+        assert_eq!(
+            Classification::SophieGermain.meaning(Class::Safe),
+            "safe prime 2p + 1, not Sophie Germain",
+        );
+    }
+
+    #[test]
+    fn palindromes_read_the_same_both_ways() {
+        let particles = Particles::find(1_000);
+        assert_eq!(picked("palindromic", Class::Palindromic, &particles), vec![2, 3, 5, 7, 11]);
+        assert_eq!(Classification::Palindromic(10).class_of(&particles, 929), Some(Class::Palindromic));
+        assert_eq!(Classification::Palindromic(10).class_of(&particles, 121), None, "121 is 11²");
+        // 5 is 101 and 7 is 111 in binary, 11 is 1011
+        let binary = Classification::Palindromic(2);
+        assert_eq!(binary.class_of(&particles, 7), Some(Class::Palindromic));
+        assert_eq!(binary.class_of(&particles, 11), None);
+        assert_eq!(binary.title(), Some(String::from("Palindromic primes in base 2")));
+        assert!(is_palindrome(0, 10));
+        assert!(is_palindrome(u64::max_value(), 2));
+    }
+
+    #[test]
+    fn classifications_go_by_their_names() {
+        for &classification in Classification::CYCLE.iter() {
+            assert_eq!(Classification::from_name(&classification.name()), Some(classification));
+        }
+        assert_eq!(Classification::CYCLE[6].next(), Classification::None);
+        assert_eq!(Classification::from_name("sophie-germain,twins"), None);
+        let sexy_twins = Classification::from_name("Sexy, twins").map(Classification::name);
+        assert_eq!(sexy_twins, Some(String::from("twins,sexy")));
+        assert_eq!(Classification::from_name("twins,"), None);
+        assert_eq!(
+            Classification::from_name("cousins,twins").and_then(Classification::title),
+            Some(String::from("Twin and cousin primes")),
+        );
+        assert_eq!(Classification::SophieGermain.title(), Some(String::from("Sophie Germain and safe primes")));
+    }
+}
@@ -0,0 +1,265 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use polar_oxides::classes::Classification;
+use polar_oxides::overlays::{self, Progression, Quadratic};
+use polar_oxides::palette::{self, Palette, Theme};
+use polar_oxides::predicate::Predicate;
+use polar_oxides::view::{Configs, DisplayMode};
+
+use crate::data_export::Format;
+
+// Something typed on the command line, doing what a key or an argument would
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Max(u64),
+    Zoom(f32),
+    GoTo(u64),
+    Search(u64),
+    Palette(usize),
+    Mode(DisplayMode),
+    Highlight(Classification),
+    // Coloring by the residues mod the modulus, or not
+    Residues(Option<u64>),
+    Theme(Theme),
+    Angle(f32),
+    // The sides of the polygonal numbers marked, none for off
+    Polygonal(Vec<u32>),
+    // The quadratic whose values are marked, or none
+    Poly(Option<Quadratic>),
+    // The arithmetic progressions marked, none for off
+    Progressions(Vec<Progression>),
+    // The predicate picking out the numbers marked, or none
+    Predicate(Option<Predicate>),
+    // Named after the time like the X key's files, when there's no file name
+    Export(Format, Option<PathBuf>),
+}
+
+// Every command's name and what it takes, in order for the error line and for completing them
+const COMMANDS: [(&str, &str); 15] = [
+    ("angle", "angle <factor>"),
+    ("ap", "ap <a,m> [<a,m>...]|off"),
+    ("export", "export csv|json [file]"),
+    ("expr", "expr <predicate>|off"),
+    ("goto", "goto <number>"),
+    ("highlight", "highlight none|sophie-germain|palindromic [base]|twins|cousins|sexy[,...]"),
+    ("max", "max <number>"),
+    ("mod", "mod <modulus>|off"),
+    ("mode", "mode all|primes|composites"),
+    ("palette", "palette <name>"),
+    ("poly", "poly <a,b,c>|euler|off"),
+    ("polygonal", "polygonal <sides>[,...]|off"),
+    ("search", "search <number>"),
+    ("theme", "theme dark|light"),
+    ("zoom", "zoom <level>"),
+];
+
+impl Command {
+    // The error is what the error line shows. `palette custom` only works with `has_custom`.
+    pub fn parse(line: &str, has_custom: bool) -> Result<Command, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (&name, arguments) = words.split_first()
+            .ok_or_else(|| format!("type one of {}, Tab finishes the name", names()))?;
+        let usage = COMMANDS.iter()
+            .find(|&&(command, _)| command == name)
+            .map(|&(_, usage)| usage)
+            .ok_or_else(|| format!("there's no `{}` command, only {}", name, names()))?;
+
+        let command = match (name, arguments) {
+            ("max", [number]) => { Command::Max(parse_number(number)?) }
+            ("zoom", [level]) => { Command::Zoom(parse_float(level)?) }
+            ("goto", [number]) => { Command::GoTo(parse_number(number)?) }
+            ("search", [number]) => { Command::Search(parse_number(number)?) }
+            ("angle", [factor]) => { Command::Angle(parse_float(factor)?) }
+            ("palette", [name]) if has_custom && name.eq_ignore_ascii_case("custom") => {
+                Command::Palette(Palette::CUSTOM)
+            }
+            ("palette", [name]) => {
+                Command::Palette(Palette::find(name).ok_or_else(|| format!("no palette is called `{}`", name))?)
+            }
+            ("mode", [mode]) => {
+                match *mode {
+                    "all" => { Command::Mode(DisplayMode::All) }
+                    "primes" => { Command::Mode(DisplayMode::PrimesOnly) }
+                    "composites" => { Command::Mode(DisplayMode::CompositesOnly) }
+                    _ => { return Err(format!("usage: {}", usage)); }
+                }
+            }
+            ("highlight", [name]) => {
+                Command::Highlight(Classification::from_name(name).ok_or_else(|| format!("usage: {}", usage))?)
+            }
+            ("highlight", [name, base]) if name.eq_ignore_ascii_case("palindromic") => {
+                let base = parse_number(base)?;
+                if !Classification::BASES.contains(&base) {
+                    return Err(format!("the base has to be between 2 and 36, not {}", base));
+                }
+                Command::Highlight(Classification::Palindromic(base))
+            }
+            ("polygonal", [off]) if off.eq_ignore_ascii_case("off") => { Command::Polygonal(Vec::new()) }
+            ("polygonal", [sides]) => { Command::Polygonal(overlays::parse_sides(sides)?) }
+            ("ap", [off]) if off.eq_ignore_ascii_case("off") => { Command::Progressions(Vec::new()) }
+            ("ap", progressions) if !progressions.is_empty() => {
+                Command::Progressions(overlays::parse_progressions(&progressions.join(" "))?)
+            }
+            ("expr", [off]) if off.eq_ignore_ascii_case("off") => { Command::Predicate(None) }
+            // The predicate can have spaces in it, so it's the rest of the line
+            ("expr", _) => {
+                let predicate = line.trim_start()[name.len()..].trim();
+                Command::Predicate(Some(Predicate::parse(predicate).map_err(|error| error.to_string())?))
+            }
+            ("poly", [off]) if off.eq_ignore_ascii_case("off") => { Command::Poly(None) }
+            ("poly", [quadratic]) => { Command::Poly(Some(Quadratic::parse(quadratic)?)) }
+            ("mod", [off]) if off.eq_ignore_ascii_case("off") => { Command::Residues(None) }
+            ("mod", [modulus]) => {
+                let modulus = parse_number(modulus)?;
+                if !Configs::is_modulus(modulus) {
+                    let max = palette::MAX_MODULUS;
+                    return Err(format!("the modulus has to be between 2 and {}, not {}", max, modulus));
+                }
+                Command::Residues(Some(modulus))
+            }
+            ("theme", [theme]) => {
+                match *theme {
+                    "dark" => { Command::Theme(Theme::Dark) }
+                    "light" => { Command::Theme(Theme::Light) }
+                    _ => { return Err(format!("usage: {}", usage)); }
+                }
+            }
+            ("export", [format]) => { Command::Export(parse_format(format, usage)?, None) }
+            ("export", [format, path]) => { Command::Export(parse_format(format, usage)?, Some(PathBuf::from(path))) }
+            _ => { return Err(format!("usage: {}", usage)); }
+        };
+        Ok(command)
+    }
+}
+
+// `typed` with the command's name finished, as far as the names starting with it agree, and a space
+// after it once it's a whole one. None when there's nothing to add.
+pub fn complete(typed: &str) -> Option<String> {
+    let start = typed.trim_start();
+    if start.contains(char::is_whitespace) {
+        return None;
+    }
+    let matches: Vec<&str> = COMMANDS.iter().map(|&(name, _)| name).filter(|name| name.starts_with(start)).collect();
+    let first = *matches.first()?;
+    let completed = if matches.len() == 1 {
+        format!("{} ", first)
+    } else {
+        // Names are ASCII, so counting characters counts bytes
+        let common = matches.iter().fold(first.len(), |length, name| {
+            first.chars().zip(name.chars()).take(length).take_while(|(a, b)| a == b).count()
+        });
+        String::from(&first[..common])
+    };
+    if completed == start { None } else { Some(completed) }
+}
+
+fn names() -> String {
+    let names: Vec<&str> = COMMANDS.iter().map(|&(name, _)| name).collect();
+    names.join(", ")
+}
+
+// Big numbers can be typed with commas or underscores between the thousands
+fn parse_number<T: FromStr>(text: &str) -> Result<T, String> {
+    text.replace(|c| c == ',' || c == '_', "").parse().map_err(|_| format!("`{}` isn't a number", text))
+}
+
+fn parse_float(text: &str) -> Result<f32, String> {
+    parse_number(text).and_then(|float: f32| {
+        if float.is_finite() { Ok(float) } else { Err(format!("`{}` isn't a number", text)) }
+    })
+}
+
+fn parse_format(format: &str, usage: &str) -> Result<Format, String> {
+    match format {
+        "csv" => { Ok(Format::Csv) }
+        "json" => { Ok(Format::Json { pretty: false }) }
+        _ => { Err(format!("usage: {}", usage)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Command, String> {
+        Command::parse(line, false)
+    }
+
+    #[test]
+    fn parses_each_command() {
+        assert_eq!(parse("max 2,000,000"), Ok(Command::Max(2_000_000)));
+        assert_eq!(parse("  zoom   -3.5 "), Ok(Command::Zoom(-3.5)));
+        assert_eq!(parse("goto 1_000"), Ok(Command::GoTo(1000)));
+        assert_eq!(parse("search 97"), Ok(Command::Search(97)));
+        assert_eq!(parse("angle 0.5"), Ok(Command::Angle(0.5)));
+        assert_eq!(parse("palette viridis"), Ok(Command::Palette(Palette::find("viridis").unwrap())));
+        assert_eq!(Command::parse("palette Custom", true), Ok(Command::Palette(Palette::CUSTOM)));
+        assert_eq!(parse("mode primes"), Ok(Command::Mode(DisplayMode::PrimesOnly)));
+        let pairs = Classification::from_name("twins,sexy").unwrap();
+        assert_eq!(parse("highlight twins,sexy"), Ok(Command::Highlight(pairs)));
+        assert_eq!(parse("highlight palindromic 2"), Ok(Command::Highlight(Classification::Palindromic(2))));
+        assert_eq!(parse("mod 7"), Ok(Command::Residues(Some(7))));
+        assert_eq!(parse("mod off"), Ok(Command::Residues(None)));
+        assert_eq!(parse("theme light"), Ok(Command::Theme(Theme::Light)));
+        assert_eq!(parse("polygonal 3,5"), Ok(Command::Polygonal(vec![3, 5])));
+        assert_eq!(parse("polygonal OFF"), Ok(Command::Polygonal(Vec::new())));
+        let path = Some(PathBuf::from("spiral.json"));
+        assert_eq!(parse("export json spiral.json"), Ok(Command::Export(Format::Json { pretty: false }, path)));
+        assert_eq!(parse("export csv"), Ok(Command::Export(Format::Csv, None)));
+    }
+
+    #[test]
+    fn parses_poly_ap_and_expr() {
+        assert_eq!(parse("poly euler"), Ok(Command::Poly(Some(Quadratic::EULER))));
+        assert_eq!(parse("poly 1,-79,1601"), Ok(Command::Poly(Some(Quadratic { a: 1, b: -79, c: 1601 }))));
+        assert_eq!(parse("poly off"), Ok(Command::Poly(None)));
+        assert!(parse("poly 0,1,1").is_err());
+
+        let progressions = vec![Progression { residue: 3, modulus: 10 }, Progression { residue: 7, modulus: 10 }];
+        assert_eq!(parse("ap 3,10 17,10 13,10"), Ok(Command::Progressions(progressions)));
+        assert_eq!(parse("ap off"), Ok(Command::Progressions(Vec::new())));
+        assert!(parse("ap 3,1").is_err());
+
+        // The rest of the line is the predicate, spaces and all
+        let predicate = Predicate::parse("n % 4 == 1 && isprime(n)").unwrap();
+        assert_eq!(parse("expr  n % 4 == 1 && isprime(n) "), Ok(Command::Predicate(Some(predicate))));
+        assert_eq!(parse("expr off"), Ok(Command::Predicate(None)));
+        assert!(parse("expr n %").is_err());
+        assert!(parse("expr").is_err());
+    }
+
+    #[test]
+    fn wrong_arguments_get_the_usage() {
+        let usage = |name: &str| COMMANDS.iter().find(|&&(command, _)| command == name).unwrap().1;
+        for &line in &["max", "max 1 2", "mode some", "theme blue", "highlight nothing", "export xml", "ap", "poly"] {
+            let name = line.split_whitespace().next().unwrap();
+            assert_eq!(parse(line), Err(format!("usage: {}", usage(name))), "{}", line);
+        }
+        assert_eq!(parse("max lots"), Err(String::from("`lots` isn't a number")));
+        assert_eq!(parse("zoom inf"), Err(String::from("`inf` isn't a number")));
+        assert_eq!(parse("palette custom"), Err(String::from("no palette is called `custom`")));
+        let max = palette::MAX_MODULUS;
+        assert_eq!(parse("mod 1"), Err(format!("the modulus has to be between 2 and {}, not 1", max)));
+        assert_eq!(parse("highlight palindromic 37"), Err(String::from("the base has to be between 2 and 36, not 37")));
+        assert!(parse("").unwrap_err().starts_with("type one of angle, ap, export"));
+        assert!(parse("jump 5").unwrap_err().starts_with("there's no `jump` command, only angle"));
+    }
+
+    #[test]
+    fn completes_the_names_as_far_as_they_agree() {
+        assert_eq!(complete("z"), Some(String::from("zoom ")));
+        assert_eq!(complete("  the"), Some(String::from("theme ")));
+        assert_eq!(complete("ex"), Some(String::from("exp")));
+        assert_eq!(complete("expr"), Some(String::from("expr ")));
+        assert_eq!(complete("pol"), Some(String::from("poly")));
+        assert_eq!(complete("poly"), None);
+        assert_eq!(complete("polyg"), Some(String::from("polygonal ")));
+        assert_eq!(complete("a"), None);
+        assert_eq!(complete("ap"), Some(String::from("ap ")));
+        assert_eq!(complete("mo"), Some(String::from("mod")));
+        assert_eq!(complete(""), None);
+        assert_eq!(complete("q"), None);
+        assert_eq!(complete("max 1"), None);
+    }
+}
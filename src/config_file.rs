@@ -0,0 +1,254 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use polar_oxides::palette::Palette;
+use polar_oxides::view::DisplayMode;
+
+use crate::args::{self, Args};
+use crate::bindings::Bindings;
+
+const FILE_NAME: &str = "polar-oxides.toml";
+const DISPLAY_MODES: [(&str, DisplayMode); 3] = [
+    ("all", DisplayMode::All),
+    ("primes_only", DisplayMode::PrimesOnly),
+    ("composites_only", DisplayMode::CompositesOnly),
+];
+
+// What polar-oxides.toml can hold, everything optional
+#[derive(Clone, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Contents {
+    max_number: Option<u64>,
+    window_size: Option<String>,
+    palette: Option<String>,
+    zoom_base: Option<f32>,
+    zoom_step: Option<f32>,
+    display_mode: Option<String>,
+    #[serde(default)]
+    keybindings: toml::value::Table,
+}
+
+// Defaults for the command line, from polar-oxides.toml in the working directory or else in the
+// platform's config directory (~/.config/polar-oxides on Linux). The command line goes on top.
+#[derive(Clone)]
+pub struct ConfigFile {
+    path: PathBuf,
+    text: String,
+    contents: Contents,
+}
+
+impl ConfigFile {
+    // `None` when there's no file in either place
+    pub fn load() -> Result<Option<ConfigFile>, String> {
+        let mut paths = Vec::new();
+        if let Ok(working_dir) = env::current_dir() {
+            paths.push(working_dir.join(FILE_NAME));
+        }
+        if let Some(config_dir) = dirs::config_dir() {
+            paths.push(config_dir.join("polar-oxides").join(FILE_NAME));
+        }
+
+        for path in paths {
+            if let Some(config_file) = Self::read(path)? {
+                return Ok(Some(config_file));
+            }
+        }
+        Ok(None)
+    }
+
+    // `None` when it isn't there
+    fn read(path: PathBuf) -> Result<Option<ConfigFile>, String> {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => { text }
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => { return Ok(None); }
+            Err(error) => { return Err(format!("{}: {}", path.display(), error)); }
+        };
+        // toml points at the line of anything that doesn't parse or has the wrong type
+        let contents = toml::from_str(&text).map_err(|error| format!("{}: {}", path.display(), error))?;
+        Ok(Some(ConfigFile { path, text, contents }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // The same file again, for when it was edited. `None` if it's gone.
+    pub fn reread(&self) -> Result<Option<ConfigFile>, String> {
+        Self::read(self.path.clone())
+    }
+
+    // What changed since `older`, checked the same way as when starting. Only what changed is
+    // taken, so whatever the command line put on top of the rest stays.
+    pub fn changes_since(&self, older: &ConfigFile) -> Result<Reload, String> {
+        let (mut before, mut after) = (Args::default(), Args::default());
+        older.apply(&mut before)?;
+        self.apply(&mut after)?;
+
+        let mut ignored = Vec::new();
+        if after.particles.max_number != before.particles.max_number {
+            ignored.push("max_number");
+        }
+        if after.window_size != before.window_size {
+            ignored.push("window_size");
+        }
+        Ok(Reload {
+            palette: changed(before.configs.palette, after.configs.palette),
+            zoom_base: changed(before.settings.zoom_base, after.settings.zoom_base),
+            zoom_step: changed(before.settings.zoom_step, after.settings.zoom_step),
+            display_mode: changed(before.configs.display_mode, after.configs.display_mode),
+            keybindings: changed(before.keybindings, after.keybindings),
+            ignored,
+        })
+    }
+
+    // Sets whatever the file has on `args`
+    pub fn apply(&self, args: &mut Args) -> Result<(), String> {
+        let contents = &self.contents;
+        if let Some(max_number) = contents.max_number {
+            args.particles.max_number = max_number;
+        }
+
+        if let Some(window_size) = &contents.window_size {
+            let message = || format!("`window_size` has to look like \"1280x800\", got \"{}\"", window_size);
+            args.window_size = args::parse_size(window_size).ok_or_else(|| self.error("window_size", message()))?;
+        }
+
+        if let Some(name) = &contents.palette {
+            args.configs.palette = Palette::find(name)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = Palette::ALL.iter().map(|palette| palette.name).collect();
+                    let message = format!("`palette` has to be one of \"{}\", got \"{}\"", names.join("\", \""), name);
+                    self.error("palette", message)
+                })?;
+        }
+
+        if let Some(zoom_base) = contents.zoom_base {
+            args.settings = args.settings.with_zoom_base(zoom_base)
+                .map_err(|error| self.error("zoom_base", format!("`zoom_base` {}", error)))?;
+        }
+
+        if let Some(zoom_step) = contents.zoom_step {
+            args.settings = args.settings.with_zoom_step(zoom_step)
+                .map_err(|error| self.error("zoom_step", format!("`zoom_step` {}", error)))?;
+        }
+
+        if let Some(name) = &contents.display_mode {
+            args.configs.display_mode = DISPLAY_MODES.iter()
+                .find(|&&(mode_name, _)| mode_name == name)
+                .map(|&(_, display_mode)| display_mode)
+                .ok_or_else(|| {
+                    let message = format!("`display_mode` has to be \"all\", \"primes_only\" or \"composites_only\", got \"{}\"", name);
+                    self.error("display_mode", message)
+                })?;
+        }
+
+        // Checked here so it's an error like the rest, they are bound once the game loads
+        let mut bindings = Bindings::default();
+        for (name, value) in &contents.keybindings {
+            bindings.bind(name, value).map_err(|error| self.error(name, error))?;
+        }
+        args.keybindings = contents.keybindings.clone();
+        Ok(())
+    }
+
+    // Where the file is and the line `key` is on, which toml only knows about while parsing
+    fn error(&self, key: &str, message: String) -> String {
+        let line = self.text.lines().position(|line| {
+            let line = line.trim_start();
+            line.starts_with(key) && line[key.len()..].trim_start().starts_with('=')
+        });
+        match line {
+            Some(line) => { format!("{}: {} at line {}", self.path.display(), message, line + 1) }
+            None => { format!("{}: {}", self.path.display(), message) }
+        }
+    }
+}
+
+// What an edit to the file changes while running, `None` for anything that stayed the same
+pub struct Reload {
+    pub palette: Option<usize>,
+    pub zoom_base: Option<f32>,
+    pub zoom_step: Option<f32>,
+    pub display_mode: Option<DisplayMode>,
+    pub keybindings: Option<toml::value::Table>,
+    // The keys that changed but only take effect on the next run, like how many numbers there are
+    pub ignored: Vec<&'static str>,
+}
+
+fn changed<T: PartialEq>(before: T, after: T) -> Option<T> {
+    if after != before { Some(after) } else { None }
+}
+
+// Everything the file can hold, as the command line and the file worked out, for starting a file from
+pub fn dump(args: &Args, bindings: &Bindings) -> String {
+    // Through their shortest text, f32s widened to f64 pick up digits that were never there
+    let float = |value: f32| toml::Value::Float(value.to_string().parse().unwrap_or_else(|_| f64::from(value)));
+    let (width, height) = args.window_size;
+    let display_mode = DISPLAY_MODES.iter()
+        .find(|&&(_, display_mode)| display_mode == args.configs.display_mode)
+        .map_or("all", |&(name, _)| name);
+
+    let mut table = toml::value::Table::new();
+    table.insert(String::from("max_number"), toml::Value::Integer(args.particles.max_number as i64));
+    table.insert(String::from("window_size"), toml::Value::String(format!("{}x{}", width, height)));
+    // A custom palette comes from --palette every time
+    if let Some(palette) = Palette::ALL.get(args.configs.palette) {
+        table.insert(String::from("palette"), toml::Value::String(String::from(palette.name)));
+    }
+    table.insert(String::from("zoom_base"), float(args.settings.zoom_base));
+    table.insert(String::from("zoom_step"), float(args.settings.zoom_step));
+    table.insert(String::from("display_mode"), toml::Value::String(String::from(display_mode)));
+    table.insert(String::from("keybindings"), toml::Value::Table(bindings.to_table()));
+    toml::to_string(&toml::Value::Table(table)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(text: &str) -> ConfigFile {
+        let contents = toml::from_str(text).expect(text);
+        ConfigFile { path: PathBuf::from(FILE_NAME), text: text.to_string(), contents }
+    }
+
+    #[test]
+    fn reloads_only_what_changed() {
+        let older = file("palette = \"classic\"\nzoom_base = 2.0\n[keybindings]\nquit = \"Q\"\n");
+        let unchanged = file("zoom_base = 2.0\npalette = \"Classic\"\n\n[keybindings]\nquit = [\"Q\"]\n");
+        let reload = unchanged.changes_since(&older).unwrap();
+        assert_eq!(reload.palette, None);
+        assert_eq!(reload.zoom_base, None);
+        assert!(reload.keybindings.is_some(), "the table itself changed, from a key to a list");
+        assert!(reload.ignored.is_empty());
+
+        let newer = file("palette = \"viridis\"\nzoom_base = 2.0\nzoom_step = 0.5\ndisplay_mode = \"primes_only\"\n");
+        let reload = newer.changes_since(&older).unwrap();
+        assert_eq!(reload.palette, Palette::find("viridis"));
+        assert_eq!(reload.zoom_base, None);
+        assert_eq!(reload.zoom_step, Some(0.5));
+        assert_eq!(reload.display_mode, Some(DisplayMode::PrimesOnly));
+        assert_eq!(reload.keybindings, Some(toml::value::Table::new()));
+    }
+
+    #[test]
+    fn keys_for_the_next_run_are_ignored() {
+        let older = file("max_number = 1000\n");
+        let newer = file("max_number = 2000\nwindow_size = \"640x480\"\n");
+        let reload = newer.changes_since(&older).unwrap();
+        assert_eq!(reload.ignored, vec!["max_number", "window_size"]);
+        assert_eq!(reload.palette, None);
+    }
+
+    #[test]
+    fn a_bad_edit_is_an_error_at_its_line() {
+        let older = file("zoom_base = 2.0\n");
+        let newer = file("zoom_base = 2.0\ndisplay_mode = \"some\"\n");
+        let error = newer.changes_since(&older).err().unwrap();
+        assert!(error.ends_with("at line 2"), "{}", error);
+        assert!(file("[keybindings]\nquit = \"NoSuchKey\"\n").changes_since(&older).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+
+use coffee::graphics::Point;
+use coffee::input::{gamepad, keyboard, mouse, ButtonState, Event, Input};
+
+use crate::recording::Session;
+
+// KeyboardAndMouse throws away everything we need from the mouse besides clicks,
+// so we keep track of the input state ourselves
+pub struct Controls {
+    cursor_position: Point,
+    cursor_moved: bool,
+    wheel_movement: f32,
+    pressed_buttons: HashSet<mouse::Button>,
+    press_position: Point,
+    left_clicks: Vec<Point>,
+    pressed_keys: HashSet<keyboard::KeyCode>,
+    released_keys: HashSet<keyboard::KeyCode>,
+    typed_characters: Vec<char>,
+    gamepad_axes: HashMap<gamepad::Axis, f32>,
+    gamepad_buttons: HashMap<gamepad::Button, f32>,
+    released_gamepad_buttons: HashSet<gamepad::Button>,
+    session: Session,
+}
+
+impl Controls {
+    // How far the cursor can wander between press and release for it to still count as a click
+    const CLICK_TOLERANCE: f32 = 3.0;
+
+    pub fn cursor_position(&self) -> Point {
+        self.cursor_position
+    }
+
+    pub fn wheel_movement(&self) -> f32 {
+        self.wheel_movement
+    }
+
+    pub fn left_clicks(&self) -> &[Point] {
+        &self.left_clicks
+    }
+
+    pub fn is_mouse_pressed(&self, button: mouse::Button) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    pub fn is_key_pressed(&self, key_code: keyboard::KeyCode) -> bool {
+        self.pressed_keys.contains(&key_code)
+    }
+
+    pub fn was_key_released(&self, key_code: keyboard::KeyCode) -> bool {
+        self.released_keys.contains(&key_code)
+    }
+
+    pub fn is_shift_pressed(&self) -> bool {
+        self.is_key_pressed(keyboard::KeyCode::LShift) || self.is_key_pressed(keyboard::KeyCode::RShift)
+    }
+
+    pub fn is_control_pressed(&self) -> bool {
+        self.is_key_pressed(keyboard::KeyCode::LControl) || self.is_key_pressed(keyboard::KeyCode::RControl)
+    }
+
+    pub fn is_alt_pressed(&self) -> bool {
+        self.is_key_pressed(keyboard::KeyCode::LAlt) || self.is_key_pressed(keyboard::KeyCode::RAlt)
+    }
+
+    pub fn typed_characters(&self) -> &[char] {
+        &self.typed_characters
+    }
+
+    // Whether nothing happened since the last clear besides the given keys being held or released.
+    // Sticks are left out, they are never quite at rest.
+    pub fn is_idle_except(&self, keys: &[keyboard::KeyCode]) -> bool {
+        !self.cursor_moved
+            && self.wheel_movement == 0.0
+            && self.pressed_buttons.is_empty()
+            && self.left_clicks.is_empty()
+            && self.typed_characters.is_empty()
+            && self.released_gamepad_buttons.is_empty()
+            && self.pressed_keys.iter().chain(self.released_keys.iter()).all(|key| keys.contains(key))
+    }
+
+    // Keys, buttons and the wheel, but not the cursor just moving around
+    pub fn is_pressing_anything(&self) -> bool {
+        self.is_pressing_anything_except(&[])
+    }
+
+    // The same, with the given keys not counting
+    pub fn is_pressing_anything_except(&self, keys: &[keyboard::KeyCode]) -> bool {
+        self.pressed_keys.iter().chain(self.released_keys.iter()).any(|key| !keys.contains(key))
+            || !self.pressed_buttons.is_empty()
+            || !self.left_clicks.is_empty()
+            || self.wheel_movement != 0.0
+    }
+
+    // Sticks go from -1.0 to 1.0, with up and right being positive
+    pub fn gamepad_axis(&self, axis: gamepad::Axis) -> f32 {
+        self.gamepad_axes.get(&axis).cloned().unwrap_or(0.0)
+    }
+
+    // Analog buttons, like the triggers, go from 0.0 to 1.0
+    pub fn gamepad_button(&self, button: gamepad::Button) -> f32 {
+        self.gamepad_buttons.get(&button).cloned().unwrap_or(0.0)
+    }
+
+    pub fn was_gamepad_button_released(&self, button: gamepad::Button) -> bool {
+        self.released_gamepad_buttons.contains(&button)
+    }
+
+    // Lets the recording or the replay know we got to `interaction`, has to be called before reading
+    // the input
+    pub fn sync(&mut self, interaction: u64) {
+        for event in self.session.sync(interaction) {
+            self.handle(event);
+        }
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Mouse(mouse::Event::CursorMoved { x, y }) => {
+                self.cursor_position = Point::new(x, y);
+                self.cursor_moved = true;
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta_y, .. }) => {
+                self.wheel_movement += delta_y;
+            }
+            Event::Mouse(mouse::Event::Input { button, state }) => {
+                match state {
+                    ButtonState::Pressed => {
+                        self.pressed_buttons.insert(button);
+                        if button == mouse::Button::Left {
+                            self.press_position = self.cursor_position;
+                        }
+                    }
+                    ButtonState::Released => {
+                        // Releasing far from where the button went down was a drag, not a click
+                        let was_pressed = self.pressed_buttons.remove(&button);
+                        let distance = (self.cursor_position - self.press_position).norm();
+                        if button == mouse::Button::Left && was_pressed && distance <= Self::CLICK_TOLERANCE {
+                            self.left_clicks.push(self.cursor_position);
+                        }
+                    }
+                }
+            }
+            Event::Keyboard(keyboard::Event::Input { key_code, state }) => {
+                match state {
+                    ButtonState::Pressed => {
+                        self.pressed_keys.insert(key_code);
+                    }
+                    ButtonState::Released => {
+                        self.pressed_keys.remove(&key_code);
+                        self.released_keys.insert(key_code);
+                    }
+                }
+            }
+            Event::Keyboard(keyboard::Event::TextEntered { character }) => {
+                self.typed_characters.push(character);
+            }
+            Event::Gamepad { event, .. } => {
+                match event {
+                    gamepad::Event::AxisChanged(axis, value) => {
+                        self.gamepad_axes.insert(axis, value);
+                    }
+                    gamepad::Event::ButtonChanged(button, value) => {
+                        self.gamepad_buttons.insert(button, value);
+                    }
+                    gamepad::Event::ButtonPressed(button) => {
+                        self.gamepad_buttons.entry(button).or_insert(1.0);
+                    }
+                    gamepad::Event::ButtonReleased(button) => {
+                        self.gamepad_buttons.remove(&button);
+                        self.released_gamepad_buttons.insert(button);
+                    }
+                    gamepad::Event::Connected | gamepad::Event::Disconnected => {
+                        self.gamepad_axes.clear();
+                        self.gamepad_buttons.clear();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Input for Controls {
+    fn new() -> Controls {
+        Controls {
+            cursor_position: Point::new(0.0, 0.0),
+            cursor_moved: false,
+            wheel_movement: 0.0,
+            pressed_buttons: HashSet::new(),
+            press_position: Point::new(0.0, 0.0),
+            left_clicks: Vec::new(),
+            pressed_keys: HashSet::new(),
+            released_keys: HashSet::new(),
+            typed_characters: Vec::new(),
+            gamepad_axes: HashMap::new(),
+            gamepad_buttons: HashMap::new(),
+            released_gamepad_buttons: HashSet::new(),
+            session: Session::take_over(),
+        }
+    }
+
+    fn update(&mut self, event: Event) {
+        if self.session.receive(&event) {
+            self.handle(event);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.cursor_moved = false;
+        self.wheel_movement = 0.0;
+        self.left_clicks.clear();
+        self.released_keys.clear();
+        self.typed_characters.clear();
+        self.released_gamepad_buttons.clear();
+    }
+}
@@ -0,0 +1,196 @@
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Local;
+use serde::{Serialize, Serializer};
+
+use polar_oxides::particles::Particles;
+use polar_oxides::view::{Configs, DisplayMode, SavedView, Settings};
+
+use crate::screenshot;
+use crate::sprites;
+
+// A row per number, with where it sits in the spiral
+const CSV_HEADER: &str = "n,x,y,is_prime";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Csv,
+    // The rows wrapped in an object along with `Metadata`, indented when pretty
+    Json { pretty: bool },
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => { "csv" }
+            Format::Json { .. } => { "json" }
+        }
+    }
+}
+
+// What the numbers were drawn with, so the same picture can be made again. JSON only.
+#[derive(Serialize)]
+pub struct Metadata {
+    version: &'static str,
+    max_number: u64,
+    min_number: u64,
+    radius_base: u64,
+    angle_factor: f32,
+    palette: &'static str,
+    zoom_level: f32,
+    display_mode: DisplayMode,
+    // What `--view` takes to open the same view
+    view: String,
+    timestamp: String,
+    // How long finding the primes, or reading them from the cache, took
+    generation_seconds: f32,
+}
+
+impl Metadata {
+    pub fn new(max_number: u64, radius_base: u64, configs: Configs, generation_time: Duration) -> Metadata {
+        Metadata {
+            version: env!("CARGO_PKG_VERSION"),
+            max_number,
+            min_number: configs.min_number,
+            radius_base,
+            angle_factor: configs.angle_factor,
+            palette: configs.palette_name(),
+            zoom_level: configs.zoom_level,
+            display_mode: configs.display_mode,
+            view: SavedView { max_number, configs }.encode(),
+            timestamp: Local::now().to_rfc3339(),
+            generation_seconds: generation_time.as_secs_f32(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Row {
+    n: u64,
+    x: f32,
+    y: f32,
+    is_prime: bool,
+}
+
+impl Row {
+    fn new(particles: &Particles, angle_factor: f32, number: u64) -> Row {
+        let position = particles.position_of(number, angle_factor);
+        Row { n: number, x: position.x, y: position.y, is_prime: particles.is_prime(number) }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(bound = "I: Iterator<Item = u64>")]
+struct Document<'a, I> {
+    metadata: &'a Metadata,
+    particles: Rows<'a, I>,
+}
+
+// Serialized straight from the iterator, so the rows are never all in memory. Serializing takes
+// `&self`, hence the cells.
+struct Rows<'a, I> {
+    particles: &'a Particles,
+    angle_factor: f32,
+    numbers: RefCell<Option<I>>,
+    count: Cell<u64>,
+}
+
+impl<'a, I: Iterator<Item = u64>> Serialize for Rows<'a, I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let numbers = self.numbers.borrow_mut().take().into_iter().flatten();
+        serializer.collect_seq(numbers.map(|number| {
+            self.count.set(self.count.get() + 1);
+            Row::new(self.particles, self.angle_factor, number)
+        }))
+    }
+}
+
+// Writes `numbers` to a file a row at a time, so millions of them never have to be in memory as
+// text. Returns how many rows were written.
+pub fn write<I>(path: &Path, format: Format, metadata: &Metadata, particles: &Particles, numbers: I) -> io::Result<u64>
+    where I: IntoIterator<Item = u64>
+{
+    let angle_factor = metadata.angle_factor;
+    let mut file = BufWriter::new(File::create(path)?);
+    let rows = match format {
+        Format::Csv => {
+            writeln!(file, "{}", CSV_HEADER)?;
+            let mut rows = 0;
+            for number in numbers {
+                let row = Row::new(particles, angle_factor, number);
+                writeln!(file, "{},{},{},{}", row.n, row.x, row.y, row.is_prime)?;
+                rows += 1;
+            }
+            rows
+        }
+        Format::Json { pretty } => {
+            let document = Document {
+                metadata,
+                particles: Rows {
+                    particles,
+                    angle_factor,
+                    numbers: RefCell::new(Some(numbers.into_iter())),
+                    count: Cell::new(0),
+                },
+            };
+            if pretty {
+                serde_json::to_writer_pretty(&mut file, &document)?;
+            } else {
+                serde_json::to_writer(&mut file, &document)?;
+            }
+            writeln!(file)?;
+            document.particles.count.get()
+        }
+    };
+    file.flush()?;
+    Ok(rows)
+}
+
+// Every number the configs draw, for when there's no window to be visible in
+pub fn drawn_numbers(particles: &Particles, configs: Configs) -> impl Iterator<Item = u64> + '_ {
+    (particles.start().max(1)..=particles.last_number())
+        .filter(move |&number| {
+            configs.shows_number(particles, number, particles.is_prime(number))
+        })
+}
+
+// The numbers visible in the window being written on a worker thread, so a big export doesn't
+// hold up drawing. It finishes even if dropped. Without a `path`, the file is named after the time
+// like screenshots.
+pub struct DataExport {
+    receiver: Receiver<io::Result<(PathBuf, u64)>>,
+}
+
+impl DataExport {
+    pub fn start(
+        particles: Arc<Particles>,
+        configs: Configs,
+        settings: Settings,
+        size: (f32, f32),
+        format: Format,
+        metadata: Metadata,
+        path: Option<PathBuf>,
+    ) -> DataExport {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let (width, height) = size;
+            let numbers = sprites::frame_numbers(&*particles, configs, settings, width, height);
+            let path = path.unwrap_or_else(|| PathBuf::from(screenshot::file_name(format.extension())));
+            let written = write(&path, format, &metadata, &particles, numbers).map(|rows| (path, rows));
+            let _ = sender.send(written);
+        });
+        DataExport { receiver }
+    }
+
+    // Where the file went and how many rows it has, once it's done. Never blocks.
+    pub fn poll(&self) -> Option<io::Result<(PathBuf, u64)>> {
+        self.receiver.try_recv().ok()
+    }
+}
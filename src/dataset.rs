@@ -0,0 +1,115 @@
+//! What the points drawn are, so the same drawing can show something other than the numbers
+
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use coffee::graphics::Point;
+
+use crate::gaussian::GaussianIntegers;
+use crate::particles::Particles;
+use crate::primality::Primality;
+
+/// Points that are each prime or not, found a slice at a time and drawn the way the numbers are.
+/// Every point has a number of its own, and the numbers go outwards from the origin, so the points
+/// in a ring are a range of them.
+pub trait Dataset: Clone + Send + Sync + 'static {
+    /// The points of the slice `start..end`, `start` being a multiple of 64, to be put together
+    /// with `append`
+    fn find_between(start: u64, end: u64, primality: Primality) -> Self;
+
+    /// Adds the points of the next slice
+    fn append(&mut self, other: Self);
+
+    fn prime_count(&self) -> u64;
+
+    fn is_prime(&self, number: u64) -> bool;
+
+    /// Where the point goes, in spiral units
+    fn position_of(&self, number: u64, angle_factor: f32) -> Point;
+
+    /// The numbers of every point somewhere between `inner` and `outer` away from the origin, and
+    /// maybe a few more
+    fn range_within(&self, inner: f32, outer: f32) -> RangeInclusive<u64>;
+
+    /// How far the point farthest from the origin is, in spiral units
+    fn bounding_radius(&self) -> f32;
+}
+
+/// The points `--mode` picked, for drawing them whichever they are
+#[derive(Clone)]
+pub enum Points {
+    Numbers(Arc<Particles>),
+    /// Along with no numbers, so what only means something for those, like twin primes, has none
+    /// to go by
+    Gaussian(Arc<GaussianIntegers>, Arc<Particles>),
+}
+
+impl Points {
+    pub fn kind(&self) -> DatasetKind {
+        match self {
+            Points::Numbers(_) => { DatasetKind::Numbers }
+            Points::Gaussian(..) => { DatasetKind::Gaussian }
+        }
+    }
+
+    /// The numbers, whichever points are drawn
+    pub fn numbers(&self) -> &Arc<Particles> {
+        match self {
+            Points::Numbers(particles) | Points::Gaussian(_, particles) => { particles }
+        }
+    }
+
+    pub fn numbers_mut(&mut self) -> &mut Arc<Particles> {
+        match self {
+            Points::Numbers(particles) | Points::Gaussian(_, particles) => { particles }
+        }
+    }
+
+    /// The numbers, when it's them that are drawn
+    pub fn drawn_numbers(&self) -> Option<&Particles> {
+        match self {
+            Points::Numbers(particles) => { Some(particles) }
+            Points::Gaussian(..) => { None }
+        }
+    }
+
+    /// See `Dataset::bounding_radius`
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            Points::Numbers(particles) => { particles.bounding_radius() }
+            Points::Gaussian(gaussian, _) => { gaussian.bounding_radius() }
+        }
+    }
+
+    /// The numbers of the first and the last points
+    pub fn point_range(&self) -> (u64, u64) {
+        match self {
+            Points::Numbers(particles) => { (particles.start(), particles.last_number()) }
+            Points::Gaussian(gaussian, _) => { (0, gaussian.len().saturating_sub(1)) }
+        }
+    }
+}
+
+/// Which points `--mode` draws
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DatasetKind {
+    /// The numbers, around the spiral
+    Numbers,
+    /// The Gaussian integers, see `GaussianIntegers`
+    Gaussian,
+}
+
+impl DatasetKind {
+    pub const ALL: [DatasetKind; 2] = [DatasetKind::Numbers, DatasetKind::Gaussian];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DatasetKind::Numbers => { "numbers" }
+            DatasetKind::Gaussian => { "gaussian" }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DatasetKind> {
+        Self::ALL.iter().cloned().find(|kind| kind.name() == name)
+    }
+}
@@ -0,0 +1,102 @@
+//! What the digits of the numbers add up to, worked out for a whole range at once
+
+use rayon::prelude::*;
+
+use crate::palette::MAX_MODULUS;
+
+/// The biggest base the digital roots are worked out in: there's a color for each root from 1 up
+/// to one less than it, and the residues' hues go as far as `MAX_MODULUS`
+pub const MAX_DIGIT_BASE: u32 = MAX_MODULUS as u32 + 1;
+
+/// The digital root of every number in a range in some base, one byte per number: the digits of a
+/// number added up, then the digits of that, until a single digit is left. It's 0 for 0 only, and
+/// from 1 up to one less than the base for the rest.
+#[derive(Clone)]
+pub struct DigitalRoots {
+    roots: Vec<u8>,
+    start: u64,
+    base: u32,
+}
+
+impl DigitalRoots {
+    const SEGMENT_NUMBERS: usize = 1 << 15;
+
+    /// The numbers in `start..end`, in `base`, which goes from 2 up to `MAX_DIGIT_BASE`
+    pub fn range(start: u64, end: u64, base: u32) -> DigitalRoots {
+        assert!(base >= 2 && base <= MAX_DIGIT_BASE, "no digital roots in base {}", base);
+        let mut roots = vec![0u8; end.saturating_sub(start) as usize];
+        roots.par_chunks_mut(Self::SEGMENT_NUMBERS)
+            .enumerate()
+            .for_each(|(segment, roots)| {
+                let segment_start = start + (segment * Self::SEGMENT_NUMBERS) as u64;
+                for (number, root) in (segment_start..).zip(roots.iter_mut()) {
+                    *root = digital_root(number, base);
+                }
+            });
+        DigitalRoots { roots, start, base }
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        self.start + self.roots.len() as u64
+    }
+
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// The digital root of `number`, `None` for numbers outside of the range
+    pub fn root(&self, number: u64) -> Option<u8> {
+        let i = number.checked_sub(self.start)?;
+        self.roots.get(i as usize).cloned()
+    }
+}
+
+/// The digits of `number` in `base` added up, as often as it takes to be left with a single one
+pub fn digital_root(number: u64, base: u32) -> u8 {
+    let base = u64::from(base);
+    let mut rest = number;
+    while rest >= base {
+        let mut sum = 0;
+        while rest > 0 {
+            sum += rest % base;
+            rest /= base;
+        }
+        rest = sum;
+    }
+    rest as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_up_the_digits_until_one_is_left() {
+        let known = [(0, 0), (1, 1), (9, 9), (10, 1), (18, 9), (19, 1), (38, 2), (99_999, 9), (123_456_789, 9)];
+        for &(number, root) in &known {
+            assert_eq!(digital_root(number, 10), root, "{}", number);
+        }
+        // 7 is 10 in base 7, and 48 is 66, 6 + 6 making 15 and 1 + 5 making 6
+        assert_eq!(digital_root(7, 7), 1);
+        assert_eq!(digital_root(48, 7), 6);
+        assert_eq!(digital_root(u64::max_value(), 2), 1);
+        assert_eq!(digital_root(u64::max_value(), 13), 3);
+    }
+
+    #[test]
+    fn is_one_more_than_the_residue_of_the_number_before() {
+        for base in 2..=MAX_DIGIT_BASE {
+            let roots = DigitalRoots::range(1_000_000 - 70_000, 1_000_000, base);
+            for number in roots.start()..roots.end() {
+                let expected = 1 + (number - 1) % u64::from(base - 1);
+                assert_eq!(roots.root(number), Some(expected as u8), "{} in base {}", number, base);
+            }
+        }
+        let roots = DigitalRoots::range(0, 100, 10);
+        assert_eq!((roots.root(0), roots.root(100), roots.base()), (Some(0), None, 10));
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use gif::SetParameter;
+use image::RgbaImage;
+
+use polar_oxides::camera_path::Easing;
+use polar_oxides::dataset::Points;
+use polar_oxides::palette::PolarOxideColors;
+use polar_oxides::particles::Particles;
+use polar_oxides::view::Configs;
+
+use crate::args::{Animation, Args, Camera, Export};
+use crate::data_export::{self, Format, Metadata};
+use crate::screenshot;
+use crate::sprites;
+use crate::svg;
+use crate::PolarOxides;
+
+// `polar-oxides export` and `export-data`: what the window would open on, written to a file
+// without ever opening the window
+pub fn run(args: &Args, export: &Export) -> Result<(), String> {
+    match export {
+        Export::Image(path) => { image(args, path) }
+        Export::Data(path, format) => { data(args, path, *format) }
+        Export::Animation(directory, animation) => { animate(args, directory, animation) }
+        Export::Gif(path, animation, fps) => { gif(args, path, animation, *fps) }
+    }
+}
+
+// The picture, drawn on the CPU, or as an SVG for a file named like one. Nothing is revealed
+// gradually, every number is drawn.
+fn image(args: &Args, path: &Path) -> Result<(), String> {
+    let points = points(args);
+    let (width, height) = args.window_size;
+    let (configs, settings) = (args.configs, args.settings);
+    if svg::is_svg(path) {
+        let max_composites = args.max_composites;
+        let sprites = svg::frame_sprites(&points, configs, settings, width as f32, height as f32, max_composites);
+        let atlas = PolarOxideColors::atlas(configs.custom_palette);
+        return svg::write(path, width, height, configs.background(), &atlas, &sprites)
+            .map(|_| ())
+            .map_err(|error| format!("couldn't write {}: {}", path.display(), error));
+    }
+    render(&points, args, configs)
+        .save(path)
+        .map_err(|error| format!("couldn't write {}: {}", path.display(), error))
+}
+
+// The frames of a zoom or a camera path, numbered so they sort in order, each one drawn like
+// `export` would. Frames already in `directory` are left alone, so a run that was stopped picks up
+// where it left off.
+fn animate(args: &Args, directory: &Path, animation: &Animation) -> Result<(), String> {
+    fs::create_dir_all(directory).map_err(|error| format!("couldn't create {}: {}", directory.display(), error))?;
+    let points = points(args);
+    let last_frame = (animation.frames - 1).max(1) as f32;
+    for frame in 0..animation.frames {
+        let path = directory.join(format!("frame-{:05}.png", frame));
+        if path.exists() {
+            eprintln!("Frame {}/{}: {} is already there", frame + 1, animation.frames, path.display());
+            continue;
+        }
+        let configs = configs_at(args, &animation.camera, frame as f32 / last_frame);
+        render(&points, args, configs)
+            .save(&path)
+            .map_err(|error| format!("couldn't write {}: {}", path.display(), error))?;
+        eprintln!("Frame {}/{}: {}", frame + 1, animation.frames, path.display());
+    }
+    Ok(())
+}
+
+// The same zoom there and back, or a camera path once, encoded as each frame is drawn so only one
+// is ever in memory. The pictures only have the palette's colors and the background, so the GIF's
+// palette holds them all and nothing is lost.
+fn gif(args: &Args, path: &Path, animation: &Animation, fps: f32) -> Result<(), String> {
+    let write_error = |error: io::Error| format!("couldn't write {}: {}", path.display(), error);
+    let (width, height) = args.window_size;
+    let too_big = || format!("a GIF can't be bigger than 65535x65535, got {}x{}", width, height);
+    let gif_width = u16::try_from(width).map_err(|_| too_big())?;
+    let gif_height = u16::try_from(height).map_err(|_| too_big())?;
+
+    let mut colors = PolarOxideColors::atlas(args.configs.custom_palette);
+    colors.push(args.configs.background());
+    let mut palette = Vec::new();
+    let mut indices = HashMap::new();
+    for color in colors {
+        let rgba = color.to_rgba();
+        if !indices.contains_key(&rgba) && indices.len() < 256 {
+            indices.insert(rgba, indices.len() as u8);
+            palette.extend_from_slice(&rgba[..3]);
+        }
+    }
+
+    let points = points(args);
+    let file = BufWriter::new(File::create(path).map_err(write_error)?);
+    let mut encoder = gif::Encoder::new(file, gif_width, gif_height, &palette).map_err(write_error)?;
+    encoder.set(gif::Repeat::Infinite).map_err(write_error)?;
+    let delay = (100.0 / fps).round() as u16;
+
+    for frame in 0..animation.frames {
+        let time = frame as f32 / animation.frames as f32;
+        let configs = match animation.camera {
+            // Up to the far end halfway through, and back to where the first frame is by the end
+            Camera::Zoom { .. } => { configs_at(args, &animation.camera, 1.0 - (2.0 * time - 1.0).abs()) }
+            Camera::Path(_) => { configs_at(args, &animation.camera, time) }
+        };
+        let pixels: Vec<u8> = render(&points, args, configs)
+            .pixels()
+            .map(|pixel| indices.get(&pixel.data).cloned().unwrap_or(0))
+            .collect();
+        let mut gif_frame = gif::Frame::from_indexed_pixels(gif_width, gif_height, &pixels, None);
+        gif_frame.delay = delay;
+        encoder.write_frame(&gif_frame).map_err(write_error)?;
+        eprintln!("Frame {}/{}", frame + 1, animation.frames);
+    }
+    Ok(())
+}
+
+// Where the camera is `progress` of the way through, from 0 to 1. A zoom starts and ends slowly,
+// smoothstep, and a path eases like its file says.
+fn configs_at(args: &Args, camera: &Camera, progress: f32) -> Configs {
+    match camera {
+        Camera::Zoom { from_zoom, to_zoom } => {
+            let from_zoom = from_zoom.unwrap_or(args.configs.zoom_level);
+            let progress = Easing::EaseInOut.apply(progress);
+            Configs { zoom_level: from_zoom + (to_zoom - from_zoom) * progress, ..args.configs }
+        }
+        Camera::Path(path) => {
+            let (zoom_level, offset) = path.sample(progress * path.duration());
+            Configs { zoom_level: Configs::clamp_zoom_level(zoom_level), offset, ..args.configs }
+        }
+    }
+}
+
+// What the window would show with `configs`, at --size
+fn render(points: &Points, args: &Args, configs: Configs) -> RgbaImage {
+    let (width, height) = args.window_size;
+    let sprites = sprites::frame_sprites(points, configs, args.settings, width as f32, height as f32);
+    let atlas = PolarOxideColors::atlas(configs.custom_palette);
+    screenshot::render(width, height, configs.background(), &atlas, &sprites)
+}
+
+// Every number the display mode shows, whether it would be in the window or not
+fn data(args: &Args, path: &Path, format: Format) -> Result<(), String> {
+    let (particles, generation_time) = particles(args);
+    let metadata = Metadata::new(args.particles.max_number, args.particles.radius_base, args.configs, generation_time);
+    let numbers = data_export::drawn_numbers(&particles, args.configs);
+    let rows = data_export::write(path, format, &metadata, &particles, numbers)
+        .map_err(|error| format!("couldn't write {}: {}", path.display(), error))?;
+    eprintln!("Wrote {} rows to {}", rows, path.display());
+    Ok(())
+}
+
+// The numbers, which is what's drawn whatever --mode says
+fn points(args: &Args) -> Points {
+    let (particles, _) = particles(args);
+    Points::Numbers(Arc::new(particles))
+}
+
+// Along with how long it took to find them or read them from the cache
+fn particles(args: &Args) -> (Particles, Duration) {
+    let started = Instant::now();
+    let config = args.particles;
+    let particles = match PolarOxides::cached_particles(config) {
+        Some(particles) => { particles }
+        None => {
+            let particles = Particles::find_between(config.start, config.max_number, config.primality);
+            PolarOxides::log_found_particles(config, started.elapsed());
+            if let Err(error) = PolarOxides::cache_particles(&particles, config) {
+                eprintln!("error: {}", error);
+            }
+            particles
+        }
+    };
+    (particles.rebased(config.radius_base), started.elapsed())
+}
@@ -0,0 +1,233 @@
+//! What the numbers are made of, worked out for a whole range at once
+
+use std::ops::RangeInclusive;
+
+use rayon::prelude::*;
+
+use crate::sieve::Sieve;
+
+/// Ω of every number in a range, how many prime factors it has counted with multiplicity, the
+/// smallest of those factors and μ, the Möbius function, four bytes per number. Found like the
+/// primes are, crossing out the multiples of every prime up to the square root of the end a segment
+/// at a time, only dividing each multiple by the prime as often as it goes instead. The first prime
+/// to cross a number out is its smallest factor, and whatever is left of it past the last one is
+/// one prime too big to have crossed anything out.
+#[derive(Clone)]
+pub struct Factors {
+    omegas: Vec<u8>,
+    // 0 for the numbers that aren't composite. Below `LIMIT` they all fit.
+    smallest: Vec<u16>,
+    moebius: Vec<i8>,
+    start: u64,
+}
+
+/// M(n), the Mertens function, the sum of μ(k) for every k from 1 up to n: where it ends up along a
+/// range of n, and how low and how high it goes on the way
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Mertens {
+    pub last: u64,
+    pub value: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Factors {
+    const SEGMENT_NUMBERS: usize = 1 << 15;
+    /// Numbers from this one on aren't factored: there would be too many primes to cross out with
+    /// for every segment
+    pub const LIMIT: u64 = 1 << 32;
+
+    /// The numbers in `start..end`, or the ones of them below `LIMIT`
+    pub fn range(start: u64, end: u64) -> Factors {
+        let end = end.min(Self::LIMIT).max(start);
+        let base_primes = Sieve::new((end as f64).sqrt() as u64 + 2).primes();
+
+        let mut omegas = vec![0u8; (end - start) as usize];
+        let mut smallest = vec![0u16; omegas.len()];
+        let mut moebius = vec![1i8; omegas.len()];
+        omegas.par_chunks_mut(Self::SEGMENT_NUMBERS)
+            .zip(smallest.par_chunks_mut(Self::SEGMENT_NUMBERS))
+            .zip(moebius.par_chunks_mut(Self::SEGMENT_NUMBERS))
+            .enumerate()
+            .for_each(|(segment, ((omegas, smallest), moebius))| {
+                let segment_start = start + (segment * Self::SEGMENT_NUMBERS) as u64;
+                let segment_end = segment_start + omegas.len() as u64;
+                // 0 has every factor, and none worth counting
+                let mut rests: Vec<u64> = (segment_start..segment_end).map(|number| number.max(1)).collect();
+                if segment_start == 0 && !moebius.is_empty() {
+                    moebius[0] = 0;
+                }
+                for &prime in &base_primes {
+                    let mut multiple = (segment_start + prime - 1) / prime * prime;
+                    while multiple < segment_end {
+                        let i = (multiple - segment_start) as usize;
+                        if smallest[i] == 0 && multiple > prime {
+                            smallest[i] = prime as u16;
+                        }
+                        let mut times = 0;
+                        while rests[i] % prime == 0 {
+                            rests[i] /= prime;
+                            times += 1;
+                        }
+                        omegas[i] += times;
+                        // A square divides it when the prime does more than once
+                        moebius[i] = if times > 1 { 0 } else { -moebius[i] };
+                        multiple += prime;
+                    }
+                }
+                for ((omega, mu), rest) in omegas.iter_mut().zip(moebius.iter_mut()).zip(rests) {
+                    if rest > 1 {
+                        *omega += 1;
+                        *mu = -*mu;
+                    }
+                }
+            });
+        Factors { omegas, smallest, moebius, start }
+    }
+
+    /// Puts a range that picks up where this one ends, or earlier, at its end. Anything of this
+    /// range from its start on is replaced with it. When there would be a hole in between, it
+    /// replaces all of this one instead.
+    pub fn append(&mut self, other: Factors) {
+        if other.start < self.start || other.start > self.end() {
+            *self = other;
+            return;
+        }
+        let kept = (other.start - self.start) as usize;
+        self.omegas.truncate(kept);
+        self.omegas.extend(other.omegas);
+        self.smallest.truncate(kept);
+        self.smallest.extend(other.smallest);
+        self.moebius.truncate(kept);
+        self.moebius.extend(other.moebius);
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        self.start + self.omegas.len() as u64
+    }
+
+    /// Ω(number), `None` for numbers outside of the range. It's 0 for 0 and 1, and 1 for the
+    /// primes.
+    pub fn big_omega(&self, number: u64) -> Option<u8> {
+        let i = number.checked_sub(self.start)?;
+        self.omegas.get(i as usize).cloned()
+    }
+
+    /// The smallest prime factor of `number`, `None` when it isn't composite or is outside of the
+    /// range
+    pub fn smallest_factor(&self, number: u64) -> Option<u64> {
+        let i = number.checked_sub(self.start)?;
+        match self.smallest.get(i as usize) {
+            Some(0) | None => { None }
+            Some(&factor) => { Some(u64::from(factor)) }
+        }
+    }
+
+    /// μ(number): 0 when a square divides it, otherwise 1 or -1 for an even or odd count of prime
+    /// factors. `None` outside of the range, and 0 for 0.
+    pub fn moebius(&self, number: u64) -> Option<i8> {
+        let i = number.checked_sub(self.start)?;
+        self.moebius.get(i as usize).cloned()
+    }
+
+    /// M(n) for every n in `numbers`, as far as they're in the range. It adds up μ from 1 on, so
+    /// only a range from there has it.
+    pub fn mertens(&self, numbers: RangeInclusive<u64>) -> Option<Mertens> {
+        let first = (*numbers.start()).max(1);
+        let last = (*numbers.end()).min(self.end().checked_sub(1)?);
+        if self.start > 1 || first > last {
+            return None;
+        }
+        let i = |number: u64| (number - self.start) as usize;
+        let before: i64 = self.moebius[i(1)..i(first)].par_iter().map(|&mu| i64::from(mu)).sum();
+        let mut mertens = Mertens { last, value: before, min: i64::max_value(), max: i64::min_value() };
+        for &mu in &self.moebius[i(first)..=i(last)] {
+            mertens.value += i64::from(mu);
+            mertens.min = mertens.min.min(mertens.value);
+            mertens.max = mertens.max.max(mertens.value);
+        }
+        Some(mertens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_prime_factors() {
+        let factors = Factors::range(0, 100_000);
+        let known = [
+            (0, 0), (1, 0), (2, 1), (3, 1), (4, 2), (6, 2), (8, 3), (12, 3), (30, 3), (49, 2), (64, 6),
+            (97, 1), (360, 6), (1024, 10), (9973, 1), (30030, 6), (65536, 16), (99_991, 1), (99_999, 4),
+        ];
+        for &(number, omega) in &known {
+            assert_eq!(factors.big_omega(number), Some(omega), "Ω({})", number);
+        }
+        assert_eq!(factors.big_omega(100_000), None);
+        // A prime factor bigger than any crossing out
+        assert_eq!(Factors::range(99_904, 100_000).big_omega(99_998), Some(2));
+    }
+
+    #[test]
+    fn finds_the_smallest_prime_factors() {
+        let factors = Factors::range(0, 100_000);
+        let known = [
+            (0, None), (1, None), (2, None), (4, Some(2)), (9, Some(3)), (35, Some(5)), (49, Some(7)),
+            (97, None), (221, Some(13)), (1369, Some(37)), (99_999, Some(3)), (99_856, Some(2)), (97_343, Some(311)),
+        ];
+        for &(number, smallest) in &known {
+            assert_eq!(factors.smallest_factor(number), smallest, "{}", number);
+        }
+        // The square of the biggest prime there's room for, and a prime right before the limit
+        let square = 65_521 * 65_521;
+        assert_eq!(Factors::range(square - 64, square + 64).smallest_factor(square), Some(65_521));
+        assert_eq!(Factors::range(Factors::LIMIT - 64, Factors::LIMIT).smallest_factor(Factors::LIMIT - 5), None);
+    }
+
+    #[test]
+    fn works_out_the_moebius_function() {
+        let factors = Factors::range(0, 1_000_001);
+        // μ(1) to μ(30)
+        let known = [
+            1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0, -1, 1, 1, 0, -1, 0, -1, 0, 1, 1, -1, 0, 0, 1, 0, 0, -1, -1,
+        ];
+        for (number, &mu) in (1..).zip(known.iter()) {
+            assert_eq!(factors.moebius(number), Some(mu), "μ({})", number);
+        }
+        assert_eq!(factors.moebius(0), Some(0));
+        // 2·3·5·7·11·13, 999,983 is prime, 998,001 = 999²
+        let moebius = [factors.moebius(30_030), factors.moebius(999_983), factors.moebius(998_001)];
+        assert_eq!(moebius, [Some(1), Some(-1), Some(0)]);
+
+        let mertens = |numbers: RangeInclusive<u64>| factors.mertens(numbers).unwrap();
+        assert_eq!(mertens(0..=1_000_000).value, 212);
+        assert_eq!(mertens(1_000_000..=2_000_000), Mertens { last: 1_000_000, value: 212, min: 212, max: 212 });
+        // M(n) for n up to 10
+        assert_eq!(mertens(1..=10), Mertens { last: 10, value: -1, min: -2, max: 1 });
+        assert_eq!(mertens(5..=10), Mertens { last: 10, value: -1, min: -2, max: -1 });
+        assert_eq!(Factors::range(64, 1000).mertens(100..=200), None);
+    }
+
+    #[test]
+    fn slices_put_together_are_the_whole_range() {
+        let whole = Factors::range(0, 200_000);
+        let mut slices = Factors::range(0, 70_000);
+        slices.append(Factors::range(69_952, 150_000));
+        slices.append(Factors::range(150_000, 200_000));
+        assert_eq!(slices.omegas, whole.omegas);
+        assert_eq!(slices.smallest, whole.smallest);
+        assert_eq!(slices.moebius, whole.moebius);
+
+        // Far out, and with a hole it starts over
+        let far = Factors::range(Factors::LIMIT - 1000, Factors::LIMIT + 1000);
+        assert_eq!((far.start(), far.end()), (Factors::LIMIT - 1000, Factors::LIMIT));
+        assert_eq!(far.big_omega(Factors::LIMIT - 1), Some(5));
+        slices.append(far);
+        assert_eq!(slices.big_omega(1000), None);
+    }
+}
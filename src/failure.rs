@@ -0,0 +1,70 @@
+use std::cell::RefCell;
+use std::process;
+
+use coffee::{Game, Result, Timer};
+use coffee::graphics::{Color, Font, Frame, Point, Text, Window, WindowSettings};
+use coffee::input::{keyboard::KeyCode, KeyboardAndMouse};
+use coffee::load::Task;
+
+use crate::FONT;
+
+thread_local! {
+    // coffee's `load` doesn't take anything, so this is how the message gets to the window. Loading
+    // happens on the thread that runs the game.
+    static MESSAGE: RefCell<String> = RefCell::new(String::new());
+}
+
+// Shows `message` in a window of its own, for when the spiral couldn't load and there's nothing
+// else to show it in. Returns once the window is closed.
+pub fn show(message: String) -> Result<()> {
+    MESSAGE.with(|cell| *cell.borrow_mut() = message);
+    Failure::run(WindowSettings {
+        title: String::from("Polar Oxides"),
+        size: (800, 200),
+        resizable: true,
+        fullscreen: false,
+    })
+}
+
+struct Failure {
+    font: Font,
+    message: String,
+}
+
+impl Failure {
+    const MARGIN: f32 = 20.0;
+    const TEXT_SIZE: f32 = 20.0;
+}
+
+impl Game for Failure {
+    type Input = KeyboardAndMouse;
+    type LoadingScreen = ();
+
+    fn load(_window: &Window) -> Task<Failure> {
+        Font::load_from_bytes(FONT).map(|font| Failure {
+            font,
+            message: MESSAGE.with(|cell| cell.borrow().clone()),
+        })
+    }
+
+    fn draw(&mut self, frame: &mut Frame, _timer: &Timer) {
+        frame.clear(Color::BLACK);
+        let bounds = (frame.width() - 2.0 * Self::MARGIN, frame.height() - 2.0 * Self::MARGIN);
+        self.font.add(Text {
+            content: &format!("{}\n\nPress Esc to close.", self.message),
+            position: Point::new(Self::MARGIN, Self::MARGIN),
+            bounds,
+            size: Self::TEXT_SIZE,
+            color: Color::WHITE,
+            ..Text::default()
+        });
+        self.font.draw(&mut frame.as_target());
+    }
+
+    // Like the spiral's own quitting, coffee has no way of closing the window from in here
+    fn interact(&mut self, input: &mut KeyboardAndMouse, _window: &mut Window) {
+        if input.was_key_released(KeyCode::Escape) {
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+use rayon::prelude::*;
+
+use polar_oxides::particles::Particles;
+use polar_oxides::view::{Configs, Settings};
+
+use crate::sprites;
+
+// How far each prime in the window is from the next one, for the gap histogram
+pub struct Gaps {
+    // Each gap size that comes up, smallest first, with how many of the primes start one
+    pub counts: Vec<(u64, u64)>,
+    // The gap size picked in the histogram, and the primes in the window that start one
+    pub picked: Option<u64>,
+    pub starts: Vec<u64>,
+}
+
+impl Gaps {
+    // The gap after the last prime isn't known, so it isn't counted
+    pub fn new(particles: &Particles, configs: Configs, settings: Settings, size: (f32, f32), picked: Option<u64>)
+        -> Gaps
+    {
+        let (width, height) = size;
+        let mut gaps: Vec<(u64, u64)> = sprites::visible(particles, configs, settings, width, height, true)
+            .filter_map(|(prime, _)| Some((prime, particles.next_prime(prime)? - prime)))
+            .collect();
+        gaps.par_sort_unstable();
+
+        let mut counts = BTreeMap::new();
+        for &(_, gap) in &gaps {
+            *counts.entry(gap).or_insert(0) += 1;
+        }
+        let starts = gaps.iter()
+            .filter(|&&(_, gap)| Some(gap) == picked)
+            .map(|&(prime, _)| prime)
+            .collect();
+        Gaps { counts: counts.into_iter().collect(), picked, starts }
+    }
+
+    // How many primes start a gap of that size
+    pub fn count(&self, gap: u64) -> u64 {
+        self.counts.iter().find(|&&(size, _)| size == gap).map_or(0, |&(_, count)| count)
+    }
+}
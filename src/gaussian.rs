@@ -0,0 +1,205 @@
+//! The Gaussian integers a + bi, drawn where they sit in the complex plane instead of the numbers
+
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+
+use coffee::graphics::Point;
+use rayon::prelude::*;
+
+use crate::dataset::Dataset;
+use crate::primality::{self, Primality};
+use crate::sieve::Sieve;
+
+/// Whether a + bi is a Gaussian prime: off the axes when its norm a² + b² is prime, on them when
+/// the other one is ±p for a prime p ≡ 3 (mod 4)
+pub fn is_gaussian_prime(a: i32, b: i32) -> bool {
+    is_prime_by_norm(i64::from(a).abs() as u64, i64::from(b).abs() as u64, primality::is_prime)
+}
+
+// The same for |a| + |b|i, with whether a norm off the axes is prime up to `is_norm_prime`
+fn is_prime_by_norm(a: u64, b: u64, is_norm_prime: impl Fn(u64) -> bool) -> bool {
+    match (a, b) {
+        (0, p) | (p, 0) => { p % 4 == 3 && primality::is_prime(p) }
+        (a, b) => { is_norm_prime(a * a + b * b) }
+    }
+}
+
+/// The Gaussian integers with a norm in a range, other than 0. Turning one by a right angle, or
+/// multiplying it by i, keeps both its norm and whether it's prime, so only the quarter with a > 0
+/// and b ≥ 0 is kept and the rest are that quarter turned. Every point's number is four times its
+/// place in the quarter plus how many turns it takes, which puts them in order of their norm.
+#[derive(Clone)]
+pub struct GaussianIntegers {
+    /// By norm, and by angle among the same norm
+    quarter: Vec<(u32, u32)>,
+    primes: Vec<bool>,
+}
+
+impl GaussianIntegers {
+    /// The point with that number, as (a, b)
+    pub fn point(&self, number: u64) -> Option<(i64, i64)> {
+        let &(a, b) = self.quarter.get((number / 4) as usize)?;
+        let (a, b) = (i64::from(a), i64::from(b));
+        Some(match number % 4 {
+            0 => { (a, b) }
+            1 => { (-b, a) }
+            2 => { (-a, -b) }
+            _ => { (b, -a) }
+        })
+    }
+
+    /// How many points there are
+    pub fn len(&self) -> u64 {
+        self.quarter.len() as u64 * 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quarter.is_empty()
+    }
+
+    fn norm((a, b): (u32, u32)) -> u64 {
+        u64::from(a) * u64::from(a) + u64::from(b) * u64::from(b)
+    }
+
+    // The first place in the quarter with a norm of at least `norm`
+    fn first_with_norm(&self, norm: u64) -> usize {
+        self.quarter
+            .binary_search_by(|point| if Self::norm(*point) < norm { Ordering::Less } else { Ordering::Greater })
+            .unwrap_or_else(|place| place)
+    }
+}
+
+impl Dataset for GaussianIntegers {
+    /// The ones with a norm in `start..end`. The norms are sieved, the ones on the axes tested on
+    /// their own.
+    fn find_between(start: u64, end: u64, primality: Primality) -> GaussianIntegers {
+        let norms = Sieve::with(primality, start, end);
+        let mut quarter: Vec<(u32, u32)> = (1..)
+            .take_while(|&a: &u64| a * a < end)
+            .flat_map(|a| {
+                // A square root can be off by a bit, the filter takes care of the one too many
+                let root = |norm: u64| (norm.saturating_sub(a * a) as f64).sqrt() as u64;
+                (root(start).saturating_sub(1)..=root(end) + 1).map(move |b| (a, b))
+            })
+            .filter(|&(a, b)| (start..end).contains(&(a * a + b * b)))
+            .map(|(a, b)| (a as u32, b as u32))
+            .collect();
+        // Turning counterclockwise from the real axis is going up in b / a
+        quarter.par_sort_unstable_by(|p, q| {
+            Self::norm(*p).cmp(&Self::norm(*q))
+                .then((u64::from(p.1) * u64::from(q.0)).cmp(&(u64::from(q.1) * u64::from(p.0))))
+        });
+        let primes = quarter.par_iter()
+            .map(|&(a, b)| is_prime_by_norm(u64::from(a), u64::from(b), |norm| norms.is_prime(norm)))
+            .collect();
+        GaussianIntegers { quarter, primes }
+    }
+
+    fn append(&mut self, other: GaussianIntegers) {
+        self.quarter.extend(other.quarter);
+        self.primes.extend(other.primes);
+    }
+
+    fn prime_count(&self) -> u64 {
+        self.primes.par_iter().filter(|&&prime| prime).count() as u64 * 4
+    }
+
+    fn is_prime(&self, number: u64) -> bool {
+        self.primes.get((number / 4) as usize).cloned().unwrap_or(false)
+    }
+
+    /// a + bi sits at (a, b), whatever the angle factor
+    fn position_of(&self, number: u64, _angle_factor: f32) -> Point {
+        let (a, b) = self.point(number).unwrap_or((0, 0));
+        Point::new(a as f32, b as f32)
+    }
+
+    fn range_within(&self, inner: f32, outer: f32) -> RangeInclusive<u64> {
+        // Padded by one on each side like `Particles::numbers_within`, and clamped before squaring
+        let limit = self.bounding_radius() + 2.0;
+        let norm = |radius: f32| {
+            let radius = f64::from(radius.max(0.0).min(limit));
+            (radius * radius) as u64
+        };
+        let first = self.first_with_norm(norm(inner - 1.0)) as u64;
+        let end = self.first_with_norm(norm(outer + 1.0) + 1) as u64;
+        if first >= end {
+            // Empty
+            return 1..=0;
+        }
+        let last = end * 4 - 1;
+        first * 4..=last
+    }
+
+    fn bounding_radius(&self) -> f32 {
+        self.quarter.last().map_or(0.0, |point| (Self::norm(*point) as f64).sqrt() as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_gaussian_primes() {
+        // 1 + i, 2 + i and 3 are, 2 = -i(1 + i)², 5 = (2 + i)(2 - i) and 1 aren't
+        for &(a, b) in &[(1, 1), (2, 1), (1, -2), (3, 0), (0, -7), (-19, 0), (4, 5)] {
+            assert!(is_gaussian_prime(a, b), "{} + {}i", a, b);
+        }
+        for &(a, b) in &[(0, 0), (1, 0), (0, -1), (2, 0), (5, 0), (0, 13), (3, 3), (2, 4)] {
+            assert!(!is_gaussian_prime(a, b), "{} + {}i", a, b);
+        }
+    }
+
+    #[test]
+    fn every_turn_of_a_point_is_there() {
+        let gaussian = GaussianIntegers::find_between(0, 500, Primality::Sieve);
+        let points: Vec<(i64, i64)> = (0..gaussian.len()).filter_map(|number| gaussian.point(number)).collect();
+        // Every point in the disc but 0, once
+        let mut sorted = points.clone();
+        sorted.sort();
+        sorted.dedup();
+        let disc = (-22..=22i64).flat_map(|a| (-22..=22i64).map(move |b| (a, b)))
+            .filter(|&(a, b)| a * a + b * b > 0 && a * a + b * b < 500)
+            .count();
+        assert_eq!(sorted.len(), disc);
+        assert_eq!(points.len(), disc);
+
+        for number in 0..gaussian.len() {
+            let (a, b) = gaussian.point(number).unwrap();
+            assert_eq!(gaussian.is_prime(number), is_gaussian_prime(a as i32, b as i32), "{} + {}i", a, b);
+            // The turn of a point is next to it and just as prime
+            let turned = number / 4 * 4 + (number + 1) % 4;
+            assert_eq!(gaussian.point(turned), Some((-b, a)));
+            assert_eq!(gaussian.is_prime(turned), gaussian.is_prime(number));
+        }
+    }
+
+    #[test]
+    fn slices_put_together_are_the_whole_disc() {
+        let whole = GaussianIntegers::find_between(0, 10_000, Primality::Sieve);
+        let mut slices = GaussianIntegers::find_between(0, 4096, Primality::MillerRabin);
+        slices.append(GaussianIntegers::find_between(4096, 10_000, Primality::Sieve));
+        assert_eq!(slices.quarter, whole.quarter);
+        assert_eq!(slices.primes, whole.primes);
+        assert_eq!(slices.prime_count(), whole.prime_count());
+    }
+
+    #[test]
+    fn only_points_in_the_ring_are_worked_out() {
+        let gaussian = GaussianIntegers::find_between(0, 10_000, Primality::Sieve);
+        let ring = gaussian.range_within(20.0, 30.0);
+        for number in 0..gaussian.len() {
+            let (a, b) = gaussian.point(number).unwrap();
+            let radius = ((a * a + b * b) as f64).sqrt();
+            if radius >= 20.0 && radius <= 30.0 {
+                assert!(ring.contains(&number), "{} + {}i", a, b);
+            }
+            if !ring.contains(&number) {
+                assert!(radius < 20.0 || radius > 30.0);
+            }
+        }
+        assert!(gaussian.range_within(200.0, 300.0).next().is_none());
+        assert!(gaussian.bounding_radius() < 100.0 && gaussian.bounding_radius() > 99.0);
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use polar_oxides::particles::Particles;
+use polar_oxides::primality::Primality;
+use polar_oxides::sieve::Sieve;
+
+// How many slices the numbers are found in, so there is something to show progress with and
+// somewhere to stop in between
+const CHUNKS: u64 = 100;
+
+// The slices of `start..end`, `start` being a multiple of 64: about `CHUNKS` of them, each one
+// starting at a multiple of 64 too like the sieve wants, and no smaller than one of its segments
+pub fn chunks(start: u64, end: u64) -> impl Iterator<Item = (u64, u64)> {
+    let chunk_size = (end.saturating_sub(start) / CHUNKS + 63) / 64 * 64;
+    let chunk_size = chunk_size.max(Sieve::SEGMENT_NUMBERS);
+    (start..end).step_by(chunk_size as usize)
+        .map(move |chunk_start| (chunk_start, (chunk_start + chunk_size).min(end)))
+}
+
+// Particles being found on a worker thread, so the window can keep drawing the old ones in the
+// meantime. Dropping it, by starting another one or otherwise, stops the worker at the next slice.
+// What it finds is meant to be appended to the current particles, which starting from 0 replaces.
+pub struct Generation {
+    max_number: u64,
+    cancelled: Arc<AtomicBool>,
+    receiver: Receiver<Particles>,
+}
+
+impl Generation {
+    // The numbers in `first..max_number`, `first` being 0 or `next_start` of the current particles
+    pub fn start(first: u64, max_number: u64, primality: Primality) -> Generation {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        let worker_cancelled = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            let mut particles = Particles::find_between(first, first, primality);
+            for (start, end) in chunks(first, max_number) {
+                if worker_cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                particles.append(Particles::find_between(start, end, primality));
+            }
+            // Nobody listening anymore means it was cancelled while on the last slice
+            let _ = sender.send(particles);
+        });
+
+        Generation { max_number, cancelled, receiver }
+    }
+
+    pub fn max_number(&self) -> u64 {
+        self.max_number
+    }
+
+    // The particles, once all of them are there. Never blocks.
+    pub fn poll(&self) -> Option<Particles> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Generation {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
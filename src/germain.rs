@@ -0,0 +1,33 @@
+use rayon::prelude::*;
+
+use polar_oxides::classes::{self, Class, Classification};
+use polar_oxides::particles::Particles;
+use polar_oxides::view::{Configs, Settings};
+
+use crate::sprites;
+
+// The Sophie Germain primes in the window, each linked to its safe prime 2p + 1 wherever that is
+pub struct GermainLines {
+    // Each prime p with its 2p + 1, smallest first, at most `MAX_LINES` of them
+    pub pairs: Vec<(u64, u64)>,
+}
+
+impl GermainLines {
+    // Past this many the lines are a haze over the points anyway
+    const MAX_LINES: usize = 5_000;
+
+    // Empty unless the Sophie Germain primes are being picked out
+    pub fn new(particles: &Particles, configs: Configs, settings: Settings, size: (f32, f32)) -> GermainLines {
+        if configs.classification != Classification::SophieGermain {
+            return GermainLines { pairs: Vec::new() };
+        }
+        let (width, height) = size;
+        let mut pairs: Vec<(u64, u64)> = sprites::visible(particles, configs, settings, width, height, true)
+            .filter(|&(prime, _)| configs.classification.class_of(particles, prime) == Some(Class::SophieGermain))
+            .filter_map(|(prime, _)| Some((prime, classes::safe_prime_of(prime)?)))
+            .collect();
+        pairs.par_sort_unstable();
+        pairs.truncate(Self::MAX_LINES);
+        GermainLines { pairs }
+    }
+}
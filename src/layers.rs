@@ -0,0 +1,37 @@
+//! What's worked out about the numbers besides which of them are prime, only once something needs it
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::digits::DigitalRoots;
+use crate::factors::Factors;
+
+/// The layers of a range of numbers, each worked out for the whole range the first time it's asked
+/// for and kept from then on. Every clone of the particles they belong to shares them, so they sit
+/// behind a lock, which is held while a layer is worked out so that it only is once.
+#[derive(Default)]
+pub struct Layers {
+    factors: Mutex<Option<Arc<Factors>>>,
+    digital_roots: Mutex<Option<Arc<DigitalRoots>>>,
+}
+
+impl Layers {
+    /// Ω and the smallest prime factor of the numbers in `start..end`, see `Factors`
+    pub fn factors(&self, start: u64, end: u64) -> Arc<Factors> {
+        let mut factors = self.factors.lock().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(factors.get_or_insert_with(|| Arc::new(Factors::range(start, end))))
+    }
+
+    /// The digital roots of the numbers in `start..end` in `base`, see `DigitalRoots`. Only the
+    /// ones in the last base asked for are kept.
+    pub fn digital_roots(&self, start: u64, end: u64, base: u32) -> Arc<DigitalRoots> {
+        let mut roots = self.digital_roots.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(ref kept) = *roots {
+            if kept.base() == base {
+                return Arc::clone(kept);
+            }
+        }
+        let worked_out = Arc::new(DigitalRoots::range(start, end, base));
+        *roots = Some(Arc::clone(&worked_out));
+        worked_out
+    }
+}
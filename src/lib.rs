@@ -0,0 +1,19 @@
+//! The spiral of numbers polar-oxides draws, without the window: which numbers are prime, where
+//! each of them goes, how they are colored and how the camera maps them onto the screen.
+
+pub mod cache;
+pub mod camera_path;
+pub mod classes;
+pub mod dataset;
+pub mod digits;
+pub mod factors;
+pub mod gaussian;
+pub mod layers;
+pub mod overlays;
+pub mod palette;
+pub mod particles;
+pub mod predicate;
+pub mod primality;
+pub mod sieve;
+pub mod special;
+pub mod view;
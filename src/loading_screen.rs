@@ -0,0 +1,115 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use coffee::Result;
+use coffee::graphics::{Color, Font, Frame, Gpu, Image, Point, Quad, Text};
+use coffee::load::{loading_screen, Progress};
+
+use crate::FONT;
+use crate::stats::grouped;
+
+// How far finding the primes got, for the loading screen to show. Whatever finds them adds to it a
+// slice at a time, the screen reads it whenever there's progress.
+pub struct Counts {
+    total: AtomicU64,
+    checked: AtomicU64,
+    primes: AtomicU64,
+}
+
+pub static COUNTS: Counts = Counts {
+    total: AtomicU64::new(0),
+    checked: AtomicU64::new(0),
+    primes: AtomicU64::new(0),
+};
+
+impl Counts {
+    // `total` numbers are about to be looked at
+    pub fn start(&self, total: u64) {
+        self.checked.store(0, Ordering::Relaxed);
+        self.primes.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, checked: u64, primes: u64) {
+        self.checked.fetch_add(checked, Ordering::Relaxed);
+        self.primes.fetch_add(primes, Ordering::Relaxed);
+    }
+
+    // None when nothing was started, like when the primes came from the cache
+    fn text(&self) -> Option<String> {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return None;
+        }
+        let checked = self.checked.load(Ordering::Relaxed).min(total);
+        let primes = self.primes.load(Ordering::Relaxed);
+        Some(format!("Checked {} / {} numbers, {} primes found", grouped(checked), grouped(total), grouped(primes)))
+    }
+}
+
+// The stage and a progress bar like coffee's own, with how many numbers were checked so far under
+// it when there are any
+pub struct LoadingScreen {
+    font: Font,
+    pencil: Image,
+    created: Instant,
+}
+
+impl LoadingScreen {
+    // Loading the default amount of numbers is over before this, and a screen that flashes by is
+    // worse than a blank one
+    const DELAY: Duration = Duration::from_millis(300);
+    const MARGIN: f32 = 50.0;
+    const BAR_HEIGHT: f32 = 50.0;
+    const TEXT_SIZE: f32 = 30.0;
+}
+
+impl loading_screen::LoadingScreen for LoadingScreen {
+    fn new(gpu: &mut Gpu) -> Result<LoadingScreen> {
+        Ok(LoadingScreen {
+            font: Font::from_bytes(gpu, FONT)?,
+            pencil: Image::from_colors(gpu, &[Color::WHITE])?,
+            created: Instant::now(),
+        })
+    }
+
+    fn draw(&mut self, progress: &Progress, frame: &mut Frame) {
+        frame.clear(Color::BLACK);
+        if self.created.elapsed() < Self::DELAY {
+            return;
+        }
+        let middle = frame.height() / 2.0;
+
+        self.pencil.draw(
+            Quad {
+                position: Point::new(Self::MARGIN, middle - Self::BAR_HEIGHT / 2.0),
+                size: ((frame.width() - 2.0 * Self::MARGIN) * progress.percentage() / 100.0, Self::BAR_HEIGHT),
+                ..Quad::default()
+            },
+            &mut frame.as_target(),
+        );
+
+        if let Some(stage) = progress.stage() {
+            self.font.add(Text {
+                content: stage,
+                position: Point::new(Self::MARGIN, middle - Self::BAR_HEIGHT / 2.0 - Self::TEXT_SIZE - 25.0),
+                size: Self::TEXT_SIZE,
+                color: Color::WHITE,
+                ..Text::default()
+            });
+        }
+
+        // Just the percentage when nothing is being counted
+        let percentage = format!("{:.0}%", progress.percentage());
+        let counts = COUNTS.text().map(|counts| format!("{}  {}", percentage, counts));
+        self.font.add(Text {
+            content: counts.as_ref().unwrap_or(&percentage),
+            position: Point::new(Self::MARGIN, middle + Self::BAR_HEIGHT / 2.0 + 25.0),
+            size: Self::TEXT_SIZE,
+            color: Color::WHITE,
+            ..Text::default()
+        });
+
+        self.font.draw(&mut frame.as_target());
+    }
+}
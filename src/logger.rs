@@ -0,0 +1,48 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+// Prints what the app logs to stderr, prefixed with the level like the rest of its messages.
+// Other crates are left out, the graphics ones are very chatty.
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Targets are module paths, which start with the crate's name for both the binary and the library
+        metadata.level() <= log::max_level() && metadata.target().starts_with(env!("CARGO_PKG_NAME"))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let prefix = match record.level() {
+            Level::Error => { "error" }
+            Level::Warn => { "warning" }
+            Level::Info => { "info" }
+            Level::Debug => { "debug" }
+            Level::Trace => { "trace" }
+        };
+        eprintln!("{}: {}", prefix, record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+// Only warnings until `set_verbosity` is called, so the ones from reading the arguments show up
+pub fn init() {
+    // Only fails if it was already set
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(LevelFilter::Warn);
+}
+
+// How many times -v was given: milestones with one, how long every step took with two
+pub fn set_verbosity(verbosity: u8) {
+    let level = match verbosity {
+        0 => { LevelFilter::Warn }
+        1 => { LevelFilter::Info }
+        _ => { LevelFilter::Debug }
+    };
+    log::set_max_level(level);
+}
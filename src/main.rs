@@ -1,209 +1,3215 @@
+mod args;
+mod batch_build;
+mod bindings;
+mod commands;
+mod config_file;
+mod controls;
+mod data_export;
+mod export;
+mod failure;
+mod gaps;
+mod germain;
+mod generation;
+mod loading_screen;
+mod logger;
+mod query;
+mod recording;
+mod rose;
+mod screenshot;
+mod sprites;
+mod stats;
+mod svg;
+mod watcher;
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 use std::env;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
+use clipboard::{ClipboardContext, ClipboardProvider};
 use coffee::{Game, Result, Timer};
 use coffee::graphics::{
-    Batch, Color, Frame, Image, Point, Rectangle, Sprite, Vector, Window,
-    WindowSettings,
+    Batch, Color, Font, Frame, HorizontalAlignment, Image, Mesh, Point, Rectangle,
+    Shape, Sprite, Text, Transformation, Vector, Window, WindowSettings,
 };
-use coffee::input::{keyboard, KeyboardAndMouse};
-use coffee::load::{Join, loading_screen::ProgressBar, Task};
-use primes::PrimeSet;
+use coffee::input::{gamepad, keyboard, mouse};
+use coffee::load::{Join, Task};
+use log::{debug, error, info, warn};
 use rayon::prelude::*;
-use std::cmp::max;
+
+use polar_oxides::cache;
+use polar_oxides::classes::Class;
+use polar_oxides::camera_path::CameraPath;
+use polar_oxides::dataset::{Dataset, DatasetKind, Points};
+use polar_oxides::palette::{CustomPalette, Marker, Palette, PolarOxideColors};
+use polar_oxides::particles::{NumberInfo, Particles};
+use polar_oxides::overlays::{self, NumberList, Overlay, Progression, Quadratic};
+use polar_oxides::predicate::Predicate;
+use polar_oxides::primality::Primality;
+use polar_oxides::special;
+use polar_oxides::view::{Bookmarks, Configs, LastSession, LegendEntry, Preset, Presets, SavedView, Settings};
+
+use args::{Args, ParticlesConfig};
+use batch_build::{BatchBuild, BuiltBatches};
+use bindings::{Action, Bindings};
+use commands::Command;
+use config_file::ConfigFile;
+use controls::Controls;
+use data_export::{DataExport, Format, Metadata};
+use gaps::Gaps;
+use germain::GermainLines;
+use generation::Generation;
+use loading_screen::{LoadingScreen, COUNTS};
+use recording::Session;
+use query::Query;
+use rose::{Rose, RoseFilter};
+use sprites::half_size;
+use stats::{grouped, Stats};
+use watcher::Watcher;
+
+thread_local! {
+    // coffee's `load` doesn't take anything, so this is how the arguments get to it. Loading
+    // happens on the thread that runs the game.
+    static ARGS: RefCell<Option<Args>> = RefCell::new(None);
+}
 
 fn main() -> Result<()> {
-    PolarOxides::run(WindowSettings {
-        title: String::from("Polar Oxides"),
-        size: (1280, 800),
+    logger::init();
+    let args = match Args::from_env() {
+        Ok(args) => { args }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            eprintln!("See `polar-oxides --help` for the options");
+            process::exit(2);
+        }
+    };
+    logger::set_verbosity(args.verbosity);
+    if args.help {
+        print!("{}", args::USAGE);
+        return Ok(());
+    }
+
+    if args.version {
+        println!("polar-oxides {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    if args.dump_config {
+        print!("{}", config_file::dump(&args, &PolarOxides::bindings(&args.keybindings)));
+        return Ok(());
+    }
+
+    if let Some(export) = &args.export {
+        if let Err(error) = export::run(&args, export) {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Input only comes in once there's a window
+    let (record, replay) = (args.record.as_ref(), args.replay.as_ref());
+    Session::hand_over(Session::start(record.map(PathBuf::as_path), replay.map(PathBuf::as_path)));
+    let window_settings = WindowSettings {
+        title: window_title(&args.particles, &args.configs),
+        size: args.window_size,
         resizable: true,
-        fullscreen: false,
-    })
+        fullscreen: args.fullscreen,
+    };
+    ARGS.with(|cell| *cell.borrow_mut() = Some(args));
+    let ran = PolarOxides::run(window_settings);
+    match ran {
+        Ok(()) => { Ok(()) }
+        // Without a window there's nowhere else to show it
+        Err(error @ coffee::Error::WindowCreation(_)) => { Err(error) }
+        // Loading failed, most likely on the GPU, and the terminal might not be where anyone looks
+        Err(error) => {
+            eprintln!("error: couldn't load: {}", error);
+            failure::show(format!("Polar Oxides couldn't load: {}", error))?;
+            process::exit(1);
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-struct Particle {
-    position: Point,
-    is_prime: bool,
+// The range of numbers, the zoom level and what's drawn, to tell windows side by side apart and
+// for screenshots of the whole window. coffee only takes a title when the window is created, so
+// it's the one the spiral starts out with.
+fn window_title(particles: &ParticlesConfig, configs: &Configs) -> String {
+    let numbers = match particles.dataset {
+        DatasetKind::Numbers => { format!("{}..{}", grouped(particles.start.max(1)), grouped(particles.max_number)) }
+        DatasetKind::Gaussian => { format!("Gaussian integers of norm below {}", grouped(particles.max_number)) }
+    };
+    format!(
+        "Polar Oxides — {} — zoom {:.0} — {}",
+        numbers,
+        configs.zoom_level,
+        configs.display_mode.name().to_lowercase(),
+    )
 }
 
-impl Particle {
-    pub fn new(number: f32, prime_tester: &PrimeSet) -> Particle {
-        Particle {
-            position: Point::new(
-                number * number.cos(),
-                number * number.sin(),
-            ),
-            is_prime: prime_tester
-                .find_vec(number as u64)
-                .map(|(_, n)| n == number as u64)
-                .unwrap_or_else(|| false),
+// What's typed in the prompt is for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Prompt {
+    GoTo,
+    Search,
+    BookmarkName,
+    Command,
+}
+
+impl Prompt {
+    // What comes before what's typed
+    pub fn label(self) -> &'static str {
+        match self {
+            Prompt::GoTo => { "Go to: " }
+            Prompt::Search => { "Search: " }
+            Prompt::BookmarkName => { "Bookmark as: " }
+            Prompt::Command => { ":" }
+        }
+    }
+
+    // Numbers for the first two, anything printable for a name or a command
+    pub fn accepts(self, c: char) -> bool {
+        match self {
+            Prompt::GoTo | Prompt::Search => { c.is_ascii_digit() }
+            Prompt::BookmarkName | Prompt::Command => { !c.is_control() }
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-struct Configs {
-    zoom_level: i32,
-    draw_nonprimes: bool,
+// The attract mode: slowly zooms in and out while drifting around where it started
+struct Tour {
+    ticks: u64,
+    start_ticks: u64,
+    start_offset: (f32, f32),
+}
+
+impl Tour {
+    const PERIOD: f32 = 120.0; // seconds for a full zoom in and out
+    const MAX_ZOOM_LEVEL: f32 = 600.0;
+    const DRIFT: f32 = 150.0; // pixels
+    // Moving the camera means rebuilding the batch, so the tour only does it every few ticks
+    const STEP_TICKS: u64 = 2;
+
+    // Starts at the point of the cycle that matches the current zoom level, so nothing jumps
+    pub fn new(configs: &Configs) -> Tour {
+        let cycle = (1.0 - 2.0 * configs.zoom_level / Self::MAX_ZOOM_LEVEL).max(-1.0).min(1.0).acos()
+            / (2.0 * std::f32::consts::PI);
+        let start_ticks = (cycle * Self::PERIOD * f32::from(PolarOxides::TICKS_PER_SECOND)) as u64;
+        Tour {
+            ticks: start_ticks,
+            start_ticks,
+            start_offset: configs.offset,
+        }
+    }
+
+    // How far into the cycle we are, from 0 to 2π
+    fn phase(ticks: u64) -> f32 {
+        let seconds = ticks as f32 / f32::from(PolarOxides::TICKS_PER_SECOND);
+        2.0 * std::f32::consts::PI * (seconds % Self::PERIOD) / Self::PERIOD
+    }
+
+    // A Lissajous curve, in pixels
+    fn drift(ticks: u64) -> (f32, f32) {
+        let phase = Self::phase(ticks);
+        (Self::DRIFT * (3.0 * phase).sin(), Self::DRIFT * (2.0 * phase).sin())
+    }
+
+    pub fn step(&mut self, configs: &mut Configs, settings: Settings) {
+        self.ticks += 1;
+        if self.ticks % Self::STEP_TICKS != 0 {
+            return;
+        }
+
+        // Cosine easing, so it slows down at both ends
+        configs.zoom_level = Self::MAX_ZOOM_LEVEL * (1.0 - Self::phase(self.ticks).cos()) / 2.0;
+
+        let pixel_rate = settings.pixel_rate(configs.zoom_level);
+        let (start_x, start_y) = Self::drift(self.start_ticks);
+        let (x, y) = Self::drift(self.ticks);
+        configs.offset = (
+            self.start_offset.0 + (x - start_x) / pixel_rate,
+            self.start_offset.1 + (y - start_y) / pixel_rate,
+        );
+    }
+}
+
+// Flying along a --play camera path, until it's over or anything is pressed
+struct Playback {
+    path: CameraPath,
+    time: f32,
+    paused: bool,
+}
+
+impl Playback {
+    pub fn new(path: CameraPath) -> Playback {
+        Playback { path, time: 0.0, paused: false }
+    }
+
+    // Moves the camera to where the path is `delta` seconds on, and says whether it got to the end
+    pub fn step(&mut self, configs: &mut Configs, delta: f32) -> bool {
+        if self.paused {
+            return false;
+        }
+        self.time += delta;
+        let (zoom_level, offset) = self.path.sample(self.time);
+        configs.zoom_level = Configs::clamp_zoom_level(zoom_level);
+        configs.offset = offset;
+        self.time >= self.path.duration()
+    }
+}
+
+// The looks, the settings and the size of the window a panel was worked out for
+type PanelView = (Configs, Settings, (f32, f32));
+
+// What can go wrong while the spiral is up, what went wrong being shown in the error banner
+type Fallible = std::result::Result<(), String>;
+
+// A short message that slides into the bottom left corner and fades away, on top of everything
+struct Toast {
+    text: String,
+    // How long it has been up, in seconds
+    age: f32,
 }
 
 struct PolarOxides  {
-    particles: Vec<Particle>,
-    batch: Batch,
+    max_number: u64,
+    // Shared with the sprites being built in the background. With `--mode gaussian` the numbers are
+    // empty and the Gaussian integers are drawn instead.
+    points: Points,
+    // Split up so hiding either kind of number is just not drawing its batch
+    prime_batch: Batch,
+    composite_batch: Batch,
+    highlight_batch: Batch,
+    // Markers over sparse sets of numbers, on top of both kinds of number and built for where the
+    // batches were, see `rebuild_overlays`
+    overlay_batch: Batch,
+    // The swatches of the legend, out of the same colors as the points
+    legend_batch: Batch,
+    font: Font,
+    bindings: Bindings,
     configs: Configs,
     prev_frame_configs: Configs,
+    settings: Settings,
+    primality: Primality,
+    // What the batch was built for. Zooming and panning away from it only moves the camera, until
+    // the view settles down or strays too far.
+    batch_configs: Configs,
+    batch_settings: Settings,
+    batch_size: (f32, f32),
+    // Set when the batch has to be rebuilt for something the comparisons above can't see
+    batch_dirty: bool,
+    // The batches that will replace the ones being drawn, once they are built
+    batch_build: Option<BatchBuild>,
+    data_export: Option<DataExport>,
+    // How long the particles took to find or load at the start, for the metadata of JSON exports
+    generation_time: Duration,
+    view_changed_at: f32,
+    // When the user last did something or something last moved, for drawing less often once idle
+    active_at: f32,
+    drag_anchor: Option<Point>,
+    drag_samples: Vec<(f32, Point)>,
+    momentum: Option<Vector>,
+    // Particles for a different max number, on their way to replace the current ones
+    generation: Option<Generation>,
+    zoom_direction: i32,
+    zoom_coarse: bool,
+    zoom_hold_time: f32,
+    prompt: Option<(Prompt, String)>,
+    // What was wrong with the last command, shown over the command line until it's closed
+    command_error: Option<String>,
+    highlight: Option<(u64, f32)>,
+    search_highlight: Option<(u64, f32)>,
+    hovered: Option<u64>,
+    // The number closest to the cursor, drawn or not, for the status bar
+    closest: Option<u64>,
+    hover_cursor: Point,
+    hover_configs: Configs,
+    selected: Option<NumberInfo>,
+    time: f32,
+    ticks: u64,
+    // How many times `interact` was called, which recordings go by
+    interactions: u64,
+    key_pan: (i32, i32),
+    last_click: Option<(Point, f32)>,
+    recenter_target: Option<(f32, f32)>,
+    gamepad_pan: (f32, f32),
+    gamepad_zoom: f32,
+    revealing: bool,
+    reveal_ticks: u64,
+    // Oldest first
+    toasts: Vec<Toast>,
+    // The last thing that went wrong, shown until it's dismissed
+    error: Option<String>,
+    show_crosshair: bool,
+    // The number closest to the middle of the window, and the offset and angle factor it was looked
+    // up for. The middle is where the offset points, whatever the zoom level and window size.
+    crosshair: Option<u64>,
+    crosshair_view: Option<((f32, f32), f32)>,
+    show_germain_lines: bool,
+    show_special_primes: bool,
+    // The k of the perfect k-th powers marked, if they are
+    power_exponent: Option<u32>,
+    show_fibonacci: bool,
+    show_lucas: bool,
+    // The sides of the polygonal numbers marked, smallest first
+    polygon_sides: Vec<u32>,
+    // The quadratic whose values are marked, if any
+    polynomial: Option<Quadratic>,
+    // The arithmetic progressions marked, each in the next color
+    progressions: Vec<Progression>,
+    // The predicate typed in to pick out numbers, if any
+    predicate: Option<Arc<Predicate>>,
+    // The numbers read from a file
+    number_list: Option<Arc<NumberList>>,
+    germain_lines: Query<PanelView, GermainLines>,
+    show_help: bool,
+    show_hud: bool,
+    show_status_bar: bool,
+    show_stats: bool,
+    show_prime_curve: bool,
+    stats: Query<PanelView, Stats>,
+    show_gap_histogram: bool,
+    // The gap size clicked in the histogram, whose primes are marked
+    picked_gap: Option<u64>,
+    // Counted again when another gap size is picked too
+    gaps: Query<(PanelView, Option<u64>), Gaps>,
+    show_rose: bool,
+    // How many slices the rose has, one of `ROSE_BINS`
+    rose_bins: usize,
+    // It doesn't depend on the camera, so moving around leaves it alone
+    rose: Query<RoseFilter, Rose>,
+    show_legend: bool,
+    // How much longer the legend stays up after the colors changed meaning, and what they meant
+    legend_time: f32,
+    coloring: Vec<LegendEntry>,
+    visible_primes: usize,
+    visible_composites: usize,
+    last_draw: Instant,
+    fps: f32,
+    should_quit: bool,
+    tour: Option<Tour>,
+    playback: Option<Playback>,
+    // For the next run to pick up, see `LastSession`
+    keeps_session: bool,
+    windowed_size: (u32, u32),
+    fullscreen: bool,
+    // Kept around, on X11 what we copied goes away with it
+    clipboard: Option<ClipboardContext>,
+    // The looks kept on the number keys
+    presets: Presets,
+    bookmarks: Bookmarks,
+    // Which bookmark is picked while the list of them is open
+    bookmark_list: Option<usize>,
+    // Edits to these are picked up while running, see `reload_changed_files`
+    config_file: Option<ConfigFile>,
+    palette_path: Option<PathBuf>,
+    watcher: Option<Watcher>,
 }
 
 impl PolarOxides  {
-    const DEFAULT_MAX_NUMBER: u64 = 50_000;
-    const BASE_PIXEL_RATE: f32 = 10.0;
-    const MAX_ZOOM_LEVEL: i32 = 1000;
+    const COARSE_ZOOM_FACTOR: f32 = 5.0; // how much faster zooming is with Shift held
+    const ZOOM_SPEED: f32 = 60.0; // zoom levels per second
+    const ZOOM_EASE_TIME: f32 = 0.5;
+    const PAN_STEP: f32 = 10.0;
+    const FLICK_WINDOW: f32 = 0.1; // seconds of dragging the flick velocity is measured over
+    const MOMENTUM_FRICTION: f32 = 4.0; // how quickly a flick slows down, per second
+    const MOMENTUM_MIN_SPEED: f32 = 5.0; // pixels per second
+    const WHEEL_ZOOM_STEP: f32 = 5.0;
+    const ADD_NUMBERS_BLOCK: u64 = 50_000;
+    const ADD_NUMBERS_COARSE_FACTOR: u64 = 10;
+    const FIT_MARGIN: f32 = 0.05;
+    const MAX_BATCH_STRETCH: f32 = 1.5;
+    const BATCH_SETTLE_TIME: f32 = 0.2; // seconds
+    const GOTO_POINT_SPACING: f32 = 4.0;
+    const HIGHLIGHT_DURATION: f32 = 2.0;
+    const HIGHLIGHT_SCALE: f32 = 8.0;
+    const SEARCH_DURATION: f32 = 4.0;
+    const SEARCH_PULSE_RATE: f32 = 2.0; // pulses per second
+    const ARROW_MARGIN: f32 = 24.0;
+    const ARROW_SIZE: f32 = 16.0;
+    const HOVER_RADIUS: f32 = 6.0;
+    const DOUBLE_CLICK_TIME: f32 = 0.3;
+    const DOUBLE_CLICK_DISTANCE: f32 = 5.0;
+    const RECENTER_SMOOTHING: f32 = 0.2;
+    const GAMEPAD_DEADZONE: f32 = 0.15;
+    const GAMEPAD_PAN_SPEED: f32 = 600.0; // pixels per second at full deflection
+    const REVEAL_SPEED: u64 = 5_000; // numbers per second
+    const TOAST_DURATION: f32 = 3.0;
+    const TOAST_SLIDE_TIME: f32 = 0.2;
+    const TOAST_FADE_TIME: f32 = 0.5;
+    const MAX_TOASTS: usize = 5;
+    const BANNER_HEIGHT: f32 = 34.0;
+    const CROSSHAIR_SIZE: f32 = 10.0; // pixels from the middle to the end of each arm
+    const GERMAIN_LINE_ALPHA: f32 = 0.25;
+    const SPECIAL_RING_RADIUS: f32 = 8.0;
+    // How far apart the rings have to be on screen for their labels to show
+    const SPECIAL_LABEL_SPACING: f32 = 120.0;
+    const STATUS_BAR_HEIGHT: f32 = 24.0;
+    const CURVE_STEP: f32 = 2.0; // pixels between the points of the prime curve
+    const TICK_SIZE: f32 = 4.0;
+    const MIN_TICK_SPACING: f32 = 40.0;
+    const HISTOGRAM_WIDTH: f32 = 320.0;
+    const HISTOGRAM_HEIGHT: f32 = 120.0;
+    const HISTOGRAM_BARS: usize = 24; // the smallest gaps, the rest are too rare to make out
+    const GAP_MARKER_SCALE: f32 = 5.0;
+    const ROSE_BINS: [usize; 3] = [180, 360, 720];
+    const LEGEND_DURATION: f32 = 3.0;
+    const LEGEND_WIDTH: f32 = 280.0;
+    const SWATCH_SIZE: f32 = 12.0;
+    const ROSE_SIZE: f32 = 0.4; // how far the fullest slice reaches, in times the window's smaller side
+    const FPS_SMOOTHING: f32 = 0.05;
+    const IDLE_DELAY: f32 = 1.0; // seconds of nothing happening before drawing slows down
+    const IDLE_FPS: f32 = 10.0;
 
-    pub fn generate_particles() -> Task<Vec<Particle>> {
-        let args: Vec<String> = env::args().collect();
-        let max_number:u64 = if args.len() > 1 {
-            match args[1].trim().parse::<u64>() {
-                Ok(i) => { i }
-                Err(_) => { Self::DEFAULT_MAX_NUMBER }
+    // From the cache when an earlier run left them there, generated (and cached) otherwise. A cache
+    // that can't be read or written is gone around, with what was wrong with it for the error banner.
+    pub fn load_particles(config: ParticlesConfig) -> Task<(Particles, Option<String>)> {
+        let (cached, unreadable) = match Self::read_cache(config) {
+            Ok(cached) => { (cached, None) }
+            Err(error) => {
+                warn!("{}", error);
+                (None, Some(error))
             }
-        } else {
-            Self::DEFAULT_MAX_NUMBER
         };
-        Task::new(move || {
-            let mut prime_tester = PrimeSet::new();
-            let (_, _) = prime_tester.find(max_number);
+        if let Some(particles) = cached {
+            return Task::new(move || (particles.clone(), None));
+        }
 
-            (1..max_number).into_par_iter()
-                .map(|number| Particle::new(number as f32, &prime_tester))
-                .collect()
+        Self::generate_particles(config).map(move |particles| {
+            let unwritable = Self::cache_particles(&particles, config).err();
+            (particles, unreadable.clone().or(unwritable))
         })
     }
 
-    pub fn generate_image() -> Task<Image> {
-        Task::using_gpu( |gpu| Image::from_colors(gpu, &COLORS))
+    // The particles, or for `--mode gaussian` the Gaussian integers. Those are quick enough to find
+    // that they aren't cached.
+    fn load_dataset(config: ParticlesConfig) -> Task<(Points, Option<String>)> {
+        let rebased = move |particles: Particles| Arc::new(particles.rebased(config.radius_base));
+        match config.dataset {
+            DatasetKind::Numbers => {
+                Self::load_particles(config).map(move |(particles, cache_error)| {
+                    (Points::Numbers(rebased(particles)), cache_error)
+                })
+            }
+            DatasetKind::Gaussian => {
+                Self::generate_particles(config).map(move |gaussian| {
+                    let numbers = rebased(Particles::find_between(0, 0, config.primality));
+                    (Points::Gaussian(Arc::new(gaussian), numbers), None)
+                })
+            }
+        }
     }
-}
 
-impl Game for PolarOxides {
-    type Input = KeyboardAndMouse;
-    type LoadingScreen = ProgressBar;
+    // The same, for when there's nowhere to show what's wrong with the cache but the log
+    pub fn cached_particles(config: ParticlesConfig) -> Option<Particles> {
+        Self::read_cache(config).unwrap_or_else(|error| {
+            warn!("{}", error);
+            None
+        })
+    }
 
-    fn load(_window: &Window) -> Task<PolarOxides> {
+    fn read_cache(config: ParticlesConfig) -> std::result::Result<Option<Particles>, String> {
+        if !config.cache_mode.reads() {
+            return Ok(None);
+        }
+        let started = Instant::now();
+        match cache::load(config.start, config.max_number, config.primality) {
+            Ok(Some(particles)) => {
+                info!("Read the numbers up to {} from the cache in {:.2?}", config.max_number, started.elapsed());
+                Ok(Some(particles))
+            }
+            // The first run for these numbers, nothing to worry about
+            Ok(None) => {
+                info!("The numbers up to {} aren't cached yet", config.max_number);
+                Ok(None)
+            }
+            Err(error) => { Err(format!("ignoring the cached particles: {}", error)) }
+        }
+    }
+
+    pub fn cache_particles(particles: &Particles, config: ParticlesConfig) -> Fallible {
+        if config.cache_mode.writes() {
+            let started = Instant::now();
+            let path = cache::store(particles, config.start, config.max_number, config.primality)
+                .map_err(|error| format!("couldn't cache the particles: {}", error))?;
+            debug!("Cached the particles to {} in {:.2?}", path.display(), started.elapsed());
+        }
+        Ok(())
+    }
+
+    // Finding the primes is what takes long, so both ways of loading the particles say how long
+    pub fn log_found_particles(config: ParticlesConfig, time: Duration) {
+        info!(
+            "Found the primes from {} to {} with {} in {:.2?}",
+            config.start, config.max_number, config.primality.name(), time,
+        );
+    }
+
+    // Generated a slice at a time, so the loading screen has something to show and count. The first
+    // slice notes when it started, for the whole of it to be timed.
+    pub fn generate_particles<D: Dataset>(config: ParticlesConfig) -> Task<D> {
+        let ParticlesConfig { start, max_number, primality, .. } = config;
+        let mut task = Task::new(move || {
+            COUNTS.start(max_number.saturating_sub(start));
+            (Instant::now(), D::find_between(start, start, primality))
+        });
+        for (start, end) in generation::chunks(start, max_number) {
+            let chunk = Task::new(move || {
+                let started = Instant::now();
+                let particles = D::find_between(start, end, primality);
+                debug!("Found the primes from {} to {} in {:.2?}", start, end, started.elapsed());
+                COUNTS.add(end - start, particles.prime_count());
+                particles
+            });
+            task = (task, chunk).join().map(|((started, mut particles), more_particles)| {
+                particles.append(more_particles);
+                (started, particles)
+            });
+        }
+        task.map(move |(started, particles)| {
+            Self::log_found_particles(config, started.elapsed());
+            particles
+        })
+    }
+
+    // keybindings.toml, with the config file's bindings on top
+    pub fn bindings(keybindings: &toml::value::Table) -> Bindings {
+        let mut bindings = Bindings::load();
+        for (name, value) in keybindings {
+            // Already checked along with the rest of the config file
+            let _ = bindings.bind(name, value);
+        }
+        bindings
+    }
+
+    // None if the saved ones can't be read
+    pub fn bookmarks() -> Bookmarks {
+        Bookmarks::load().unwrap_or_else(|error| {
+            warn!("starting without bookmarks: {}", error);
+            Bookmarks::default()
+        })
+    }
+
+    // Watches the config file and the palette file, if there are any
+    fn watcher(args: &Args) -> Option<Watcher> {
+        let config_path = args.config_file.as_ref().map(|config_file| config_file.path().to_path_buf());
+        let paths: Vec<PathBuf> = config_path.into_iter().chain(args.palette_path.clone()).collect();
+        if paths.is_empty() {
+            return None;
+        }
+        Watcher::start(paths).map_err(|error| eprintln!("error: edits won't be reloaded: {}", error)).ok()
+    }
+
+    // The built-in presets if the saved ones can't be read
+    pub fn presets() -> Presets {
+        Presets::load().unwrap_or_else(|error| {
+            warn!("using the built-in presets: {}", error);
+            Presets::default()
+        })
+    }
+
+    // `update` runs at a fixed rate, so every call advances the same amount of time
+    pub fn tick_delta() -> f32 {
+        1.0 / f32::from(Self::TICKS_PER_SECOND)
+    }
+
+    // Holding Shift zooms in coarser steps
+    fn zoom_speed_factor(&self) -> f32 {
+        if self.zoom_coarse { Self::COARSE_ZOOM_FACTOR } else { 1.0 }
+    }
+
+    // The numbers, empty with `--mode gaussian`
+    fn particles(&self) -> &Arc<Particles> {
+        self.points.numbers()
+    }
+
+    // Centers the spiral and picks the zoom level that makes all of it fit inside the window
+    pub fn zoom_to_fit(&mut self, window: &Window) {
+        let half_extent = window.width().min(window.height()) / 2.0 * (1.0 - Self::FIT_MARGIN);
+        let zoom_level = self.settings.zoom_level_for(half_extent / self.points.bounding_radius().max(1.0));
+
+        self.configs.zoom_level = Configs::clamp_zoom_level(zoom_level);
+        self.configs.offset = (0.0, 0.0);
+    }
+
+    // The visible number closest to a pixel, as long as it is within `HOVER_RADIUS` pixels of it
+    pub fn number_at(&self, point: Point, width: f32, height: f32) -> Option<u64> {
+        let target = self.configs.spiral_position(self.settings, point, width, height);
+        let max_distance = Self::HOVER_RADIUS / self.settings.pixel_rate(self.configs.zoom_level);
+        let (configs, particles) = (self.configs, &**self.particles());
+
+        particles
+            .nearest(target, max_distance, configs.angle_factor, |number, is_prime| {
+                configs.shows_number(particles, number, is_prime)
+            })
+            .map(|particle| particle.number)
+    }
+
+    // Which of the prime and the composite batches have to be rebuilt, rather than just drawn
+    // somewhere else. Looks only change one of them, the rest changes both.
+    fn batches_to_rebuild(&self, width: f32, height: f32) -> (bool, bool) {
+        if self.batches_need_rebuild(width, height) {
+            return (true, true);
+        }
         (
-            Task::stage(
-                "Finding primes and generating points...",
-                Self::generate_particles(),
-            ),
-            Task::stage(
-                "Generating image...",
-                Self::generate_image()
-            )
+            sprites::appearance(self.configs, true) != sprites::appearance(self.batch_configs, true)
+                || self.configs.classification != self.batch_configs.classification
+                || self.configs.highlighted_only != self.batch_configs.highlighted_only
+                || self.configs.gap_colors != self.batch_configs.gap_colors
+                || self.coloring_changed()
+                || self.residues_changed(),
+            sprites::appearance(self.configs, false) != sprites::appearance(self.batch_configs, false)
+                || self.coloring_changed()
+                || self.residues_changed(),
         )
-        .join()
-        .map(|(particles, image)| PolarOxides {
-            particles,
-            batch: Batch::new(image),
-            configs: Configs {
-                zoom_level: 0,
-                draw_nonprimes: true,
-            },
-            prev_frame_configs: Configs {
-                zoom_level: -1,
-                draw_nonprimes: true,
+    }
+
+    // Only the colors change with the coloring, of both kinds of numbers, the base of the digits
+    // included
+    fn coloring_changed(&self) -> bool {
+        (self.configs.coloring, self.configs.digit_base) != (self.batch_configs.coloring, self.batch_configs.digit_base)
+    }
+
+    // Only the colors change with the residues, of both kinds of numbers
+    fn residues_changed(&self) -> bool {
+        (self.configs.residues, self.configs.modulus) != (self.batch_configs.residues, self.batch_configs.modulus)
+    }
+
+    // Whether the batches can't just be moved around to show the current view anymore
+    fn batches_need_rebuild(&self, width: f32, height: f32) -> bool {
+        // What's left once the camera and the looks of the points are taken out changes which
+        // sprites there are. The display mode only picks which batches get drawn.
+        let contents = Configs {
+            zoom_level: self.batch_configs.zoom_level,
+            classification: self.batch_configs.classification,
+            highlighted_only: self.batch_configs.highlighted_only,
+            residues: self.batch_configs.residues,
+            modulus: self.batch_configs.modulus,
+            gap_colors: self.batch_configs.gap_colors,
+            coloring: self.batch_configs.coloring,
+            digit_base: self.batch_configs.digit_base,
+            offset: self.batch_configs.offset,
+            display_mode: self.batch_configs.display_mode,
+            prime_scale: self.batch_configs.prime_scale,
+            composite_scale: self.batch_configs.composite_scale,
+            theme: self.batch_configs.theme,
+            palette: self.batch_configs.palette,
+            ..self.configs
+        };
+        if self.batch_dirty
+            || contents != self.batch_configs
+            || self.settings != self.batch_settings
+            || (width, height) != self.batch_size
+        {
+            return true;
+        }
+
+        // Points keep the same size in pixels whatever the zoom, but scaling the batch scales them too
+        let stretch = self.batch_stretch();
+        if stretch > Self::MAX_BATCH_STRETCH || stretch < 1.0 / Self::MAX_BATCH_STRETCH {
+            return true;
+        }
+
+        // The batch holds everything within `kept_half_extents` of where it was built, stretched
+        // along with it. The window has to stay inside of that.
+        let (kept_width, kept_height) = Configs::kept_half_extents(width, height);
+        let pixel_rate = self.settings.pixel_rate(self.configs.zoom_level);
+        let shift = Vector::new(
+            self.configs.offset.0 - self.batch_configs.offset.0,
+            self.configs.offset.1 - self.batch_configs.offset.1,
+        ) * pixel_rate;
+        if width / 2.0 + shift.x.abs() > kept_width * stretch || height / 2.0 + shift.y.abs() > kept_height * stretch {
+            return true;
+        }
+
+        // Once the camera stops, the batches are rebuilt to get the point sizes and the HUD right again
+        let camera_moved = (self.configs.zoom_level, self.configs.offset)
+            != (self.batch_configs.zoom_level, self.batch_configs.offset);
+        camera_moved && self.time - self.view_changed_at >= Self::BATCH_SETTLE_TIME
+    }
+
+    // How much bigger the view is than when the batch was built
+    fn batch_stretch(&self) -> f32 {
+        self.settings.pixel_rate(self.configs.zoom_level) / self.settings.pixel_rate(self.batch_configs.zoom_level)
+    }
+
+    // Starts building the batches for the current view in the background, unless what is being built
+    // already will do. Looks only change one of the batches, so that one is built for where the other
+    // one was, for both to keep moving together.
+    fn rebuild_batches(&mut self, width: f32, height: f32, primes: bool, composites: bool) {
+        let both = primes && composites;
+        let configs = if both {
+            self.configs
+        } else {
+            Configs { zoom_level: self.batch_configs.zoom_level, offset: self.batch_configs.offset, ..self.configs }
+        };
+        let size = (width, height);
+        let covered = self.batch_build.as_ref()
+            .map_or(false, |build| build.covers(configs, self.settings, size, primes, composites));
+        if !covered {
+            let build = BatchBuild::start(self.points.clone(), configs, self.settings, size, primes, composites);
+            self.batch_build = Some(build);
+        }
+    }
+
+    // Puts the built sprites in place of the ones being drawn
+    fn swap_batches(&mut self, built: BuiltBatches) {
+        let swapping = Instant::now();
+        let (mut sprites, mut building) = (0, Duration::from_secs(0));
+        if let Some(primes) = built.primes {
+            sprites += primes.sprites.len();
+            building += primes.time;
+            self.prime_batch.clear();
+            self.prime_batch.par_extend(primes.sprites);
+            self.visible_primes = primes.visible;
+        }
+
+        if let Some(composites) = built.composites {
+            sprites += composites.sprites.len();
+            building += composites.time;
+            self.composite_batch.clear();
+            self.composite_batch.par_extend(composites.sprites);
+            self.visible_composites = composites.visible;
+        }
+        debug!("Swapped the batches in {:.2?}", swapping.elapsed());
+        info!(
+            "Rebuilt the batches with {} sprites in {:.2?}, {:.2?} of it working them out",
+            sprites, built.started.elapsed(), building,
+        );
+
+        self.batch_configs = built.configs;
+        self.batch_settings = built.settings;
+        self.batch_size = built.size;
+        // Anything that dirtied the batches since would have thrown this build away
+        self.batch_dirty = false;
+        self.rebuild_overlays();
+    }
+
+    // The sets of numbers marked on top of the points, in the order they are drawn
+    fn overlays(&self) -> Vec<Overlay> {
+        let mut overlays: Vec<Overlay> = self.power_exponent.into_iter().map(Overlay::Powers).collect();
+        overlays.extend(self.polygon_sides.iter().map(|&sides| Overlay::Polygonal(sides)));
+        overlays.extend(self.polynomial.map(Overlay::Polynomial));
+        overlays.extend(self.progressions.iter().enumerate().map(|(index, &progression)| {
+            Overlay::Progression(index, progression)
+        }));
+        overlays.extend(self.predicate.clone().map(Overlay::Predicate));
+        overlays.extend(self.number_list.clone().map(Overlay::List));
+        if self.show_lucas {
+            overlays.push(Overlay::Lucas);
+        }
+        if self.show_fibonacci {
+            overlays.push(Overlay::Fibonacci);
+        }
+        overlays
+    }
+
+    // For when another overlay is shown or hidden: the stats count the overlays' numbers too
+    fn overlays_changed(&mut self) {
+        self.rebuild_overlays();
+        self.stats.invalidate();
+    }
+
+    // How many of the numbers read from a file the spiral doesn't get to, for when it changed. The
+    // arguments tell about them at the start.
+    fn report_unlisted_numbers(&mut self) {
+        let (start, last) = (self.particles().start(), self.particles().last_number());
+        let message = self.number_list.as_ref()
+            .map(|list| (list, list.outside(start, last.saturating_add(1))))
+            .filter(|&(_, outside)| outside > 0)
+            .map(|(list, outside)| {
+                format!("{} of the {} numbers in {} are outside {} to {}", outside, list.len(), list.name, start, last)
+            });
+        if let Some(message) = message {
+            self.notify(message);
+        }
+    }
+
+    // The overlays are sparse, so they are built right away for where the batches were built, and
+    // moved around with them
+    fn rebuild_overlays(&mut self) {
+        self.overlay_batch.clear();
+        let (width, height) = self.batch_size;
+        let (palette, theme) = (self.batch_configs.palette, self.batch_configs.theme);
+        let particles = &**self.points.numbers();
+        for overlay in self.overlays() {
+            let markers = sprites::overlay_sprites(
+                particles, self.batch_configs, self.batch_settings, width, height,
+                |start, end| overlay.numbers(start, end),
+                |number| PolarOxideColors::marker_source(overlay.marker(particles.is_prime(number)), palette, theme),
+            );
+            self.overlay_batch.extend(markers);
+        }
+    }
+
+    // For when the batches have to be rebuilt for something `batches_need_rebuild` can't see. A build
+    // that's underway is for what was there before, so it is thrown away.
+    fn invalidate_batches(&mut self) {
+        self.batch_dirty = true;
+        self.batch_build = None;
+    }
+
+    // Whether anything on screen is moving or counting down by itself, the batches being built included
+    fn is_animating(&self) -> bool {
+        self.tour.is_some()
+            || self.playback.as_ref().map_or(false, |playback| !playback.paused)
+            || self.revealing
+            || self.momentum.is_some()
+            || self.recenter_target.is_some()
+            || self.drag_anchor.is_some()
+            || self.zoom_direction != 0
+            || self.key_pan != (0, 0)
+            || self.gamepad_pan != (0.0, 0.0)
+            || self.gamepad_zoom != 0.0
+            || self.generation.is_some()
+            || self.batch_build.is_some()
+            || self.data_export.is_some()
+            || self.highlight.is_some()
+            || self.search_highlight.is_some()
+            || !self.toasts.is_empty()
+            || self.legend_time > 0.0
+    }
+
+    // Nothing has happened for a while, so the same picture would be drawn over and over
+    fn is_idle(&self) -> bool {
+        self.time - self.active_at.max(self.view_changed_at) >= Self::IDLE_DELAY
+    }
+
+    // Moves the batch from where it was built to where the camera is now
+    fn camera_transformation(&self, width: f32, height: f32) -> Transformation {
+        let pixel_rate = self.settings.pixel_rate(self.configs.zoom_level);
+        let shift = Vector::new(
+            self.configs.offset.0 - self.batch_configs.offset.0,
+            self.configs.offset.1 - self.batch_configs.offset.1,
+        ) * pixel_rate;
+        Transformation::translate(Vector::new(width / 2.0, height / 2.0) + shift)
+            * Transformation::scale(self.batch_stretch())
+    }
+
+    // The highlighted and selected numbers' markers, and the primes starting the picked gap size
+    pub fn markers(&self, width: f32, height: f32) -> Vec<Sprite> {
+        let mut markers = Vec::new();
+        // Once they've been found, and under the rest
+        let gaps = self.gaps.result().filter(|gaps| self.show_gap_histogram && gaps.picked == self.picked_gap);
+        for &prime in gaps.map_or(&[][..], |gaps| &gaps.starts) {
+            let mut marker = self.marker(prime, Marker::Gap, width, height);
+            marker.position = marker.position + half_size(Self::HIGHLIGHT_SCALE) - half_size(Self::GAP_MARKER_SCALE);
+            marker.scale = (Self::GAP_MARKER_SCALE, Self::GAP_MARKER_SCALE);
+            markers.push(marker);
+        }
+
+        if let Some((number, _)) = self.highlight {
+            markers.push(self.marker(number, Marker::Highlight, width, height));
+        }
+
+        if let Some(selected) = &self.selected {
+            markers.push(self.marker(selected.number, Marker::Selection, width, height));
+        }
+
+        if let Some((number, _)) = self.search_highlight {
+            let mut marker = self.marker(number, Marker::Search, width, height);
+            let pulse = 1.0 + 0.5 * (self.time * Self::SEARCH_PULSE_RATE * 2.0 * std::f32::consts::PI).sin();
+            let scale = Self::HIGHLIGHT_SCALE * pulse;
+            marker.position = marker.position + half_size(Self::HIGHLIGHT_SCALE) - half_size(scale);
+            marker.scale = (scale, scale);
+            markers.push(marker);
+        }
+        markers
+    }
+
+    // A big square drawn over a number to make it stand out
+    pub fn marker(&self, number: u64, marker: Marker, width: f32, height: f32) -> Sprite {
+        let position_in_spiral = self.particles().position_of(number, self.configs.angle_factor);
+        let position = self.configs.screen_position(self.settings, position_in_spiral, width, height);
+        Sprite {
+            source: Rectangle {
+                x: PolarOxideColors::marker_source(marker, self.configs.palette, self.configs.theme),
+                y: 0,
+                width: 1,
+                height: 1,
             },
-        })
+            position: position - half_size(Self::HIGHLIGHT_SCALE),
+            scale: (Self::HIGHLIGHT_SCALE, Self::HIGHLIGHT_SCALE),
+        }
     }
 
-    fn draw(&mut self, frame: &mut Frame, _timer: &Timer) {
-        frame.clear(PolarOxideColors::BLACK);
+    // A triangle at the edge of the window pointing towards the searched number, if it is off-screen
+    pub fn search_arrow(&self, width: f32, height: f32) -> Option<Mesh> {
+        let (number, _) = self.search_highlight?;
+        let position_in_spiral = self.particles().position_of(number, self.configs.angle_factor);
+        let target = self.configs.screen_position(self.settings, position_in_spiral, width, height);
+        if target.x >= 0.0 && target.x <= width && target.y >= 0.0 && target.y <= height {
+            return None;
+        }
 
-        // Only update things if zoom has changed
-        if self.configs != self.prev_frame_configs {
-            let x_origin = frame.width() / 2.0;
-            let y_origin = frame.height() / 2.0;
+        let center = Point::new(width / 2.0, height / 2.0);
+        let direction = (target - center).normalize();
+        // Scaled until it hits whichever edge comes first
+        let reach = ((width / 2.0 - Self::ARROW_MARGIN) / direction.x.abs())
+            .min((height / 2.0 - Self::ARROW_MARGIN) / direction.y.abs());
+        let tip = center + direction * reach;
+        let back = tip - direction * Self::ARROW_SIZE;
+        let side = Vector::new(-direction.y, direction.x) * (Self::ARROW_SIZE / 2.0);
 
-            let pixel_rate = Self::BASE_PIXEL_RATE / 1.02_f32.powi(self.configs.zoom_level);
-            let centralize_vector = Vector::new(x_origin, y_origin);
+        let mut arrow = Mesh::new();
+        arrow.fill(
+            Shape::Polyline { points: vec![tip, back + side, back - side] },
+            PolarOxideColors::MAGENTA,
+        );
+        Some(arrow)
+    }
 
-            let draw_nonprime = self.configs.draw_nonprimes;
-            let frame_bound = max(frame.width() as i32, frame.height() as i32) as f32;
+    // Highlights `number` wherever it is, without moving the camera
+    pub fn search_number(&mut self, number: u64) {
+        if self.particles().get(number, self.configs.angle_factor).is_none() {
+            self.notify(format!("{} isn't in the spiral", number));
+            return;
+        }
+        self.search_highlight = Some((number, Self::SEARCH_DURATION));
+    }
 
-            let sprites = self.particles.par_iter()
-                .filter(|particle| {
-                    let max_dim = max((particle.position * pixel_rate).x.abs() as i32,
-                                      (particle.position * pixel_rate).y.abs() as i32) as f32;
-                    max_dim >= 1.0 && max_dim / 2.0 <= frame_bound && (particle.is_prime || draw_nonprime)
-                })
-                .map(|particle| {
-                    Sprite {
-                        source: Rectangle {
-                            x: if particle.is_prime {
-                                    PolarOxideColors::index_of(PolarOxideColors::BLUE)
-                                } else {
-                                    PolarOxideColors::index_of(PolarOxideColors::YELLOW)
-                                },
-                            y: 0,
-                            width: 1,
-                            height: 1,
-                        },
-                        position: particle.position * pixel_rate + centralize_vector,
-                        scale: (2.0, 2.0)
-                    }
-                });
+    // Centers the view on `number`, zoomed in enough to tell it apart from the points around it.
+    // Numbers we haven't generated only get a toast saying so.
+    pub fn go_to_number(&mut self, number: u64) {
+        let position = match self.particles().get(number, self.configs.angle_factor) {
+            Some(particle) => { particle.position }
+            None => {
+                self.notify(format!("{} isn't in the spiral", number));
+                return;
+            }
+        };
+        // Around radius r the spiral holds one point per 2πr of area
+        let point_spacing = (2.0 * std::f32::consts::PI * position.coords.norm().max(1.0)).sqrt();
+        let zoom_level = self.settings.zoom_level_for(Self::GOTO_POINT_SPACING / point_spacing);
+
+        self.configs.zoom_level = Configs::clamp_zoom_level(zoom_level);
+        self.configs.offset = (-position.x, -position.y);
+        self.highlight = Some((number, Self::HIGHLIGHT_DURATION));
+    }
+
+    // The number closest to the middle of the window. Numbers sit at a radius equal to themselves,
+    // less the base, so it is just the distance from the origin to the center of the view, rounded.
+    pub fn centered_number(&self) -> u64 {
+        let (x, y) = self.configs.offset;
+        let radius = (x * x + y * y).sqrt().round() as u64;
+        let particles = self.particles();
+        (radius + particles.radius_base()).max(particles.start().max(1)).min(particles.last_number())
+    }
+
+    // Goes to the prime right after (or before) the centered one, staying put at the ends of the spiral
+    pub fn go_to_neighbor_prime(&mut self, forward: bool) {
+        let center = self.centered_number();
+        let neighbor = if forward {
+            self.particles().next_prime(center)
+        } else {
+            self.particles().previous_prime(center)
+        };
+
+        match neighbor {
+            Some(prime) => { self.go_to_number(prime); }
+            None => {
+                let notice = if forward { "No primes after" } else { "No primes before" };
+                self.notify(format!("{} {}", notice, center));
+            }
+        }
+    }
+
+    // How fast the cursor was moving over the last moments of a drag, in pixels per second
+    fn flick_velocity(&self) -> Option<Vector> {
+        let &(first_time, first) = self.drag_samples.first()?;
+        let &(last_time, last) = self.drag_samples.last()?;
+        let elapsed = last_time - first_time;
+        // Holding still before letting go doesn't flick
+        if elapsed <= 0.0 || self.time - last_time > Self::FLICK_WINDOW / 2.0 {
+            return None;
+        }
 
-            self.batch.clear();
-            self.batch.par_extend(sprites);
+        let velocity = (last - first) / elapsed;
+        if velocity.norm() < Self::MOMENTUM_MIN_SPEED {
+            None
+        } else {
+            Some(velocity)
         }
-        self.batch.draw(&mut frame.as_target());
-        self.prev_frame_configs = self.configs
     }
 
-    fn interact(&mut self, input: &mut KeyboardAndMouse, window: &mut Window) {
-        if input.is_key_pressed(keyboard::KeyCode::W) {
-            if self.configs.zoom_level > 0 {
-                self.configs.zoom_level -= 1;
+    // Changes the zoom level while keeping the spiral point under `anchor` at the same place on screen
+    pub fn zoom_towards(&mut self, zoom_level: f32, anchor: Point, window: &Window) {
+        let zoom_level = Configs::clamp_zoom_level(zoom_level);
+        let old_rate = self.settings.pixel_rate(self.configs.zoom_level);
+        let new_rate = self.settings.pixel_rate(zoom_level);
+
+        let anchor_x = anchor.x - window.width() / 2.0;
+        let anchor_y = anchor.y - window.height() / 2.0;
+        self.configs.offset.0 += anchor_x / new_rate - anchor_x / old_rate;
+        self.configs.offset.1 += anchor_y / new_rate - anchor_y / old_rate;
+        self.configs.zoom_level = zoom_level;
+    }
+
+    fn interact_mouse(&mut self, input: &Controls, window: &Window) {
+        // Scrolling up zooms in, towards whatever is under the cursor
+        let wheel_movement = input.wheel_movement();
+        if wheel_movement != 0.0 {
+            let zoom_delta = -wheel_movement * Self::WHEEL_ZOOM_STEP;
+            self.zoom_towards(self.configs.zoom_level + zoom_delta, input.cursor_position(), window);
+        }
+
+        // Dragging moves the spiral along with the cursor, pixel by pixel
+        if input.is_mouse_pressed(mouse::Button::Left) {
+            let cursor = input.cursor_position();
+            if let Some(anchor) = self.drag_anchor {
+                self.recenter_target = None;
+                let pixel_rate = self.settings.pixel_rate(self.configs.zoom_level);
+                self.configs.offset.0 += (cursor.x - anchor.x) / pixel_rate;
+                self.configs.offset.1 += (cursor.y - anchor.y) / pixel_rate;
+            }
+            self.drag_anchor = Some(cursor);
+
+            let time = self.time;
+            self.drag_samples.retain(|&(sample_time, _)| time - sample_time <= Self::FLICK_WINDOW);
+            self.drag_samples.push((time, cursor));
+        } else if self.drag_anchor.take().is_some() {
+            // Letting go while the cursor is still moving flicks the view
+            self.momentum = self.flick_velocity();
+            self.drag_samples.clear();
+        }
+
+        // Looking for the hovered number means working out where every number around the cursor
+        // is, so only do it when the cursor or the view actually changed
+        let cursor = input.cursor_position();
+        if cursor != self.hover_cursor || self.configs != self.hover_configs {
+            self.hovered = self.number_at(cursor, window.width(), window.height());
+            if self.show_status_bar {
+                let target = self.configs.spiral_position(self.settings, cursor, window.width(), window.height());
+                self.closest = self.particles().closest_number(target, self.configs.angle_factor);
+            }
+            self.hover_cursor = cursor;
+            self.hover_configs = self.configs;
+        }
+
+        // Clicking a number selects it, clicking anywhere else clears the selection
+        if let Some(&click) = input.left_clicks().last() {
+            // Except on a bar of the gap histogram, which picks its gap size or lets go of it
+            if let Some(gap) = self.gap_bar_at(click, window.height()) {
+                self.picked_gap = if self.picked_gap == Some(gap) { None } else { Some(gap) };
+                return;
+            }
+
+            self.selected = self.number_at(click, window.width(), window.height())
+                .map(|number| NumberInfo::new(number, self.particles()));
+
+            // A second click close enough in time and space recenters the view on it
+            let is_double_click = match self.last_click {
+                Some((previous, time)) => {
+                    self.time - time <= Self::DOUBLE_CLICK_TIME
+                        && (click - previous).norm() <= Self::DOUBLE_CLICK_DISTANCE
+                }
+                None => { false }
+            };
+
+            if is_double_click {
+                let target = self.configs.spiral_position(self.settings, click, window.width(), window.height());
+                self.recenter_target = Some((-target.x, -target.y));
+                self.last_click = None;
+            } else {
+                self.last_click = Some((click, self.time));
             }
         }
+    }
 
-        if input.is_key_pressed(keyboard::KeyCode::S) {
-            if self.configs.zoom_level <= Self::MAX_ZOOM_LEVEL {
-                self.configs.zoom_level += 1;
+    fn interact_gamepad(&mut self, input: &Controls, window: &mut Window) {
+        // Sticks rarely rest at exactly zero, so small deflections are ignored
+        let deadzone = |value: f32| {
+            if value.abs() < Self::GAMEPAD_DEADZONE {
+                0.0
+            } else {
+                value.signum() * (value.abs() - Self::GAMEPAD_DEADZONE) / (1.0 - Self::GAMEPAD_DEADZONE)
             }
+        };
+
+        self.gamepad_pan = (
+            deadzone(input.gamepad_axis(gamepad::Axis::LeftStickX)),
+            deadzone(input.gamepad_axis(gamepad::Axis::LeftStickY)),
+        );
+        // Right stick up or the right trigger zoom in, right stick down or the left trigger zoom out
+        self.gamepad_zoom = -deadzone(input.gamepad_axis(gamepad::Axis::RightStickY))
+            + input.gamepad_button(gamepad::Button::LeftTrigger2)
+            - input.gamepad_button(gamepad::Button::RightTrigger2);
+
+        if input.was_gamepad_button_released(gamepad::Button::South) {
+            self.configs.display_mode = self.configs.display_mode.next();
+        }
+
+        if input.was_gamepad_button_released(gamepad::Button::West) {
+            self.configs.reset_view();
+        }
+
+        if input.was_gamepad_button_released(gamepad::Button::North) {
+            self.toggle_fullscreen(window);
+        }
+    }
+
+    // Everything the help overlay lists: the controls and the current settings
+    // The HUD, the stats and what's going on, stacked down from the top right corner
+    fn draw_top_right(&mut self, width: f32, foreground: Color) {
+        let mut top_right = 10.0;
+        if self.show_hud {
+            let display_mode = self.configs.display_mode;
+            let visible_primes = if display_mode.shows(true) { self.visible_primes } else { 0 };
+            let visible_composites = if display_mode.shows(false) { self.visible_composites } else { 0 };
+            let hud = format!(
+                "Max number: {}\nZoom level: {:.1}\nPixel rate: {:.4}\nVisible points: {}\nVisible primes: {}\nFPS: {:.0}",
+                self.particles().last_number(),
+                self.configs.zoom_level,
+                self.settings.pixel_rate(self.configs.zoom_level),
+                visible_primes + visible_composites,
+                visible_primes,
+                self.fps,
+            );
+            self.font.add(Text {
+                content: &hud,
+                position: Point::new(width - 10.0, top_right),
+                size: 20.0,
+                color: foreground,
+                horizontal_alignment: HorizontalAlignment::Right,
+                ..Text::default()
+            });
+            top_right += 6.0 * 22.0;
         }
 
-        if input.was_key_released(keyboard::KeyCode::F) {
-            window.toggle_fullscreen();
-            self.configs.zoom_level += 1;
+        if self.show_stats {
+            let text = self.stats.result().map_or_else(|| String::from("Counting..."), Stats::to_text);
+            self.font.add(Text {
+                content: &text,
+                position: Point::new(width - 10.0, top_right),
+                size: 20.0,
+                color: foreground,
+                horizontal_alignment: HorizontalAlignment::Right,
+                ..Text::default()
+            });
+            top_right += text.lines().count() as f32 * 22.0;
         }
 
-        if input.was_key_released(keyboard::KeyCode::D) {
-            self.configs.draw_nonprimes = !self.configs.draw_nonprimes;
+        if self.configs.reveal_cutoff < self.points.point_range().1 {
+            self.font.add(Text {
+                content: &format!("n = {}{}", self.configs.reveal_cutoff, if self.revealing { "" } else { " (paused)" }),
+                position: Point::new(width - 10.0, top_right),
+                size: 20.0,
+                color: foreground,
+                horizontal_alignment: HorizontalAlignment::Right,
+                ..Text::default()
+            });
+            top_right += 22.0;
         }
 
-        if input.was_key_released(keyboard::KeyCode::Escape) {
-            process::exit(0);
+        if let Some(generation) = &self.generation {
+            self.font.add(Text {
+                content: &format!("Generating {} points...", generation.max_number()),
+                position: Point::new(width - 10.0, top_right),
+                size: 20.0,
+                color: foreground,
+                horizontal_alignment: HorizontalAlignment::Right,
+                ..Text::default()
+            });
+        }
+    }
+
+    // π(r), how many primes there are up to each radius, plotted from the origin of the spiral with r
+    // going right and the count going up, at the spiral's own scale so it follows the camera around.
+    // Only the primes in the spiral are counted, and the axes have ticks at powers of 10.
+    fn draw_prime_curve(&mut self, frame: &mut Frame, foreground: Color) {
+        let (width, height) = (frame.width(), frame.height());
+        let pixel_rate = self.settings.pixel_rate(self.configs.zoom_level);
+        let origin = self.configs.screen_position(self.settings, Point::new(0.0, 0.0), width, height);
+        let base = self.particles().radius_base();
+        let axis_color = Color { a: 0.6, ..foreground };
+        let mut mesh = Mesh::new();
+
+        // To the edges of the window, or just the origin when it's past them
+        let right = Point::new(width.max(origin.x), origin.y);
+        let up = Point::new(origin.x, origin.y.min(0.0));
+        mesh.stroke(Shape::Polyline { points: vec![origin, right] }, axis_color, 1);
+        mesh.stroke(Shape::Polyline { points: vec![origin, up] }, axis_color, 1);
+
+        // A point every couple of pixels, from where the window or the spiral starts to where either ends
+        let first_x = origin.x.max(0.0);
+        let last_x = width.min(origin.x + self.particles().bounding_radius() * pixel_rate);
+        if first_x < last_x {
+            let samples = ((last_x - first_x) / Self::CURVE_STEP) as usize + 1;
+            let numbers: Vec<u64> = (0..=samples)
+                .map(|sample| (first_x + sample as f32 * Self::CURVE_STEP).min(last_x))
+                .map(|x| ((x - origin.x) / pixel_rate).round() as u64 + base)
+                .collect();
+            let counts = self.particles().primes_up_to(&numbers);
+            let points = numbers.iter().zip(counts)
+                .map(|(&number, count)| {
+                    Point::new(origin.x + (number - base) as f32 * pixel_rate, origin.y - count as f32 * pixel_rate)
+                })
+                .collect();
+            mesh.stroke(Shape::Polyline { points }, self.configs.curve_color(), 2);
+        }
+
+        // Only as far down as they can be told apart, the powers of 10 crowd together near 0
+        for power in 0..20 {
+            let value = 10u64.pow(power);
+            let distance = value as f32 * pixel_rate;
+            if distance * 0.9 < Self::MIN_TICK_SPACING && power > 0 {
+                continue;
+            }
+            let label = if power == 0 { String::from("1") } else { format!("1e{}", power) };
+            let x = origin.x + distance;
+            if x >= 0.0 && x <= width {
+                let tick = vec![Point::new(x, origin.y - Self::TICK_SIZE), Point::new(x, origin.y + Self::TICK_SIZE)];
+                mesh.stroke(Shape::Polyline { points: tick }, axis_color, 1);
+                let below = Point::new(x, origin.y + Self::TICK_SIZE);
+                self.add_tick_label(&label, below, HorizontalAlignment::Center, foreground);
+            }
+            let y = origin.y - distance;
+            if y >= 0.0 && y <= height {
+                let tick = vec![Point::new(origin.x - Self::TICK_SIZE, y), Point::new(origin.x + Self::TICK_SIZE, y)];
+                mesh.stroke(Shape::Polyline { points: tick }, axis_color, 1);
+                let left = Point::new(origin.x - Self::TICK_SIZE - 2.0, y - 7.0);
+                self.add_tick_label(&label, left, HorizontalAlignment::Right, foreground);
+            }
+        }
+        mesh.draw(&mut frame.as_target());
+    }
+
+    // Shows `message` for a few seconds, under any that are still up
+    fn notify(&mut self, message: String) {
+        self.add_toast(Toast { text: message, age: 0.0 });
+    }
+
+    // Puts what went wrong in the banner, over whatever went wrong before, and in the terminal
+    fn report(&mut self, result: Fallible) {
+        if let Err(error) = result {
+            eprintln!("error: {}", error);
+            self.error = Some(error);
+        }
+    }
+
+    fn add_toast(&mut self, toast: Toast) {
+        self.toasts.push(toast);
+        if self.toasts.len() > Self::MAX_TOASTS {
+            self.toasts.remove(0);
         }
     }
-}
 
-struct PolarOxideColors { }
+    // The toasts stacked up from the bottom left corner, newest at the bottom. They slide in from the
+    // left and fade out at the end, timed to the frame with how far along to the next tick we are.
+    // Drawn straight to the frame, they never touch the batches.
+    fn draw_toasts(&mut self, frame: &mut Frame, bottom: f32, timer: &Timer) {
+        let between_ticks = timer.next_tick_proximity() * Self::tick_delta();
+        let (line_height, size) = (30.0, 20.0);
+        let mut backdrops = Mesh::new();
+        let mut top = bottom - 30.0;
+        for toast in self.toasts.iter().rev() {
+            top -= line_height;
+            let age = toast.age + between_ticks;
+            let slide = (age / Self::TOAST_SLIDE_TIME).min(1.0);
+            let opacity = ((Self::TOAST_DURATION - age) / Self::TOAST_FADE_TIME).max(0.0).min(1.0);
+            let (text_width, _) = self.font.measure(Text { content: &toast.text, size, ..Text::default() });
+            let width = text_width + 20.0;
+            let left = 10.0 - (width + 10.0) * (1.0 - slide) * (1.0 - slide);
 
-impl PolarOxideColors {
-    const BLACK: Color = Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0};
-    const YELLOW: Color = Color {r: 0.91, g: 0.92, b: 0.18, a: 1.0};
-    const BLUE: Color = Color {r: 0.36, g: 0.82, b: 0.69, a: 1.0};
+            let (backdrop, color) = (self.configs.background(), self.configs.foreground());
+            backdrops.fill(
+                Shape::Rectangle(Rectangle { x: left, y: top, width, height: line_height - 4.0 }),
+                Color { a: 0.9 * opacity, ..backdrop },
+            );
+            self.font.add(Text {
+                content: &toast.text,
+                position: Point::new(left + 10.0, top + 3.0),
+                size,
+                color: Color { a: opacity, ..color },
+                ..Text::default()
+            });
+        }
+        backdrops.draw(&mut frame.as_target());
+        self.font.draw(&mut frame.as_target());
+    }
+
+    // The last thing that went wrong, in red across the top of the window until it's dismissed
+    fn draw_error_banner(&mut self, frame: &mut Frame) {
+        let text = match &self.error {
+            Some(error) => { format!("Error: {} ({} to dismiss)", error, self.bindings.describe(Action::Quit)) }
+            None => { return; }
+        };
+        let mut banner = Mesh::new();
+        banner.fill(
+            Shape::Rectangle(Rectangle { x: 0.0, y: 0.0, width: frame.width(), height: Self::BANNER_HEIGHT }),
+            PolarOxideColors::RED,
+        );
+        banner.draw(&mut frame.as_target());
+        self.font.add(Text {
+            content: &text,
+            position: Point::new(10.0, 7.0),
+            bounds: (frame.width() - 20.0, Self::BANNER_HEIGHT),
+            size: 20.0,
+            color: PolarOxideColors::WHITE,
+            ..Text::default()
+        });
+        self.font.draw(&mut frame.as_target());
+    }
+
+    // What the colors of the points mean, and of the markers that stay up
+    fn coloring(&self) -> Vec<LegendEntry> {
+        let mut coloring = self.configs.gap_legend(self.particles().max_gap());
+        coloring.extend(self.configs.legend());
+        if let (true, Some(gap)) = (self.show_gap_histogram, self.picked_gap) {
+            coloring.push(LegendEntry {
+                label: format!("prime followed by a gap of {}", gap),
+                ..LegendEntry::marker(&self.configs, Marker::Gap)
+            });
+        }
+        if self.show_special_primes {
+            coloring.push(LegendEntry::marker(&self.configs, Marker::Special));
+        }
+        // The prime Fibonacci and Lucas numbers share a color, and a line
+        for (marker, label) in self.overlays().iter().flat_map(Overlay::legend) {
+            if coloring.iter().all(|entry| entry.label != label) {
+                coloring.push(LegendEntry { label, ..LegendEntry::marker(&self.configs, marker) });
+            }
+        }
+        coloring
+    }
+
+    // `coloring` along with the markers that come and go, in a box above the texts in the bottom
+    // right corner. The swatches are sprites from the same colors as the points, so they always match.
+    fn draw_legend(&mut self, frame: &mut Frame, bottom: f32, foreground: Color) {
+        let mut legend = self.coloring();
+        let markers = [
+            (self.highlight.is_some(), Marker::Highlight),
+            (self.selected.is_some(), Marker::Selection),
+            (self.search_highlight.is_some(), Marker::Search),
+        ];
+        legend.extend(markers.iter().filter(|(shown, _)| *shown).map(|&(_, marker)| {
+            LegendEntry::marker(&self.configs, marker)
+        }));
+
+        let line_height = 22.0;
+        let height = legend.len() as f32 * line_height + 10.0;
+        let (left, top) = (frame.width() - 10.0 - Self::LEGEND_WIDTH, bottom - 80.0 - height);
+        let mut backdrop = Mesh::new();
+        backdrop.fill(
+            Shape::Rectangle(Rectangle { x: left, y: top, width: Self::LEGEND_WIDTH, height }),
+            Color { a: 0.85, ..self.configs.background() },
+        );
+        backdrop.draw(&mut frame.as_target());
+
+        self.legend_batch.clear();
+        for (index, entry) in legend.iter().enumerate() {
+            let line_top = top + 5.0 + index as f32 * line_height;
+            self.legend_batch.add(Sprite {
+                source: Rectangle { x: entry.source, y: 0, width: 1, height: 1 },
+                position: Point::new(left + 10.0, line_top + (line_height - Self::SWATCH_SIZE) / 2.0),
+                scale: (Self::SWATCH_SIZE, Self::SWATCH_SIZE),
+            });
+            self.font.add(Text {
+                content: &entry.label,
+                position: Point::new(left + 20.0 + Self::SWATCH_SIZE, line_top + 2.0),
+                size: 18.0,
+                color: foreground,
+                ..Text::default()
+            });
+        }
+        self.legend_batch.draw(&mut frame.as_target());
+    }
 
-    pub fn index_of(c: Color) -> u16 {
-        match COLORS.iter().position(|color| color.eq(&c)) {
-            Some(i) => { i as u16 }
-            None => { 0 } // Black if we can't find a color
+    // What's drawn and in which colors, for the bottom right corner
+    fn looks_text(&self) -> String {
+        let looks = format!("{} · {}", self.configs.display_mode.name(), self.configs.palette_name());
+        if self.configs.residues {
+            return format!("{} · mod {}", looks, self.configs.modulus);
         }
+        if self.configs.gap_colors {
+            return format!("{} · gaps", looks);
+        }
+        if let Some(title) = self.configs.coloring.title() {
+            if self.configs.coloring.needs_digits() && self.configs.digit_base != 10 {
+                return format!("{} · {} in base {}", looks, title, self.configs.digit_base);
+            }
+            return format!("{} · {}", looks, title);
+        }
+        match self.configs.classification.title() {
+            Some(title) => { format!("{} · {}", looks, title) }
+            None => { looks }
+        }
+    }
+
+    // The plots and the pointers drawn over the points, under the text
+    fn draw_panels(&mut self, frame: &mut Frame, foreground: Color) {
+        if self.show_germain_lines {
+            self.draw_germain_lines(frame);
+        }
+
+        if self.show_prime_curve {
+            self.draw_prime_curve(frame, foreground);
+        }
+
+        if self.show_rose {
+            self.draw_rose(frame, foreground);
+        }
+
+        if self.show_gap_histogram {
+            self.draw_gap_histogram(frame, foreground);
+        }
+
+        if self.show_special_primes {
+            self.draw_special_primes(frame);
+        }
+
+        self.draw_overlay_labels(frame);
+
+        if let Some(arrow) = self.search_arrow(frame.width(), frame.height()) {
+            arrow.draw(&mut frame.as_target());
+        }
+
+        if self.show_crosshair {
+            self.draw_crosshair(frame, foreground);
+        }
+    }
+
+    // A faint line from each Sophie Germain prime in the window to its safe prime, in the color of
+    // the Sophie Germain primes. Lines found for a view the camera has since left are drawn where
+    // the numbers are now, so they don't lag behind while new ones are found.
+    fn draw_germain_lines(&self, frame: &mut Frame) {
+        let lines = match self.germain_lines.result() {
+            Some(lines) if !lines.pairs.is_empty() => { lines }
+            _ => { return; }
+        };
+        let (width, height) = (frame.width(), frame.height());
+        let source = Palette::class_source(self.configs.palette, self.configs.theme, Class::SophieGermain);
+        let color = PolarOxideColors::atlas(self.configs.custom_palette)[source as usize];
+        let color = Color { a: Self::GERMAIN_LINE_ALPHA, ..color };
+        let at = |number: u64| {
+            let position = self.particles().position_of(number, self.configs.angle_factor);
+            self.configs.screen_position(self.settings, position, width, height)
+        };
+
+        let mut mesh = Mesh::new();
+        for &(prime, safe) in &lines.pairs {
+            mesh.stroke(Shape::Polyline { points: vec![at(prime), at(safe)] }, color, 1);
+        }
+        mesh.draw(&mut frame.as_target());
+    }
+
+    // A ring around each Mersenne and Fermat prime in the window, labeled with its form once zoomed
+    // in far enough that the label doesn't run into the other rings. The text is drawn with the rest
+    // of it.
+    fn draw_special_primes(&mut self, frame: &mut Frame) {
+        let (width, height) = (frame.width(), frame.height());
+        let source = PolarOxideColors::marker_source(Marker::Special, self.configs.palette, self.configs.theme);
+        let color = PolarOxideColors::atlas(self.configs.custom_palette)[source as usize];
+        // 3 is both kinds, so it gets one ring with both forms
+        let mut rings: Vec<(u64, Point, Vec<String>)> = Vec::new();
+        for special in special::special_primes(self.particles()) {
+            let number = special.number();
+            match rings.last_mut() {
+                Some((last, _, labels)) if *last == number => { labels.push(special.label()); }
+                _ => {
+                    let position = self.particles().position_of(number, self.configs.angle_factor);
+                    let position = self.configs.screen_position(self.settings, position, width, height);
+                    rings.push((number, position, vec![special.label()]));
+                }
+            }
+        }
+
+        let radius = Self::SPECIAL_RING_RADIUS;
+        let mut mesh = Mesh::new();
+        for (number, position, labels) in &rings {
+            let on_screen = position.x >= -radius && position.x <= width + radius
+                && position.y >= -radius && position.y <= height + radius;
+            if !on_screen || !self.configs.draws(*number) || !self.configs.display_mode.shows(true) {
+                continue;
+            }
+            mesh.stroke(Shape::Circle { center: *position, radius }, color, 2);
+            let crowded = rings.iter().any(|(other, elsewhere, _)| {
+                other != number && (*elsewhere - *position).norm() < Self::SPECIAL_LABEL_SPACING
+            });
+            if !crowded {
+                self.font.add(Text {
+                    content: &format!("{} = {}", grouped(*number), labels.join(" = ")),
+                    position: *position + Vector::new(radius + 4.0, -9.0),
+                    size: 18.0,
+                    color,
+                    ..Text::default()
+                });
+            }
+        }
+        mesh.draw(&mut frame.as_target());
+    }
+
+    // Which term of its sequence each marked Fibonacci or Lucas number in the window is, next to its
+    // marker in the same color, once zoomed in far enough that the labels don't run into each other.
+    // The markers themselves are in the overlay batch, and the text is drawn with the rest of it.
+    fn draw_overlay_labels(&mut self, frame: &mut Frame) {
+        let labeled: Vec<Overlay> = self.overlays().into_iter().filter(|overlay| overlay.is_labeled()).collect();
+        if labeled.is_empty() {
+            return;
+        }
+        let (width, height) = (frame.width(), frame.height());
+        let atlas = PolarOxideColors::atlas(self.configs.custom_palette);
+        let end = self.particles().last_number().saturating_add(1);
+        let mut labels: Vec<(Point, String, Color)> = Vec::new();
+        for overlay in labeled {
+            let numbers = overlay.numbers(self.particles().start(), end);
+            for number in numbers.into_iter().filter(|&number| self.configs.draws(number)) {
+                let name = match overlay.name_of(number) {
+                    Some(name) => { name }
+                    None => { continue; }
+                };
+                let position = self.particles().position_of(number, self.configs.angle_factor);
+                let position = self.configs.screen_position(self.settings, position, width, height);
+                if position.x < 0.0 || position.x > width || position.y < 0.0 || position.y > height {
+                    continue;
+                }
+                let marker = overlay.marker(self.particles().is_prime(number));
+                let source = PolarOxideColors::marker_source(marker, self.configs.palette, self.configs.theme);
+                labels.push((position, format!("{} = {}", name, grouped(number)), atlas[source as usize]));
+            }
+        }
+
+        for (index, (position, text, color)) in labels.iter().enumerate() {
+            let crowded = labels.iter().enumerate().any(|(other, (elsewhere, _, _))| {
+                other != index && (*elsewhere - *position).norm() < Self::SPECIAL_LABEL_SPACING
+            });
+            if !crowded {
+                self.font.add(Text {
+                    content: text,
+                    position: *position + Vector::new(8.0, -9.0),
+                    size: 18.0,
+                    color: *color,
+                    ..Text::default()
+                });
+            }
+        }
+    }
+
+    // A plus in the middle of the window with the number closest to it next to it. The text is drawn
+    // with the rest of it.
+    fn draw_crosshair(&mut self, frame: &mut Frame, foreground: Color) {
+        let middle = Point::new(frame.width() / 2.0, frame.height() / 2.0);
+        let arm = Self::CROSSHAIR_SIZE;
+        let color = Color { a: 0.8, ..foreground };
+        let (across, down) = (Vector::new(arm, 0.0), Vector::new(0.0, arm));
+        let mut mesh = Mesh::new();
+        mesh.stroke(Shape::Polyline { points: vec![middle - across, middle + across] }, color, 1);
+        mesh.stroke(Shape::Polyline { points: vec![middle - down, middle + down] }, color, 1);
+        mesh.draw(&mut frame.as_target());
+
+        if let Some(number) = self.crosshair {
+            self.font.add(Text {
+                content: &format!("n = {}", grouped(number)),
+                position: middle + Vector::new(arm + 4.0, arm + 4.0),
+                size: 18.0,
+                color,
+                ..Text::default()
+            });
+        }
+    }
+
+    // Only looked up again when the camera moves, or turns. It's a walk over the numbers around one
+    // radius, cheap enough to do every time it does.
+    fn update_crosshair(&mut self) {
+        let ((x, y), angle_factor) = (self.configs.offset, self.configs.angle_factor);
+        if self.crosshair_view != Some(((x, y), angle_factor)) {
+            self.crosshair = self.particles().closest_number(Point::new(-x, -y), angle_factor);
+            self.crosshair_view = Some(((x, y), angle_factor));
+        }
+    }
+
+    // A wedge for every slice of the angles around the origin of the spiral, as long as how many
+    // numbers are in it compared to the fullest slice, which reaches the circle. With all numbers
+    // drawn, the primes get a rose of their own on top.
+    fn draw_rose(&mut self, frame: &mut Frame, foreground: Color) {
+        let rose = match self.rose.result() {
+            Some(rose) => { rose }
+            None => { return; }
+        };
+        let (width, height) = (frame.width(), frame.height());
+        let origin = self.configs.screen_position(self.settings, Point::new(0.0, 0.0), width, height);
+        let radius = width.min(height) * Self::ROSE_SIZE;
+        let outline = |counts: &[u64]| {
+            let (bins, fullest) = (counts.len(), counts.iter().cloned().max().unwrap_or(0).max(1));
+            let at = |bin: usize, length: f32| {
+                let angle = bin as f32 / bins as f32 * 2.0 * std::f32::consts::PI;
+                origin + Vector::new(angle.cos(), angle.sin()) * length
+            };
+            let mut points: Vec<Point> = counts.iter().enumerate()
+                .flat_map(|(bin, &count)| {
+                    let length = radius * count as f32 / fullest as f32;
+                    vec![at(bin, length), at(bin + 1, length)]
+                })
+                .collect();
+            points.extend(points.first().cloned());
+            points
+        };
+
+        let mut mesh = Mesh::new();
+        mesh.stroke(Shape::Circle { center: origin, radius }, Color { a: 0.3, ..foreground }, 1);
+        let curve_color = self.configs.curve_color();
+        let numbers_color = if rose.primes.is_some() { Color { a: 0.8, ..foreground } } else { curve_color };
+        mesh.stroke(Shape::Polyline { points: outline(&rose.numbers) }, numbers_color, 1);
+        if let Some(primes) = &rose.primes {
+            mesh.stroke(Shape::Polyline { points: outline(primes) }, curve_color, 2);
+        }
+        mesh.draw(&mut frame.as_target());
+
+        let counted = |counts: &[u64]| grouped(counts.iter().sum());
+        let legend = match &rose.primes {
+            Some(primes) => { format!("{} numbers, {} primes", counted(&rose.numbers), counted(primes)) }
+            None => { format!("{} numbers", counted(&rose.numbers)) }
+        };
+        self.font.add(Text {
+            content: &format!("Angles of {} in {} slices", legend, rose.filter.bins),
+            position: Point::new(origin.x, origin.y - radius - 28.0),
+            size: 18.0,
+            color: foreground,
+            horizontal_alignment: HorizontalAlignment::Center,
+            ..Text::default()
+        });
+    }
+
+    // Where the bars of the gap histogram go, in the bottom left corner above the prompt and a toast
+    fn histogram_area(&self, height: f32) -> Rectangle<f32> {
+        let bottom = if self.show_status_bar { height - Self::STATUS_BAR_HEIGHT } else { height };
+        Rectangle {
+            x: 20.0,
+            y: bottom - 80.0 - Self::HISTOGRAM_HEIGHT,
+            width: Self::HISTOGRAM_WIDTH,
+            height: Self::HISTOGRAM_HEIGHT,
+        }
+    }
+
+    // The gap sizes that get a bar, smallest first, with how many primes start one
+    fn histogram_bars(&self) -> &[(u64, u64)] {
+        self.gaps.result().map_or(&[], |gaps| &gaps.counts[..gaps.counts.len().min(Self::HISTOGRAM_BARS)])
+    }
+
+    // The gap size of the bar under `point`, anywhere from the top of the histogram down
+    fn gap_bar_at(&self, point: Point, height: f32) -> Option<u64> {
+        let area = self.histogram_area(height);
+        let bars = self.histogram_bars();
+        let inside = point.x >= area.x && point.x < area.x + area.width
+            && point.y >= area.y && point.y <= area.y + area.height;
+        if !self.show_gap_histogram || !inside || bars.is_empty() {
+            return None;
+        }
+        let index = ((point.x - area.x) / area.width * bars.len() as f32) as usize;
+        bars.get(index).map(|&(gap, _)| gap)
+    }
+
+    // How many primes in the window start each gap size, over a backdrop so the points don't get in
+    // the way. The picked size is drawn in the prime curve's color, like its primes on the spiral.
+    fn draw_gap_histogram(&mut self, frame: &mut Frame, foreground: Color) {
+        let area = self.histogram_area(frame.height());
+        let bottom = area.y + area.height;
+        let mut mesh = Mesh::new();
+        mesh.fill(
+            Shape::Rectangle(Rectangle {
+                x: area.x - 10.0,
+                y: area.y - 50.0,
+                width: area.width + 20.0,
+                height: area.height + 70.0,
+            }),
+            Color { a: 0.85, ..self.configs.background() },
+        );
+        let axis_color = Color { a: 0.6, ..foreground };
+        mesh.stroke(
+            Shape::Polyline { points: vec![Point::new(area.x, bottom), Point::new(area.x + area.width, bottom)] },
+            axis_color,
+            1,
+        );
+
+        let bars = self.histogram_bars().to_vec();
+        let tallest = bars.iter().map(|&(_, count)| count).max().unwrap_or(0);
+        let bar_width = area.width / bars.len().max(1) as f32;
+        // Narrow bars only get a label every few of them, so the labels don't run into each other
+        let label_every = (24.0 / bar_width).ceil() as usize;
+        for (index, &(gap, count)) in bars.iter().enumerate() {
+            let x = area.x + index as f32 * bar_width;
+            let bar_height = area.height * count as f32 / tallest as f32;
+            let color = if Some(gap) == self.picked_gap { self.configs.curve_color() } else { axis_color };
+            mesh.fill(
+                Shape::Rectangle(Rectangle {
+                    x: x + 1.0,
+                    y: bottom - bar_height,
+                    width: (bar_width - 2.0).max(1.0),
+                    height: bar_height,
+                }),
+                color,
+            );
+            if index % label_every == 0 {
+                let below = Point::new(x + bar_width / 2.0, bottom + 2.0);
+                self.add_tick_label(&gap.to_string(), below, HorizontalAlignment::Center, foreground);
+            }
+        }
+        mesh.draw(&mut frame.as_target());
+
+        let title = match (self.gaps.result(), self.picked_gap) {
+            (None, _) => { String::from("Counting the gaps...") }
+            (Some(gaps), Some(gap)) => { format!("Primes followed by a gap of {}: {}", gap, grouped(gaps.count(gap))) }
+            (Some(_), None) => { String::from("Gaps after the primes in the window") }
+        };
+        self.font.add(Text {
+            content: &title,
+            position: Point::new(area.x, area.y - 44.0),
+            size: 18.0,
+            color: foreground,
+            ..Text::default()
+        });
+        if tallest > 0 {
+            let scale = format!("{} primes", grouped(tallest));
+            self.add_tick_label(&scale, Point::new(area.x, area.y - 18.0), HorizontalAlignment::Left, foreground);
+        }
+    }
+
+    fn add_tick_label(&mut self, label: &str, position: Point, alignment: HorizontalAlignment, color: Color) {
+        self.font.add(Text {
+            content: label,
+            position,
+            size: 14.0,
+            color,
+            horizontal_alignment: alignment,
+            ..Text::default()
+        });
+    }
+
+    // Where the cursor is in the window, on the spiral, in polar coordinates there, and the number
+    // closest to it. The angle goes from 0 to 2π like the numbers' own.
+    fn status_text(&self, width: f32, height: f32) -> String {
+        let cursor = self.hover_cursor;
+        let target = self.configs.spiral_position(self.settings, cursor, width, height);
+        let angle = target.y.atan2(target.x).rem_euclid(2.0 * std::f32::consts::PI);
+        let closest = self.closest.map_or_else(|| String::from("none"), |number| number.to_string());
+        format!(
+            "{:.0}, {:.0} px    x = {:.2}, y = {:.2}    r = {:.2}, θ = {:.4} rad    closest n = {}",
+            cursor.x, cursor.y, target.x, target.y, target.coords.norm(), angle, closest,
+        )
+    }
+
+    // A band along the bottom of the window with `status_text` on it, drawn over the points like the
+    // help overlay so moving the mouse never rebuilds a batch. Returns how tall it is.
+    fn draw_status_bar(&mut self, frame: &mut Frame) -> f32 {
+        let (width, height) = (frame.width(), frame.height());
+        let top = height - Self::STATUS_BAR_HEIGHT;
+        let mut band = Mesh::new();
+        band.fill(
+            Shape::Rectangle(Rectangle { x: 0.0, y: top, width, height: Self::STATUS_BAR_HEIGHT }),
+            Color { a: 0.85, ..self.configs.background() },
+        );
+        band.draw(&mut frame.as_target());
+
+        self.font.add(Text {
+            content: &self.status_text(width, height),
+            position: Point::new(10.0, top + 4.0),
+            size: 16.0,
+            color: self.configs.foreground(),
+            ..Text::default()
+        });
+        Self::STATUS_BAR_HEIGHT
+    }
+
+    fn help_text(&self) -> String {
+        let mut lines = vec![String::from("Controls"), String::new()];
+        for &action in Action::ALL.iter() {
+            lines.push(format!("{:<16} {}", self.bindings.describe(action), action.description()));
+        }
+        lines.push(format!("{:<16} {}", "Wheel", "Zoom towards the cursor"));
+        lines.push(format!("{:<16} {}", "Drag", "Pan"));
+        lines.push(format!("{:<16} {}", "Click", "Select a number"));
+        lines.push(format!("{:<16} {}", "Double click", "Center on the clicked point"));
+
+        lines.push(String::new());
+        lines.push(String::from("Settings"));
+        lines.push(String::new());
+        lines.push(format!("{:<16} {:.1}", "Zoom level", self.configs.zoom_level));
+        lines.push(format!("{:<16} {}", "Max number", self.max_number));
+        lines.push(format!("{:<16} {}", "Primality", self.primality.name()));
+        lines.push(format!("{:<16} {}", "Display mode", self.configs.display_mode.name()));
+        lines.join("\n")
+    }
+
+    fn save_view(&mut self) -> Fallible {
+        let view = SavedView {
+            max_number: self.max_number,
+            configs: self.configs,
+        };
+
+        let path = view.save().map_err(|error| format!("couldn't save the view: {}", error))?;
+        self.notify(format!("Saved the view to {}", path.display()));
+        Ok(())
+    }
+
+    // Goes back to a saved view, generating the particles again if it was saved with a different
+    // amount of them
+    fn restore_view(&mut self) -> Fallible {
+        let view = SavedView::load().map_err(|error| format!("couldn't restore the view: {}", error))?;
+        self.apply_view(view);
+        self.notify(String::from("Restored the saved view"));
+        Ok(())
+    }
+
+    // Grows the spiral by `count` numbers in the background, on top of whatever is already on its way.
+    // When that ends up past the current numbers, only the ones after them are sieved.
+    fn add_numbers(&mut self, count: u64) -> Fallible {
+        let wanted = self.generation.as_ref().map_or(self.max_number, Generation::max_number);
+        self.set_max_number(wanted.saturating_add(count))
+    }
+
+    // Finds the numbers up to `max_number` in the background, only the ones after the current ones
+    // when there are more of them
+    fn set_max_number(&mut self, max_number: u64) -> Fallible {
+        if self.points.kind() == DatasetKind::Gaussian {
+            return Err(String::from("the Gaussian integers can't be grown or cut down"));
+        }
+        if max_number == self.max_number {
+            self.generation = None;
+            return Ok(());
+        }
+        let first = if max_number > self.max_number { self.particles().next_start() } else { self.particles().start() };
+        self.generation = Some(Generation::start(first, max_number, self.primality));
+        Ok(())
+    }
+
+    fn apply_view(&mut self, view: SavedView) {
+        // Views don't bring a custom palette along, the one given at the start stays
+        let configs = Configs { custom_palette: self.configs.custom_palette, ..view.configs }.sanitized();
+        self.configs = configs;
+
+        // The current particles keep being drawn until the new ones are there
+        if view.max_number == self.max_number || self.points.kind() == DatasetKind::Gaussian {
+            self.generation = None;
+        } else if self.generation.as_ref().map(Generation::max_number) != Some(view.max_number) {
+            self.generation = Some(Generation::start(self.particles().start(), view.max_number, self.primality));
+        }
+
+        // Whatever was selected might not exist anymore
+        self.selected = None;
+        self.highlight = None;
+        self.search_highlight = None;
+        self.hovered = None;
+        self.revealing = false;
+        self.recenter_target = None;
+        self.momentum = None;
+    }
+
+    fn add_bookmark(&mut self, name: &str) -> Fallible {
+        let view = SavedView { max_number: self.max_number, configs: self.configs };
+        self.bookmarks.add(name, &view);
+        self.bookmarks.save().map_err(|error| format!("couldn't save the bookmarks: {}", error))?;
+        self.notify(format!("Bookmarked the view as `{}`", name));
+        Ok(())
+    }
+
+    // Numbers past the ones there are get generated on the way
+    fn go_to_bookmark(&mut self, index: usize) -> Fallible {
+        let bookmark = match self.bookmarks.list().get(index) {
+            Some(bookmark) => { bookmark }
+            None => { return Ok(()); }
+        };
+
+        let view = bookmark.view(self.configs).map_err(|error| format!("couldn't open the {}", error))?;
+        let message = if view.max_number > self.max_number {
+            format!("Going to `{}`, generating the numbers up to {}", bookmark.name, view.max_number)
+        } else {
+            format!("Went to `{}`", bookmark.name)
+        };
+        self.apply_view(view);
+        self.notify(message);
+        Ok(())
+    }
+
+    fn remove_bookmark(&mut self, index: usize) -> Fallible {
+        let removed = match self.bookmarks.remove(index) {
+            Some(removed) => { removed }
+            None => { return Ok(()); }
+        };
+
+        self.bookmarks.save().map_err(|error| format!("couldn't save the bookmarks: {}", error))?;
+        self.notify(format!("Removed `{}`", removed.name));
+        Ok(())
+    }
+
+    // The overlay listing the bookmarks, one a line with the picked one marked
+    fn bookmark_list_text(&self, selected: usize) -> String {
+        let mut lines = vec![
+            String::from("Bookmarks"),
+            String::from("Up/Down to pick, Enter to go, Delete to remove, Esc to close"),
+            String::new(),
+        ];
+        if self.bookmarks.list().is_empty() {
+            let keys = self.bindings.describe(Action::AddBookmark);
+            lines.push(format!("Nothing bookmarked yet, {} bookmarks the view", keys));
+        }
+        for (index, bookmark) in self.bookmarks.list().iter().enumerate() {
+            let marker = if index == selected { ">" } else { " " };
+            lines.push(format!("{} {}", marker, bookmark.name));
+        }
+        lines.join("\n")
+    }
+
+    // Slots count from 0, but are shown counting from 1 like their keys
+    fn apply_preset(&mut self, slot: usize) {
+        self.configs = self.presets.get(slot).apply(self.configs);
+        self.notify(format!("Preset {}: {}", slot + 1, self.configs.palette_name()));
+    }
+
+    fn save_preset(&mut self, slot: usize) -> Fallible {
+        self.presets.set(slot, Preset::new(&self.configs));
+        self.presets.save().map_err(|error| format!("couldn't save the presets: {}", error))?;
+        self.notify(format!("Saved the looks to preset {}", slot + 1));
+        Ok(())
+    }
+
+    fn copy_selection(&mut self) -> Fallible {
+        let text = match &self.selected {
+            Some(selected) => { selected.to_text(self.configs.angle_factor) }
+            None => {
+                self.notify(String::from("Click a number to select it first"));
+                return Ok(());
+            }
+        };
+
+        self.copy_to_clipboard(text, "Copied to the clipboard")
+    }
+
+    fn copy_view(&mut self) -> Fallible {
+        let view = SavedView { max_number: self.max_number, configs: self.configs };
+        self.copy_to_clipboard(view.encode(), "Copied the view string to the clipboard")
+    }
+
+    fn paste_view(&mut self) -> Fallible {
+        let view = self.clipboard()
+            .and_then(|clipboard| clipboard.get_contents())
+            .map_err(|error| error.to_string())
+            .and_then(|text| SavedView::decode(&text, self.configs))
+            .map_err(|error| format!("couldn't paste the view: {}", error))?;
+
+        self.apply_view(view);
+        self.notify(String::from("Applied the pasted view"));
+        Ok(())
+    }
+
+    // Connects to the clipboard the first time it's needed
+    fn clipboard(&mut self) -> std::result::Result<&mut ClipboardContext, Box<dyn Error>> {
+        let clipboard = match self.clipboard.take() {
+            Some(clipboard) => { clipboard }
+            None => { ClipboardProvider::new()? }
+        };
+        Ok(self.clipboard.get_or_insert(clipboard))
+    }
+
+    fn copy_to_clipboard(&mut self, text: String, notice: &str) -> Fallible {
+        self.clipboard()
+            .and_then(|clipboard| clipboard.set_contents(text))
+            .map_err(|error| format!("couldn't copy to the clipboard: {}", error))?;
+        self.notify(String::from(notice));
+        Ok(())
+    }
+
+    // Everything that has to happen before we go away, whether the window was closed or we quit
+    fn shut_down(&mut self) {
+        if self.keeps_session {
+            let session = LastSession::new(&self.configs, Some(self.windowed_size), self.fullscreen);
+            if let Err(error) = session.save() {
+                eprintln!("error: couldn't save the session: {}", error);
+            }
+        }
+        io::stdout().flush().ok();
+        io::stderr().flush().ok();
+    }
+
+    // Coffee only ends its loop when the window is asked to close, and we have no way of asking
+    // from inside the game, so quitting shuts down like a close request would and exits from here
+    fn quit(&mut self) -> ! {
+        self.shut_down();
+        process::exit(0);
+    }
+
+    // Saves what is on screen, minus the text, to a PNG in the working directory
+    fn take_screenshot(&mut self, window: &Window) -> Fallible {
+        let (width, height) = (window.width(), window.height());
+        let mut sprites = sprites::frame_sprites(&self.points, self.configs, self.settings, width, height);
+        sprites.extend(self.markers(window.width(), window.height()));
+
+        let image = screenshot::render(
+            window.width() as u32,
+            window.height() as u32,
+            self.configs.background(),
+            &PolarOxideColors::atlas(self.configs.custom_palette),
+            &sprites,
+        );
+
+        let file_name = screenshot::save(&image).map_err(|error| format!("couldn't save the screenshot: {}", error))?;
+        self.notify(format!("Saved {}", file_name));
+        Ok(())
+    }
+
+    // What `take_screenshot` saves, as circles in an SVG
+    fn take_svg_screenshot(&mut self, window: &Window) -> Fallible {
+        let (width, height) = (window.width(), window.height());
+        let (configs, settings, max_composites) = (self.configs, self.settings, svg::DEFAULT_MAX_COMPOSITES);
+        let mut sprites = svg::frame_sprites(&self.points, configs, settings, width, height, max_composites);
+        sprites.extend(self.markers(width, height));
+
+        let path = PathBuf::from(screenshot::file_name("svg"));
+        let (background, atlas) = (self.configs.background(), PolarOxideColors::atlas(self.configs.custom_palette));
+        svg::write(&path, width as u32, height as u32, background, &atlas, &sprites)
+            .map_err(|error| format!("couldn't save the screenshot: {}", error))?;
+        self.notify(format!("Saved {}", path.display()));
+        Ok(())
+    }
+
+    // Written in the background, `update` shows where it went
+    fn export_data(&mut self, window: &Window, format: Format, path: Option<PathBuf>) {
+        if self.data_export.is_some() {
+            return;
+        }
+        if self.points.kind() == DatasetKind::Gaussian {
+            self.report(Err(String::from("only the numbers can be exported")));
+            return;
+        }
+        let size = (window.width(), window.height());
+        let particles = Arc::clone(self.particles());
+        let metadata = Metadata::new(self.max_number, particles.radius_base(), self.configs, self.generation_time);
+        self.data_export = Some(
+            DataExport::start(particles, self.configs, self.settings, size, format, metadata, path),
+        );
+        self.notify(String::from("Saving the visible numbers..."));
+    }
+
+    // Takes in edits to the config file and the palette file. One that can't be read is reported and
+    // what was there before stays, to be fixed with another edit.
+    fn reload_changed_files(&mut self, window: &mut Window) {
+        let changed = match &self.watcher {
+            Some(watcher) => { watcher.poll() }
+            None => { return; }
+        };
+
+        for path in changed {
+            let reloaded = if Some(&path) == self.palette_path.as_ref() {
+                self.reload_palette(&path, window)
+            } else {
+                self.reload_config_file()
+            };
+            self.report(reloaded);
+        }
+    }
+
+    fn reload_palette(&mut self, path: &Path, window: &mut Window) -> Fallible {
+        let reloaded = CustomPalette::load(path).and_then(|custom| {
+            let image = Image::from_colors(window.gpu(), &PolarOxideColors::atlas(Some(custom)))
+                .map_err(|error| format!("couldn't upload the colors to the GPU: {}", error))?;
+            Ok((custom, image))
+        });
+
+        let (custom, image) =
+            reloaded.map_err(|error| format!("couldn't reload the palette, keeping the previous colors: {}", error))?;
+        self.prime_batch = Batch::new(image.clone());
+        self.composite_batch = Batch::new(image.clone());
+        self.highlight_batch = Batch::new(image.clone());
+        self.overlay_batch = Batch::new(image.clone());
+        self.legend_batch = Batch::new(image);
+        self.configs.custom_palette = Some(custom);
+        // The new batches are empty until they are built again
+        self.invalidate_batches();
+        info!("Reloaded the palette from {}", path.display());
+        self.notify(String::from("Reloaded the palette"));
+        Ok(())
+    }
+
+    fn reload_config_file(&mut self) -> Fallible {
+        let older = match &self.config_file {
+            Some(older) => { older }
+            None => { return Ok(()); }
+        };
+        let reloaded = older.reread().and_then(|newer| match newer {
+            Some(newer) => { newer.changes_since(older).map(|reload| Some((newer, reload))) }
+            // Some editors save by moving the new file in place, it'll be back
+            None => { Ok(None) }
+        });
+
+        let (newer, reload) = match reloaded {
+            Ok(Some(reloaded)) => { reloaded }
+            Ok(None) => { return Ok(()); }
+            Err(error) => { return Err(format!("couldn't reload the settings, keeping the previous ones: {}", error)); }
+        };
+
+        if let Some(palette) = reload.palette {
+            self.configs.palette = palette;
+        }
+        if let Some(zoom_base) = reload.zoom_base {
+            self.settings.zoom_base = zoom_base;
+        }
+        if let Some(zoom_step) = reload.zoom_step {
+            self.settings.zoom_step = zoom_step;
+        }
+        if let Some(display_mode) = reload.display_mode {
+            self.configs.display_mode = display_mode;
+        }
+        if let Some(keybindings) = &reload.keybindings {
+            self.bindings = Self::bindings(keybindings);
+        }
+        for key in &reload.ignored {
+            warn!("{}: `{}` only changes on the next run", newer.path().display(), key);
+        }
+        info!("Reloaded the settings from {}", newer.path().display());
+        self.config_file = Some(newer);
+        self.invalidate_batches();
+        self.notify(String::from("Reloaded the settings"));
+        Ok(())
+    }
+
+    fn toggle_fullscreen(&mut self, window: &mut Window) {
+        window.toggle_fullscreen();
+        self.fullscreen = !self.fullscreen;
+        // The new size may only show up in a later frame, or be the same if the window was maximized
+        self.invalidate_batches();
+    }
+
+    // While the bookmarks are listed the keyboard belongs to the list, returns whether it took it
+    fn interact_bookmark_list(&mut self, input: &Controls) -> bool {
+        let selected = match self.bookmark_list {
+            Some(selected) => { selected }
+            None => { return false; }
+        };
+
+        let count = self.bookmarks.list().len();
+        let released = |key| input.was_key_released(key);
+        if released(keyboard::KeyCode::Up) {
+            self.bookmark_list = Some(selected.saturating_sub(1));
+        } else if released(keyboard::KeyCode::Down) {
+            self.bookmark_list = Some((selected + 1).min(count.saturating_sub(1)));
+        } else if released(keyboard::KeyCode::Return) || released(keyboard::KeyCode::NumpadEnter) {
+            self.bookmark_list = None;
+            let went = self.go_to_bookmark(selected);
+            self.report(went);
+        } else if released(keyboard::KeyCode::Delete) {
+            let removed = self.remove_bookmark(selected);
+            self.report(removed);
+            self.bookmark_list = Some(selected.min(self.bookmarks.list().len().saturating_sub(1)));
+        } else if released(keyboard::KeyCode::Escape) || self.bindings.was_released(input, Action::Bookmarks) {
+            self.bookmark_list = None;
+        }
+        self.zoom_direction = 0;
+        self.key_pan = (0, 0);
+        true
+    }
+
+    // While something is being typed the keyboard belongs to the prompt, returns whether it took it
+    fn interact_prompt(&mut self, input: &Controls, window: &Window) -> bool {
+        let (prompt, typed) = match &mut self.prompt {
+            Some((prompt, typed)) => { (*prompt, typed) }
+            None => { return false; }
+        };
+
+        let before = typed.len();
+        typed.extend(input.typed_characters().iter().filter(|&&c| prompt.accepts(c)));
+
+        if input.was_key_released(keyboard::KeyCode::Back) {
+            typed.pop();
+        }
+        if typed.len() != before {
+            self.command_error = None;
+        }
+
+        // Tab finishes the name of a command
+        if prompt == Prompt::Command && input.was_key_released(keyboard::KeyCode::Tab) {
+            if let Some(completed) = commands::complete(typed) {
+                *typed = completed;
+            }
+        }
+
+        if input.was_key_released(keyboard::KeyCode::Return)
+            || input.was_key_released(keyboard::KeyCode::NumpadEnter) {
+            let typed = typed.trim().to_string();
+            self.prompt = None;
+            self.command_error = None;
+            match (prompt, typed.parse::<u64>()) {
+                (Prompt::GoTo, Ok(number)) => { self.go_to_number(number); }
+                (Prompt::Search, Ok(number)) => { self.search_number(number); }
+                (Prompt::BookmarkName, _) if !typed.is_empty() => {
+                    let added = self.add_bookmark(&typed);
+                    self.report(added);
+                }
+                (Prompt::Command, _) => { self.run_command_line(typed, window); }
+                _ => {}
+            }
+        } else if input.was_key_released(keyboard::KeyCode::Escape) {
+            self.prompt = None;
+            self.command_error = None;
+        }
+        self.zoom_direction = 0;
+        true
+    }
+
+    // A command that can't be made sense of or can't be done stays on the command line, to be fixed,
+    // with what's wrong with it over it
+    fn run_command_line(&mut self, typed: String, window: &Window) {
+        let ran = Command::parse(&typed, self.configs.custom_palette.is_some())
+            .and_then(|command| self.run_command(command, window));
+        if let Err(error) = ran {
+            self.command_error = Some(error);
+            self.prompt = Some((Prompt::Command, typed));
+        }
+    }
+
+    // The same as the keys and arguments that do these
+    fn run_command(&mut self, command: Command, window: &Window) -> Fallible {
+        match command {
+            Command::Max(max_number) if max_number <= self.particles().start() + 1 => {
+                return Err(format!("the spiral starts at {}", self.particles().start()));
+            }
+            Command::Max(max_number) => { self.set_max_number(max_number)?; }
+            Command::Zoom(zoom_level) => { self.configs.zoom_level = Configs::clamp_zoom_level(zoom_level); }
+            Command::GoTo(number) => { self.go_to_number(number); }
+            Command::Search(number) => { self.search_number(number); }
+            Command::Palette(palette) => { self.configs.palette = palette; }
+            Command::Mode(display_mode) => { self.configs.display_mode = display_mode; }
+            Command::Highlight(classification) => { self.configs.classification = classification; }
+            Command::Residues(None) => { self.configs.residues = false; }
+            Command::Residues(Some(modulus)) => {
+                self.configs.residues = true;
+                self.configs.modulus = modulus;
+            }
+            Command::Theme(theme) => { self.configs.theme = theme; }
+            Command::Angle(angle_factor) => { self.configs.angle_factor = angle_factor; }
+            Command::Polygonal(sides) => {
+                self.polygon_sides = sides;
+                self.overlays_changed();
+            }
+            Command::Poly(polynomial) => {
+                self.polynomial = polynomial;
+                self.overlays_changed();
+            }
+            Command::Predicate(predicate) => {
+                self.predicate = predicate.map(Arc::new);
+                self.overlays_changed();
+            }
+            Command::Progressions(progressions) => {
+                for warning in progressions.iter().filter_map(|progression| progression.warning()) {
+                    self.notify(warning);
+                }
+                self.progressions = progressions;
+                self.overlays_changed();
+            }
+            Command::Export(format, path) => { self.export_data(window, format, path); }
+        }
+        Ok(())
+    }
+
+    fn interact_keyboard(&mut self, input: &Controls, window: &mut Window) {
+        if self.bindings.was_released(input, Action::GoTo) {
+            self.prompt = Some((Prompt::GoTo, String::new()));
+            self.zoom_direction = 0;
+            return;
+        }
+
+        if self.bindings.was_released(input, Action::Search) {
+            self.prompt = Some((Prompt::Search, String::new()));
+            self.zoom_direction = 0;
+            return;
+        }
+
+        if self.bindings.was_released(input, Action::CommandLine) {
+            self.prompt = Some((Prompt::Command, String::new()));
+            self.zoom_direction = 0;
+            return;
+        }
+
+        if self.bindings.was_released(input, Action::NextPrime) {
+            self.go_to_neighbor_prime(true);
+        }
+
+        if self.bindings.was_released(input, Action::PreviousPrime) {
+            self.go_to_neighbor_prime(false);
+        }
+
+        if self.bindings.was_released(input, Action::AddNumbers) {
+            let factor = if input.is_shift_pressed() { Self::ADD_NUMBERS_COARSE_FACTOR } else { 1 };
+            let added = self.add_numbers(Self::ADD_NUMBERS_BLOCK * factor);
+            self.report(added);
+        }
+
+        // Holding W/S is integrated in `update`, a fresh press gives a small discrete step
+        let mut zoom_direction = 0;
+        if self.bindings.is_pressed(input, Action::ZoomIn) {
+            zoom_direction -= 1;
+        }
+
+        if self.bindings.is_pressed(input, Action::ZoomOut) {
+            zoom_direction += 1;
+        }
+
+        self.zoom_coarse = input.is_shift_pressed();
+        if zoom_direction != self.zoom_direction {
+            self.zoom_direction = zoom_direction;
+            self.zoom_hold_time = 0.0;
+            let center = Point::new(window.width() / 2.0, window.height() / 2.0);
+            let step = self.settings.zoom_step * self.zoom_speed_factor();
+            self.zoom_towards(self.configs.zoom_level + zoom_direction as f32 * step, center, window);
+        }
+
+        // Held keys pan in `update`, so it doesn't depend on how often we get input
+        self.key_pan = (0, 0);
+        if self.bindings.is_pressed(input, Action::PanLeft) {
+            self.key_pan.0 += 1;
+        }
+
+        if self.bindings.is_pressed(input, Action::PanRight) {
+            self.key_pan.0 -= 1;
+        }
+
+        if self.bindings.is_pressed(input, Action::PanUp) {
+            self.key_pan.1 += 1;
+        }
+
+        if self.bindings.is_pressed(input, Action::PanDown) {
+            self.key_pan.1 -= 1;
+        }
+
+        if self.bindings.was_released(input, Action::ResetView) {
+            self.configs.reset_view();
+        }
+
+        if self.bindings.was_released(input, Action::ZoomToFit) {
+            self.zoom_to_fit(window);
+        }
+
+        if self.bindings.was_released(input, Action::ToggleFullscreen) {
+            self.toggle_fullscreen(window);
+        }
+
+        self.interact_panels(input);
+        self.interact_files(input, window);
+        self.interact_bookmarks(input);
+        self.interact_clipboard(input);
+        self.interact_appearance(input);
+        self.interact_presets(input);
+
+        if self.bindings.was_released(input, Action::Quit) {
+            if self.error.is_some() {
+                self.error = None;
+            } else if self.selected.is_some() {
+                self.selected = None;
+            } else {
+                self.should_quit = true;
+            }
+        }
+    }
+
+    // Saving the view, screenshots and the visible numbers, and opening the saved view
+    fn interact_files(&mut self, input: &Controls, window: &Window) {
+        if self.bindings.was_released(input, Action::SaveView) {
+            let saved = self.save_view();
+            self.report(saved);
+        }
+
+        if self.bindings.was_released(input, Action::RestoreView) {
+            let restored = self.restore_view();
+            self.report(restored);
+        }
+
+        if self.bindings.was_released(input, Action::Screenshot) {
+            let saved = if input.is_shift_pressed() {
+                self.take_svg_screenshot(window)
+            } else {
+                self.take_screenshot(window)
+            };
+            self.report(saved);
+        }
+
+        if self.bindings.was_released(input, Action::ExportData) {
+            let format = if input.is_shift_pressed() { Format::Json { pretty: false } } else { Format::Csv };
+            self.export_data(window, format, None);
+        }
+    }
+
+    // Bookmarking the view under a name, and the list of bookmarks
+    fn interact_bookmarks(&mut self, input: &Controls) {
+        if self.bindings.was_released(input, Action::AddBookmark) {
+            self.prompt = Some((Prompt::BookmarkName, String::new()));
+            self.zoom_direction = 0;
+        } else if self.bindings.was_released(input, Action::Bookmarks) {
+            self.bookmark_list = Some(0);
+            self.zoom_direction = 0;
+        }
+    }
+
+    // Copying the selection, and copying and pasting whole views
+    fn interact_clipboard(&mut self, input: &Controls) {
+        let copied = if self.bindings.was_released(input, Action::CopySelection) {
+            self.copy_selection()
+        } else if self.bindings.was_released(input, Action::CopyView) {
+            self.copy_view()
+        } else if self.bindings.was_released(input, Action::PasteView) {
+            self.paste_view()
+        } else {
+            Ok(())
+        };
+        self.report(copied);
+    }
+
+    // Switching to the looks in a slot, or saving the current ones there
+    fn interact_presets(&mut self, input: &Controls) {
+        if let Some(slot) = self.bindings.released_index(input, Action::SavePreset) {
+            if slot < Presets::COUNT {
+                let saved = self.save_preset(slot);
+                self.report(saved);
+            }
+        } else if let Some(slot) = self.bindings.released_index(input, Action::Preset) {
+            if slot < Presets::COUNT {
+                self.apply_preset(slot);
+            }
+        }
+    }
+
+    // Keys that change how the spiral is drawn, rather than where we are looking at it
+    fn interact_appearance(&mut self, input: &Controls) {
+        // Holding Shift only resizes the primes
+        let primes_only = input.is_shift_pressed();
+        if self.bindings.was_released(input, Action::GrowPoints) {
+            self.configs.scale_points(Configs::POINT_SCALE_STEP, primes_only);
+        }
+
+        if self.bindings.was_released(input, Action::ShrinkPoints) {
+            self.configs.scale_points(1.0 / Configs::POINT_SCALE_STEP, primes_only);
+        }
+
+        if self.bindings.was_released(input, Action::ResetPointSize) {
+            self.configs.reset_point_scale();
+        }
+
+        // Holding Shift takes coarse steps
+        let angle_step = if input.is_shift_pressed() { Configs::ANGLE_COARSE_STEP } else { Configs::ANGLE_FINE_STEP };
+        let mut angle_change = 0.0;
+        if self.bindings.was_released(input, Action::DecreaseAngle) {
+            angle_change -= angle_step;
+        }
+
+        if self.bindings.was_released(input, Action::IncreaseAngle) {
+            angle_change += angle_step;
+        }
+
+        // Positions are worked out from the angle factor as the batches get built, so this is all
+        // it takes
+        self.configs.angle_factor += angle_change;
+
+        if self.bindings.was_released(input, Action::ToggleTheme) {
+            self.configs.theme = self.configs.theme.toggle();
+        }
+
+        // The residues and the gaps would cover up what the numbers are colored by
+        if self.bindings.was_released(input, Action::CycleColoring) {
+            self.configs.coloring = self.configs.coloring.next();
+            self.configs.residues = false;
+            self.configs.gap_colors = false;
+        } else if self.bindings.was_released(input, Action::CyclePalette) {
+            self.configs.palette = Palette::next(self.configs.palette, self.configs.custom_palette.is_some());
+        }
+
+        // Space pauses and resumes the camera path while there is one, and otherwise the reveal, or
+        // starts it over once every number is showing
+        if self.bindings.was_released(input, Action::ToggleReveal) {
+            if let Some(playback) = &mut self.playback {
+                playback.paused = !playback.paused;
+            } else {
+                let (first, last) = self.points.point_range();
+                if !self.revealing && self.configs.reveal_cutoff >= last {
+                    self.reveal_ticks = 0;
+                    self.configs.reveal_cutoff = first;
+                }
+                self.revealing = !self.revealing;
+            }
+        }
+
+        if self.bindings.was_released(input, Action::ToggleTour) {
+            self.tour = match self.tour {
+                Some(_) => { None }
+                None => { Some(Tour::new(&self.configs)) }
+            };
+        }
+
+        if self.bindings.was_released(input, Action::CycleClassification) {
+            self.configs.classification = self.configs.classification.next();
+        } else if self.bindings.was_released(input, Action::CycleDisplayMode) {
+            self.configs.display_mode = self.configs.display_mode.next();
+        }
+
+        // Going on to the next modulus colors by the residues if it didn't already
+        if self.bindings.was_released(input, Action::CycleModulus) {
+            let modulus = self.configs.modulus + 1;
+            self.configs.modulus = if Configs::is_modulus(modulus) { modulus } else { 2 };
+            self.configs.residues = true;
+        } else if self.bindings.was_released(input, Action::ToggleResidues) {
+            self.configs.residues = !self.configs.residues;
+        }
+
+        // The residues would cover the gaps' colors up
+        if self.bindings.was_released(input, Action::ToggleGapColors) {
+            self.configs.gap_colors = !self.configs.gap_colors;
+            self.configs.residues &= !self.configs.gap_colors;
+        }
+    }
+
+    // Highlights, toasts and the legend go away after a while
+    fn count_down(&mut self) {
+        // The legend shows up by itself whenever the colors mean something else
+        let coloring = self.coloring();
+        if coloring != self.coloring {
+            self.coloring = coloring;
+            self.legend_time = Self::LEGEND_DURATION;
+        }
+        self.legend_time = (self.legend_time - Self::tick_delta()).max(0.0);
+
+        if let Some((number, time_left)) = self.highlight {
+            let time_left = time_left - Self::tick_delta();
+            self.highlight = if time_left > 0.0 { Some((number, time_left)) } else { None };
+        }
+
+        if let Some((number, time_left)) = self.search_highlight {
+            let time_left = time_left - Self::tick_delta();
+            self.search_highlight = if time_left > 0.0 { Some((number, time_left)) } else { None };
+        }
+
+        for toast in &mut self.toasts {
+            toast.age += Self::tick_delta();
+        }
+        self.toasts.retain(|toast| toast.age < Self::TOAST_DURATION);
+    }
+
+    // What's drawn over the points besides them
+    fn interact_panels(&mut self, input: &Controls) {
+        if self.bindings.was_released(input, Action::ToggleHelp) {
+            self.show_help = !self.show_help;
+        }
+
+        if self.bindings.was_released(input, Action::ToggleHud) {
+            self.show_hud = !self.show_hud;
+        }
+
+        if self.bindings.was_released(input, Action::ToggleLucas) {
+            self.show_lucas = !self.show_lucas;
+            self.overlays_changed();
+        } else if self.bindings.was_released(input, Action::ToggleFibonacci) {
+            self.show_fibonacci = !self.show_fibonacci;
+            self.overlays_changed();
+        } else if self.bindings.was_released(input, Action::CyclePowers) {
+            self.power_exponent = overlays::next_power_exponent(self.power_exponent);
+            self.overlays_changed();
+        } else if self.bindings.was_released(input, Action::ToggleSpecialPrimes) {
+            self.show_special_primes = !self.show_special_primes;
+        }
+
+        if self.bindings.was_released(input, Action::ToggleGermainLines) {
+            self.show_germain_lines = !self.show_germain_lines;
+            self.germain_lines.clear();
+        } else if self.bindings.was_released(input, Action::ToggleCrosshair) {
+            self.show_crosshair = !self.show_crosshair;
+        }
+
+        if self.bindings.was_released(input, Action::TogglePrimeCurve) {
+            self.show_prime_curve = !self.show_prime_curve;
+        } else if self.bindings.was_released(input, Action::ToggleStatusBar) {
+            self.show_status_bar = !self.show_status_bar;
+            // For the closest number to be looked up on the next frame
+            self.hover_cursor = Point::new(-1.0, -1.0);
+        }
+
+        if self.bindings.was_released(input, Action::ToggleStats) {
+            self.show_stats = !self.show_stats;
+            self.stats.clear();
+        }
+
+        if self.bindings.was_released(input, Action::ToggleGapHistogram) {
+            self.show_gap_histogram = !self.show_gap_histogram;
+            self.picked_gap = None;
+            self.gaps.clear();
+        }
+
+        if self.bindings.was_released(input, Action::CycleRoseBins) {
+            let current = Self::ROSE_BINS.iter().position(|&bins| bins == self.rose_bins);
+            self.rose_bins = Self::ROSE_BINS[current.map_or(0, |index| (index + 1) % Self::ROSE_BINS.len())];
+            self.show_rose = true;
+            self.notify(format!("{} slices", self.rose_bins));
+        } else if self.bindings.was_released(input, Action::ToggleRose) {
+            self.show_rose = !self.show_rose;
+        }
+
+        if self.bindings.was_released(input, Action::ToggleLegend) {
+            self.show_legend = !self.show_legend;
+            self.legend_time = 0.0;
+        }
+    }
+
+    fn update_stats(&mut self, window: &Window) {
+        let view = (self.configs, self.settings, (window.width(), window.height()));
+        if self.stats.should_start(&view) {
+            let particles = Arc::clone(self.particles());
+            let (configs, settings, (width, height)) = view;
+            let overlays = self.overlays();
+            self.stats.start(view, move || Stats::new(&particles, configs, settings, width, height, &overlays));
+        }
+    }
+
+    fn update_germain_lines(&mut self, window: &Window) {
+        let view = (self.configs, self.settings, (window.width(), window.height()));
+        if self.germain_lines.should_start(&view) {
+            let particles = Arc::clone(self.particles());
+            let (configs, settings, size) = view;
+            self.germain_lines.start(view, move || GermainLines::new(&particles, configs, settings, size));
+        }
+    }
+
+    fn update_gaps(&mut self, window: &Window) {
+        let view = ((self.configs, self.settings, (window.width(), window.height())), self.picked_gap);
+        if self.gaps.should_start(&view) {
+            let particles = Arc::clone(self.particles());
+            let ((configs, settings, size), picked) = view;
+            self.gaps.start(view, move || Gaps::new(&particles, configs, settings, size, picked));
+        }
+    }
+
+    fn update_rose(&mut self) {
+        let filter = RoseFilter::new(&self.configs, self.rose_bins);
+        if self.rose.should_start(&filter) {
+            let (particles, configs) = (Arc::clone(self.particles()), self.configs);
+            self.rose.start(filter, move || Rose::new(&particles, &configs, filter));
+        }
+    }
+
+    pub fn generate_image(custom_palette: Option<CustomPalette>) -> Task<Image> {
+        Task::using_gpu(move |gpu| {
+            let started = Instant::now();
+            let image = Image::from_colors(gpu, &PolarOxideColors::atlas(custom_palette));
+            match &image {
+                Ok(_) => { debug!("Uploaded the colors to the GPU in {:.2?}", started.elapsed()); }
+                Err(error) => { error!("couldn't upload the colors to the GPU: {}", error); }
+            }
+            image
+        })
+    }
+
+    pub fn load_font() -> Task<Font> {
+        Font::load_from_bytes(FONT)
+    }
+}
+
+impl Game for PolarOxides {
+    type Input = Controls;
+    type LoadingScreen = LoadingScreen;
+
+    fn load(_window: &Window) -> Task<PolarOxides> {
+        // Parsed in `main`, once, so what it warns about is only warned about once
+        let args = ARGS.with(|cell| cell.borrow_mut().take()).unwrap_or_default();
+        let max_number = args.particles.max_number;
+        let configs = args.configs;
+        // The particles are the first stage, so this is about when they start being looked for
+        let started = Instant::now();
+        (
+            Task::stage(
+                "Finding primes and generating points...",
+                Self::load_dataset(args.particles).map(move |(points, cache_error)| {
+                    (points, cache_error, started.elapsed())
+                }),
+            ),
+            Task::stage(
+                "Generating image...",
+                Self::generate_image(configs.custom_palette)
+            ),
+            Task::stage(
+                "Loading font...",
+                Self::load_font()
+            )
+        )
+        .join()
+        .map(move |((points, cache_error, generation_time), image, font)| PolarOxides {
+            max_number,
+            points,
+            prime_batch: Batch::new(image.clone()),
+            composite_batch: Batch::new(image.clone()),
+            highlight_batch: Batch::new(image.clone()),
+            overlay_batch: Batch::new(image.clone()),
+            legend_batch: Batch::new(image),
+            font,
+            bindings: Self::bindings(&args.keybindings),
+            configs,
+            // Already checked in `main`
+            settings: args.settings,
+            primality: args.particles.primality,
+            batch_configs: Configs::default(),
+            batch_settings: Settings::default(),
+            batch_size: (0.0, 0.0),
+            batch_dirty: true,
+            batch_build: None,
+            data_export: None,
+            generation_time,
+            view_changed_at: 0.0,
+            active_at: 0.0,
+            prev_frame_configs: Configs::default(),
+            drag_anchor: None,
+            drag_samples: Vec::new(),
+            momentum: None,
+            generation: None,
+            zoom_direction: 0,
+            zoom_coarse: false,
+            zoom_hold_time: 0.0,
+            prompt: None,
+            command_error: None,
+            highlight: None,
+            search_highlight: None,
+            hovered: None,
+            closest: None,
+            hover_cursor: Point::new(-1.0, -1.0),
+            hover_configs: Configs::default(),
+            selected: None,
+            time: 0.0,
+            ticks: 0,
+            interactions: 0,
+            key_pan: (0, 0),
+            last_click: None,
+            recenter_target: None,
+            gamepad_pan: (0.0, 0.0),
+            gamepad_zoom: 0.0,
+            revealing: false,
+            reveal_ticks: 0,
+            toasts: Vec::new(),
+            error: cache_error,
+            show_crosshair: false,
+            crosshair: None,
+            crosshair_view: None,
+            show_germain_lines: false,
+            show_special_primes: false,
+            power_exponent: args.powers,
+            show_fibonacci: args.fibonacci,
+            show_lucas: args.lucas,
+            polygon_sides: args.polygonal.clone(),
+            polynomial: args.poly,
+            progressions: args.progressions.clone(),
+            predicate: args.expr.clone().map(Arc::new),
+            number_list: args.highlight_file.clone().map(Arc::new),
+            germain_lines: Query::default(),
+            show_help: false,
+            show_hud: false,
+            show_status_bar: false,
+            show_stats: false,
+            show_prime_curve: false,
+            stats: Query::default(),
+            show_gap_histogram: false,
+            picked_gap: None,
+            gaps: Query::default(),
+            show_rose: false,
+            rose_bins: Self::ROSE_BINS[1],
+            rose: Query::default(),
+            show_legend: false,
+            legend_time: 0.0,
+            coloring: configs.legend(),
+            visible_primes: 0,
+            visible_composites: 0,
+            last_draw: Instant::now(),
+            fps: 0.0,
+            should_quit: false,
+            tour: if args.tour { Some(Tour::new(&configs)) } else { None },
+            playback: args.play.clone().map(Playback::new),
+            keeps_session: args.keeps_session,
+            windowed_size: args.window_size,
+            fullscreen: args.fullscreen,
+            clipboard: None,
+            presets: Self::presets(),
+            bookmarks: Self::bookmarks(),
+            bookmark_list: None,
+            config_file: args.config_file.clone(),
+            palette_path: args.palette_path.clone(),
+            watcher: Self::watcher(&args),
+        })
+    }
+
+    fn draw(&mut self, frame: &mut Frame, timer: &Timer) {
+        let (background, foreground) = (self.configs.background(), self.configs.foreground());
+        frame.clear(background);
+
+        // coffee presents a frame every time around its loop, so drawing less often means holding it
+        // up here. Input is only looked at between frames, so it wakes up within one idle frame.
+        if self.is_idle() {
+            let idle_frame_time = Duration::from_secs_f32(1.0 / Self::IDLE_FPS);
+            if let Some(remaining) = idle_frame_time.checked_sub(self.last_draw.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+
+        // The timer only knows about ticks, so frames are timed by hand
+        let now = Instant::now();
+        let frame_time = now.duration_since(self.last_draw);
+        let frame_time = frame_time.as_secs() as f32 + frame_time.subsec_nanos() as f32 / 1_000_000_000.0;
+        if frame_time > 0.0 {
+            self.fps += (1.0 / frame_time - self.fps) * Self::FPS_SMOOTHING;
+        }
+        self.last_draw = now;
+
+        if !self.fullscreen {
+            self.windowed_size = (frame.width() as u32, frame.height() as u32);
+        }
+
+        if self.configs != self.prev_frame_configs {
+            self.view_changed_at = self.time;
+        }
+        self.prev_frame_configs = self.configs;
+
+        // The batches being drawn keep being moved around to follow the camera until the new ones are
+        // built, however many frames that takes
+        let built = self.batch_build.as_ref().and_then(BatchBuild::poll);
+        if let Some(built) = built {
+            self.batch_build = None;
+            self.swap_batches(built);
+        }
+
+        let (primes, composites) = self.batches_to_rebuild(frame.width(), frame.height());
+        if primes || composites {
+            self.rebuild_batches(frame.width(), frame.height(), primes, composites);
+        }
+        let camera_transformation = self.camera_transformation(frame.width(), frame.height());
+        let mut target = frame.as_target();
+        let mut camera = target.transform(camera_transformation);
+        // Primes go on top
+        if self.configs.display_mode.shows(false) {
+            self.composite_batch.draw(&mut camera);
+        }
+
+        if self.configs.display_mode.shows(true) {
+            self.prime_batch.draw(&mut camera);
+        }
+        self.overlay_batch.draw(&mut camera);
+
+        // Highlights are a couple of sprites, so it is cheaper to rebuild them every frame
+        // than to dirty the whole batch when they come and go
+        let markers = self.markers(frame.width(), frame.height());
+        self.highlight_batch.clear();
+        self.highlight_batch.extend(markers);
+        self.highlight_batch.draw(&mut frame.as_target());
+
+        self.draw_panels(frame, foreground);
+
+        if let Some(selected) = &self.selected {
+            self.font.add(Text {
+                content: &selected.describe(),
+                position: Point::new(10.0, 10.0),
+                size: 20.0,
+                color: foreground,
+                ..Text::default()
+            });
+        }
+
+        if let Some(number) = self.hovered {
+            let kind = if self.particles().is_prime(number) { "prime" } else { "composite" };
+            self.font.add(Text {
+                content: &format!("{} ({})", number, kind),
+                position: self.hover_cursor + Vector::new(12.0, 12.0),
+                size: 18.0,
+                color: foreground,
+                ..Text::default()
+            });
+        }
+
+        // Everything along the bottom sits on the status bar when it's there
+        let bottom = if self.show_status_bar {
+            frame.height() - self.draw_status_bar(frame)
+        } else {
+            frame.height()
+        };
+
+        self.font.add(Text {
+            content: &self.looks_text(),
+            position: Point::new(frame.width() - 10.0, bottom - 30.0),
+            size: 20.0,
+            color: foreground,
+            horizontal_alignment: HorizontalAlignment::Right,
+            ..Text::default()
+        });
+
+        self.font.add(Text {
+            content: &format!("angle = {:.3}·n", self.configs.angle_factor),
+            position: Point::new(frame.width() - 10.0, bottom - 55.0),
+            size: 20.0,
+            color: foreground,
+            horizontal_alignment: HorizontalAlignment::Right,
+            ..Text::default()
+        });
+
+        self.draw_top_right(frame.width(), foreground);
+
+        if self.show_legend || self.legend_time > 0.0 {
+            self.draw_legend(frame, bottom, foreground);
+        }
+
+        // The overlay is drawn on top of the points instead of changing `configs`, so toggling it
+        // doesn't rebuild the batch
+        if self.show_help {
+            let mut overlay = Mesh::new();
+            overlay.fill(
+                Shape::Rectangle(Rectangle {
+                    x: 0.0,
+                    y: 0.0,
+                    width: frame.width(),
+                    height: frame.height(),
+                }),
+                Color { a: 0.85, ..background },
+            );
+            overlay.draw(&mut frame.as_target());
+
+            self.font.add(Text {
+                content: &self.help_text(),
+                position: Point::new(40.0, 40.0),
+                size: 18.0,
+                color: foreground,
+                ..Text::default()
+            });
+        }
+
+        if let Some(selected) = self.bookmark_list {
+            let mut overlay = Mesh::new();
+            overlay.fill(
+                Shape::Rectangle(Rectangle {
+                    x: 0.0,
+                    y: 0.0,
+                    width: frame.width(),
+                    height: frame.height(),
+                }),
+                Color { a: 0.85, ..background },
+            );
+            overlay.draw(&mut frame.as_target());
+
+            self.font.add(Text {
+                content: &self.bookmark_list_text(selected),
+                position: Point::new(40.0, 40.0),
+                size: 18.0,
+                color: foreground,
+                ..Text::default()
+            });
+        }
+
+        if let Some((prompt, typed)) = &self.prompt {
+            self.font.add(Text {
+                content: &format!("{}{}_", prompt.label(), typed),
+                position: Point::new(10.0, bottom - 30.0),
+                size: 20.0,
+                color: foreground,
+                ..Text::default()
+            });
+        }
+
+        if let Some(error) = &self.command_error {
+            self.font.add(Text {
+                content: error,
+                position: Point::new(10.0, bottom - 55.0),
+                size: 20.0,
+                color: PolarOxideColors::RED,
+                ..Text::default()
+            });
+        }
+        self.font.draw(&mut frame.as_target());
+
+        // After the rest of the text is out, so nothing ends up over them
+        if !self.toasts.is_empty() {
+            self.draw_toasts(frame, bottom, timer);
+        }
+        if self.error.is_some() {
+            self.draw_error_banner(frame);
+        }
+    }
+
+    fn update(&mut self, window: &Window) {
+        if self.should_quit {
+            self.quit();
+        }
+
+        self.time += Self::tick_delta();
+        self.ticks += 1;
+        if self.is_animating() {
+            self.active_at = self.time;
+        }
+
+        // Particles generated in the background replace or extend the current ones as soon as all of
+        // them are there
+        let generated = self.generation.as_ref()
+            .and_then(|generation| Some((generation.max_number(), generation.poll()?)));
+        if let Some((max_number, particles)) = generated {
+            self.generation = None;
+            self.max_number = max_number;
+            self.invalidate_batches();
+            Arc::make_mut(self.points.numbers_mut()).append(particles);
+            self.report_unlisted_numbers();
+            self.hovered = None;
+            self.stats.invalidate();
+            self.gaps.invalidate();
+            self.rose.invalidate();
+            self.crosshair_view = None;
+            self.germain_lines.invalidate();
+        }
+
+        if let Some(written) = self.data_export.as_ref().and_then(DataExport::poll) {
+            self.data_export = None;
+            match written {
+                Ok((path, rows)) => { self.notify(format!("Saved {} rows to {}", rows, path.display())); }
+                Err(error) => { self.report(Err(format!("couldn't save the visible numbers: {}", error))); }
+            }
+        }
+
+        // A flick keeps the view gliding, slowing down the same way whatever the frame rate
+        if let Some(velocity) = self.momentum {
+            let delta = Self::tick_delta();
+            let pixel_rate = self.settings.pixel_rate(self.configs.zoom_level);
+            self.configs.offset.0 += velocity.x * delta / pixel_rate;
+            self.configs.offset.1 += velocity.y * delta / pixel_rate;
+
+            let velocity = velocity * (-Self::MOMENTUM_FRICTION * delta).exp();
+            self.momentum = if velocity.norm() < Self::MOMENTUM_MIN_SPEED { None } else { Some(velocity) };
+        }
+
+        // Panning moves the camera a fixed amount of screen pixels, whatever the zoom level
+        if self.key_pan != (0, 0) {
+            let pan_step = Self::PAN_STEP / self.settings.pixel_rate(self.configs.zoom_level);
+            self.configs.offset.0 += self.key_pan.0 as f32 * pan_step;
+            self.configs.offset.1 += self.key_pan.1 as f32 * pan_step;
+        }
+
+        self.count_down();
+
+        if let Some(tour) = &mut self.tour {
+            tour.step(&mut self.configs, self.settings);
+        }
+
+        if self.show_stats {
+            self.update_stats(window);
+        }
+
+        if self.show_gap_histogram {
+            self.update_gaps(window);
+        }
+
+        if self.show_rose {
+            self.update_rose();
+        }
+
+        if self.show_crosshair {
+            self.update_crosshair();
+        }
+
+        if self.show_germain_lines {
+            self.update_germain_lines(window);
+        }
+
+        if let Some(playback) = &mut self.playback {
+            if playback.step(&mut self.configs, Self::tick_delta()) {
+                self.playback = None;
+                info!("The camera path is over");
+                self.notify(String::from("The camera path is over"));
+            }
+        }
+
+        if self.revealing {
+            self.reveal_ticks += 1;
+            let (first, max_number) = self.points.point_range();
+            let cutoff = first + self.reveal_ticks * Self::REVEAL_SPEED / u64::from(Self::TICKS_PER_SECOND);
+            if cutoff >= max_number {
+                self.configs.reveal_cutoff = max_number;
+                self.revealing = false;
+            } else {
+                self.configs.reveal_cutoff = cutoff;
+            }
+        }
+
+        if self.zoom_direction != 0 {
+            // Ticks happen at a fixed rate, so this is the same on every machine
+            let delta = Self::tick_delta();
+            self.zoom_hold_time += delta;
+
+            // Smoothstep from standstill to full speed
+            let t = (self.zoom_hold_time / Self::ZOOM_EASE_TIME).min(1.0);
+            let speed = Self::ZOOM_SPEED * self.zoom_speed_factor() * t * t * (3.0 - 2.0 * t);
+
+            let center = Point::new(window.width() / 2.0, window.height() / 2.0);
+            self.zoom_towards(self.configs.zoom_level + self.zoom_direction as f32 * speed * delta, center, window);
+        }
+
+        // Sticks move the view proportionally to how far they are pushed
+        let (pan_x, pan_y) = self.gamepad_pan;
+        if pan_x != 0.0 || pan_y != 0.0 {
+            let pan_step = Self::GAMEPAD_PAN_SPEED * Self::tick_delta() / self.settings.pixel_rate(self.configs.zoom_level);
+            self.configs.offset.0 -= pan_x * pan_step;
+            self.configs.offset.1 += pan_y * pan_step;
+        }
+
+        if self.gamepad_zoom != 0.0 {
+            let center = Point::new(window.width() / 2.0, window.height() / 2.0);
+            let zoom_delta = self.gamepad_zoom * Self::ZOOM_SPEED * Self::tick_delta();
+            self.zoom_towards(self.configs.zoom_level + zoom_delta, center, window);
+        }
+
+        // Glide towards the double-clicked point instead of jumping there
+        if let Some((target_x, target_y)) = self.recenter_target {
+            let (x, y) = self.configs.offset;
+            let remaining = ((target_x - x).powi(2) + (target_y - y).powi(2)).sqrt();
+
+            if remaining * self.settings.pixel_rate(self.configs.zoom_level) < 0.5 {
+                self.configs.offset = (target_x, target_y);
+                self.recenter_target = None;
+            } else {
+                self.configs.offset = (
+                    x + (target_x - x) * Self::RECENTER_SMOOTHING,
+                    y + (target_y - y) * Self::RECENTER_SMOOTHING,
+                );
+            }
+        }
+    }
+
+    fn on_close_request(&mut self) -> bool {
+        self.shut_down();
+        true
+    }
+
+    fn interact(&mut self, input: &mut Controls, window: &mut Window) {
+        self.reload_changed_files(window);
+        self.interactions += 1;
+        input.sync(self.interactions);
+        if !input.is_idle_except(&[]) {
+            self.active_at = self.time;
+        }
+
+        // Touching anything hands control back, except for the tour key, which stops it by itself
+        let tour_keys = self.bindings.keys(Action::ToggleTour);
+        if self.tour.is_some() && !input.is_idle_except(&tour_keys) {
+            self.tour = None;
+        }
+        // The same for the camera path and its pause key, though the mouse just moving doesn't count
+        let pause_keys = self.bindings.keys(Action::ToggleReveal);
+        if self.playback.is_some() && input.is_pressing_anything_except(&pause_keys) {
+            self.playback = None;
+        }
+
+        // Anything else the user does stops a glide, before the mouse gets a chance to start a new one
+        if input.is_pressing_anything() {
+            self.momentum = None;
+        }
+
+        self.interact_mouse(input, window);
+        self.interact_gamepad(input, window);
+        if self.gamepad_pan != (0.0, 0.0) || self.gamepad_zoom != 0.0 {
+            self.tour = None;
+            self.playback = None;
+        }
+
+        if self.interact_prompt(input, window) || self.interact_bookmark_list(input) {
+            return;
+        }
+
+        self.interact_keyboard(input, window);
     }
 }
 
-const COLORS: [Color; 3] = [
-    PolarOxideColors::BLACK,
-    PolarOxideColors::YELLOW,
-    PolarOxideColors::BLUE,
-];
\ No newline at end of file
+const FONT: &[u8] = include_bytes!("../resources/font/Inconsolata-Regular.ttf");
\ No newline at end of file
@@ -0,0 +1,735 @@
+//! Sparse sets of numbers marked on top of the points, whatever those are colored by
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::palette::{Marker, MAX_MODULUS};
+use crate::predicate::Predicate;
+
+/// A set of numbers with a marker over each of them, few enough to be worked out for whatever part
+/// of the spiral is in view
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Overlay {
+    /// The perfect powers with this exponent
+    Powers(u32),
+    Fibonacci,
+    Lucas,
+    /// The polygonal numbers with this many sides, from `MIN_SIDES` to `MAX_SIDES`
+    Polygonal(u32),
+    /// The values of a quadratic for n from 0 on
+    Polynomial(Quadratic),
+    /// The numbers in an arithmetic progression, the how-manyth of those shown picking its colors
+    Progression(usize, Progression),
+    /// The numbers a predicate picks, shared rather than copied
+    Predicate(Arc<Predicate>),
+    /// Numbers read from a file, shared the same way
+    List(Arc<NumberList>),
+}
+
+impl Overlay {
+    /// Its numbers in `start..end`, smallest first
+    pub fn numbers(&self, start: u64, end: u64) -> Vec<u64> {
+        let terms = match *self {
+            Overlay::Powers(exponent) => { return perfect_powers(exponent, start, end); }
+            Overlay::Polygonal(sides) => { return polygonal_numbers(sides, start, end); }
+            Overlay::Polynomial(quadratic) => { return quadratic.values(start, end); }
+            Overlay::Progression(_, progression) => { return progression.terms(start, end); }
+            Overlay::Predicate(ref predicate) => { return predicate.pick(start, end).numbers(); }
+            Overlay::List(ref list) => { return list.numbers(start, end); }
+            Overlay::Fibonacci => { fibonacci() }
+            Overlay::Lucas => { lucas() }
+        };
+        let mut numbers: Vec<u64> = terms.into_iter()
+            .map(|(_, number)| number)
+            .filter(|number| (start..end).contains(number))
+            .collect();
+        numbers.sort();
+        numbers.dedup();
+        numbers
+    }
+
+    /// The marker drawn over one of its numbers. The primes among the sequences' get one of their
+    /// own, the same for both, and so do the polynomial's and the progressions'.
+    pub fn marker(&self, prime: bool) -> Marker {
+        match *self {
+            Overlay::Powers(_) => { Marker::Power }
+            Overlay::Polygonal(sides) => { Marker::Polygonal(sides) }
+            Overlay::Polynomial(_) if prime => { Marker::PolynomialPrime }
+            Overlay::Polynomial(_) => { Marker::Polynomial }
+            Overlay::Progression(index, _) if prime => { Marker::ProgressionPrime(index) }
+            Overlay::Progression(index, _) => { Marker::Progression(index) }
+            Overlay::Predicate(_) => { Marker::Predicate }
+            Overlay::List(_) => { Marker::Listed }
+            Overlay::Fibonacci | Overlay::Lucas if prime => { Marker::SequencePrime }
+            Overlay::Fibonacci => { Marker::Fibonacci }
+            Overlay::Lucas => { Marker::Lucas }
+        }
+    }
+
+    /// What its markers mean, for the legend
+    pub fn label(&self) -> String {
+        match *self {
+            Overlay::Powers(exponent) => { format!("perfect {}", power_name(exponent)) }
+            Overlay::Polygonal(sides) => { format!("{} number", polygon_name(sides)) }
+            Overlay::Polynomial(quadratic) => { format!("value of {}", quadratic) }
+            Overlay::Progression(_, progression) => { format!("number ≡ {}", progression) }
+            Overlay::Predicate(ref predicate) => { format!("number where {}", predicate) }
+            Overlay::List(ref list) => { format!("number in {}", list.name) }
+            Overlay::Fibonacci => { String::from(Marker::Fibonacci.meaning()) }
+            Overlay::Lucas => { String::from(Marker::Lucas.meaning()) }
+        }
+    }
+
+    /// The same for more than one of its numbers, for the statistics
+    pub fn plural(&self) -> String {
+        match *self {
+            Overlay::Polynomial(quadratic) => { format!("values of {}", quadratic) }
+            Overlay::Progression(_, progression) => { format!("numbers ≡ {}", progression) }
+            Overlay::Predicate(ref predicate) => { format!("numbers where {}", predicate) }
+            Overlay::List(ref list) => { format!("numbers in {}", list.name) }
+            _ => { format!("{}s", self.label()) }
+        }
+    }
+
+    /// Its markers and what they mean, for the legend: the one its numbers get, then the one its
+    /// primes get if that's another one
+    pub fn legend(&self) -> Vec<(Marker, String)> {
+        let mut legend = vec![(self.marker(false), self.label())];
+        match *self {
+            Overlay::Polynomial(quadratic) => {
+                legend.push((self.marker(true), format!("prime value of {}", quadratic)));
+            }
+            Overlay::Progression(_, progression) => {
+                legend.push((self.marker(true), format!("prime ≡ {}", progression)));
+            }
+            _ if self.marker(true) != self.marker(false) => {
+                legend.push((self.marker(true), String::from(self.marker(true).meaning())));
+            }
+            _ => {}
+        }
+        legend
+    }
+
+    /// Whether its markers get labels, see `name_of`
+    pub fn is_labeled(&self) -> bool {
+        match *self {
+            Overlay::Fibonacci | Overlay::Lucas => { true }
+            Overlay::Powers(_) | Overlay::Polygonal(_) | Overlay::Polynomial(_) => { false }
+            Overlay::Progression(..) | Overlay::Predicate(_) | Overlay::List(_) => { false }
+        }
+    }
+
+    /// Which term of the sequence `number` is, like F(12), for the label next to its marker. The
+    /// rest are too many to label. 1 is F(1), F(2) and L(1), and goes by the first.
+    pub fn name_of(&self, number: u64) -> Option<String> {
+        let (letter, terms) = match *self {
+            Overlay::Fibonacci => { ("F", fibonacci()) }
+            Overlay::Lucas => { ("L", lucas()) }
+            Overlay::Powers(_) | Overlay::Polygonal(_) | Overlay::Polynomial(_) => { return None; }
+            Overlay::Progression(..) | Overlay::Predicate(_) | Overlay::List(_) => { return None; }
+        };
+        let &(index, _) = terms.iter().find(|&&(_, term)| term == number)?;
+        Some(format!("{}({})", letter, index))
+    }
+}
+
+/// The exponents the perfect power markers cycle through, before they're off again
+pub const POWER_EXPONENTS: [u32; 3] = [2, 3, 4];
+/// 2^64 is past what a u64 holds, so from there on only 0 and 1 would be left
+pub const MAX_POWER_EXPONENT: u32 = 63;
+
+/// The exponent of the perfect powers marked after `exponent`'s: the next one in `POWER_EXPONENTS`,
+/// none after the last one or one that isn't in there, and the first one after none
+pub fn next_power_exponent(exponent: Option<u32>) -> Option<u32> {
+    match exponent {
+        None => { Some(POWER_EXPONENTS[0]) }
+        Some(exponent) => {
+            let place = POWER_EXPONENTS.iter().position(|&other| other == exponent)?;
+            POWER_EXPONENTS.get(place + 1).cloned()
+        }
+    }
+}
+
+/// What the powers with an exponent are called, for the legend: squares, cubes, then 4th powers
+/// and so on
+pub fn power_name(exponent: u32) -> String {
+    match exponent {
+        2 => { String::from("square") }
+        3 => { String::from("cube") }
+        exponent => { format!("{}th power", exponent) }
+    }
+}
+
+/// Every m^exponent in `start..end`, smallest first, for an exponent from 2 on. 0 and 1 are one
+/// for every exponent.
+pub fn perfect_powers(exponent: u32, start: u64, end: u64) -> Vec<u64> {
+    // A root in floating point can be off by one, so it starts a bit before
+    let root = (start as f64).powf(1.0 / f64::from(exponent)) as u64;
+    (root.saturating_sub(1)..)
+        .map(|base| base.checked_pow(exponent))
+        .take_while(|power| power.map_or(false, |power| power < end))
+        .flatten()
+        .filter(|&power| power >= start)
+        .collect()
+}
+
+/// The fewest and the most sides the polygonal numbers can be marked for
+pub const MIN_SIDES: u32 = 3;
+pub const MAX_SIDES: u32 = 8;
+
+/// What the polygonal numbers with that many sides are called, triangular for 3 up to octagonal
+/// for 8
+pub fn polygon_name(sides: u32) -> &'static str {
+    match sides {
+        3 => { "triangular" }
+        4 => { "square" }
+        5 => { "pentagonal" }
+        6 => { "hexagonal" }
+        7 => { "heptagonal" }
+        _ => { "octagonal" }
+    }
+}
+
+/// The sides in a list like 3,5,6, smallest first and each once
+pub fn parse_sides(list: &str) -> Result<Vec<u32>, String> {
+    let mut sides = list.split(',')
+        .map(|side| {
+            let side = side.trim();
+            side.parse::<u32>().ok()
+                .filter(|sides| (MIN_SIDES..=MAX_SIDES).contains(sides))
+                .ok_or_else(|| format!("the sides have to be from {} to {}, got `{}`", MIN_SIDES, MAX_SIDES, side))
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+    sides.sort();
+    sides.dedup();
+    Ok(sides)
+}
+
+/// The s-gonal numbers ((s - 2)n² - (s - 4)n) / 2 in `start..end`, smallest first, for s sides
+/// from 3 on and n from 0 on: how many dots the first n of a row of polygons nested in one corner
+/// take, like the triangular numbers n(n + 1) / 2.
+pub fn polygonal_numbers(sides: u32, start: u64, end: u64) -> Vec<u64> {
+    let sides = i128::from(sides);
+    let number = |n: i128| ((sides - 2) * n * n - (sides - 4) * n) / 2;
+    // They're about (s - 2)n² / 2, and never far enough over it for this to skip any
+    let first = (2.0 * start as f64 / (sides - 2) as f64).sqrt() as i128;
+    ((first - 2).max(0)..)
+        .map(number)
+        .take_while(|&polygonal| polygonal < i128::from(end))
+        .filter(|&polygonal| polygonal >= i128::from(start))
+        .map(|polygonal| polygonal as u64)
+        .collect()
+}
+
+/// The most progressions marked at once, each in one of the residues' hues
+pub const MAX_PROGRESSIONS: usize = MAX_MODULUS as usize;
+
+/// The numbers a, a + m, a + 2m... that leave a remainder of a when divided by m, with a below m
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Progression {
+    pub residue: u64,
+    pub modulus: u64,
+}
+
+impl Progression {
+    /// a and m like 3,10 for the numbers ending in 3, a taken mod m
+    pub fn parse(text: &str) -> Result<Progression, String> {
+        let numbers = text.split(',')
+            .map(|number| {
+                let number = number.trim();
+                number.parse::<u64>().map_err(|_| format!("`{}` isn't a whole number", number))
+            })
+            .collect::<Result<Vec<u64>, String>>()?;
+        match numbers[..] {
+            [_, modulus] if modulus < 2 => { Err(format!("the modulus has to be at least 2, got `{}`", text)) }
+            [residue, modulus] => { Ok(Progression { residue: residue % modulus, modulus }) }
+            _ => { Err(format!("expected a,m like 3,10, got `{}`", text)) }
+        }
+    }
+
+    /// What's wrong with marking it, if anything: when a and m share a factor, so does every
+    /// number in it, which leaves room for one prime at most instead of the infinitely many of
+    /// Dirichlet's theorem
+    pub fn warning(self) -> Option<String> {
+        let factor = gcd(self.residue, self.modulus);
+        if factor == 1 {
+            return None;
+        }
+        Some(format!(
+            "{} and {} share the factor {}, so {} holds at most one prime",
+            self.residue, self.modulus, factor, self,
+        ))
+    }
+
+    /// Its numbers in `start..end`, smallest first
+    pub fn terms(self, start: u64, end: u64) -> Vec<u64> {
+        // Worked out without adding to the modulus, which can be as big as a u64 gets
+        let offset = start % self.modulus;
+        let ahead = if self.residue >= offset { self.residue - offset } else { self.modulus - (offset - self.residue) };
+        match start.checked_add(ahead) {
+            Some(first) => { (first..end).step_by(self.modulus as usize).collect() }
+            None => { Vec::new() }
+        }
+    }
+}
+
+impl fmt::Display for Progression {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} (mod {})", self.residue, self.modulus)
+    }
+}
+
+/// The progressions in a list like 3,10 7,10, in order and each once
+pub fn parse_progressions(list: &str) -> Result<Vec<Progression>, String> {
+    let mut progressions: Vec<Progression> = Vec::new();
+    for text in list.split_whitespace() {
+        let progression = Progression::parse(text)?;
+        if !progressions.contains(&progression) {
+            progressions.push(progression);
+        }
+    }
+    if progressions.len() > MAX_PROGRESSIONS {
+        let count = progressions.len();
+        return Err(format!("there are colors for {} progressions at once, not {}", MAX_PROGRESSIONS, count));
+    }
+    Ok(progressions)
+}
+
+/// The greatest common divisor, with gcd(a, 0) = a
+pub fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// a n² + b n + c, with a other than 0 so there are only so many values in a range
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Quadratic {
+    pub a: i64,
+    pub b: i64,
+    pub c: i64,
+}
+
+impl Quadratic {
+    /// n² + n + 41, Euler's, which is prime for every n from 0 to 39
+    pub const EULER: Quadratic = Quadratic { a: 1, b: 1, c: 41 };
+
+    /// Coefficients like 1,-79,1601 for n² - 79n + 1601, or euler for `EULER`
+    pub fn parse(text: &str) -> Result<Quadratic, String> {
+        if text.eq_ignore_ascii_case("euler") {
+            return Ok(Quadratic::EULER);
+        }
+        let coefficients = text.split(',')
+            .map(|coefficient| {
+                let coefficient = coefficient.trim();
+                coefficient.parse::<i64>().map_err(|_| format!("`{}` isn't a whole number", coefficient))
+            })
+            .collect::<Result<Vec<i64>, String>>()?;
+        match coefficients[..] {
+            [0, _, _] => { Err(String::from("a can't be 0, there would be too many values to mark")) }
+            [a, b, c] => { Ok(Quadratic { a, b, c }) }
+            _ => { Err(format!("expected the three coefficients a,b,c or euler, got `{}`", text)) }
+        }
+    }
+
+    /// Its value at n, unless that's negative or past what a u64 holds
+    pub fn value(self, n: u64) -> Option<u64> {
+        let n = i128::from(n);
+        let value = i128::from(self.a).checked_mul(n)?.checked_mul(n)?
+            .checked_add(i128::from(self.b).checked_mul(n)?)?
+            .checked_add(i128::from(self.c))?;
+        u64::try_from(value).ok()
+    }
+
+    /// Its values in `start..end` for n from 0 on, smallest first and each once. It goes one way up
+    /// to the n where it turns around and the other way from there, so each side is searched for
+    /// where it's in the range, which keeps it to the values there are.
+    pub fn values(self, start: u64, end: u64) -> Vec<u64> {
+        // Far enough out to be past any range, and never overflowing i128
+        let at = |n: u64| {
+            let n = i128::from(n);
+            i128::from(self.a).saturating_mul(n).saturating_mul(n)
+                .saturating_add(i128::from(self.b).saturating_mul(n))
+                .saturating_add(i128::from(self.c))
+        };
+        // f(n + 1) - f(n) = a(2n + 1) + b takes the sign of a from the first n from (-a - b) / 2a on
+        let (a, b) = (i128::from(self.a), i128::from(self.b));
+        let (over, under) = if a > 0 { (-a - b, 2 * a) } else { (a + b, -2 * a) };
+        let turn = -(-over).div_euclid(under);
+        let turn = u64::try_from(turn.max(0)).unwrap_or(u64::max_value());
+
+        let (start, end) = (i128::from(start), i128::from(end));
+        let mut values = Vec::new();
+        for &(first, last, rising) in &[(0, turn, a < 0), (turn, u64::max_value(), a > 0)] {
+            // Where it's at least `bound` going up, or below it going down
+            let past = |bound: i128| first_where(first, last, |n| (at(n) >= bound) == rising);
+            let (from, to) = if rising { (past(start), past(end)) } else { (past(end), past(start)) };
+            values.extend((from..to).filter_map(|n| self.value(n)));
+        }
+        values.sort();
+        values.dedup();
+        values
+    }
+}
+
+impl fmt::Display for Quadratic {
+    /// Like n² - 79n + 1601, leaving out what's 0 and the 1s in front of n
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let terms = [(self.a, "n²"), (self.b, "n"), (self.c, "")];
+        let mut first = true;
+        for &(coefficient, power) in terms.iter().filter(|&&(coefficient, _)| coefficient != 0) {
+            let sign = match (first, coefficient < 0) {
+                (true, true) => { "-" }
+                (true, false) => { "" }
+                (false, true) => { " - " }
+                (false, false) => { " + " }
+            };
+            let size = i128::from(coefficient).abs().to_string();
+            let size = if size == "1" && !power.is_empty() { "" } else { &size };
+            write!(f, "{}{}{}", sign, size, power)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+// The first n in `first..last` `past` says yes to, or `last`, for a `past` that says no up to some
+// n and yes from there on
+fn first_where<F: Fn(u64) -> bool>(mut first: u64, mut last: u64, past: F) -> u64 {
+    while first < last {
+        let middle = first + (last - first) / 2;
+        if past(middle) {
+            last = middle;
+        } else {
+            first = middle + 1;
+        }
+    }
+    first
+}
+
+/// Numbers read from a file, like a sequence downloaded from the OEIS, each once. They're kept as a
+/// sorted list, or as one bit per number from the smallest on when they're dense enough for that
+/// to take less room.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NumberList {
+    /// Where they came from, for the legend
+    pub name: String,
+    storage: Storage,
+    count: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Storage {
+    Sorted(Vec<u64>),
+    // Bit `i % 64` of word `i / 64` is set for `start + i`, like `Sieve`'s
+    Bits { words: Vec<u64>, start: u64 },
+}
+
+impl NumberList {
+    pub fn new(name: &str, mut numbers: Vec<u64>) -> NumberList {
+        numbers.sort();
+        numbers.dedup();
+        let count = numbers.len() as u64;
+        let storage = match (numbers.first(), numbers.last()) {
+            (Some(&first), Some(&last)) if (last - first) / 64 < count => {
+                let start = first / 64 * 64;
+                let mut words = vec![0u64; ((last - start) / 64 + 1) as usize];
+                for number in numbers {
+                    let bit = number - start;
+                    words[(bit / 64) as usize] |= 1 << (bit % 64);
+                }
+                Storage::Bits { words, start }
+            }
+            _ => { Storage::Sorted(numbers) }
+        };
+        NumberList { name: String::from(name), storage, count }
+    }
+
+    /// One number a line, or two like in the OEIS's b-files, n and then a(n), of which the second
+    /// one counts. Blank lines and anything after a # are skipped. The lines that are something
+    /// else come back with their numbers, counted from 1, instead of the whole file being an error.
+    pub fn parse(name: &str, text: &str) -> (NumberList, Vec<(usize, String)>) {
+        let mut numbers = Vec::new();
+        let mut bad_lines = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let content = line.split('#').next().unwrap_or("").trim();
+            if content.is_empty() {
+                continue;
+            }
+            let words: Vec<&str> = content.split_whitespace().collect();
+            match words.last().filter(|_| words.len() <= 2).and_then(|word| word.parse::<u64>().ok()) {
+                Some(number) => { numbers.push(number); }
+                None => { bad_lines.push((index + 1, String::from(line.trim()))); }
+            }
+        }
+        (NumberList::new(name, numbers), bad_lines)
+    }
+
+    /// How many numbers there are, each counted once
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Its numbers in `start..end`, smallest first
+    pub fn numbers(&self, start: u64, end: u64) -> Vec<u64> {
+        match &self.storage {
+            Storage::Sorted(numbers) => {
+                let first = numbers.binary_search(&start).unwrap_or_else(|place| place);
+                numbers[first..].iter().cloned().take_while(|&number| number < end).collect()
+            }
+            Storage::Bits { words, start: bits_start } => {
+                let first_word = (start.max(*bits_start) - bits_start) / 64;
+                let mut numbers = Vec::new();
+                for (index, &word) in words.iter().enumerate().skip(first_word as usize) {
+                    let mut rest = word;
+                    while rest != 0 {
+                        let number = bits_start + index as u64 * 64 + u64::from(rest.trailing_zeros());
+                        if number >= end {
+                            return numbers;
+                        }
+                        if number >= start {
+                            numbers.push(number);
+                        }
+                        rest &= rest - 1;
+                    }
+                }
+                numbers
+            }
+        }
+    }
+
+    /// How many of its numbers aren't in `start..end`, so none of them goes missing unnoticed
+    pub fn outside(&self, start: u64, end: u64) -> u64 {
+        self.count - self.numbers(start, end).len() as u64
+    }
+}
+
+/// The Fibonacci numbers F(k) a u64 holds along with their k, from F(0) = 0 and F(1) = 1 on, each
+/// the sum of the two before it
+pub fn fibonacci() -> Vec<(u32, u64)> {
+    sequence(0, 1)
+}
+
+/// The Lucas numbers L(k) a u64 holds, which add up the same way from L(0) = 2 and L(1) = 1
+pub fn lucas() -> Vec<(u32, u64)> {
+    sequence(2, 1)
+}
+
+fn sequence(first: u64, second: u64) -> Vec<(u32, u64)> {
+    let mut terms = vec![(0, first), (1, second)];
+    loop {
+        let (_, before) = terms[terms.len() - 2];
+        let (index, last) = terms[terms.len() - 1];
+        match last.checked_add(before) {
+            Some(next) => { terms.push((index + 1, next)); }
+            None => { return terms; }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::particles::Particles;
+
+    #[test]
+    fn finds_the_perfect_powers_in_a_range() {
+        assert_eq!(perfect_powers(2, 0, 50), vec![0, 1, 4, 9, 16, 25, 36, 49]);
+        assert_eq!(perfect_powers(3, 100, 1000), vec![125, 216, 343, 512, 729]);
+        assert_eq!(perfect_powers(4, 16, 256), vec![16, 81]);
+        assert_eq!(perfect_powers(2, 50, 64), Vec::<u64>::new());
+        assert_eq!(perfect_powers(63, 2, u64::max_value()), vec![1 << 63]);
+        // The last square a u64 holds, without overflowing past it
+        let last = u64::from(u32::max_value()) * u64::from(u32::max_value());
+        assert_eq!(perfect_powers(2, last - 1_000_000_000, u64::max_value()), vec![last]);
+    }
+
+    #[test]
+    fn the_exponents_cycle_through_to_off() {
+        let mut exponent = None;
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            exponent = next_power_exponent(exponent);
+            seen.push(exponent);
+        }
+        assert_eq!(seen, vec![Some(2), Some(3), Some(4), None]);
+        // One given with --powers
+        assert_eq!(next_power_exponent(Some(7)), None);
+        assert_eq!(power_name(4), "4th power");
+    }
+
+    #[test]
+    fn the_sequences_go_as_far_as_a_u64_does() {
+        let fibonacci = fibonacci();
+        assert_eq!(fibonacci.len(), 94);
+        assert_eq!(fibonacci[12], (12, 144));
+        assert_eq!(fibonacci.last(), Some(&(93, 12_200_160_415_121_876_738)));
+        let lucas = lucas();
+        assert_eq!(&lucas[..6], &[(0, 2), (1, 1), (2, 3), (3, 4), (4, 7), (5, 11)]);
+        assert_eq!(lucas[10], (10, 123));
+        assert!(lucas.last().unwrap().1.checked_add(lucas[lucas.len() - 2].1).is_none());
+
+        assert_eq!(Overlay::Fibonacci.numbers(0, 22), vec![0, 1, 2, 3, 5, 8, 13, 21]);
+        assert_eq!(Overlay::Lucas.numbers(1, 30), vec![1, 2, 3, 4, 7, 11, 18, 29]);
+        assert_eq!(Overlay::Powers(2).numbers(10, 40), vec![16, 25, 36]);
+        assert_eq!(Overlay::Fibonacci.name_of(1), Some(String::from("F(1)")));
+        assert_eq!(Overlay::Lucas.name_of(123), Some(String::from("L(10)")));
+        assert_eq!(Overlay::Lucas.name_of(5), None);
+        assert_eq!(Overlay::Powers(2).name_of(16), None);
+        assert!(Overlay::Lucas.is_labeled() && !Overlay::Powers(3).is_labeled());
+        assert_eq!(Overlay::Fibonacci.marker(true), Overlay::Lucas.marker(true));
+    }
+
+    #[test]
+    fn finds_the_polygonal_numbers_in_a_range() {
+        assert_eq!(polygonal_numbers(3, 0, 40), vec![0, 1, 3, 6, 10, 15, 21, 28, 36]);
+        assert_eq!(polygonal_numbers(4, 0, 40), perfect_powers(2, 0, 40));
+        assert_eq!(polygonal_numbers(5, 0, 100), vec![0, 1, 5, 12, 22, 35, 51, 70, 92]);
+        assert_eq!(polygonal_numbers(6, 10, 100), vec![15, 28, 45, 66, 91]);
+        assert_eq!(polygonal_numbers(8, 0, 100), vec![0, 1, 8, 21, 40, 65, 96]);
+        // Far out, where the first one is worked out from the start
+        let triangular = polygonal_numbers(3, 5_000_000_000, 5_000_200_000);
+        assert_eq!(triangular, vec![5_000_050_000, 5_000_150_001]);
+        assert_eq!(polygonal_numbers(7, u64::max_value() - 1000, u64::max_value()), Vec::<u64>::new());
+
+        assert_eq!(parse_sides("6,3, 5,3"), Ok(vec![3, 5, 6]));
+        assert!(parse_sides("3,9").unwrap_err().contains("`9`"));
+        assert!(parse_sides("").is_err());
+        assert_eq!(Overlay::Polygonal(7).label(), "heptagonal number");
+        assert_eq!(Overlay::Polygonal(5).numbers(2, 20), vec![5, 12]);
+    }
+
+    #[test]
+    fn the_first_40_values_of_eulers_polynomial_are_prime() {
+        let particles = Particles::find(2000);
+        let euler = Overlay::Polynomial(Quadratic::EULER);
+        let values = euler.numbers(0, 2000);
+        let first: Vec<u64> = (0..40).map(|n| n * n + n + 41).collect();
+        assert_eq!(&values[..40], &first[..]);
+        for &value in &first {
+            assert!(particles.is_prime(value), "{}", value);
+            assert_eq!(euler.marker(particles.is_prime(value)), Marker::PolynomialPrime);
+        }
+        // 40² + 40 + 41 = 41²
+        assert_eq!(values[40], 1681);
+        assert_eq!(euler.marker(particles.is_prime(1681)), Marker::Polynomial);
+        assert_eq!(euler.legend()[1].1, "prime value of n² + n + 41");
+        assert_eq!(euler.plural(), "values of n² + n + 41");
+    }
+
+    #[test]
+    fn every_value_in_the_range_is_found_once() {
+        let brute_force = |quadratic: Quadratic, start: u64, end: u64| {
+            let mut values: Vec<u64> = (0..10_000)
+                .filter_map(|n| quadratic.value(n))
+                .filter(|value| (start..end).contains(value))
+                .collect();
+            values.sort();
+            values.dedup();
+            values
+        };
+        let quadratics = [
+            Quadratic::EULER,
+            Quadratic { a: 1, b: -79, c: 1601 },
+            Quadratic { a: -1, b: 10, c: 5 },
+            Quadratic { a: 3, b: -1, c: 0 },
+            Quadratic { a: -2, b: 300, c: -7 },
+        ];
+        for &quadratic in &quadratics {
+            for &(start, end) in &[(0, 100), (0, 20_000), (1000, 1500), (1601, 1602)] {
+                assert_eq!(quadratic.values(start, end), brute_force(quadratic, start, end), "{}", quadratic);
+            }
+        }
+        assert_eq!(Quadratic { a: -1, b: 10, c: 5 }.values(0, 100), vec![5, 14, 21, 26, 29, 30]);
+        // Nothing overflows on the way
+        let huge = Quadratic { a: i64::max_value(), b: i64::min_value(), c: i64::max_value() };
+        assert_eq!(huge.values(0, u64::max_value()), brute_force(huge, 0, u64::max_value()));
+        assert_eq!(huge.value(1 << 40), None);
+    }
+
+    #[test]
+    fn progressions_step_through_the_range() {
+        let ending_in_3 = Progression::parse("13, 10").unwrap();
+        assert_eq!(ending_in_3, Progression { residue: 3, modulus: 10 });
+        assert_eq!(ending_in_3.terms(0, 40), vec![3, 13, 23, 33]);
+        assert_eq!(ending_in_3.terms(23, 53), vec![23, 33, 43]);
+        assert_eq!(ending_in_3.terms(24, 33), Vec::<u64>::new());
+        let last = u64::max_value();
+        assert_eq!(ending_in_3.terms(last - 3, last), vec![last - 2]);
+        assert_eq!(ending_in_3.terms(last - 1, last), Vec::<u64>::new());
+        let huge = Progression::parse("5,18446744073709551615").unwrap();
+        assert_eq!(huge.terms(0, 100), vec![5]);
+        assert_eq!(huge.terms(6, last), Vec::<u64>::new());
+        assert_eq!(huge.terms(0, last), vec![5]);
+        let overlay = Overlay::Progression(2, ending_in_3);
+        assert_eq!(overlay.legend()[1], (Marker::ProgressionPrime(2), String::from("prime ≡ 3 (mod 10)")));
+        assert_eq!(overlay.plural(), "numbers ≡ 3 (mod 10)");
+
+        // Dirichlet's theorem only holds with a and m coprime
+        assert_eq!(ending_in_3.warning(), None);
+        let even = Progression::parse("4,6").unwrap();
+        let warning = String::from("4 and 6 share the factor 2, so 4 (mod 6) holds at most one prime");
+        assert_eq!(even.warning(), Some(warning));
+        assert!(Progression::parse("0,7").unwrap().warning().unwrap().contains("factor 7"));
+        assert_eq!((gcd(12, 18), gcd(7, 0), gcd(1, 10)), (6, 7, 1));
+
+        let ending_in_7 = Progression { residue: 7, modulus: 10 };
+        assert_eq!(parse_progressions("3,10 7,10  13,10"), Ok(vec![ending_in_3, ending_in_7]));
+        for &bad in &["3,1", "3", "3,10,2", "a,10", "3;10"] {
+            assert!(parse_progressions(bad).is_err(), "{}", bad);
+        }
+        let too_many: Vec<String> = (0..=MAX_PROGRESSIONS).map(|residue| format!("{},100", residue)).collect();
+        assert!(parse_progressions(&too_many.join(" ")).is_err());
+    }
+
+    #[test]
+    fn number_lists_are_read_leniently() {
+        let text = "# The primes\n2\n3\n\n5 # and so on\n  7\nseven\n1 11\n2 13 17\n-19\n3\n";
+        let (list, bad_lines) = NumberList::parse("primes.txt", text);
+        assert_eq!(list.numbers(0, 100), vec![2, 3, 5, 7, 11]);
+        assert_eq!(list.len(), 5);
+        let bad: Vec<usize> = bad_lines.iter().map(|&(line, _)| line).collect();
+        assert_eq!(bad, vec![7, 9, 10]);
+        assert_eq!(bad_lines[0].1, "seven");
+        assert_eq!(Overlay::List(Arc::new(list)).label(), "number in primes.txt");
+        assert!(NumberList::parse("empty", "# nothing\n\n").0.is_empty());
+    }
+
+    #[test]
+    fn number_lists_are_the_same_sparse_or_dense() {
+        // The squares are sparse, every other number from a million on isn't
+        let squares: Vec<u64> = (0..1000).map(|n| n * n).collect();
+        let sparse = NumberList::new("squares", squares.clone());
+        assert!(match sparse.storage { Storage::Sorted(_) => { true } _ => { false } });
+        assert_eq!(sparse.numbers(0, u64::max_value()), squares);
+        assert_eq!(sparse.numbers(10, 50), vec![16, 25, 36, 49]);
+
+        let evens: Vec<u64> = (500_000..1_000_000).map(|n| 1_000_000 + 2 * n).rev().collect();
+        let dense = NumberList::new("evens", evens.iter().chain(&evens).cloned().collect());
+        assert!(match dense.storage { Storage::Bits { .. } => { true } _ => { false } });
+        assert_eq!(dense.len(), 500_000);
+        assert_eq!(dense.numbers(2_000_001, 2_000_008), vec![2_000_002, 2_000_004, 2_000_006]);
+        assert_eq!(dense.numbers(0, 2_000_003), vec![2_000_000, 2_000_002]);
+        assert_eq!(dense.numbers(2_999_998, u64::max_value()), vec![2_999_998]);
+        assert_eq!(dense.numbers(3_000_000, u64::max_value()), Vec::<u64>::new());
+        assert_eq!(dense.outside(0, 2_000_010), 499_995);
+        assert_eq!(sparse.outside(0, 100), 990);
+    }
+
+    #[test]
+    fn quadratics_are_read_and_written_like_they_look() {
+        assert_eq!(Quadratic::parse("1,-79, 1601"), Ok(Quadratic { a: 1, b: -79, c: 1601 }));
+        assert_eq!(Quadratic::parse("Euler"), Ok(Quadratic::EULER));
+        for &bad in &["0,1,2", "1,2", "1,x,3", "1,2,3,4", ""] {
+            assert!(Quadratic::parse(bad).is_err(), "{}", bad);
+        }
+        let written: Vec<String> = [(1, -79, 1601), (1, 1, 41), (-2, 0, 5), (3, -1, 0), (-1, 1, -1)].iter()
+            .map(|&(a, b, c)| Quadratic { a, b, c }.to_string())
+            .collect();
+        assert_eq!(written, vec!["n² - 79n + 1601", "n² + n + 41", "-2n² + 5", "3n² - n", "-n² + n - 1"]);
+    }
+}
@@ -0,0 +1,906 @@
+//! The colors points, text and markers are drawn with
+
+use std::fs;
+use std::path::Path;
+
+use coffee::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::classes::Class;
+use crate::overlays::{MAX_SIDES, MIN_SIDES};
+
+pub struct PolarOxideColors { }
+
+impl PolarOxideColors {
+    pub const BLACK: Color = Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0};
+    pub const RED: Color = Color {r: 0.94, g: 0.25, b: 0.27, a: 1.0};
+    pub const WHITE: Color = Color {r: 1.0, g: 1.0, b: 1.0, a: 1.0};
+    pub const MAGENTA: Color = Color {r: 1.0, g: 0.2, b: 0.85, a: 1.0};
+    pub const AMBER: Color = Color {r: 1.0, g: 0.7, b: 0.1, a: 1.0};
+    pub const CYAN: Color = Color {r: 0.2, g: 0.85, b: 1.0, a: 1.0};
+    pub const LIME: Color = Color {r: 0.6, g: 0.95, b: 0.2, a: 1.0};
+    pub const ORANGE: Color = Color {r: 1.0, g: 0.5, b: 0.1, a: 1.0};
+    pub const VIOLET: Color = Color {r: 0.6, g: 0.4, b: 1.0, a: 1.0};
+    pub const GOLD: Color = Color {r: 1.0, g: 0.9, b: 0.35, a: 1.0};
+    pub const TEAL: Color = Color {r: 0.1, g: 0.75, b: 0.7, a: 1.0};
+    pub const ROSE: Color = Color {r: 1.0, g: 0.3, b: 0.45, a: 1.0};
+    pub const AZURE: Color = Color {r: 0.3, g: 0.55, b: 1.0, a: 1.0};
+    pub const MINT: Color = Color {r: 0.6, g: 1.0, b: 0.8, a: 1.0};
+
+    /// Every color we draw with: the fixed ones first, then each palette's entries in the order
+    /// `Palette::source` expects them, the custom palette's last, then the classes' colors in the
+    /// order `Palette::class_source` expects them, then the residues' in the order
+    /// `Palette::residue_source` does, then the gaps' shades for `Palette::gap_source`, then the
+    /// prime factor counts' for `Palette::omega_source`, then the smallest factors' for
+    /// `Palette::smallest_factor_source`, then μ's for `Palette::moebius_source`, then the
+    /// semiprimes' for `Palette::semiprime_source`, then the polygonal numbers' markers. There's
+    /// always room for a custom palette, taking the first one's colors when there's none, so every
+    /// source is in the image.
+    pub fn atlas(custom: Option<CustomPalette>) -> Vec<Color> {
+        let mut colors = COLORS.to_vec();
+        for palette in Palette::ALL.iter() {
+            colors.extend_from_slice(&[
+                palette.dark.prime,
+                palette.dark.composite,
+                palette.light.prime,
+                palette.light.composite,
+            ]);
+        }
+        let custom = custom.unwrap_or_else(|| CustomPalette {
+            background: Theme::Dark.background(),
+            prime: Palette::ALL[0].dark.prime,
+            composite: Palette::ALL[0].dark.composite,
+            highlight: PolarOxideColors::RED,
+        });
+        // The same for both themes, which don't matter to a custom palette
+        colors.extend_from_slice(&[custom.prime, custom.composite, custom.prime, custom.composite]);
+        colors.extend_from_slice(&[custom.highlight, custom.foreground()]);
+        for palette in Palette::ALL.iter() {
+            for entries in &[&palette.dark, &palette.light] {
+                colors.extend(Class::ALL.iter().map(|&class| entries.class(class)));
+            }
+        }
+        // A custom palette only has the one color to spare
+        colors.extend(Class::ALL.iter().chain(Class::ALL.iter()).map(|_| custom.highlight));
+        for &theme in &[Theme::Dark, Theme::Light] {
+            for &is_prime in &[true, false] {
+                colors.extend(RESIDUE_HUES.iter().map(|&hue| residue_color(hue, theme, is_prime)));
+            }
+        }
+        for &theme in &[Theme::Dark, Theme::Light] {
+            colors.extend((0..GAP_SHADES).map(|shade| gap_color(theme, shade)));
+        }
+        colors.extend_from_slice(&OMEGA_DARK);
+        colors.extend_from_slice(&OMEGA_LIGHT);
+        colors.extend_from_slice(&SMALLEST_FACTOR_DARK);
+        colors.extend_from_slice(&SMALLEST_FACTOR_LIGHT);
+        colors.extend_from_slice(&MOEBIUS_DARK);
+        colors.extend_from_slice(&MOEBIUS_LIGHT);
+        colors.extend_from_slice(&SEMIPRIME_DARK);
+        colors.extend_from_slice(&SEMIPRIME_LIGHT);
+        colors.extend_from_slice(&POLYGONAL);
+        colors
+    }
+
+    /// Where the color of a marker sits in the image built by `atlas`
+    pub fn marker_source(marker: Marker, palette: usize, theme: Theme) -> u16 {
+        let custom = palette == Palette::CUSTOM;
+        let source = match marker {
+            Marker::Highlight if custom => { CUSTOM_HIGHLIGHT }
+            Marker::Highlight => { RED }
+            Marker::Selection if custom => { CUSTOM_FOREGROUND }
+            Marker::Selection => {
+                match theme {
+                    Theme::Dark => { WHITE }
+                    Theme::Light => { BLACK }
+                }
+            }
+            Marker::Search => { MAGENTA }
+            Marker::Gap if custom => { CUSTOM_HIGHLIGHT }
+            Marker::Gap => { AMBER }
+            Marker::Special if custom => { CUSTOM_HIGHLIGHT }
+            Marker::Special => { CYAN }
+            Marker::Power if custom => { CUSTOM_HIGHLIGHT }
+            Marker::Power => { LIME }
+            Marker::Fibonacci | Marker::Lucas | Marker::SequencePrime if custom => { CUSTOM_HIGHLIGHT }
+            Marker::Fibonacci => { ORANGE }
+            Marker::Lucas => { VIOLET }
+            Marker::SequencePrime => { GOLD }
+            Marker::Polynomial | Marker::PolynomialPrime if custom => { CUSTOM_HIGHLIGHT }
+            Marker::Polynomial => { TEAL }
+            Marker::PolynomialPrime => { ROSE }
+            Marker::Predicate if custom => { CUSTOM_HIGHLIGHT }
+            Marker::Predicate => { AZURE }
+            Marker::Listed if custom => { CUSTOM_HIGHLIGHT }
+            Marker::Listed => { MINT }
+            // Several families can be up at once, so even a custom palette keeps them apart
+            Marker::Polygonal(sides) => {
+                POLYGONAL_COLORS + (sides.max(MIN_SIDES).min(MAX_SIDES) - MIN_SIDES) as usize
+            }
+            // The same goes for the progressions, which take the residues' hues in turn
+            Marker::Progression(index) | Marker::ProgressionPrime(index) => {
+                let is_prime = marker == Marker::ProgressionPrime(index);
+                return Palette::residue_source(theme, is_prime, index as u64 % MAX_MODULUS);
+            }
+        };
+        source as u16
+    }
+}
+
+/// What a marker drawn over a number is for, which picks its color
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Marker {
+    Highlight,
+    Selection,
+    Search,
+    /// A prime starting a gap picked in the histogram
+    Gap,
+    /// A Mersenne or Fermat prime, see `special::special_primes`
+    Special,
+    /// A perfect square, cube or higher power, see `overlays::perfect_powers`
+    Power,
+    Fibonacci,
+    Lucas,
+    /// A prime that's a Fibonacci or a Lucas number
+    SequencePrime,
+    /// A polygonal number with this many sides, see `overlays::polygonal_numbers`
+    Polygonal(u32),
+    /// A value of the quadratic given with `--poly`, see `overlays::Quadratic`
+    Polynomial,
+    PolynomialPrime,
+    /// A number in the how-manyth of the progressions given with `--highlight-ap`, see
+    /// `overlays::Progression`
+    Progression(usize),
+    ProgressionPrime(usize),
+    /// A number the predicate given with `--expr` picks, see `predicate::Predicate`
+    Predicate,
+    /// A number read from the file given with `--highlight-file`, see `overlays::NumberList`
+    Listed,
+}
+
+impl Marker {
+    /// What a marker of this kind is drawn over, for the legend
+    pub fn meaning(self) -> &'static str {
+        match self {
+            Marker::Highlight => { "number gone to" }
+            Marker::Selection => { "selected number" }
+            Marker::Search => { "searched number" }
+            Marker::Gap => { "prime starting the picked gap" }
+            Marker::Special => { "Mersenne or Fermat prime" }
+            Marker::Power => { "perfect power" }
+            Marker::Fibonacci => { "Fibonacci number" }
+            Marker::Lucas => { "Lucas number" }
+            Marker::SequencePrime => { "Fibonacci or Lucas prime" }
+            Marker::Polygonal(_) => { "polygonal number" }
+            Marker::Polynomial => { "value of the polynomial" }
+            Marker::PolynomialPrime => { "prime value of the polynomial" }
+            Marker::Progression(_) => { "number in the progression" }
+            Marker::ProgressionPrime(_) => { "prime in the progression" }
+            Marker::Predicate => { "number the expression picks" }
+            Marker::Listed => { "number from the file" }
+        }
+    }
+}
+
+// Colors used for the background, text and markers, which don't depend on the palette
+const COLORS: [Color; FIXED_COLORS] = [
+    PolarOxideColors::BLACK,
+    PolarOxideColors::RED,
+    PolarOxideColors::WHITE,
+    PolarOxideColors::MAGENTA,
+    PolarOxideColors::AMBER,
+    PolarOxideColors::CYAN,
+    PolarOxideColors::LIME,
+    PolarOxideColors::ORANGE,
+    PolarOxideColors::VIOLET,
+    PolarOxideColors::GOLD,
+    PolarOxideColors::TEAL,
+    PolarOxideColors::ROSE,
+    PolarOxideColors::AZURE,
+    PolarOxideColors::MINT,
+];
+const FIXED_COLORS: usize = 14;
+// Where those are in the atlas
+const BLACK: usize = 0;
+const RED: usize = 1;
+const WHITE: usize = 2;
+const MAGENTA: usize = 3;
+const AMBER: usize = 4;
+const CYAN: usize = 5;
+const LIME: usize = 6;
+const ORANGE: usize = 7;
+const VIOLET: usize = 8;
+const GOLD: usize = 9;
+const TEAL: usize = 10;
+const ROSE: usize = 11;
+const AZURE: usize = 12;
+const MINT: usize = 13;
+// The custom palette's markers, after its points' four entries
+const CUSTOM_HIGHLIGHT: usize = FIXED_COLORS + (Palette::CUSTOM + 1) * 4;
+const CUSTOM_FOREGROUND: usize = CUSTOM_HIGHLIGHT + 1;
+// Then each palette's class colors, for one theme after the other
+const CLASS_COLORS: usize = CUSTOM_FOREGROUND + 1;
+// Then the residues', for the primes and the composites on one theme after the other
+const RESIDUE_COLORS: usize = CLASS_COLORS + (Palette::CUSTOM + 1) * 2 * Class::COUNT;
+// Then the gaps' shades, for one theme after the other
+const GAP_COLORS: usize = RESIDUE_COLORS + 2 * 2 * MAX_MODULUS as usize;
+// Then the prime factor counts', for one theme after the other
+const OMEGA_COLORS: usize = GAP_COLORS + 2 * GAP_SHADES;
+// Then the smallest factors', for one theme after the other
+const SMALLEST_FACTOR_COLORS: usize = OMEGA_COLORS + 2 * OMEGA_SHADES;
+// Then μ's, for one theme after the other
+const MOEBIUS_COLORS: usize = SMALLEST_FACTOR_COLORS + 2 * SMALLEST_FACTOR_CLASS_COUNT;
+// Then the semiprimes', for one theme after the other
+const SEMIPRIME_COLORS: usize = MOEBIUS_COLORS + 2 * 3;
+// Then the polygonal numbers' markers, by their sides
+const POLYGONAL_COLORS: usize = SEMIPRIME_COLORS + 2 * 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub fn toggle(self) -> Theme {
+        match self {
+            Theme::Dark => { Theme::Light }
+            Theme::Light => { Theme::Dark }
+        }
+    }
+
+    pub fn background(self) -> Color {
+        match self {
+            Theme::Dark => { PolarOxideColors::BLACK }
+            Theme::Light => { PolarOxideColors::WHITE }
+        }
+    }
+
+    /// Text and the selection marker, which have to stand out from the background
+    pub fn foreground(self) -> Color {
+        match self {
+            Theme::Dark => { PolarOxideColors::WHITE }
+            Theme::Light => { PolarOxideColors::BLACK }
+        }
+    }
+}
+
+pub struct PaletteEntries {
+    pub prime: Color,
+    pub composite: Color,
+    /// Primes of a `Class`, standing out from the rest of them
+    pub twin: Color,
+    pub cousin: Color,
+    pub sexy: Color,
+    /// Two shades of one color, so each Sophie Germain prime looks like the safe prime it makes
+    pub sophie_germain: Color,
+    pub safe: Color,
+    pub palindromic: Color,
+}
+
+impl PaletteEntries {
+    pub fn class(&self, class: Class) -> Color {
+        match class {
+            Class::Twin => { self.twin }
+            Class::Cousin => { self.cousin }
+            Class::Sexy => { self.sexy }
+            Class::SophieGermain => { self.sophie_germain }
+            Class::Safe => { self.safe }
+            Class::Palindromic => { self.palindromic }
+        }
+    }
+}
+
+/// A color scheme for primes and composites, with a variant for each theme so points
+/// stay visible on either background
+pub struct Palette {
+    pub name: &'static str,
+    /// What `--palette` also takes
+    pub short_name: &'static str,
+    pub dark: PaletteEntries,
+    pub light: PaletteEntries,
+}
+
+impl Palette {
+    /// How many built-in palettes there are
+    pub const COUNT: usize = 6;
+    /// The index `Configs::palette` has for a custom palette, right after the built-in ones
+    pub const CUSTOM: usize = Self::COUNT;
+    pub const ALL: [Palette; Self::COUNT] = [
+        Palette {
+            name: "Classic",
+            short_name: "classic",
+            dark: PaletteEntries {
+                prime: Color {r: 0.36, g: 0.82, b: 0.69, a: 1.0},
+                composite: Color {r: 0.91, g: 0.92, b: 0.18, a: 1.0},
+                twin: Color {r: 1.0, g: 0.42, b: 0.42, a: 1.0},
+                cousin: Color {r: 0.62, g: 0.48, b: 1.0, a: 1.0},
+                sexy: Color {r: 1.0, g: 0.6, b: 0.2, a: 1.0},
+                sophie_germain: Color {r: 1.0, g: 0.55, b: 0.8, a: 1.0},
+                safe: Color {r: 0.75, g: 0.3, b: 0.6, a: 1.0},
+                palindromic: Color {r: 1.0, g: 0.85, b: 0.55, a: 1.0},
+            },
+            light: PaletteEntries {
+                prime: Color {r: 0.08, g: 0.2, b: 0.5, a: 1.0},
+                composite: Color {r: 0.62, g: 0.62, b: 0.62, a: 1.0},
+                twin: Color {r: 0.8, g: 0.1, b: 0.2, a: 1.0},
+                cousin: Color {r: 0.1, g: 0.55, b: 0.2, a: 1.0},
+                sexy: Color {r: 0.55, g: 0.2, b: 0.7, a: 1.0},
+                sophie_germain: Color {r: 0.75, g: 0.1, b: 0.5, a: 1.0},
+                safe: Color {r: 0.95, g: 0.5, b: 0.75, a: 1.0},
+                palindromic: Color {r: 0.6, g: 0.35, b: 0.0, a: 1.0},
+            },
+        },
+        Palette {
+            name: "Viridis",
+            short_name: "viridis",
+            dark: PaletteEntries {
+                prime: Color {r: 0.99, g: 0.91, b: 0.14, a: 1.0},
+                composite: Color {r: 0.23, g: 0.32, b: 0.55, a: 1.0},
+                twin: Color {r: 0.13, g: 0.57, b: 0.55, a: 1.0},
+                cousin: Color {r: 0.48, g: 0.82, b: 0.32, a: 1.0},
+                sexy: Color {r: 0.7, g: 0.5, b: 0.9, a: 1.0},
+                sophie_germain: Color {r: 0.98, g: 0.55, b: 0.35, a: 1.0},
+                safe: Color {r: 0.72, g: 0.22, b: 0.47, a: 1.0},
+                palindromic: Color {r: 0.85, g: 0.95, b: 0.55, a: 1.0},
+            },
+            light: PaletteEntries {
+                prime: Color {r: 0.27, g: 0.0, b: 0.33, a: 1.0},
+                composite: Color {r: 0.37, g: 0.79, b: 0.38, a: 1.0},
+                twin: Color {r: 0.19, g: 0.41, b: 0.56, a: 1.0},
+                cousin: Color {r: 0.13, g: 0.57, b: 0.55, a: 1.0},
+                sexy: Color {r: 0.8, g: 0.7, b: 0.0, a: 1.0},
+                sophie_germain: Color {r: 0.72, g: 0.22, b: 0.47, a: 1.0},
+                safe: Color {r: 0.98, g: 0.55, b: 0.35, a: 1.0},
+                palindromic: Color {r: 0.23, g: 0.32, b: 0.55, a: 1.0},
+            },
+        },
+        Palette {
+            name: "High contrast",
+            short_name: "high-contrast",
+            dark: PaletteEntries {
+                prime: Color {r: 1.0, g: 1.0, b: 1.0, a: 1.0},
+                composite: Color {r: 0.2, g: 0.2, b: 0.2, a: 1.0},
+                twin: Color {r: 1.0, g: 0.85, b: 0.0, a: 1.0},
+                cousin: Color {r: 0.0, g: 0.9, b: 1.0, a: 1.0},
+                sexy: Color {r: 1.0, g: 0.3, b: 1.0, a: 1.0},
+                sophie_germain: Color {r: 1.0, g: 0.3, b: 0.3, a: 1.0},
+                safe: Color {r: 0.7, g: 0.1, b: 0.1, a: 1.0},
+                palindromic: Color {r: 0.0, g: 1.0, b: 0.4, a: 1.0},
+            },
+            light: PaletteEntries {
+                prime: Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0},
+                composite: Color {r: 0.85, g: 0.85, b: 0.85, a: 1.0},
+                twin: Color {r: 0.0, g: 0.27, b: 0.8, a: 1.0},
+                cousin: Color {r: 0.85, g: 0.0, b: 0.0, a: 1.0},
+                sexy: Color {r: 0.0, g: 0.55, b: 0.0, a: 1.0},
+                sophie_germain: Color {r: 0.8, g: 0.0, b: 0.0, a: 1.0},
+                safe: Color {r: 1.0, g: 0.45, b: 0.45, a: 1.0},
+                palindromic: Color {r: 0.6, g: 0.0, b: 0.6, a: 1.0},
+            },
+        },
+        Palette {
+            name: "Grayscale",
+            short_name: "grayscale",
+            dark: PaletteEntries {
+                prime: Color {r: 0.75, g: 0.75, b: 0.75, a: 1.0},
+                composite: Color {r: 0.4, g: 0.4, b: 0.4, a: 1.0},
+                twin: Color {r: 1.0, g: 1.0, b: 1.0, a: 1.0},
+                cousin: Color {r: 0.88, g: 0.88, b: 0.88, a: 1.0},
+                sexy: Color {r: 0.58, g: 0.58, b: 0.58, a: 1.0},
+                sophie_germain: Color {r: 1.0, g: 1.0, b: 1.0, a: 1.0},
+                safe: Color {r: 0.55, g: 0.55, b: 0.55, a: 1.0},
+                palindromic: Color {r: 1.0, g: 1.0, b: 1.0, a: 1.0},
+            },
+            light: PaletteEntries {
+                prime: Color {r: 0.25, g: 0.25, b: 0.25, a: 1.0},
+                composite: Color {r: 0.6, g: 0.6, b: 0.6, a: 1.0},
+                twin: Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0},
+                cousin: Color {r: 0.12, g: 0.12, b: 0.12, a: 1.0},
+                sexy: Color {r: 0.42, g: 0.42, b: 0.42, a: 1.0},
+                sophie_germain: Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0},
+                safe: Color {r: 0.45, g: 0.45, b: 0.45, a: 1.0},
+                palindromic: Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0},
+            },
+        },
+        // Okabe and Ito's colors, told apart with every kind of color blindness
+        Palette {
+            name: "Okabe-Ito",
+            short_name: "cb1",
+            dark: PaletteEntries {
+                prime: Color {r: 0.9, g: 0.62, b: 0.0, a: 1.0},
+                composite: Color {r: 0.34, g: 0.71, b: 0.91, a: 1.0},
+                twin: Color {r: 0.0, g: 0.62, b: 0.45, a: 1.0},
+                cousin: Color {r: 0.94, g: 0.89, b: 0.26, a: 1.0},
+                sexy: Color {r: 0.8, g: 0.47, b: 0.65, a: 1.0},
+                sophie_germain: Color {r: 0.0, g: 0.62, b: 0.45, a: 1.0},
+                safe: Color {r: 0.0, g: 0.42, b: 0.3, a: 1.0},
+                palindromic: Color {r: 0.94, g: 0.89, b: 0.26, a: 1.0},
+            },
+            light: PaletteEntries {
+                prime: Color {r: 0.84, g: 0.37, b: 0.0, a: 1.0},
+                composite: Color {r: 0.0, g: 0.45, b: 0.7, a: 1.0},
+                twin: Color {r: 0.8, g: 0.47, b: 0.65, a: 1.0},
+                cousin: Color {r: 0.0, g: 0.62, b: 0.45, a: 1.0},
+                sexy: Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0},
+                sophie_germain: Color {r: 0.8, g: 0.47, b: 0.65, a: 1.0},
+                safe: Color {r: 0.55, g: 0.25, b: 0.42, a: 1.0},
+                palindromic: Color {r: 0.0, g: 0.62, b: 0.45, a: 1.0},
+            },
+        },
+        // Blue and orange stay apart for red-green color blindness, and in lightness for the rest
+        Palette {
+            name: "Blue/orange",
+            short_name: "cb2",
+            dark: PaletteEntries {
+                prime: Color {r: 1.0, g: 0.69, b: 0.0, a: 1.0},
+                composite: Color {r: 0.39, g: 0.56, b: 1.0, a: 1.0},
+                twin: Color {r: 1.0, g: 1.0, b: 1.0, a: 1.0},
+                cousin: Color {r: 1.0, g: 0.87, b: 0.6, a: 1.0},
+                sexy: Color {r: 0.7, g: 0.8, b: 1.0, a: 1.0},
+                sophie_germain: Color {r: 1.0, g: 1.0, b: 1.0, a: 1.0},
+                safe: Color {r: 0.6, g: 0.6, b: 0.6, a: 1.0},
+                palindromic: Color {r: 1.0, g: 1.0, b: 1.0, a: 1.0},
+            },
+            light: PaletteEntries {
+                prime: Color {r: 1.0, g: 0.38, b: 0.0, a: 1.0},
+                composite: Color {r: 0.23, g: 0.37, b: 0.8, a: 1.0},
+                twin: Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0},
+                cousin: Color {r: 0.55, g: 0.25, b: 0.0, a: 1.0},
+                sexy: Color {r: 0.05, g: 0.15, b: 0.45, a: 1.0},
+                sophie_germain: Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0},
+                safe: Color {r: 0.45, g: 0.45, b: 0.45, a: 1.0},
+                palindromic: Color {r: 0.0, g: 0.0, b: 0.0, a: 1.0},
+            },
+        },
+    ];
+
+    /// Where the palette with `name`, or that short name, is in `ALL`, whatever the case
+    pub fn find(name: &str) -> Option<usize> {
+        Self::ALL.iter().position(|palette| {
+            palette.name.eq_ignore_ascii_case(name) || palette.short_name.eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// The one after `index`, going through the custom palette too when there is one
+    pub fn next(index: usize, has_custom: bool) -> usize {
+        let count = if has_custom { Self::ALL.len() + 1 } else { Self::ALL.len() };
+        (index + 1) % count
+    }
+
+    /// Where the color of a prime or composite sits in the image built by `PolarOxideColors::atlas`
+    pub fn source(index: usize, theme: Theme, is_prime: bool) -> u16 {
+        let theme_offset = match theme {
+            Theme::Dark => { 0 }
+            Theme::Light => { 2 }
+        };
+        let kind_offset = if is_prime { 0 } else { 1 };
+        (COLORS.len() + index * 4 + theme_offset + kind_offset) as u16
+    }
+
+    /// Where the color of the primes of a class sits in the image built by `PolarOxideColors::atlas`
+    pub fn class_source(index: usize, theme: Theme, class: Class) -> u16 {
+        let theme_index = match theme {
+            Theme::Dark => { 0 }
+            Theme::Light => { 1 }
+        };
+        (CLASS_COLORS + (index * 2 + theme_index) * Class::COUNT + class.index()) as u16
+    }
+
+    /// Where the color of the primes or the composites `residue` mod anything sits in the image
+    /// built by `PolarOxideColors::atlas`. Every palette shares them, so there's a hue for each
+    /// residue up to `MAX_MODULUS`.
+    pub fn residue_source(theme: Theme, is_prime: bool, residue: u64) -> u16 {
+        let theme_index = match theme {
+            Theme::Dark => { 0 }
+            Theme::Light => { 1 }
+        };
+        let kind_index = if is_prime { 0 } else { 1 };
+        (RESIDUE_COLORS + (theme_index * 2 + kind_index) * RESIDUE_HUES.len() + residue as usize) as u16
+    }
+
+    /// Where the color of the primes or the composites with a digital root of `root` sits in the
+    /// image built by `PolarOxideColors::atlas`: the residues' hues, so there's one for each root up
+    /// to `MAX_MODULUS`. 0 only has 0 for one, which gets the first.
+    pub fn digital_root_source(theme: Theme, is_prime: bool, root: u8) -> u16 {
+        Self::residue_source(theme, is_prime, u64::from(root.max(1) - 1))
+    }
+
+    /// Where the color of a prime followed by a gap of `gap` sits in the image built by
+    /// `PolarOxideColors::atlas`. The shades go from a gap of 2 up to the one
+    /// `widest_shaded_gap` makes of `max_gap`, the biggest gap there is, and every palette shares
+    /// them.
+    pub fn gap_source(theme: Theme, gap: u64, max_gap: u64) -> u16 {
+        let theme_index = match theme {
+            Theme::Dark => { 0 }
+            Theme::Light => { 1 }
+        };
+        let widest = Self::widest_shaded_gap(max_gap);
+        let steps = GAP_SHADES as u64 - 1;
+        // Rounded to the closest shade
+        let shade = ((gap.max(2).min(widest) - 2) * steps * 2 + widest - 2) / ((widest - 2) * 2);
+        (GAP_COLORS + theme_index * GAP_SHADES + shade as usize) as u16
+    }
+
+    /// The gap that gets the last shade when the biggest one is `max_gap`: that one, as long as it
+    /// isn't past `MAX_SHADED_GAP`
+    pub fn widest_shaded_gap(max_gap: u64) -> u64 {
+        max_gap.min(MAX_SHADED_GAP).max(4)
+    }
+
+    /// Where the color of a number with `omega` prime factors sits in the image built by
+    /// `PolarOxideColors::atlas`, from 1 for the primes up to `OMEGA_SHADES` or more. Every palette
+    /// shares them.
+    pub fn omega_source(theme: Theme, omega: u8) -> u16 {
+        let theme_index = match theme {
+            Theme::Dark => { 0 }
+            Theme::Light => { 1 }
+        };
+        let shade = usize::from(omega).max(1).min(OMEGA_SHADES) - 1;
+        (OMEGA_COLORS + theme_index * OMEGA_SHADES + shade) as u16
+    }
+
+    /// Where the color of a composite with `factor` as its smallest prime factor sits in the image
+    /// built by `PolarOxideColors::atlas`, one for each of `SMALLEST_FACTOR_CLASSES`. Every palette
+    /// shares them.
+    pub fn smallest_factor_source(theme: Theme, factor: u64) -> u16 {
+        let theme_index = match theme {
+            Theme::Dark => { 0 }
+            Theme::Light => { 1 }
+        };
+        let class = SMALLEST_FACTOR_CLASSES.iter().rposition(|&(from, _)| from <= factor).unwrap_or(0);
+        (SMALLEST_FACTOR_COLORS + theme_index * SMALLEST_FACTOR_CLASS_COUNT + class) as u16
+    }
+
+    /// Where the color of a number with a Möbius function of `mu` sits in the image built by
+    /// `PolarOxideColors::atlas`. Every palette shares them.
+    pub fn moebius_source(theme: Theme, mu: i8) -> u16 {
+        let theme_index = match theme {
+            Theme::Dark => { 0 }
+            Theme::Light => { 1 }
+        };
+        let index = match mu {
+            1 => { 0 }
+            -1 => { 1 }
+            _ => { 2 }
+        };
+        (MOEBIUS_COLORS + theme_index * 3 + index) as u16
+    }
+
+    /// Where the color of a semiprime, or of the square of a prime, sits in the image built by
+    /// `PolarOxideColors::atlas`. Every palette shares them.
+    pub fn semiprime_source(theme: Theme, square: bool) -> u16 {
+        let theme_index = match theme {
+            Theme::Dark => { 0 }
+            Theme::Light => { 1 }
+        };
+        (SEMIPRIME_COLORS + theme_index * 2 + square as usize) as u16
+    }
+}
+
+/// How many colors the numbers are drawn in by how many prime factors they have, the last one for
+/// that many or more
+pub const OMEGA_SHADES: usize = 5;
+
+// Going from cold for the primes to hot for the smoothest numbers, which have the most factors
+const OMEGA_DARK: [Color; OMEGA_SHADES] = [
+    Color {r: 0.3, g: 0.45, b: 0.95, a: 1.0},
+    Color {r: 0.3, g: 0.8, b: 0.75, a: 1.0},
+    Color {r: 0.6, g: 0.9, b: 0.3, a: 1.0},
+    Color {r: 1.0, g: 0.75, b: 0.2, a: 1.0},
+    Color {r: 1.0, g: 0.35, b: 0.3, a: 1.0},
+];
+const OMEGA_LIGHT: [Color; OMEGA_SHADES] = [
+    Color {r: 0.1, g: 0.2, b: 0.7, a: 1.0},
+    Color {r: 0.0, g: 0.5, b: 0.45, a: 1.0},
+    Color {r: 0.35, g: 0.6, b: 0.0, a: 1.0},
+    Color {r: 0.8, g: 0.5, b: 0.0, a: 1.0},
+    Color {r: 0.8, g: 0.1, b: 0.1, a: 1.0},
+];
+
+const SMALLEST_FACTOR_CLASS_COUNT: usize = 6;
+
+/// The composites are colored by which of these their smallest prime factor is in: the smallest
+/// factor it takes, and how the legend says it
+pub const SMALLEST_FACTOR_CLASSES: [(u64, &str); SMALLEST_FACTOR_CLASS_COUNT] = [
+    (2, "2"),
+    (3, "3"),
+    (5, "5"),
+    (7, "7"),
+    (11, "11 to 31"),
+    (37, "37 or more"),
+];
+
+// Half the composites are even, so those get the dimmest color and the rarer ones stand out more
+const SMALLEST_FACTOR_DARK: [Color; SMALLEST_FACTOR_CLASS_COUNT] = [
+    Color {r: 0.3, g: 0.35, b: 0.55, a: 1.0},
+    Color {r: 0.2, g: 0.7, b: 0.7, a: 1.0},
+    Color {r: 0.45, g: 0.85, b: 0.35, a: 1.0},
+    Color {r: 0.95, g: 0.8, b: 0.25, a: 1.0},
+    Color {r: 1.0, g: 0.5, b: 0.2, a: 1.0},
+    Color {r: 1.0, g: 0.3, b: 0.55, a: 1.0},
+];
+const SMALLEST_FACTOR_LIGHT: [Color; SMALLEST_FACTOR_CLASS_COUNT] = [
+    Color {r: 0.7, g: 0.74, b: 0.85, a: 1.0},
+    Color {r: 0.0, g: 0.55, b: 0.55, a: 1.0},
+    Color {r: 0.25, g: 0.6, b: 0.1, a: 1.0},
+    Color {r: 0.75, g: 0.6, b: 0.0, a: 1.0},
+    Color {r: 0.85, g: 0.4, b: 0.0, a: 1.0},
+    Color {r: 0.8, g: 0.1, b: 0.4, a: 1.0},
+];
+
+// μ = 1 and μ = -1 as far apart as they go, the squareful numbers in between fading away
+const MOEBIUS_DARK: [Color; 3] = [
+    Color {r: 0.35, g: 0.75, b: 1.0, a: 1.0},
+    Color {r: 1.0, g: 0.45, b: 0.35, a: 1.0},
+    Color {r: 0.3, g: 0.3, b: 0.3, a: 1.0},
+];
+const MOEBIUS_LIGHT: [Color; 3] = [
+    Color {r: 0.1, g: 0.35, b: 0.8, a: 1.0},
+    Color {r: 0.8, g: 0.2, b: 0.1, a: 1.0},
+    Color {r: 0.78, g: 0.78, b: 0.78, a: 1.0},
+];
+
+// Bright next to the palettes' composites, the squares a deeper shade of the same color
+const SEMIPRIME_DARK: [Color; 2] = [
+    Color {r: 1.0, g: 0.55, b: 0.8, a: 1.0},
+    Color {r: 0.75, g: 0.3, b: 0.95, a: 1.0},
+];
+const SEMIPRIME_LIGHT: [Color; 2] = [
+    Color {r: 0.85, g: 0.2, b: 0.5, a: 1.0},
+    Color {r: 0.45, g: 0.1, b: 0.65, a: 1.0},
+];
+
+// The markers of the triangular numbers up to the octagonal ones, around the color wheel so the ones
+// that are up together tell apart on either theme
+const POLYGONAL: [Color; (MAX_SIDES - MIN_SIDES + 1) as usize] = [
+    Color {r: 1.0, g: 0.45, b: 0.3, a: 1.0},
+    Color {r: 0.95, g: 0.8, b: 0.2, a: 1.0},
+    Color {r: 0.4, g: 0.85, b: 0.4, a: 1.0},
+    Color {r: 0.25, g: 0.65, b: 1.0, a: 1.0},
+    Color {r: 0.7, g: 0.45, b: 0.95, a: 1.0},
+    Color {r: 0.95, g: 0.45, b: 0.75, a: 1.0},
+];
+
+/// How many shades the primes are colored in by the gaps after them
+pub const GAP_SHADES: usize = 16;
+/// Past this one every gap gets the last shade, so the few huge ones don't wash out the rest
+pub const MAX_SHADED_GAP: u64 = 100;
+
+// The small gaps, by far the most of them, fade into the background, the big ones stand out
+fn gap_color(theme: Theme, shade: usize) -> Color {
+    let stops = match theme {
+        Theme::Dark => {
+            [
+                Color {r: 0.2, g: 0.25, b: 0.7, a: 1.0},
+                Color {r: 0.85, g: 0.25, b: 0.55, a: 1.0},
+                Color {r: 1.0, g: 0.9, b: 0.25, a: 1.0},
+            ]
+        }
+        Theme::Light => {
+            [
+                Color {r: 0.6, g: 0.75, b: 0.95, a: 1.0},
+                Color {r: 0.9, g: 0.5, b: 0.2, a: 1.0},
+                Color {r: 0.6, g: 0.05, b: 0.1, a: 1.0},
+            ]
+        }
+    };
+    let along = shade as f32 / (GAP_SHADES - 1) as f32 * 2.0;
+    let (from, to, by) = if along <= 1.0 { (stops[0], stops[1], along) } else { (stops[1], stops[2], along - 1.0) };
+    let mix = |from: f32, to: f32| from + (to - from) * by;
+    Color { r: mix(from.r, to.r), g: mix(from.g, to.g), b: mix(from.b, to.b), a: 1.0 }
+}
+
+/// The biggest modulus numbers can be colored by the residues of, one hue each
+pub const MAX_MODULUS: u64 = 12;
+
+// Told apart from each other more than they go with any palette
+const RESIDUE_HUES: [Color; MAX_MODULUS as usize] = [
+    Color {r: 0.95, g: 0.3, b: 0.3, a: 1.0},
+    Color {r: 0.3, g: 0.75, b: 1.0, a: 1.0},
+    Color {r: 1.0, g: 0.8, b: 0.2, a: 1.0},
+    Color {r: 0.4, g: 0.9, b: 0.4, a: 1.0},
+    Color {r: 0.8, g: 0.45, b: 1.0, a: 1.0},
+    Color {r: 1.0, g: 0.55, b: 0.15, a: 1.0},
+    Color {r: 0.3, g: 0.95, b: 0.9, a: 1.0},
+    Color {r: 1.0, g: 0.45, b: 0.75, a: 1.0},
+    Color {r: 0.7, g: 0.9, b: 0.3, a: 1.0},
+    Color {r: 0.6, g: 0.6, b: 1.0, a: 1.0},
+    Color {r: 0.85, g: 0.65, b: 0.45, a: 1.0},
+    Color {r: 0.9, g: 0.9, b: 0.9, a: 1.0},
+];
+
+// A residue's hue as the primes or the composites are drawn in on the theme's background: the
+// primes stand out, the composites fade into it
+fn residue_color(hue: Color, theme: Theme, is_prime: bool) -> Color {
+    let scaled = |by: f32| Color { r: hue.r * by, g: hue.g * by, b: hue.b * by, a: 1.0 };
+    let faded = |by: f32| {
+        Color { r: hue.r + (1.0 - hue.r) * by, g: hue.g + (1.0 - hue.g) * by, b: hue.b + (1.0 - hue.b) * by, a: 1.0 }
+    };
+    match (theme, is_prime) {
+        (Theme::Dark, true) => { hue }
+        (Theme::Dark, false) => { scaled(0.4) }
+        (Theme::Light, true) => { scaled(0.7) }
+        (Theme::Light, false) => { faded(0.65) }
+    }
+}
+
+/// A palette read from a file, drawn with the same colors whatever the theme. See `parse` for
+/// what the file looks like.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CustomPalette {
+    pub background: Color,
+    pub prime: Color,
+    pub composite: Color,
+    /// The marker over the number gone to
+    pub highlight: Color,
+}
+
+// The file as it is, before the colors are checked
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomPaletteFile {
+    background: String,
+    prime: String,
+    composite: String,
+    highlight: String,
+}
+
+impl CustomPalette {
+    pub const NAME: &'static str = "Custom";
+
+    pub fn load(path: &Path) -> Result<CustomPalette, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// TOML with a hex color for each entry, like `prime = "#5ED1B0"`. A bad color is an error
+    /// naming its entry.
+    pub fn parse(text: &str) -> Result<CustomPalette, String> {
+        let file: CustomPaletteFile = toml::from_str(text).map_err(|error| error.to_string())?;
+        let color = |name: &str, value: &str| {
+            parse_hex(value).ok_or_else(|| format!("`{}` has to be a color like \"#5ED1B0\", got \"{}\"", name, value))
+        };
+        Ok(CustomPalette {
+            background: color("background", &file.background)?,
+            prime: color("prime", &file.prime)?,
+            composite: color("composite", &file.composite)?,
+            highlight: color("highlight", &file.highlight)?,
+        })
+    }
+
+    /// Text and the selection marker: white on a dark background, black on a light one
+    pub fn foreground(&self) -> Color {
+        let luminance = 0.2126 * self.background.r + 0.7152 * self.background.g + 0.0722 * self.background.b;
+        if luminance < 0.5 { PolarOxideColors::WHITE } else { PolarOxideColors::BLACK }
+    }
+}
+
+/// `#RRGGBB`, or `#RRGGBBAA` with the alpha, the `#` being optional
+pub fn parse_hex(text: &str) -> Option<Color> {
+    let digits = text.trim().trim_start_matches('#');
+    if !(digits.len() == 6 || digits.len() == 8) || !digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok().map(|value| f32::from(value) / 255.0);
+    let a = if digits.len() == 8 { channel(6)? } else { 1.0 };
+    Some(Color { r: channel(0)?, g: channel(2)?, b: channel(4)?, a })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_are_parsed() {
+        assert_eq!(parse_hex("#000000"), Some(PolarOxideColors::BLACK));
+        assert_eq!(parse_hex("FFFFFF"), Some(PolarOxideColors::WHITE));
+        assert_eq!(parse_hex("#ff000080").map(|color| color.to_rgba()), Some([255, 0, 0, 128]));
+        for bad in &["", "#", "#12345", "#1234567", "#GGGGGG", "#+1+2+3"] {
+            assert_eq!(parse_hex(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn custom_palettes_name_their_bad_entries() {
+        let text = "background = \"#101010\"\nprime = \"#5ED1B0\"\ncomposite = \"#E8EB2E\"\nhighlight = \"#F04045\"\n";
+        let palette = CustomPalette::parse(text).unwrap();
+        assert_eq!(palette.prime.to_rgba(), [0x5E, 0xD1, 0xB0, 255]);
+        assert_eq!(palette.foreground(), PolarOxideColors::WHITE);
+
+        let error = CustomPalette::parse(&text.replace("#E8EB2E", "yellow")).unwrap_err();
+        assert!(error.contains("`composite`") && error.contains("yellow"), "{}", error);
+        assert!(CustomPalette::parse(&text.replace("highlight", "glow")).is_err());
+    }
+
+    #[test]
+    fn palettes_are_found_by_either_name() {
+        assert_eq!(Palette::find("viridis"), Some(1));
+        assert_eq!(Palette::find("CB1"), Palette::find("Okabe-Ito"));
+        assert_eq!(Palette::find("cb2").map(|index| Palette::ALL[index].name), Some("Blue/orange"));
+        assert_eq!(Palette::find("cb3"), None);
+    }
+
+    #[test]
+    fn every_source_is_in_the_atlas() {
+        let atlas = PolarOxideColors::atlas(None);
+        for palette in 0..=Palette::CUSTOM {
+            for &theme in &[Theme::Dark, Theme::Light] {
+                assert!((Palette::source(palette, theme, true) as usize) < atlas.len());
+                assert!((Palette::source(palette, theme, false) as usize) < atlas.len());
+                for &class in Class::ALL.iter() {
+                    assert!((Palette::class_source(palette, theme, class) as usize) < atlas.len());
+                }
+                for &is_prime in &[true, false] {
+                    assert!((Palette::residue_source(theme, is_prime, MAX_MODULUS - 1) as usize) < atlas.len());
+                }
+                let markers = [
+                    Marker::Highlight, Marker::Selection, Marker::Search, Marker::Gap, Marker::Special, Marker::Power,
+                    Marker::Fibonacci, Marker::Lucas, Marker::SequencePrime, Marker::Polygonal(MIN_SIDES),
+                    Marker::Polynomial, Marker::PolynomialPrime, Marker::Progression(0), Marker::ProgressionPrime(13),
+                    Marker::Predicate, Marker::Listed,
+                ];
+                for &marker in &markers {
+                    assert!((PolarOxideColors::marker_source(marker, palette, theme) as usize) < atlas.len());
+                }
+            }
+        }
+        let custom = CustomPalette {
+            background: PolarOxideColors::WHITE,
+            prime: PolarOxideColors::RED,
+            composite: PolarOxideColors::BLACK,
+            highlight: PolarOxideColors::MAGENTA,
+        };
+        let atlas = PolarOxideColors::atlas(Some(custom));
+        let highlight = PolarOxideColors::marker_source(Marker::Highlight, Palette::CUSTOM, Theme::Dark);
+        assert_eq!(atlas[highlight as usize], PolarOxideColors::MAGENTA);
+        let selection = PolarOxideColors::marker_source(Marker::Selection, Palette::CUSTOM, Theme::Light);
+        assert_eq!(atlas[selection as usize], PolarOxideColors::BLACK);
+        let gap = PolarOxideColors::marker_source(Marker::Gap, 0, Theme::Dark);
+        assert_eq!(atlas[gap as usize], PolarOxideColors::AMBER);
+        let power = PolarOxideColors::marker_source(Marker::Power, 0, Theme::Light);
+        assert_eq!(atlas[power as usize], PolarOxideColors::LIME);
+        let lucas = PolarOxideColors::marker_source(Marker::Lucas, 0, Theme::Dark);
+        assert_eq!(atlas[lucas as usize], PolarOxideColors::VIOLET);
+        assert_eq!(atlas[Palette::source(Palette::CUSTOM, Theme::Light, false) as usize], custom.composite);
+        assert_eq!(atlas[Palette::class_source(1, Theme::Light, Class::Twin) as usize], Palette::ALL[1].light.twin);
+        assert_eq!(atlas[Palette::class_source(4, Theme::Dark, Class::Sexy) as usize], Palette::ALL[4].dark.sexy);
+        assert_eq!(atlas[Palette::class_source(Palette::CUSTOM, Theme::Dark, Class::Twin) as usize], custom.highlight);
+        assert_eq!(atlas[Palette::residue_source(Theme::Dark, true, 3) as usize], RESIDUE_HUES[3]);
+        assert_eq!(atlas[Palette::gap_source(Theme::Dark, 2, 36) as usize], gap_color(Theme::Dark, 0));
+        assert_eq!(atlas[Palette::gap_source(Theme::Light, 36, 36) as usize], gap_color(Theme::Light, GAP_SHADES - 1));
+        let octagonal = PolarOxideColors::marker_source(Marker::Polygonal(MAX_SIDES), Palette::CUSTOM, Theme::Dark);
+        assert_eq!(atlas[octagonal as usize], POLYGONAL[5]);
+        assert_eq!(atlas.len(), octagonal as usize + 1);
+    }
+
+    #[test]
+    fn factors_are_colored_by_their_class() {
+        let atlas = PolarOxideColors::atlas(None);
+        assert_eq!(atlas[Palette::omega_source(Theme::Dark, 1) as usize], OMEGA_DARK[0]);
+        assert_eq!(atlas[Palette::omega_source(Theme::Light, 2) as usize], OMEGA_LIGHT[1]);
+        assert_eq!(Palette::omega_source(Theme::Dark, 9), Palette::omega_source(Theme::Dark, OMEGA_SHADES as u8));
+        assert_eq!(atlas[Palette::smallest_factor_source(Theme::Dark, 2) as usize], SMALLEST_FACTOR_DARK[0]);
+        assert_eq!(atlas[Palette::smallest_factor_source(Theme::Light, 29) as usize], SMALLEST_FACTOR_LIGHT[4]);
+        let class = |factor: u64| Palette::smallest_factor_source(Theme::Dark, factor) - SMALLEST_FACTOR_COLORS as u16;
+        let classes: Vec<u16> = [2, 3, 5, 7, 11, 31, 37, 65_521].iter().map(|&factor| class(factor)).collect();
+        assert_eq!(classes, vec![0, 1, 2, 3, 4, 4, 5, 5]);
+        assert_eq!(atlas[Palette::moebius_source(Theme::Dark, -1) as usize], MOEBIUS_DARK[1]);
+        assert_eq!(atlas[Palette::moebius_source(Theme::Light, 0) as usize], MOEBIUS_LIGHT[2]);
+        assert_eq!(atlas[Palette::semiprime_source(Theme::Dark, true) as usize], SEMIPRIME_DARK[1]);
+    }
+
+    #[test]
+    fn gaps_are_shaded_up_to_the_biggest_one() {
+        let shade = |gap: u64, max_gap: u64| Palette::gap_source(Theme::Dark, gap, max_gap) - GAP_COLORS as u16;
+        let last = GAP_SHADES as u16 - 1;
+        assert_eq!((shade(1, 20), shade(2, 20), shade(20, 20), shade(40, 20)), (0, 0, last, last));
+        assert_eq!(shade(11, 20), last / 2 + 1);
+        // Past `MAX_SHADED_GAP` it stops rescaling
+        assert_eq!(shade(100, 250), last);
+        assert_eq!(shade(50, 250), shade(50, 100));
+        assert!(shade(50, 250) < last);
+        // Too few primes for a gap still make a scale
+        assert_eq!(shade(2, 0), 0);
+    }
+}
@@ -0,0 +1,653 @@
+//! The numbers of the spiral: where each of them goes, which ones are prime and what they are made of
+
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use coffee::graphics::Point;
+use rayon::prelude::*;
+
+use crate::dataset::Dataset;
+use crate::digits::DigitalRoots;
+use crate::factors::Factors;
+use crate::layers::Layers;
+use crate::primality::Primality;
+use crate::sieve::Sieve;
+
+/// A number and where it sits in the spiral. Nothing keeps these around, they are worked out from
+/// `Particles` when they're needed.
+#[derive(Debug, Clone)]
+pub struct Particle {
+    pub number: u64,
+    /// In spiral units, which are 1 for every number away from the origin
+    pub position: Point,
+    pub is_prime: bool,
+}
+
+impl Particle {
+    /// `number` sits at radius `number` and angle `angle_factor * number`. All of it is worked out
+    /// in f64, with the angle brought down below 2π before the trigonometry: past 2^24 an f32 can't
+    /// even hold every number, and a number off by one is a whole radian off around the spiral.
+    pub fn position_of(number: u64, angle_factor: f32) -> Point {
+        Self::rebased_position_of(number, 0, angle_factor)
+    }
+
+    /// Like `position_of`, but `radius_base` units closer to the origin: `number` sits at radius
+    /// `number - radius_base`, still at the angle of `number` itself
+    pub fn rebased_position_of(number: u64, radius_base: u64, angle_factor: f32) -> Point {
+        let radius = number.saturating_sub(radius_base) as f64;
+        let angle = Self::angle_of(number, angle_factor);
+        Point::new(
+            (radius * angle.cos()) as f32,
+            (radius * angle.sin()) as f32,
+        )
+    }
+
+    /// The angle `number` sits at, from 0 up to 2π
+    pub fn angle_of(number: u64, angle_factor: f32) -> f64 {
+        (number as f64 * f64::from(angle_factor)).rem_euclid(2.0 * std::f64::consts::PI)
+    }
+}
+
+/// Every number in `1..max_number`, or in a slice of them. The only thing stored is which of them
+/// are prime, one bit per number, since where a number sits follows from the number and the angle
+/// factor alone. What they're made of is kept in `Layers`, once something asks for it.
+#[derive(Clone)]
+pub struct Particles {
+    sieve: Sieve,
+    layers: Arc<Layers>,
+    // Subtracted from every radius, so a slice far out can be drawn around the origin
+    radius_base: u64,
+    // Kept up to date as slices are appended, it takes going through every prime
+    max_gap: u64,
+}
+
+impl Particles {
+    const CLOSEST_CANDIDATES: u64 = 1 << 16;
+
+    /// The numbers in `1..max_number`, sieved
+    pub fn find(max_number: u64) -> Particles {
+        Self::find_between(0, max_number, Primality::Sieve)
+    }
+
+    /// The numbers in `start..end`, `start` being a multiple of 64, to be put together with `append`
+    pub fn find_between(start: u64, end: u64, primality: Primality) -> Particles {
+        Self::sieved(Sieve::with(primality, start, end))
+    }
+
+    fn sieved(sieve: Sieve) -> Particles {
+        Particles { max_gap: sieve.max_gap(), sieve, layers: Arc::default(), radius_base: 0 }
+    }
+
+    /// Adds the numbers found by `find_between`, starting at `next_start` or earlier. Any of these
+    /// from where those start on are replaced.
+    pub fn append(&mut self, other: Particles) {
+        let seam = other.start();
+        self.max_gap = self.max_gap.max(other.max_gap);
+        self.sieve.append(other.sieve);
+        // Whatever was worked out is about fewer numbers than there are now
+        self.layers = Arc::default();
+        // The gap across where the two meet is in neither of them
+        if let Some(before) = self.sieve.previous_prime(seam) {
+            let after = self.sieve.next_prime(before).unwrap_or(before);
+            self.max_gap = self.max_gap.max(after - before);
+        }
+    }
+
+    /// See `Sieve::to_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.sieve.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Particles, String> {
+        Sieve::from_bytes(bytes).map(Self::sieved)
+    }
+
+    /// The same particles, each `radius_base` units closer to the origin, see
+    /// `Particle::rebased_position_of`. Anything appended later is moved in the same way.
+    pub fn rebased(self, radius_base: u64) -> Particles {
+        Particles { radius_base, ..self }
+    }
+
+    /// How much closer to the origin every number is than its own radius
+    pub fn radius_base(&self) -> u64 {
+        self.radius_base
+    }
+
+    /// Where these were found from, the `start` given to `find_between`
+    pub fn start(&self) -> u64 {
+        self.sieve.start()
+    }
+
+    /// Where `find_between` has to start for what it finds to be appended to these
+    pub fn next_start(&self) -> u64 {
+        self.sieve.aligned_end()
+    }
+
+    /// The biggest number in the spiral, 0 when there's none
+    pub fn last_number(&self) -> u64 {
+        self.sieve.end().saturating_sub(1)
+    }
+
+    pub fn is_prime(&self, number: u64) -> bool {
+        self.sieve.is_prime(number)
+    }
+
+    pub fn prime_count(&self) -> u64 {
+        self.sieve.prime_count()
+    }
+
+    /// How many prime factors each number has and the smallest of them, worked out the first time
+    /// they're asked for, which takes a while. Numbers past `Factors::LIMIT` aren't in them.
+    pub fn factors(&self) -> Arc<Factors> {
+        self.layers.factors(self.start(), self.sieve.end())
+    }
+
+    /// The digital root of each number in `base`, worked out the first time it's asked for in that
+    /// base, see `DigitalRoots`
+    pub fn digital_roots(&self, base: u32) -> Arc<DigitalRoots> {
+        self.layers.digital_roots(self.start(), self.sieve.end(), base)
+    }
+
+    /// The biggest gap between primes next to each other in the spiral
+    pub fn max_gap(&self) -> u64 {
+        self.max_gap
+    }
+
+    /// π of each of `numbers`, in order, counting only the primes in the spiral
+    pub fn primes_up_to(&self, numbers: &[u64]) -> Vec<u64> {
+        self.sieve.primes_up_to(numbers)
+    }
+
+    /// The smallest prime in the spiral bigger than `number`
+    pub fn next_prime(&self, number: u64) -> Option<u64> {
+        self.sieve.next_prime(number)
+    }
+
+    /// The biggest prime in the spiral smaller than `number`
+    pub fn previous_prime(&self, number: u64) -> Option<u64> {
+        self.sieve.previous_prime(number)
+    }
+
+    /// How far the particle farthest from the origin is, in spiral units. Numbers sit at a radius
+    /// equal to themselves, less the base, so that's the last one.
+    pub fn bounding_radius(&self) -> f32 {
+        self.last_number().saturating_sub(self.radius_base) as f32
+    }
+
+    /// Where `number` sits, rebased like the rest of these
+    pub fn position_of(&self, number: u64, angle_factor: f32) -> Point {
+        Particle::rebased_position_of(number, self.radius_base, angle_factor)
+    }
+
+    /// `None` for numbers that aren't in the spiral
+    pub fn get(&self, number: u64, angle_factor: f32) -> Option<Particle> {
+        if number < self.sieve.start().max(1) || number > self.last_number() {
+            return None;
+        }
+        Some(self.particle(number, angle_factor))
+    }
+
+    /// The particles of every number somewhere between `inner` and `outer` away from the origin.
+    /// Only these get their positions worked out, so keeping the range tight is what keeps this
+    /// cheap.
+    pub fn within(&self, inner: f32, outer: f32, angle_factor: f32) -> impl ParallelIterator<Item = Particle> + '_ {
+        self.numbers_within(inner, outer)
+            .map(move |number| self.particle(number, angle_factor))
+    }
+
+    /// Like `within`, but only the numbers. Telling primes from composites is a bit lookup, much
+    /// cheaper than the trigonometry of a position, so it's better to filter these first.
+    pub fn numbers_within(&self, inner: f32, outer: f32) -> impl ParallelIterator<Item = u64> {
+        self.range_within(inner, outer).into_par_iter()
+    }
+
+    fn range_within(&self, inner: f32, outer: f32) -> RangeInclusive<u64> {
+        let first = self.sieve.start().max(1);
+        let last = self.last_number();
+        let base = self.radius_base;
+        // Clamped before casting, a float too big for the integer isn't something to cast. Padded by
+        // one on each side and by how far apart f32s are out there, positions are only so precise.
+        let limit = last.saturating_sub(base) as f32 + 2.0;
+        let padding = |radius: f32| 1 + (radius * std::f32::EPSILON) as u64;
+        let (inner, outer) = (inner.max(0.0).min(limit), outer.max(0.0).min(limit));
+        let inner = ((inner as u64).saturating_sub(padding(inner)) + base).max(first);
+        let outer = (outer as u64 + 1 + padding(outer) + base).min(last);
+        inner..=outer
+    }
+
+    /// The particle closest to `point` and no farther than `max_distance` from it, out of the ones
+    /// `keep` says yes to given their number and whether it's prime. Only numbers with a radius
+    /// within `max_distance` of the point's can be that close, so only those are looked at.
+    pub fn nearest<F>(&self, point: Point, max_distance: f32, angle_factor: f32, keep: F) -> Option<Particle>
+    where
+        F: Fn(u64, bool) -> bool + Sync + Send,
+    {
+        let radius = point.coords.norm();
+        self.numbers_within(radius - max_distance, radius + max_distance)
+            .filter(|&number| keep(number, self.is_prime(number)))
+            .map(|number| self.particle(number, angle_factor))
+            .map(|particle| {
+                let distance = (particle.position - point).norm();
+                (particle, distance)
+            })
+            .filter(|(_, distance)| *distance <= max_distance)
+            // Ties go to the smaller number, whichever thread finds what first
+            .min_by(|(a, a_distance), (b, b_distance)| {
+                a_distance.partial_cmp(b_distance).unwrap_or(Ordering::Equal).then(a.number.cmp(&b.number))
+            })
+            .map(|(particle, _)| particle)
+    }
+
+    /// The number closest to `point`, however far it is and whether it's drawn or not. Numbers
+    /// are looked at outwards from the one at the point's radius, until no number left could be
+    /// closer or `CLOSEST_CANDIDATES` of them were. With angle factors that put the numbers on a
+    /// few straight lines the closest one can be farther out than that, and the closest of those
+    /// is the answer instead.
+    pub fn closest_number(&self, point: Point, angle_factor: f32) -> Option<u64> {
+        let (first, last) = (self.start().max(1), self.last_number());
+        if first > last {
+            return None;
+        }
+        let distance = |number: u64| (self.position_of(number, angle_factor) - point).norm();
+        let radius = point.coords.norm().round() as u64;
+        let middle = radius.saturating_add(self.radius_base).max(first).min(last);
+        let mut closest = (middle, distance(middle));
+        for step in 1..Self::CLOSEST_CANDIDATES {
+            // Numbers this far away are more than this far from the point's radius alone
+            if step as f32 - 1.0 > closest.1 {
+                break;
+            }
+            let candidates = middle.checked_sub(step).into_iter().chain(middle.checked_add(step));
+            for number in candidates.filter(|number| (first..=last).contains(number)) {
+                let candidate = (number, distance(number));
+                if candidate.1 < closest.1 {
+                    closest = candidate;
+                }
+            }
+        }
+        Some(closest.0)
+    }
+
+    /// How many of the numbers `keep` says yes to sit at each angle, around the spiral in `bins`
+    /// equal slices starting at angle 0. Every number in the spiral is looked at.
+    pub fn angle_counts<F>(&self, bins: usize, angle_factor: f32, keep: F) -> Vec<u64>
+    where
+        F: Fn(u64) -> bool + Sync + Send,
+    {
+        let bin_of = |number: u64| {
+            let turns = Particle::angle_of(number, angle_factor) / (2.0 * std::f64::consts::PI);
+            ((turns * bins as f64) as usize).min(bins - 1)
+        };
+        (self.start().max(1)..=self.last_number()).into_par_iter()
+            .filter(|&number| keep(number))
+            .fold(|| vec![0; bins], |mut counts, number| {
+                counts[bin_of(number)] += 1;
+                counts
+            })
+            .reduce(|| vec![0; bins], |mut counts, other| {
+                counts.iter_mut().zip(other).for_each(|(count, other)| *count += other);
+                counts
+            })
+    }
+
+    fn particle(&self, number: u64, angle_factor: f32) -> Particle {
+        Particle {
+            number,
+            position: self.position_of(number, angle_factor),
+            is_prime: self.sieve.is_prime(number),
+        }
+    }
+}
+
+impl Dataset for Particles {
+    fn find_between(start: u64, end: u64, primality: Primality) -> Particles {
+        Particles::find_between(start, end, primality)
+    }
+
+    fn append(&mut self, other: Particles) {
+        Particles::append(self, other);
+    }
+
+    fn prime_count(&self) -> u64 {
+        Particles::prime_count(self)
+    }
+
+    fn is_prime(&self, number: u64) -> bool {
+        Particles::is_prime(self, number)
+    }
+
+    fn position_of(&self, number: u64, angle_factor: f32) -> Point {
+        Particles::position_of(self, number, angle_factor)
+    }
+
+    fn range_within(&self, inner: f32, outer: f32) -> RangeInclusive<u64> {
+        Particles::range_within(self, inner, outer)
+    }
+
+    fn bounding_radius(&self) -> f32 {
+        Particles::bounding_radius(self)
+    }
+}
+
+/// Everything the info panel shows about a selected number, worked out once when it gets selected
+#[derive(Debug, Clone)]
+pub struct NumberInfo {
+    pub number: u64,
+    pub is_prime: bool,
+    /// How far from the origin it sits, in spiral units
+    pub radius: u64,
+    /// Each prime factor and its power, smallest first
+    pub factors: Vec<(u64, u32)>,
+    pub previous_prime: Option<u64>,
+    pub next_prime: Option<u64>,
+}
+
+impl NumberInfo {
+    /// `number` has to be in `particles`. The neighboring primes are only looked for among them.
+    pub fn new(number: u64, particles: &Particles) -> NumberInfo {
+        NumberInfo {
+            number,
+            is_prime: particles.is_prime(number),
+            radius: number.saturating_sub(particles.radius_base),
+            factors: factorize(number),
+            previous_prime: particles.previous_prime(number),
+            next_prime: particles.next_prime(number),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let kind = if self.is_prime { "prime" } else { "composite" };
+        let factors = self.factors.iter()
+            .map(|&(prime, power)| if power > 1 { format!("{}^{}", prime, power) } else { prime.to_string() })
+            .collect::<Vec<_>>()
+            .join(" × ");
+        let previous = match self.previous_prime {
+            Some(p) => { format!("{} (-{})", p, self.number - p) }
+            None => { String::from("none") }
+        };
+        let next = match self.next_prime {
+            Some(p) => { format!("{} (+{})", p, p - self.number) }
+            None => { String::from("none") }
+        };
+
+        format!(
+            "{} ({})\nFactors: {}\nPrevious prime: {}\nNext prime: {}",
+            self.number,
+            kind,
+            if factors.is_empty() { String::from("-") } else { factors },
+            previous,
+            next,
+        )
+    }
+
+    /// The description plus where the number sits, for pasting somewhere else
+    pub fn to_text(&self, angle_factor: f32) -> String {
+        let angle = (self.number as f64 * f64::from(angle_factor)) % (2.0 * std::f64::consts::PI);
+        format!("{}\nPolar coordinates: r = {}, θ = {:.6} rad\n", self.describe(), self.radius, angle)
+    }
+}
+
+// Trial division by 2 and the odd numbers. The particles may be a slice far from the small
+// primes, so they can't be counted on for these.
+fn factorize(mut number: u64) -> Vec<(u64, u32)> {
+    let mut factors = Vec::new();
+    let candidates = std::iter::once(2).chain((3..).step_by(2));
+    for candidate in candidates {
+        if candidate > number / candidate {
+            break;
+        }
+
+        let mut power = 0;
+        while number % candidate == 0 {
+            number /= candidate;
+            power += 1;
+        }
+        if power > 0 {
+            factors.push((candidate, power));
+        }
+    }
+
+    if number > 1 {
+        factors.push((number, 1));
+    }
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Point, b: Point) {
+        assert!((a - b).norm() < 1e-3, "{:?} isn't {:?}", a, b);
+    }
+
+    #[test]
+    fn numbers_sit_at_their_own_radius() {
+        for &number in &[1, 2, 10, 12_345, 9_999_991] {
+            let position = Particle::position_of(number, 1.0);
+            let radius = position.coords.norm();
+            assert!((radius - number as f32).abs() <= number as f32 * 1e-6, "{} is at radius {}", number, radius);
+        }
+    }
+
+    #[test]
+    fn the_angle_is_the_number_times_the_angle_factor() {
+        assert_close(Particle::position_of(1, 0.0), Point::new(1.0, 0.0));
+        assert_close(Particle::position_of(2, std::f32::consts::FRAC_PI_4), Point::new(0.0, 2.0));
+        assert_close(Particle::position_of(4, std::f32::consts::FRAC_PI_4), Point::new(-4.0, 0.0));
+        assert_close(Particle::position_of(3, std::f32::consts::FRAC_PI_2), Point::new(0.0, -3.0));
+    }
+
+    #[test]
+    fn positions_stay_right_for_big_numbers() {
+        // n·(cos(k·n), sin(k·n)) worked out by hand, with k as close to the given one as an f32 gets
+        let expected = [
+            (10_000_000, 1.0, (-9_072_703.9, 4_205_477.9)),
+            (20_000_001, 1.0, (19_826_271.8, 2_630_396.6)),
+            (10_000_019, 0.001, (-9_460_251.5, -3_240_990.8)),
+            (16_777_217, 2.5, (-16_191_224.3, -4_395_368.9)),
+        ];
+        // In f64, the positions themselves don't fit in an f32 exactly
+        let distance = |a: Point, (x, y): (f64, f64)| (f64::from(a.x) - x).hypot(f64::from(a.y) - y);
+        // Everything in f32, like positions used to be worked out
+        let f32_position = |number: u64, angle_factor: f32| {
+            let radius = number as f32;
+            let angle = radius * angle_factor;
+            Point::new(radius * angle.cos(), radius * angle.sin())
+        };
+
+        for &(number, angle_factor, position) in &expected {
+            // An f32 this big is only precise to a couple of units
+            let error = distance(Particle::position_of(number, angle_factor), position);
+            assert!(error < 4.0, "{} is {} away from where it should be", number, error);
+        }
+        // Only off when the f32s can't hold the number or the angle
+        for &(number, angle_factor, position) in &expected[1..] {
+            let error = distance(f32_position(number, angle_factor), position);
+            assert!(error > 100.0, "{} is only {} away in f32", number, error);
+        }
+    }
+
+    #[test]
+    fn particles_know_whether_they_are_prime() {
+        let particles = Particles::find(100);
+        assert_eq!(particles.last_number(), 99);
+        assert!((particles.bounding_radius() - 99.0).abs() < 1e-6);
+        assert!(particles.get(0, 1.0).is_none());
+        assert!(particles.get(100, 1.0).is_none());
+
+        let all: Vec<Particle> = particles.within(0.0, std::f32::INFINITY, 1.0).collect();
+        assert_eq!(all.len(), 99);
+        assert_eq!(all[0].number, 1);
+        let primes: Vec<u64> = all.iter().filter(|particle| particle.is_prime).map(|particle| particle.number).collect();
+        assert_eq!(&primes[..10], &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        for &composite in &[1, 4, 9, 15, 49, 91, 99] {
+            assert!(!particles.is_prime(composite), "{} isn't prime", composite);
+        }
+    }
+
+    #[test]
+    fn only_numbers_in_the_ring_are_worked_out() {
+        let particles = Particles::find(1_000);
+        let numbers: Vec<u64> = particles.within(100.2, 110.7, 1.0).map(|particle| particle.number).collect();
+        // Padded by one on each side
+        assert_eq!(numbers, (99..=112).collect::<Vec<_>>());
+        for particle in particles.within(500.0, 600.0, 0.3).collect::<Vec<_>>() {
+            assert_close(particle.position, Particle::position_of(particle.number, 0.3));
+        }
+
+        assert_eq!(particles.within(5_000.0, 6_000.0, 1.0).count(), 0);
+        assert_eq!(particles.within(-10.0, 1e30, 1.0).count(), 999);
+        assert_eq!(Particles::find(0).within(0.0, 10.0, 1.0).count(), 0);
+    }
+
+    #[test]
+    fn finds_the_nearest_particle() {
+        let particles = Particles::find(10_000);
+        let angle_factor = 0.37;
+        let brute_force = |point: Point, max_distance: f32, primes_only: bool| {
+            particles.within(0.0, std::f32::INFINITY, angle_factor)
+                .filter(|particle| particle.is_prime || !primes_only)
+                .map(|particle| (particle.number, (particle.position - point).norm()))
+                .filter(|&(_, distance)| distance <= max_distance)
+                .min_by(|(a, a_distance), (b, b_distance)| {
+                    a_distance.partial_cmp(b_distance).unwrap().then(a.cmp(b))
+                })
+                .map(|(number, _)| number)
+        };
+
+        for &(x, y) in &[(0.0, 0.0), (120.3, -44.0), (-5_000.0, 2_100.5), (9_990.0, 0.0), (20_000.0, 0.0)] {
+            let point = Point::new(x, y);
+            for &max_distance in &[1.0, 25.0] {
+                for &primes_only in &[false, true] {
+                    let nearest = particles.nearest(point, max_distance, angle_factor, |_, is_prime| is_prime || !primes_only);
+                    assert_eq!(nearest.map(|particle| particle.number), brute_force(point, max_distance, primes_only));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_closest_number() {
+        let particles = Particles::find(10_000);
+        let angle_factor = 0.37;
+        let brute_force = |point: Point| {
+            particles.within(0.0, std::f32::INFINITY, angle_factor)
+                .map(|particle| (particle.number, (particle.position - point).norm()))
+                .min_by(|(a, a_distance), (b, b_distance)| a_distance.partial_cmp(b_distance).unwrap().then(a.cmp(b)))
+                .map(|(number, _)| number)
+        };
+
+        for &(x, y) in &[(0.0, 0.0), (120.3, -44.0), (-5_000.0, 2_100.5), (9_990.0, 0.0), (20_000.0, 0.0)] {
+            let point = Point::new(x, y);
+            assert_eq!(particles.closest_number(point, angle_factor), brute_force(point));
+        }
+        assert_eq!(Particles::find(0).closest_number(Point::new(1.0, 1.0), angle_factor), None);
+    }
+
+    #[test]
+    fn counts_the_numbers_at_each_angle() {
+        // A quarter turn per number, so every number sits in the slice of its remainder by 4
+        let particles = Particles::find(100);
+        let angle_factor = std::f32::consts::FRAC_PI_2;
+        let all = particles.angle_counts(4, angle_factor, |_| true);
+        assert_eq!(all, vec![24, 25, 25, 25]);
+
+        // Past 2 they are odd, so they only ever point up or down
+        let primes = particles.angle_counts(4, angle_factor, |number| particles.is_prime(number));
+        assert_eq!(primes, vec![0, 11, 1, 13]);
+        assert_eq!(primes.iter().sum::<u64>(), particles.prime_count());
+
+        assert_eq!(particles.angle_counts(3, -1.0, |number| number < 10).iter().sum::<u64>(), 9);
+    }
+
+    #[test]
+    fn slices_put_together_are_the_whole_range() {
+        let whole = Particles::find(10_000);
+        let mut particles = Particles::find(0);
+        for start in (0..10_000).step_by(640) {
+            particles.append(Particles::find_between(start, (start + 640).min(10_000), Primality::Sieve));
+        }
+
+        assert_eq!(particles.last_number(), whole.last_number());
+        // From 9551 to 9587
+        assert_eq!(particles.max_gap(), 36);
+        assert_eq!(whole.max_gap(), 36);
+        for number in 0..10_001 {
+            assert_eq!(particles.is_prime(number), whole.is_prime(number), "{}", number);
+        }
+    }
+
+    #[test]
+    fn factors_are_worked_out_once_until_more_numbers_come() {
+        let particles = Particles::find(10_000);
+        let factors = particles.factors();
+        assert!(Arc::ptr_eq(&factors, &particles.clone().factors()));
+        assert_eq!((factors.start(), factors.end()), (0, 10_000));
+        assert_eq!(factors.big_omega(9_999), Some(4));
+
+        let mut more = particles.clone();
+        more.append(Particles::find_between(9_984, 20_000, Primality::Sieve));
+        assert_eq!(more.factors().end(), 20_000);
+        assert_eq!(particles.factors().end(), 10_000);
+    }
+
+    #[test]
+    fn digital_roots_are_worked_out_again_in_another_base() {
+        let particles = Particles::find(1_000);
+        let decimal = particles.digital_roots(10);
+        assert!(Arc::ptr_eq(&decimal, &particles.clone().digital_roots(10)));
+        assert_eq!((decimal.end(), decimal.root(999)), (1_000, Some(9)));
+        let base_7 = particles.digital_roots(7);
+        assert_eq!((base_7.base(), base_7.root(48)), (7, Some(6)));
+        assert!(!Arc::ptr_eq(&decimal, &particles.digital_roots(10)));
+    }
+
+    #[test]
+    fn number_info_factorizes() {
+        let particles = Particles::find(1_000);
+        let info = NumberInfo::new(360, &particles);
+        assert!(!info.is_prime);
+        assert_eq!(info.factors, vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!((info.previous_prime, info.next_prime), (Some(359), Some(367)));
+
+        let info = NumberInfo::new(997, &particles);
+        assert!(info.is_prime);
+        assert_eq!(info.factors, vec![(997, 1)]);
+        assert_eq!(info.next_prime, None);
+
+        let info = NumberInfo::new(1, &particles);
+        assert!(info.factors.is_empty());
+        assert_eq!((info.previous_prime, info.next_prime), (None, Some(2)));
+    }
+
+    #[test]
+    fn slices_far_out_stay_at_their_radius_or_get_rebased() {
+        let (start, end) = (999_999_936, 1_000_100_000);
+        let particles = Particles::find_between(start, end, Primality::Sieve);
+        assert_eq!(particles.start(), start);
+        for number in (start..end).step_by(997) {
+            assert_eq!(particles.is_prime(number), crate::primality::is_prime(number), "{}", number);
+        }
+
+        // Only the annulus the slice makes is there, and the bands around it are empty
+        assert_eq!(particles.numbers_within(0.0, 1e6).count(), 0);
+        assert_eq!(particles.numbers_within(0.0, 2e9).count() as u64, end - start);
+        let band: Vec<u64> = particles.numbers_within(1_000_050_000.0, 1_000_050_100.0).collect();
+        assert!(band.contains(&1_000_050_000) && band.contains(&1_000_050_100), "{:?}", (band[0], band[band.len() - 1]));
+
+        let rebased = particles.clone().rebased(1_000_000_000);
+        assert!((rebased.bounding_radius() - 99_999.0).abs() < 1e-3);
+        let position = rebased.position_of(1_000_000_010, 1.0);
+        assert!((position.coords.norm() - 10.0).abs() < 1e-3);
+        let unrebased = particles.position_of(1_000_000_010, 1.0);
+        assert!((position.coords.normalize() - unrebased.coords.normalize()).norm() < 1e-3, "same angle");
+        let band: Vec<u64> = rebased.numbers_within(9.5, 10.5).collect();
+        assert_eq!(band, (1_000_000_008..=1_000_000_012).collect::<Vec<_>>());
+
+        let info = NumberInfo::new(1_000_000_010, &rebased);
+        assert_eq!(info.radius, 10);
+        assert_eq!(info.factors, vec![(2, 1), (5, 1), (17, 1), (5_882_353, 1)]);
+    }
+}
@@ -0,0 +1,547 @@
+//! Predicates typed in to pick out numbers, like `isprime(n) && n % 6 == 1`, read once and worked
+//! out for a whole range at once
+
+use std::fmt;
+
+use rayon::prelude::*;
+
+use crate::primality;
+use crate::sieve::Sieve;
+
+/// An expression in n, picking out the numbers it isn't 0 for. Everything in it is a whole number:
+/// the comparisons and `!`, `&&` and `||` give 1 for true and 0 for false, and take anything other
+/// than 0 as true, like in C. A number it can't be worked out for, dividing by 0 or going past what
+/// an i128 holds on the way, isn't picked.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Predicate {
+    source: String,
+    root: Node,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Node {
+    Number(i128),
+    N,
+    Not(Box<Node>),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Operator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl Operator {
+    // From the ones binding the loosest to the tightest, the comparisons in the middle
+    const LEVELS: [&'static [(&'static str, Operator)]; 5] = [
+        &[("||", Operator::Or)],
+        &[("&&", Operator::And)],
+        &[
+            ("==", Operator::Equal),
+            ("!=", Operator::NotEqual),
+            ("<=", Operator::LessOrEqual),
+            (">=", Operator::GreaterOrEqual),
+            ("<", Operator::Less),
+            (">", Operator::Greater),
+        ],
+        &[("+", Operator::Add), ("-", Operator::Subtract)],
+        &[("*", Operator::Multiply), ("/", Operator::Divide), ("%", Operator::Remainder)],
+    ];
+    const COMPARISONS: usize = 2;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Function {
+    IsPrime,
+    Gcd,
+    Mod,
+    DigitSum,
+}
+
+impl Function {
+    const ALL: [Function; 4] = [Function::IsPrime, Function::Gcd, Function::Mod, Function::DigitSum];
+
+    fn name(self) -> &'static str {
+        match self {
+            Function::IsPrime => { "isprime" }
+            Function::Gcd => { "gcd" }
+            Function::Mod => { "mod" }
+            Function::DigitSum => { "digitsum" }
+        }
+    }
+
+    // The fewest and the most arguments it takes. digitsum's second one is the base.
+    fn arguments(self) -> (usize, usize) {
+        match self {
+            Function::IsPrime => { (1, 1) }
+            Function::Gcd | Function::Mod => { (2, 2) }
+            Function::DigitSum => { (1, 2) }
+        }
+    }
+}
+
+/// Where reading a predicate went wrong: the token it stopped at, counted in characters from 0,
+/// and what it expected instead
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub column: usize,
+    pub width: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    /// The message over the predicate, with the token underlined under it, for a terminal
+    pub fn pointing_into(&self, source: &str) -> String {
+        let underline = format!("{}{}", " ".repeat(self.column), "^".repeat(self.width.max(1)));
+        format!("{}\n  {}\n  {}", self.message, source, underline)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} at column {}", self.message, self.column + 1)
+    }
+}
+
+// A piece of the predicate: a number, a name, an operator or a bracket, or the end of it
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Token {
+    text: String,
+    column: usize,
+}
+
+impl Token {
+    fn is(&self, text: &str) -> bool {
+        self.text == text
+    }
+
+    fn is_end(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError { column: self.column, width: self.text.chars().count(), message }
+    }
+
+    // How it's called in a message
+    fn describe(&self) -> String {
+        if self.is_end() { String::from("the end") } else { format!("`{}`", self.text) }
+    }
+}
+
+// The two-character ones first, so `<=` isn't read as `<`
+const SYMBOLS: [&str; 17] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")", ",",
+];
+
+fn tokens(source: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut column = 0;
+    while column < chars.len() {
+        let start = column;
+        let c = chars[column];
+        if c.is_whitespace() {
+            column += 1;
+            continue;
+        }
+        if c.is_ascii_alphanumeric() || c == '_' {
+            while column < chars.len() && (chars[column].is_ascii_alphanumeric() || chars[column] == '_') {
+                column += 1;
+            }
+        } else {
+            let rest: String = chars[column..chars.len().min(column + 2)].iter().collect();
+            let symbol = SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)).ok_or_else(|| {
+                let message = match c {
+                    '=' => { String::from("expected `==` to compare") }
+                    '&' => { String::from("expected `&&`") }
+                    '|' => { String::from("expected `||`") }
+                    c => { format!("unexpected `{}`", c) }
+                };
+                ParseError { column, width: 1, message }
+            })?;
+            column += symbol.len();
+        }
+        tokens.push(Token { text: chars[start..column].iter().collect(), column: start });
+    }
+    tokens.push(Token { text: String::new(), column: chars.len() });
+    Ok(tokens)
+}
+
+// How deep brackets, `!` and `-` can go inside each other, so a predicate can't take the parser's
+// stack with it
+const MAX_DEPTH: usize = 64;
+
+// Reads the tokens from the loosest binding operators in, one level of `Operator::LEVELS` at a time
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+    // How many `unary`s are underway
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].clone();
+        if !token.is_end() {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn operator(&self, level: usize) -> Option<Operator> {
+        let token = self.peek();
+        Operator::LEVELS[level].iter().find(|&&(text, _)| token.is(text)).map(|&(_, operator)| operator)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Node, ParseError> {
+        if level == Operator::LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(operator) = self.operator(level) {
+            self.advance();
+            let right = self.binary(level + 1)?;
+            left = Node::Binary(operator, Box::new(left), Box::new(right));
+            // a < b < c doesn't mean what it looks like, so it isn't allowed
+            if level == Operator::COMPARISONS && self.operator(level).is_some() {
+                return Err(self.peek().error(String::from("comparisons don't chain, join them with `&&`")));
+            }
+        }
+        Ok(left)
+    }
+
+    // Everything nested goes through here, which is where how deep it goes is kept in check
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.peek().error(format!("nested more than {} deep", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let node = self.nested();
+        self.depth -= 1;
+        node
+    }
+
+    fn nested(&mut self) -> Result<Node, ParseError> {
+        if self.peek().is("!") {
+            self.advance();
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.peek().is("-") {
+            self.advance();
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node, ParseError> {
+        let token = self.advance();
+        if token.is("(") {
+            let inside = self.binary(0)?;
+            self.expect(")")?;
+            return Ok(inside);
+        }
+        let first = token.text.chars().next().unwrap_or(' ');
+        if first.is_ascii_digit() {
+            let digits = token.text.replace('_', "");
+            let number = digits.parse::<i128>()
+                .map_err(|_| token.error(format!("{} isn't a number, or is too big a one", token.describe())))?;
+            return Ok(Node::Number(number));
+        }
+        if !first.is_ascii_alphabetic() && first != '_' {
+            let message = format!("expected a number, n, a function or `(`, found {}", token.describe());
+            return Err(token.error(message));
+        }
+        if !self.peek().is("(") {
+            if token.text.eq_ignore_ascii_case("n") {
+                return Ok(Node::N);
+            }
+            return Err(token.error(format!("there's no `{}`, n is the only variable", token.text)));
+        }
+
+        let function = Function::ALL.iter().cloned()
+            .find(|function| token.text.eq_ignore_ascii_case(function.name()))
+            .ok_or_else(|| {
+                token.error(format!("there's no `{}` function, only isprime, gcd, mod and digitsum", token.text))
+            })?;
+        self.advance();
+        let mut arguments = Vec::new();
+        if !self.peek().is(")") {
+            arguments.push(self.binary(0)?);
+            while self.peek().is(",") {
+                self.advance();
+                arguments.push(self.binary(0)?);
+            }
+        }
+        self.expect(")")?;
+        let (fewest, most) = function.arguments();
+        if arguments.len() < fewest || arguments.len() > most {
+            let expected = if fewest == most { fewest.to_string() } else { format!("{} or {}", fewest, most) };
+            let plural = if most == 1 { "" } else { "s" };
+            let message = format!("{} takes {} argument{}, not {}", function.name(), expected, plural, arguments.len());
+            return Err(token.error(message));
+        }
+        Ok(Node::Call(function, arguments))
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), ParseError> {
+        if !self.peek().is(text) {
+            return Err(self.peek().error(format!("expected `{}`, found {}", text, self.peek().describe())));
+        }
+        self.advance();
+        Ok(())
+    }
+}
+
+impl Predicate {
+    pub fn parse(source: &str) -> Result<Predicate, ParseError> {
+        let mut parser = Parser { tokens: tokens(source)?, next: 0, depth: 0 };
+        if parser.peek().is_end() {
+            return Err(parser.peek().error(String::from("expected an expression in n, like `isprime(n)`")));
+        }
+        let root = parser.binary(0)?;
+        if !parser.peek().is_end() {
+            return Err(parser.peek().error(format!("unexpected {}", parser.peek().describe())));
+        }
+        Ok(Predicate { source: source.trim().to_string(), root })
+    }
+
+    /// Whether it picks `number`, testing primes with Miller–Rabin
+    pub fn picks(&self, number: u64) -> bool {
+        self.picks_with(number, &primality::is_prime)
+    }
+
+    /// Which numbers in `start..end` it picks, worked out in parallel. When it tests for primes the
+    /// range is sieved first.
+    pub fn pick(&self, start: u64, end: u64) -> Picked {
+        let end = end.max(start);
+        let sieve = if self.root.tests_primes() { Some(Sieve::range(start / 64 * 64, end)) } else { None };
+        let is_prime = |x: u64| match &sieve {
+            Some(sieve) if x >= sieve.start() && x < sieve.end() => { sieve.is_prime(x) }
+            _ => { primality::is_prime(x) }
+        };
+        let mut words = vec![0u64; ((end - start + 63) / 64) as usize];
+        words.par_iter_mut()
+            .enumerate()
+            .for_each(|(index, word)| {
+                let first = start + index as u64 * 64;
+                for bit in 0..(end - first).min(64) {
+                    if self.picks_with(first + bit, &is_prime) {
+                        *word |= 1 << bit;
+                    }
+                }
+            });
+        Picked { words, start, end }
+    }
+
+    fn picks_with(&self, number: u64, is_prime: &dyn Fn(u64) -> bool) -> bool {
+        self.root.value(i128::from(number), is_prime).map_or(false, |value| value != 0)
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.source)
+    }
+}
+
+impl Node {
+    fn tests_primes(&self) -> bool {
+        match self {
+            Node::Number(_) | Node::N => { false }
+            Node::Not(inside) | Node::Negate(inside) => { inside.tests_primes() }
+            Node::Binary(_, left, right) => { left.tests_primes() || right.tests_primes() }
+            Node::Call(function, arguments) => {
+                *function == Function::IsPrime || arguments.iter().any(Node::tests_primes)
+            }
+        }
+    }
+
+    // `None` when it can't be worked out for this n
+    fn value(&self, n: i128, is_prime: &dyn Fn(u64) -> bool) -> Option<i128> {
+        let truth = |true_or_not: bool| if true_or_not { 1 } else { 0 };
+        let value = match self {
+            Node::Number(number) => { *number }
+            Node::N => { n }
+            Node::Not(inside) => { truth(inside.value(n, is_prime)? == 0) }
+            Node::Negate(inside) => { inside.value(n, is_prime)?.checked_neg()? }
+            // Only as far as it takes to tell
+            Node::Binary(Operator::Or, left, right) => {
+                truth(left.value(n, is_prime)? != 0 || right.value(n, is_prime)? != 0)
+            }
+            Node::Binary(Operator::And, left, right) => {
+                truth(left.value(n, is_prime)? != 0 && right.value(n, is_prime)? != 0)
+            }
+            Node::Binary(operator, left, right) => {
+                let (left, right) = (left.value(n, is_prime)?, right.value(n, is_prime)?);
+                match operator {
+                    Operator::Equal => { truth(left == right) }
+                    Operator::NotEqual => { truth(left != right) }
+                    Operator::Less => { truth(left < right) }
+                    Operator::LessOrEqual => { truth(left <= right) }
+                    Operator::Greater => { truth(left > right) }
+                    Operator::GreaterOrEqual => { truth(left >= right) }
+                    Operator::Add => { left.checked_add(right)? }
+                    Operator::Subtract => { left.checked_sub(right)? }
+                    Operator::Multiply => { left.checked_mul(right)? }
+                    Operator::Divide => { left.checked_div_euclid(right)? }
+                    Operator::Remainder => { left.checked_rem_euclid(right)? }
+                    Operator::Or | Operator::And => { unreachable!() }
+                }
+            }
+            Node::Call(function, arguments) => {
+                let values = arguments.iter()
+                    .map(|argument| argument.value(n, is_prime))
+                    .collect::<Option<Vec<i128>>>()?;
+                match function {
+                    Function::IsPrime => { truth(is_whole(values[0]).map_or(false, is_prime)) }
+                    Function::Gcd => { gcd(values[0], values[1])? }
+                    Function::Mod => { values[0].checked_rem_euclid(values[1])? }
+                    Function::DigitSum => { digit_sum(values[0], values.get(1).cloned().unwrap_or(10))? }
+                }
+            }
+        };
+        Some(value)
+    }
+}
+
+// A value that's a u64, the only ones that can be prime
+fn is_whole(value: i128) -> Option<u64> {
+    if value >= 0 && value <= i128::from(u64::max_value()) { Some(value as u64) } else { None }
+}
+
+fn gcd(a: i128, b: i128) -> Option<i128> {
+    let (mut a, mut b) = (a.checked_abs()?, b.checked_abs()?);
+    while b != 0 {
+        let rest = a % b;
+        a = b;
+        b = rest;
+    }
+    Some(a)
+}
+
+// The sum of the digits of the number without its sign, in a base from 2 to 36
+fn digit_sum(number: i128, base: i128) -> Option<i128> {
+    if base < 2 || base > 36 {
+        return None;
+    }
+    let mut rest = number.checked_abs()?;
+    let mut sum = 0;
+    while rest > 0 {
+        sum += rest % base;
+        rest /= base;
+    }
+    Some(sum)
+}
+
+/// Which numbers in `start..end` a predicate picks, one bit per number: bit `i % 64` of word
+/// `i / 64` is set when it picks `start + i`, like `Sieve`'s primes
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Picked {
+    words: Vec<u64>,
+    start: u64,
+    end: u64,
+}
+
+impl Picked {
+    pub fn contains(&self, number: u64) -> bool {
+        if number < self.start || number >= self.end {
+            return false;
+        }
+        let bit = number - self.start;
+        self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+    }
+
+    pub fn count(&self) -> u64 {
+        self.words.iter().map(|word| u64::from(word.count_ones())).sum()
+    }
+
+    /// The numbers picked, smallest first
+    pub fn numbers(&self) -> Vec<u64> {
+        let mut numbers = Vec::with_capacity(self.count() as usize);
+        for (index, &word) in self.words.iter().enumerate() {
+            let mut rest = word;
+            while rest != 0 {
+                numbers.push(self.start + index as u64 * 64 + u64::from(rest.trailing_zeros()));
+                rest &= rest - 1;
+            }
+        }
+        numbers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picked(source: &str, end: u64) -> Vec<u64> {
+        Predicate::parse(source).unwrap().pick(0, end).numbers()
+    }
+
+    #[test]
+    fn picks_the_numbers_it_is_true_for() {
+        assert_eq!(picked("isprime(n) && n % 6 == 1", 50), vec![7, 13, 19, 31, 37, 43]);
+        assert_eq!(picked("n < 5 || n >= 48", 50), vec![0, 1, 2, 3, 4, 48, 49]);
+        assert_eq!(picked("!isprime(n) && mod(n, 2) == 1 && n > 1", 30), vec![9, 15, 21, 25, 27]);
+        assert_eq!(picked("gcd(n, 12) == 4", 30), vec![4, 8, 16, 20, 28]);
+        assert_eq!(picked("digitsum(n) == 10 && n < 50", 100), vec![19, 28, 37, 46]);
+        assert_eq!(picked("digitsum(n, 2) == 1", 100), vec![1, 2, 4, 8, 16, 32, 64]);
+        // Precedence, and the numbers it can't be worked out for
+        assert_eq!(picked("-n + 2 * 3 == 1 + 2 * 2 - 3 * (n - 1) - -1 * 0", 10), vec![1]);
+        assert_eq!(picked("6 / (n - 3) == 2", 10), vec![6]);
+        assert_eq!(picked("n * 170141183460469231731687303715884105727 > 0 || n == 1", 4), vec![1]);
+        assert_eq!(picked("digitsum(n, 40) > 0", 4), Vec::<u64>::new());
+        assert_eq!(picked("0", 100), Vec::<u64>::new());
+
+        // The sieve and Miller–Rabin agree, in the range and out of it
+        let twin = Predicate::parse("isprime(n) && isprime(n + 2)").unwrap();
+        let start = 1_000_000_000_000;
+        let twins = twin.pick(start, start + 10_000);
+        assert_eq!(twins.numbers(), (start..start + 10_000).filter(|&n| twin.picks(n)).collect::<Vec<u64>>());
+        assert_eq!(twins.count(), twins.numbers().len() as u64);
+        assert!(twins.contains(twins.numbers()[0]) && !twins.contains(start - 1));
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_token() {
+        let error = |source: &str| Predicate::parse(source).unwrap_err();
+        assert_eq!(error("isprime(n) && n % 6 = 1").column, 20);
+        assert_eq!(error("isprime(n) && m > 2").message, "there's no `m`, n is the only variable");
+        assert_eq!(error("isprim(n)").width, 6);
+        assert_eq!(error("gcd(n)").message, "gcd takes 2 arguments, not 1");
+        assert_eq!(error("digitsum(n, 2, 3)").message, "digitsum takes 1 or 2 arguments, not 3");
+        assert_eq!(error("(n + 1").message, "expected `)`, found the end");
+        assert_eq!(error("1 < n < 5").column, 6);
+        assert_eq!(error("n ≡ 1").message, "unexpected `≡`");
+        assert_eq!(error("n 1").message, "unexpected `1`");
+        assert_eq!(error("n + )").message, "expected a number, n, a function or `(`, found `)`");
+        assert_eq!(error("  ").column, 2);
+        let deep = format!("{}n{}", "(".repeat(100), ")".repeat(100));
+        assert_eq!(error(&deep).column, MAX_DEPTH);
+        assert_eq!(error(&format!("{}n", "!-".repeat(50))).message, "nested more than 64 deep");
+        assert!(Predicate::parse(&format!("{}n{}", "(".repeat(MAX_DEPTH - 1), ")".repeat(MAX_DEPTH - 1))).is_ok());
+
+        let bad = error("n & 1");
+        assert_eq!(bad.to_string(), "expected `&&` at column 3");
+        assert_eq!(bad.pointing_into("n & 1"), "expected `&&`\n  n & 1\n    ^");
+        assert_eq!(Predicate::parse(" N%2==0 ").unwrap().to_string(), "N%2==0");
+    }
+}
@@ -0,0 +1,124 @@
+//! Telling primes apart one number at a time, and picking between that and the sieve
+
+/// How the primes among the particles are found
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Primality {
+    /// Crossing out multiples with `Sieve::range`
+    Sieve,
+    /// Testing every number on its own with `is_prime`, see `Sieve::tested`
+    MillerRabin,
+}
+
+impl Primality {
+    pub fn name(self) -> &'static str {
+        match self {
+            Primality::Sieve => { "sieve" }
+            Primality::MillerRabin => { "miller-rabin" }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Primality> {
+        [Primality::Sieve, Primality::MillerRabin].iter().cloned().find(|primality| primality.name() == name)
+    }
+}
+
+/// Whether `number` is prime, using a deterministic Miller–Rabin test. The witnesses are enough to
+/// never get a u64 wrong.
+pub fn is_prime(number: u64) -> bool {
+    // The first twelve primes are witnesses for every u64, the first four for anything below
+    // 3,215,031,751
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if number < 2 {
+        return false;
+    }
+    for &prime in &WITNESSES {
+        if number % prime == 0 {
+            return number == prime;
+        }
+    }
+
+    let witnesses = if number < 3_215_031_751 { &WITNESSES[..4] } else { &WITNESSES[..] };
+    // number - 1 = odd_part · 2^twos
+    let twos = (number - 1).trailing_zeros();
+    let odd_part = (number - 1) >> twos;
+    witnesses.iter().all(|&witness| passes(witness, odd_part, twos, number))
+}
+
+// Whether `number` looks prime to `witness`: witness^odd_part is 1, or squaring it fewer than
+// `twos` times gets to -1
+fn passes(witness: u64, odd_part: u64, twos: u32, number: u64) -> bool {
+    let mut x = pow_mod(witness, odd_part, number);
+    if x == 1 || x == number - 1 {
+        return true;
+    }
+
+    for _ in 1..twos {
+        x = mul_mod(x, x, number);
+        if x == number - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    // Both are below the modulus, so below 2^32 the product fits and u128 division can be skipped
+    if modulus <= u64::from(u32::max_value()) {
+        a * b % modulus
+    } else {
+        (u128::from(a) * u128::from(b) % u128::from(modulus)) as u64
+    }
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1;
+    base %= modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sieve::Sieve;
+
+    #[test]
+    fn agrees_with_the_sieve() {
+        let sieve = Sieve::new(200_000);
+        for number in 0..200_000 {
+            assert_eq!(is_prime(number), sieve.is_prime(number), "{}", number);
+        }
+    }
+
+    #[test]
+    fn knows_big_primes() {
+        for &prime in &[1_000_000_007, 2_147_483_647, 1_000_000_000_000_000_003, 18_446_744_073_709_551_557] {
+            assert!(is_prime(prime), "{} is prime", prime);
+        }
+    }
+
+    #[test]
+    fn is_not_fooled_by_pseudoprimes() {
+        // Carmichael numbers, and strong pseudoprimes to the first few witnesses
+        let pseudoprimes = [561, 1_105, 41_041, 2_047, 1_373_653, 25_326_001, 3_215_031_751, 2_152_302_898_747,
+            3_474_749_660_383, 341_550_071_728_321, 3_825_123_056_546_413_051];
+        for &composite in &pseudoprimes {
+            assert!(!is_prime(composite), "{} isn't prime", composite);
+        }
+        assert!(!is_prime(18_446_744_073_709_551_615));
+    }
+
+    #[test]
+    fn primality_names() {
+        for &primality in &[Primality::Sieve, Primality::MillerRabin] {
+            assert_eq!(Primality::from_name(primality.name()), Some(primality));
+        }
+        assert_eq!(Primality::from_name("trial-division"), None);
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::mpsc::{self, Receiver};
+
+// Something worked out on rayon's threads, like the stats or the rose, so moving around doesn't wait
+// for it. It's started over whenever what it's for changed and the last one is in, so it keeps up
+// without piling up. What's being worked out is thrown away if this is dropped first.
+pub struct Query<K, T> {
+    result: Option<T>,
+    receiver: Option<Receiver<T>>,
+    // What the result shown, or being worked out, is for
+    key: Option<K>,
+}
+
+impl<K, T> Default for Query<K, T> {
+    fn default() -> Query<K, T> {
+        Query { result: None, receiver: None, key: None }
+    }
+}
+
+impl<K: PartialEq, T: Send + 'static> Query<K, T> {
+    // The last result that came in, if any, which may be for something else by now
+    pub fn result(&self) -> Option<&T> {
+        self.result.as_ref()
+    }
+
+    // Takes in the result if it's in, then whether it's time to `start` over for `key`: nothing is
+    // being worked out anymore, and the last one was for something else. Never blocks.
+    pub fn should_start(&mut self, key: &K) -> bool {
+        if let Some(result) = self.receiver.as_ref().and_then(|receiver| receiver.try_recv().ok()) {
+            self.receiver = None;
+            self.result = Some(result);
+        }
+        self.receiver.is_none() && self.key.as_ref() != Some(key)
+    }
+
+    pub fn start<F>(&mut self, key: K, work: F)
+        where F: FnOnce() -> T + Send + 'static
+    {
+        let (sender, receiver) = mpsc::channel();
+        rayon::spawn(move || {
+            let _ = sender.send(work());
+        });
+        self.receiver = Some(receiver);
+        self.key = Some(key);
+    }
+
+    // For when what the result is for changed in a way the key doesn't show, like more numbers
+    // coming in, so the next check starts over
+    pub fn invalidate(&mut self) {
+        self.key = None;
+    }
+
+    // The same, throwing the last result away too
+    pub fn clear(&mut self) {
+        self.result = None;
+        self.key = None;
+    }
+}
@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use coffee::input::{keyboard, mouse, ButtonState, Event};
+
+use crate::bindings::{key_from_name, key_name};
+
+thread_local! {
+    // coffee makes the `Controls` itself, so this is how the session gets to them. It happens on
+    // the thread that runs the game.
+    static STARTED: RefCell<Option<Session>> = RefCell::new(None);
+}
+
+// Where input comes from: the window, the window while also being written to a file, or a
+// file written by an earlier recording. Events are tagged with the call to `interact` that
+// handled them, which coffee makes as many times as it likes per tick, so a replay goes through
+// the same sequence of views as the recording.
+pub enum Session {
+    Live,
+    Recording {
+        file: BufWriter<File>,
+        pending: Vec<Event>,
+    },
+    Replaying {
+        events: VecDeque<(u64, Event)>,
+    },
+}
+
+impl Session {
+    // `--record <file>` or `--replay <file>`, the replay going first. Anything wrong with them is
+    // reported and we go on with live input.
+    pub fn start(record: Option<&Path>, replay: Option<&Path>) -> Session {
+        if let Some(path) = replay {
+            match Self::replay(path) {
+                Ok(session) => { return session; }
+                Err(error) => { eprintln!("error: couldn't replay {}: {}", path.display(), error); }
+            }
+        } else if let Some(path) = record {
+            match File::create(path) {
+                Ok(file) => {
+                    return Session::Recording {
+                        file: BufWriter::new(file),
+                        pending: Vec::new(),
+                    };
+                }
+                Err(error) => { eprintln!("error: couldn't record to {}: {}", path.display(), error); }
+            }
+        }
+        Session::Live
+    }
+
+    // Hands `session` over to the `Controls` coffee makes once the game is loaded
+    pub fn hand_over(session: Session) {
+        STARTED.with(|cell| *cell.borrow_mut() = Some(session));
+    }
+
+    // The session handed over, or live input without one
+    pub fn take_over() -> Session {
+        STARTED.with(|cell| cell.borrow_mut().take()).unwrap_or(Session::Live)
+    }
+
+    fn replay(path: &Path) -> Result<Session, String> {
+        let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let events = contents.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| decode(line).map_err(|error| format!("line {}: {}", i + 1, error)))
+            .collect::<Result<_, _>>()?;
+        Ok(Session::Replaying { events })
+    }
+
+    // Called by `Controls` for every event coming from the window. Returns whether it should be
+    // handled, which it isn't while a replay is feeding the input instead.
+    pub fn receive(&mut self, event: &Event) -> bool {
+        match self {
+            Session::Live => { true }
+            Session::Recording { pending, .. } => {
+                pending.push(*event);
+                true
+            }
+            Session::Replaying { .. } => { false }
+        }
+    }
+
+    // Writes what was received since the last call as handled by `interaction`, or hands out the
+    // replayed events up to `interaction`
+    pub fn sync(&mut self, interaction: u64) -> Vec<Event> {
+        match self {
+            Session::Live => { Vec::new() }
+            Session::Recording { file, pending } => {
+                let lines: Vec<String> = pending.drain(..).filter_map(|event| encode(interaction, &event)).collect();
+                if !lines.is_empty() {
+                    // Flushed right away, quitting doesn't give us a chance to do it later
+                    let result = lines.iter()
+                        .try_for_each(|line| writeln!(file, "{}", line))
+                        .and_then(|_| file.flush());
+                    if let Err(error) = result {
+                        eprintln!("error: couldn't write the recording, stopping it: {}", error);
+                        *self = Session::Live;
+                    }
+                }
+                Vec::new()
+            }
+            Session::Replaying { events } => {
+                let mut due = Vec::new();
+                while events.front().map(|(handled_by, _)| *handled_by <= interaction).unwrap_or(false) {
+                    if let Some((_, event)) = events.pop_front() {
+                        due.push(event);
+                    }
+                }
+
+                if events.is_empty() {
+                    eprintln!("replay finished, back to live input");
+                    *self = Session::Live;
+                }
+                due
+            }
+        }
+    }
+}
+
+// One event per line: the interaction, what happened and its details, e.g. "120 key W pressed"
+fn encode(interaction: u64, event: &Event) -> Option<String> {
+    let description = match event {
+        Event::Mouse(mouse::Event::CursorMoved { x, y }) => { format!("cursor {} {}", x, y) }
+        Event::Mouse(mouse::Event::WheelScrolled { delta_x, delta_y }) => {
+            format!("wheel {} {}", delta_x, delta_y)
+        }
+        Event::Mouse(mouse::Event::Input { button, state }) => {
+            let button = match button {
+                mouse::Button::Left => { String::from("Left") }
+                mouse::Button::Right => { String::from("Right") }
+                mouse::Button::Middle => { String::from("Middle") }
+                mouse::Button::Other(other) => { other.to_string() }
+            };
+            format!("mouse {} {}", button, state_name(*state))
+        }
+        Event::Keyboard(keyboard::Event::Input { key_code, state }) => {
+            format!("key {} {}", key_name(*key_code)?, state_name(*state))
+        }
+        Event::Keyboard(keyboard::Event::TextEntered { character }) => {
+            format!("text {}", *character as u32)
+        }
+        // Gamepads and window events aren't recorded
+        _ => { return None; }
+    };
+    Some(format!("{} {}", interaction, description))
+}
+
+fn decode(line: &str) -> Result<(u64, Event), String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let interaction = parts.get(0)
+        .and_then(|interaction| interaction.parse::<u64>().ok())
+        .ok_or_else(|| String::from("expected an interaction"))?;
+    let float = |i: usize| {
+        parts.get(i)
+            .and_then(|value| value.parse::<f32>().ok())
+            .ok_or_else(|| format!("expected a number in `{}`", line))
+    };
+    let state = |i: usize| {
+        match parts.get(i) {
+            Some(&"pressed") => { Ok(ButtonState::Pressed) }
+            Some(&"released") => { Ok(ButtonState::Released) }
+            _ => { Err(format!("expected pressed or released in `{}`", line)) }
+        }
+    };
+
+    let event = match parts.get(1) {
+        Some(&"cursor") => { Event::Mouse(mouse::Event::CursorMoved { x: float(2)?, y: float(3)? }) }
+        Some(&"wheel") => {
+            Event::Mouse(mouse::Event::WheelScrolled { delta_x: float(2)?, delta_y: float(3)? })
+        }
+        Some(&"mouse") => {
+            let button = match parts.get(2) {
+                Some(&"Left") => { mouse::Button::Left }
+                Some(&"Right") => { mouse::Button::Right }
+                Some(&"Middle") => { mouse::Button::Middle }
+                Some(other) => {
+                    mouse::Button::Other(other.parse().map_err(|_| format!("unknown mouse button `{}`", other))?)
+                }
+                None => { return Err(format!("expected a mouse button in `{}`", line)); }
+            };
+            Event::Mouse(mouse::Event::Input { button, state: state(3)? })
+        }
+        Some(&"key") => {
+            let name = parts.get(2).ok_or_else(|| format!("expected a key in `{}`", line))?;
+            let key_code = key_from_name(name).ok_or_else(|| format!("unknown key `{}`", name))?;
+            Event::Keyboard(keyboard::Event::Input { key_code, state: state(3)? })
+        }
+        Some(&"text") => {
+            let character = parts.get(2)
+                .and_then(|code| code.parse::<u32>().ok())
+                .and_then(std::char::from_u32)
+                .ok_or_else(|| format!("expected a character code in `{}`", line))?;
+            Event::Keyboard(keyboard::Event::TextEntered { character })
+        }
+        _ => { return Err(format!("unknown event `{}`", line)); }
+    };
+    Ok((interaction, event))
+}
+
+fn state_name(state: ButtonState) -> &'static str {
+    match state {
+        ButtonState::Pressed => { "pressed" }
+        ButtonState::Released => { "released" }
+    }
+}
@@ -0,0 +1,58 @@
+use polar_oxides::classes::Classification;
+use polar_oxides::particles::Particles;
+use polar_oxides::view::{Configs, DisplayMode};
+
+// What a rose depends on, for telling when to count it again: which numbers are drawn, the angles
+// they're drawn at and how many slices there are. Unlike the other panels it doesn't depend on the
+// camera.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RoseFilter {
+    pub display_mode: DisplayMode,
+    pub min_number: u64,
+    pub reveal_cutoff: u64,
+    // With `highlighted_only`, the primes the classification doesn't pick out aren't counted
+    pub classification: Classification,
+    pub highlighted_only: bool,
+    pub angle_factor: f32,
+    pub bins: usize,
+}
+
+impl RoseFilter {
+    pub fn new(configs: &Configs, bins: usize) -> RoseFilter {
+        RoseFilter {
+            display_mode: configs.display_mode,
+            min_number: configs.min_number,
+            reveal_cutoff: configs.reveal_cutoff,
+            classification: configs.classification,
+            highlighted_only: configs.highlighted_only,
+            angle_factor: configs.angle_factor,
+            bins,
+        }
+    }
+}
+
+// How many numbers sit at each angle around the spiral, for the rose diagram
+pub struct Rose {
+    pub filter: RoseFilter,
+    // Every number the display mode shows
+    pub numbers: Vec<u64>,
+    // And the primes among them, when those aren't all of them already
+    pub primes: Option<Vec<u64>>,
+}
+
+impl Rose {
+    // What `configs` draws, which `filter` has to have been made from
+    pub fn new(particles: &Particles, configs: &Configs, filter: RoseFilter) -> Rose {
+        let numbers = particles.angle_counts(filter.bins, filter.angle_factor, |number| {
+            configs.shows_number(particles, number, particles.is_prime(number))
+        });
+        let primes = if filter.display_mode == DisplayMode::All {
+            Some(particles.angle_counts(filter.bins, filter.angle_factor, |number| {
+                particles.is_prime(number) && configs.shows_number(particles, number, true)
+            }))
+        } else {
+            None
+        };
+        Rose { filter, numbers, primes }
+    }
+}
@@ -0,0 +1,48 @@
+use std::io;
+
+use chrono::Local;
+use coffee::graphics::{Color, Sprite};
+use image::{Rgba, RgbaImage};
+
+// Coffee can't read back what it drew to the window, so screenshots draw the same sprites
+// again on the CPU. `atlas` is the palette image the sprites' sources point into.
+pub fn render(width: u32, height: u32, background: Color, atlas: &[Color], sprites: &[Sprite]) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(width, height, Rgba(background.to_rgba()));
+
+    for sprite in sprites {
+        let color = match atlas.get(sprite.source.x as usize) {
+            Some(color) => { Rgba(color.to_rgba()) }
+            None => { continue; }
+        };
+
+        // Every sprite covers at least a pixel, like it does on the GPU
+        let left = sprite.position.x.round();
+        let top = sprite.position.y.round();
+        let right = (sprite.position.x + sprite.scale.0).round().max(left + 1.0);
+        let bottom = (sprite.position.y + sprite.scale.1).round().max(top + 1.0);
+
+        let left = left.max(0.0).min(width as f32) as u32;
+        let top = top.max(0.0).min(height as f32) as u32;
+        let right = right.max(0.0).min(width as f32) as u32;
+        let bottom = bottom.max(0.0).min(height as f32) as u32;
+
+        for y in top..bottom {
+            for x in left..right {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+    image
+}
+
+// Saves to the working directory, named after the current time. Returns the file name.
+pub fn save(image: &RgbaImage) -> io::Result<String> {
+    let file_name = file_name("png");
+    image.save(&file_name)?;
+    Ok(file_name)
+}
+
+// A file name with the current time in it, so saving again doesn't overwrite anything
+pub fn file_name(extension: &str) -> String {
+    format!("polar-oxides-{}.{}", Local::now().format("%Y-%m-%dT%H-%M-%S"), extension)
+}
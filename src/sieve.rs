@@ -0,0 +1,439 @@
+//! Finding primes
+
+use rayon::prelude::*;
+
+use crate::primality::{self, Primality};
+
+/// Which numbers in `start..end` are prime, one bit per number: bit `i % 64` of word `i / 64` is
+/// set when `start + i` is prime. Worked out in segments small enough to stay in cache, in parallel.
+/// A hundred million numbers take 12.5MB.
+#[derive(Clone)]
+pub struct Sieve {
+    words: Vec<u64>,
+    start: u64,
+    end: u64,
+}
+
+impl Sieve {
+    const SEGMENT_WORDS: usize = 4096; // 32KiB
+    const BYTES_VERSION: u8 = 1;
+    /// How many numbers are sieved at a time, ranges smaller than this aren't worth splitting
+    pub const SEGMENT_NUMBERS: u64 = Self::SEGMENT_WORDS as u64 * 64;
+
+    /// The numbers below `limit`
+    pub fn new(limit: u64) -> Sieve {
+        Self::range(0, limit)
+    }
+
+    /// The numbers in `start..end`. `start` has to be a multiple of 64, so ranges next to each
+    /// other line up.
+    pub fn range(start: u64, end: u64) -> Sieve {
+        assert!(start % 64 == 0, "sieve ranges start at multiples of 64");
+        let end = end.max(start);
+        let word_count = ((end - start + 63) / 64) as usize;
+        // Every composite below `end` has a factor no bigger than its square root
+        let base_primes = Self::small_primes((end as f64).sqrt() as u64 + 1);
+
+        let mut words = vec![!0u64; word_count];
+        words.par_chunks_mut(Self::SEGMENT_WORDS)
+            .enumerate()
+            .for_each(|(segment, chunk)| {
+                let segment_start = start + (segment * Self::SEGMENT_WORDS * 64) as u64;
+                let segment_end = segment_start + chunk.len() as u64 * 64;
+                for &prime in &base_primes {
+                    // Smaller multiples were crossed out by smaller primes already
+                    let mut multiple = (prime * prime).max((segment_start + prime - 1) / prime * prime);
+                    while multiple < segment_end {
+                        let bit = multiple - segment_start;
+                        chunk[(bit / 64) as usize] &= !(1 << (bit % 64));
+                        multiple += prime;
+                    }
+                }
+            });
+
+        let mut sieve = Sieve { words, start, end };
+        if start == 0 {
+            for number in 0..2.min(end) {
+                sieve.words[0] &= !(1 << number);
+            }
+        }
+        // The last word goes past the end
+        let tail = (end - start) % 64;
+        if tail != 0 {
+            if let Some(last) = sieve.words.last_mut() {
+                *last &= (1 << tail) - 1;
+            }
+        }
+        sieve
+    }
+
+    /// The range and its bits as bytes, to keep them on disk: a format version, the start and the
+    /// end, the words, and a checksum of all of that, little endian
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + 8 * (self.words.len() + 3));
+        bytes.push(Self::BYTES_VERSION);
+        bytes.extend_from_slice(&self.start.to_le_bytes());
+        bytes.extend_from_slice(&self.end.to_le_bytes());
+        for word in &self.words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    /// The inverse of `to_bytes`. Bytes from another version of it, or damaged since, are an error.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Sieve, String> {
+        if bytes.len() < 25 || (bytes.len() - 25) % 8 != 0 {
+            return Err(format!("{} bytes can't be a sieve", bytes.len()));
+        }
+        let (contents, checksum_bytes) = bytes.split_at(bytes.len() - 8);
+        if checksum(contents) != read_u64(checksum_bytes) {
+            return Err(String::from("the checksum doesn't match"));
+        }
+        if contents[0] != Self::BYTES_VERSION {
+            return Err(format!("unknown version {}", contents[0]));
+        }
+
+        let start = read_u64(&contents[1..9]);
+        let end = read_u64(&contents[9..17]);
+        let words: Vec<u64> = contents[17..].chunks(8).map(read_u64).collect();
+        if start % 64 != 0 || end < start || words.len() as u64 != (end - start + 63) / 64 {
+            return Err(format!("{} words don't make the range {}..{}", words.len(), start, end));
+        }
+        Ok(Sieve { words, start, end })
+    }
+
+    /// Puts a range that picks up where this one ends at its end. It has to start no later than
+    /// `aligned_end`, anything of this range from its start on is replaced with it.
+    pub fn append(&mut self, other: Sieve) {
+        assert!(
+            other.start >= self.start && other.start <= self.aligned_end(),
+            "appended sieve ranges have to line up",
+        );
+        self.words.truncate(((other.start - self.start) / 64) as usize);
+        self.words.extend(other.words);
+        self.end = other.end;
+    }
+
+    /// Where a range has to start to be appended to this one: the end, or the multiple of 64 right
+    /// before it, so the numbers sharing a word with the end get sieved again along with the rest
+    pub fn aligned_end(&self) -> u64 {
+        self.start + (self.end - self.start) / 64 * 64
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// The same as `range`, but testing every number on its own with Miller–Rabin instead of
+    /// crossing out multiples
+    pub fn tested(start: u64, end: u64) -> Sieve {
+        assert!(start % 64 == 0, "sieve ranges start at multiples of 64");
+        let end = end.max(start);
+        let word_count = ((end - start + 63) / 64) as usize;
+
+        let words = (0..word_count).into_par_iter()
+            .map(|i| {
+                let word_start = start + i as u64 * 64;
+                (0..64.min(end - word_start))
+                    .filter(|&bit| primality::is_prime(word_start + bit))
+                    .fold(0, |word, bit| word | 1 << bit)
+            })
+            .collect();
+        Sieve { words, start, end }
+    }
+
+    /// Whichever of `range` and `tested` `primality` says
+    pub fn with(primality: Primality, start: u64, end: u64) -> Sieve {
+        match primality {
+            Primality::Sieve => { Self::range(start, end) }
+            Primality::MillerRabin => { Self::tested(start, end) }
+        }
+    }
+
+    // A plain sieve for the primes below `limit`, to cross out the segments with
+    fn small_primes(limit: u64) -> Vec<u64> {
+        let mut is_prime = vec![true; limit as usize];
+        let mut primes = Vec::new();
+        for number in 2..limit {
+            if is_prime[number as usize] {
+                primes.push(number);
+                let mut multiple = number * number;
+                while multiple < limit {
+                    is_prime[multiple as usize] = false;
+                    multiple += number;
+                }
+            }
+        }
+        primes
+    }
+
+    /// Numbers outside of the range aren't prime as far as we know
+    pub fn is_prime(&self, number: u64) -> bool {
+        if number < self.start || number >= self.end {
+            return false;
+        }
+        let bit = number - self.start;
+        self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+    }
+
+    /// The smallest prime in the range bigger than `number`
+    pub fn next_prime(&self, number: u64) -> Option<u64> {
+        let from = number.saturating_add(1).max(self.start);
+        if from >= self.end {
+            return None;
+        }
+
+        let bit = from - self.start;
+        let mut index = (bit / 64) as usize;
+        // Without the bits before `from`
+        let mut word = self.words[index] & (!0 << (bit % 64));
+        while word == 0 {
+            index += 1;
+            word = *self.words.get(index)?;
+        }
+        Some(self.start + index as u64 * 64 + u64::from(word.trailing_zeros()))
+    }
+
+    /// The biggest prime in the range smaller than `number`
+    pub fn previous_prime(&self, number: u64) -> Option<u64> {
+        let until = number.min(self.end);
+        if until <= self.start {
+            return None;
+        }
+
+        let bit = until - 1 - self.start;
+        let mut index = (bit / 64) as usize;
+        // Without the bits after `until - 1`
+        let mut word = self.words[index] & (!0 >> (63 - bit % 64));
+        while word == 0 {
+            index = index.checked_sub(1)?;
+            word = self.words[index];
+        }
+        Some(self.start + index as u64 * 64 + 63 - u64::from(word.leading_zeros()))
+    }
+
+    /// How many primes there are in the range, without listing them
+    pub fn prime_count(&self) -> u64 {
+        self.words.par_iter().map(|word| u64::from(word.count_ones())).sum()
+    }
+
+    /// How many primes there are in the range up to each of `numbers`, included, counted in one
+    /// pass over the bits. `numbers` have to be in order.
+    pub fn primes_up_to(&self, numbers: &[u64]) -> Vec<u64> {
+        let (mut word, mut count) = (0, 0);
+        numbers.iter()
+            .map(|&number| {
+                let bits = number.saturating_add(1).max(self.start).min(self.end) - self.start;
+                let full_words = (bits / 64) as usize;
+                while word < full_words {
+                    count += u64::from(self.words[word].count_ones());
+                    word += 1;
+                }
+                let rest = bits % 64;
+                if rest == 0 {
+                    count
+                } else {
+                    count + u64::from((self.words[full_words] & ((1 << rest) - 1)).count_ones())
+                }
+            })
+            .collect()
+    }
+
+    /// The biggest gap between primes next to each other in the range, 0 with fewer than two
+    pub fn max_gap(&self) -> u64 {
+        let (mut previous, mut max_gap) = (None, 0);
+        for (i, &word) in self.words.iter().enumerate() {
+            let mut word = word;
+            while word != 0 {
+                let prime = self.start + i as u64 * 64 + u64::from(word.trailing_zeros());
+                if let Some(previous) = previous {
+                    max_gap = max_gap.max(prime - previous);
+                }
+                previous = Some(prime);
+                word &= word - 1;
+            }
+        }
+        max_gap
+    }
+
+    /// Every prime in the range, in order
+    pub fn primes(&self) -> Vec<u64> {
+        let start = self.start;
+        self.words.par_iter()
+            .enumerate()
+            .flat_map(|(i, &word)| {
+                let mut word = word;
+                let mut primes = Vec::with_capacity(word.count_ones() as usize);
+                while word != 0 {
+                    primes.push(start + i as u64 * 64 + u64::from(word.trailing_zeros()));
+                    word &= word - 1;
+                }
+                primes
+            })
+            .collect()
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut array = [0; 8];
+    array.copy_from_slice(bytes);
+    u64::from_le_bytes(array)
+}
+
+// 64-bit FNV-1a, enough to notice a damaged file
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_primes_below_powers_of_ten() {
+        // π(10^n)
+        let counts = [(10, 4), (100, 25), (1_000, 168), (10_000, 1_229), (100_000, 9_592), (1_000_000, 78_498)];
+        for &(limit, count) in &counts {
+            let sieve = Sieve::new(limit);
+            assert_eq!(sieve.primes().len(), count, "primes below {}", limit);
+            assert_eq!(sieve.prime_count(), count as u64, "primes below {}", limit);
+        }
+    }
+
+    #[test]
+    fn counts_the_primes_up_to_numbers() {
+        let sieve = Sieve::range(640, 10_000);
+        let primes = sieve.primes();
+        let numbers = [0, 640, 641, 700, 1_000, 1_024, 5_000, 9_999, 10_000, 50_000];
+        let expected: Vec<u64> = numbers.iter()
+            .map(|&number| primes.iter().filter(|&&prime| prime <= number).count() as u64)
+            .collect();
+        assert_eq!(sieve.primes_up_to(&numbers), expected);
+        assert_eq!(Sieve::new(1_000_000).primes_up_to(&[1_000_000]), vec![78_498]);
+    }
+
+    #[test]
+    fn knows_which_numbers_are_prime() {
+        let sieve = Sieve::new(1_000_000);
+        for &prime in &[2, 3, 5, 97, 7_919, 104_729, 999_983] {
+            assert!(sieve.is_prime(prime), "{} is prime", prime);
+        }
+        for &composite in &[0, 1, 4, 91, 561, 7_917, 104_730, 999_981] {
+            assert!(!sieve.is_prime(composite), "{} isn't prime", composite);
+        }
+        // Past the end
+        assert!(!sieve.is_prime(1_000_003));
+    }
+
+    #[test]
+    fn ranges_line_up() {
+        let whole = Sieve::new(1_000_003).primes();
+        let step = 64 * 777;
+        let parts: Vec<u64> = (0..1_000_003).step_by(step as usize)
+            .flat_map(|start| Sieve::range(start, (start + step).min(1_000_003)).primes())
+            .collect();
+        assert_eq!(parts, whole);
+    }
+
+    #[test]
+    fn finds_neighboring_primes() {
+        let sieve = Sieve::new(1_000);
+        assert_eq!(sieve.next_prime(0), Some(2));
+        assert_eq!(sieve.next_prime(2), Some(3));
+        assert_eq!(sieve.next_prime(113), Some(127));
+        assert_eq!(sieve.next_prime(990), Some(991));
+        assert_eq!(sieve.next_prime(997), None);
+        assert_eq!(sieve.previous_prime(2), None);
+        assert_eq!(sieve.previous_prime(3), Some(2));
+        assert_eq!(sieve.previous_prime(127), Some(113));
+        assert_eq!(sieve.previous_prime(5_000), Some(997));
+
+        // Every gap, crossing words both ways
+        let primes = sieve.primes();
+        for pair in primes.windows(2) {
+            assert_eq!(sieve.next_prime(pair[0]), Some(pair[1]));
+            assert_eq!(sieve.previous_prime(pair[1]), Some(pair[0]));
+        }
+    }
+
+    #[test]
+    fn finds_the_biggest_gap() {
+        // From 887 to 907
+        assert_eq!(Sieve::new(1_000).max_gap(), 20);
+        assert_eq!(Sieve::range(1_280, 1_400).max_gap(), 34);
+        assert_eq!(Sieve::new(3).max_gap(), 0);
+        assert_eq!(Sieve::new(4).max_gap(), 1);
+    }
+
+    #[test]
+    fn appended_ranges_are_the_whole_range() {
+        let mut sieve = Sieve::new(0);
+        for start in (0..100_000).step_by(64 * 100) {
+            sieve.append(Sieve::range(start, (start + 64 * 100).min(100_000)));
+        }
+        assert_eq!((sieve.start(), sieve.end()), (0, 100_000));
+        assert_eq!(sieve.primes(), Sieve::new(100_000).primes());
+    }
+
+    #[test]
+    fn ranges_can_be_extended_past_ends_that_are_not_aligned() {
+        let mut sieve = Sieve::new(50_000);
+        assert_eq!(sieve.aligned_end(), 49_984);
+        sieve.append(Sieve::range(sieve.aligned_end(), 100_000));
+        assert_eq!((sieve.start(), sieve.end()), (0, 100_000));
+        assert_eq!(sieve.primes(), Sieve::new(100_000).primes());
+
+        // Starting over from the beginning replaces everything
+        sieve.append(Sieve::new(1_000));
+        assert_eq!(sieve.primes(), Sieve::new(1_000).primes());
+    }
+
+    #[test]
+    fn testing_every_number_finds_the_same_primes() {
+        for &(start, end) in &[(0, 100_000), (1_000_000_000 / 64 * 64, 1_000_050_000), (64 * 5, 64 * 5 + 63)] {
+            let (sieved, tested) = (Sieve::range(start, end), Sieve::tested(start, end));
+            assert_eq!(tested.words, sieved.words, "{}..{}", start, end);
+        }
+        assert!(Sieve::tested(0, 2).primes().is_empty());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        for &(start, end) in &[(0, 0), (0, 1_000), (64 * 100, 50_001)] {
+            let sieve = Sieve::range(start, end);
+            let read = Sieve::from_bytes(&sieve.to_bytes()).unwrap();
+            assert_eq!((read.start, read.end, read.words), (sieve.start, sieve.end, sieve.words));
+        }
+    }
+
+    #[test]
+    fn damaged_bytes_are_errors() {
+        let bytes = Sieve::new(10_000).to_bytes();
+        assert!(Sieve::from_bytes(&bytes[..bytes.len() - 8]).is_err());
+        assert!(Sieve::from_bytes(&bytes[..10]).is_err());
+
+        let mut flipped = bytes.clone();
+        flipped[100] ^= 4;
+        assert!(Sieve::from_bytes(&flipped).is_err());
+
+        // A different version with a checksum that matches
+        let mut newer = bytes[..bytes.len() - 8].to_vec();
+        newer[0] = Sieve::BYTES_VERSION + 1;
+        let checksum = checksum(&newer);
+        newer.extend_from_slice(&checksum.to_le_bytes());
+        assert!(Sieve::from_bytes(&newer).is_err());
+    }
+
+    #[test]
+    fn tiny_limits() {
+        assert!(Sieve::new(0).primes().is_empty());
+        assert!(Sieve::new(2).primes().is_empty());
+        assert_eq!(Sieve::new(3).primes(), vec![2]);
+    }
+}
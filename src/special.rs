@@ -0,0 +1,70 @@
+//! The few primes of a famous form, marked on the spiral one by one
+
+use crate::particles::Particles;
+
+/// A prime of a famous form. There's only a handful of each below 2^64.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpecialPrime {
+    /// 2^k - 1, with the exponent k
+    Mersenne(u32),
+    /// 2^(2^k) + 1, with the k
+    Fermat(u32),
+}
+
+impl SpecialPrime {
+    pub fn number(self) -> u64 {
+        match self {
+            SpecialPrime::Mersenne(exponent) => { (1 << exponent) - 1 }
+            SpecialPrime::Fermat(index) => { (1 << (1 << index)) + 1 }
+        }
+    }
+
+    /// What's written next to its marker
+    pub fn label(self) -> String {
+        match self {
+            SpecialPrime::Mersenne(exponent) => { format!("2^{} - 1", exponent) }
+            SpecialPrime::Fermat(index) => { format!("2^{} + 1", 1 << index) }
+        }
+    }
+}
+
+/// The Mersenne and Fermat primes in the spiral, smallest first. 3 is both, and comes up once as
+/// each.
+pub fn special_primes(particles: &Particles) -> Vec<SpecialPrime> {
+    // 2^63 - 1 and 2^32 + 1 are the last of each form a u64 holds
+    let candidates = (2..64).map(SpecialPrime::Mersenne).chain((0..6).map(SpecialPrime::Fermat));
+    let mut special: Vec<SpecialPrime> = candidates
+        .filter(|special| {
+            let number = special.number();
+            number >= particles.start() && number <= particles.last_number() && particles.is_prime(number)
+        })
+        .collect();
+    special.sort_by_key(|special| special.number());
+    special
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::primality::Primality;
+
+    #[test]
+    fn the_special_primes_are_the_ones_in_the_spiral() {
+        let numbers = |particles: &Particles| {
+            special_primes(particles).into_iter().map(SpecialPrime::number).collect::<Vec<_>>()
+        };
+        let particles = Particles::find(100_000);
+        assert_eq!(numbers(&particles), vec![3, 3, 5, 7, 17, 31, 127, 257, 8191, 65537]);
+        let particles = Particles::find_between(192, 10_000, Primality::Sieve);
+        assert_eq!(numbers(&particles), vec![257, 8191]);
+    }
+
+    #[test]
+    fn labels_have_the_exponent() {
+        assert_eq!(SpecialPrime::Mersenne(13).label(), "2^13 - 1");
+        assert_eq!(SpecialPrime::Fermat(4).label(), "2^16 + 1");
+        assert_eq!(SpecialPrime::Fermat(5).number(), 4_294_967_297);
+        assert_eq!(SpecialPrime::Mersenne(63).number(), u64::max_value() / 2);
+    }
+}
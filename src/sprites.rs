@@ -0,0 +1,260 @@
+use coffee::graphics::{Point, Rectangle, Sprite, Vector};
+use rayon::prelude::*;
+
+use polar_oxides::dataset::{Dataset, Points};
+use polar_oxides::digits::DigitalRoots;
+use polar_oxides::factors::Factors;
+use polar_oxides::palette::Palette;
+use polar_oxides::particles::Particles;
+use polar_oxides::view::{Coloring, Configs, Settings};
+
+// What `source_of` needs of the particles' layers, as far as the coloring goes by them
+#[derive(Clone, Copy)]
+pub struct Layers<'a> {
+    pub factors: Option<&'a Factors>,
+    pub roots: Option<&'a DigitalRoots>,
+}
+
+// How much bigger an overlay's markers are than the bigger of the points
+const OVERLAY_GROWTH: f32 = 1.75;
+
+// Every prime, or every composite, that is visible in a window of the given size, along with where
+// it is in the spiral.
+pub fn visible(particles: &Particles, configs: Configs, settings: Settings, width: f32, height: f32, primes: bool)
+    -> impl ParallelIterator<Item = (u64, Point)> + '_
+{
+    visible_points(particles, Some(particles), configs, settings, width, height, primes)
+}
+
+// The same for any points. `numbers` are the points as numbers, when they are those, for what only
+// means something for them.
+fn visible_points<'a, D: Dataset>(
+    dataset: &'a D,
+    numbers: Option<&'a Particles>,
+    configs: Configs,
+    settings: Settings,
+    width: f32,
+    height: f32,
+    primes: bool,
+) -> impl ParallelIterator<Item = (u64, Point)> + 'a {
+    let (scale, _) = appearance(configs, primes);
+    let (inner, outer) = configs.radii_in_frame(settings, scale, width, height);
+
+    dataset.range_within(inner, outer).into_par_iter()
+        .filter(move |&number| {
+            dataset.is_prime(number) == primes
+                && match numbers {
+                    Some(numbers) => { configs.shows_number(numbers, number, primes) }
+                    None => { configs.draws(number) && configs.display_mode.shows(primes) }
+                }
+        })
+        .map(move |number| (number, dataset.position_of(number, configs.angle_factor)))
+        .filter(move |&(_, position)| configs.is_in_frame(settings, position, scale, width, height))
+}
+
+// The sprites of the points `visible` finds, relative to the middle of the window
+pub fn sprites(points: &Points, configs: Configs, settings: Settings, width: f32, height: f32, primes: bool)
+    -> Vec<Sprite>
+{
+    match points {
+        Points::Numbers(particles) => {
+            sprites_of(&**particles, Some(particles), configs, settings, width, height, primes)
+        }
+        Points::Gaussian(gaussian, _) => { sprites_of(&**gaussian, None, configs, settings, width, height, primes) }
+    }
+}
+
+fn sprites_of<D: Dataset>(
+    dataset: &D,
+    numbers: Option<&Particles>,
+    configs: Configs,
+    settings: Settings,
+    width: f32,
+    height: f32,
+    primes: bool,
+) -> Vec<Sprite> {
+    let pixel_rate = settings.pixel_rate(configs.zoom_level);
+    // The offset is kept in spiral units, so it has to be scaled like the particles
+    let offset_vector = Vector::new(configs.offset.0, configs.offset.1) * pixel_rate;
+    let (scale, color) = appearance(configs, primes);
+    // Worked out here if nothing needed them before
+    let factors = numbers.filter(|_| configs.coloring.needs_factors()).map(Particles::factors);
+    let roots = numbers.filter(|_| configs.coloring.needs_digits())
+        .map(|numbers| numbers.digital_roots(configs.digit_base));
+    let layers = Layers {
+        factors: factors.as_ref().map(|factors| &**factors),
+        roots: roots.as_ref().map(|roots| &**roots),
+    };
+
+    visible_points(dataset, numbers, configs, settings, width, height, primes)
+        .map(|(number, position)| Sprite {
+            source: Rectangle {
+                x: source_of(numbers, layers, configs, number, primes, color),
+                y: 0,
+                width: 1,
+                height: 1,
+            },
+            position: position * pixel_rate + offset_vector - half_size(scale),
+            scale: (scale, scale)
+        })
+        .collect()
+}
+
+// Markers over the numbers of a sparse set that are in frame, each in the color `source` gives it,
+// relative to the middle of the window like `sprites`. `numbers` gives the set's numbers in a range,
+// smallest first. They're a bit bigger than the points so they show around whichever point is under them, and
+// there whatever the display mode hides.
+pub fn overlay_sprites<D, F, S>(
+    particles: &D,
+    configs: Configs,
+    settings: Settings,
+    width: f32,
+    height: f32,
+    numbers: F,
+    source: S,
+) -> Vec<Sprite>
+where
+    D: Dataset,
+    F: FnOnce(u64, u64) -> Vec<u64>,
+    S: Fn(u64) -> u16 + Sync,
+{
+    let pixel_rate = settings.pixel_rate(configs.zoom_level);
+    let offset_vector = Vector::new(configs.offset.0, configs.offset.1) * pixel_rate;
+    let scale = configs.prime_scale.max(configs.composite_scale) * OVERLAY_GROWTH;
+    let (inner, outer) = configs.radii_in_frame(settings, scale, width, height);
+    let range = particles.range_within(inner, outer);
+    if range.start() > range.end() {
+        return Vec::new();
+    }
+    numbers(*range.start(), range.end().saturating_add(1)).into_par_iter()
+        .filter(|&number| configs.draws(number))
+        .map(|number| (number, particles.position_of(number, configs.angle_factor)))
+        .filter(|&(_, position)| configs.is_in_frame(settings, position, scale, width, height))
+        .map(|(number, position)| Sprite {
+            source: Rectangle { x: source(number), y: 0, width: 1, height: 1 },
+            position: position * pixel_rate + offset_vector - half_size(scale),
+            scale: (scale, scale),
+        })
+        .collect()
+}
+
+// Everything the display mode shows, the way `draw` puts it on screen: composites first with the
+// primes on top, relative to the top left corner. For drawing on the CPU.
+pub fn frame_sprites(points: &Points, configs: Configs, settings: Settings, width: f32, height: f32) -> Vec<Sprite> {
+    let mut frame_sprites = frame_sprites_of(points, configs, settings, width, height, false);
+    frame_sprites.extend(frame_sprites_of(points, configs, settings, width, height, true));
+    frame_sprites
+}
+
+// The primes', or the composites', part of `frame_sprites`. Empty when the display mode hides them.
+pub fn frame_sprites_of(
+    points: &Points,
+    configs: Configs,
+    settings: Settings,
+    width: f32,
+    height: f32,
+    primes: bool,
+) -> Vec<Sprite> {
+    if !configs.display_mode.shows(primes) {
+        return Vec::new();
+    }
+    let center = Vector::new(width / 2.0, height / 2.0);
+    let mut sprites = sprites(points, configs, settings, width, height, primes);
+    sprites.par_iter_mut().for_each(|sprite| sprite.position += center);
+    sprites
+}
+
+// Every number the display mode shows in a window of the given size, in order
+pub fn frame_numbers(particles: &Particles, configs: Configs, settings: Settings, width: f32, height: f32)
+    -> Vec<u64>
+{
+    let mut numbers = Vec::new();
+    for &primes in &[false, true] {
+        if configs.display_mode.shows(primes) {
+            numbers.par_extend(visible(particles, configs, settings, width, height, primes).map(|(number, _)| number));
+        }
+    }
+    numbers.par_sort_unstable();
+    numbers
+}
+
+// The size and the color of the primes' or the composites' points
+pub fn appearance(configs: Configs, primes: bool) -> (f32, u16) {
+    let scale = if primes { configs.prime_scale } else { configs.composite_scale };
+    (scale, Palette::source(configs.palette, configs.theme, primes))
+}
+
+// The color of a number's point: its residue's when the numbers are colored by those, for a prime
+// the gap after it's when they are colored by those, its count of prime factors', μ's or for a
+// composite its smallest factor's or a semiprime's when they are colored by those and it was
+// factored, its digital root's when they are colored by that, its class's when the classification
+// picks it out, `kind_color` otherwise. Points that aren't numbers, without `particles`, get
+// `kind_color`. `layers` are the particles', for the colorings that need them.
+pub fn source_of(
+    particles: Option<&Particles>,
+    layers: Layers,
+    configs: Configs,
+    number: u64,
+    is_prime: bool,
+    kind_color: u16,
+) -> u16 {
+    let particles = match particles {
+        Some(particles) => { particles }
+        None => { return kind_color; }
+    };
+    if configs.residues {
+        return Palette::residue_source(configs.theme, is_prime, number % configs.modulus);
+    }
+    if configs.gap_colors && is_prime {
+        // The last prime's gap is past the end
+        if let Some(next) = particles.next_prime(number) {
+            return Palette::gap_source(configs.theme, next - number, particles.max_gap());
+        }
+    }
+    if let Some(roots) = layers.roots {
+        if configs.coloring == Coloring::DigitalRoot || is_prime {
+            // 0 has none
+            match roots.root(number) {
+                Some(0) | None => {}
+                Some(root) => { return Palette::digital_root_source(configs.theme, is_prime, root); }
+            }
+        }
+    }
+    if let Some(factors) = layers.factors {
+        match configs.coloring {
+            Coloring::Kinds | Coloring::DigitalRoot | Coloring::PrimeDigitalRoot => {}
+            // 0 and 1 have none
+            Coloring::Omega => {
+                match factors.big_omega(number) {
+                    Some(0) | None => {}
+                    Some(omega) => { return Palette::omega_source(configs.theme, omega); }
+                }
+            }
+            Coloring::SmallestFactor => {
+                if let Some(factor) = factors.smallest_factor(number) {
+                    return Palette::smallest_factor_source(configs.theme, factor);
+                }
+            }
+            Coloring::Moebius => {
+                if let Some(mu) = factors.moebius(number) {
+                    return Palette::moebius_source(configs.theme, mu);
+                }
+            }
+            Coloring::Semiprimes => {
+                if factors.big_omega(number) == Some(2) {
+                    let square = factors.smallest_factor(number).map_or(false, |factor| factor * factor == number);
+                    return Palette::semiprime_source(configs.theme, square);
+                }
+            }
+        }
+    }
+    match configs.classification.class_of(particles, number) {
+        Some(class) => { Palette::class_source(configs.palette, configs.theme, class) }
+        None => { kind_color }
+    }
+}
+
+// Sprites are positioned by their corner, this is how much to move them so they are centered instead
+pub fn half_size(scale: f32) -> Vector {
+    Vector::new(scale / 2.0, scale / 2.0)
+}
@@ -0,0 +1,193 @@
+use rayon::prelude::*;
+
+use polar_oxides::dataset::Dataset;
+use polar_oxides::factors::Mertens;
+use polar_oxides::overlays::Overlay;
+use polar_oxides::particles::Particles;
+use polar_oxides::view::{Coloring, Configs, Settings};
+
+// What the statistics panel shows about the window
+pub struct Stats {
+    // The ones the display mode shows
+    pub numbers: u64,
+    pub primes: u64,
+    // How near and how far from the origin the window goes, and how many of the numbers in between,
+    // drawn or not, are prime
+    pub radii: (f32, f32),
+    pub density: f64,
+    pub largest_prime: Option<u64>,
+    // The two primes furthest apart with nothing prime between them, around a number in the window
+    pub largest_gap: Option<(u64, u64)>,
+    // Along the numbers at the window's radii, while they are colored by μ
+    pub mertens: Option<Mertens>,
+    // For each overlay shown, in order
+    pub overlays: Vec<OverlayCount>,
+}
+
+// How many of an overlay's numbers are drawn in the window, how many of those are prime and which
+// is the largest
+pub struct OverlayCount {
+    pub overlay: Overlay,
+    pub numbers: u64,
+    pub primes: u64,
+    pub largest: Option<u64>,
+}
+
+// Everything `Stats` is made of, added up a number at a time on rayon's threads
+#[derive(Clone, Copy, Default)]
+struct Totals {
+    numbers: u64,
+    primes: u64,
+    annulus_numbers: u64,
+    annulus_primes: u64,
+    largest_prime: Option<u64>,
+    largest_gap: Option<(u64, u64)>,
+}
+
+impl Totals {
+    fn merge(self, other: Totals) -> Totals {
+        Totals {
+            numbers: self.numbers + other.numbers,
+            primes: self.primes + other.primes,
+            annulus_numbers: self.annulus_numbers + other.annulus_numbers,
+            annulus_primes: self.annulus_primes + other.annulus_primes,
+            largest_prime: self.largest_prime.max(other.largest_prime),
+            largest_gap: widest(self.largest_gap, other.largest_gap),
+        }
+    }
+}
+
+// The first of the widest gaps, so which thread finds what first doesn't matter
+fn widest(a: Option<(u64, u64)>, b: Option<(u64, u64)>) -> Option<(u64, u64)> {
+    match (a, b) {
+        (Some(a), Some(b)) => { Some(if (b.1 - b.0, a.0) > (a.1 - a.0, b.0) { b } else { a }) }
+        (a, b) => { a.or(b) }
+    }
+}
+
+impl Stats {
+    // Only numbers at the window's radii can be in it, so only those are looked at
+    pub fn new(
+        particles: &Particles,
+        configs: Configs,
+        settings: Settings,
+        width: f32,
+        height: f32,
+        overlays: &[Overlay],
+    ) -> Stats {
+        let radii = configs.radii_in_window(settings, width, height);
+        let in_window = |number: u64| {
+            let position = particles.position_of(number, configs.angle_factor);
+            let point = configs.screen_position(settings, position, width, height);
+            point.x >= 0.0 && point.x <= width && point.y >= 0.0 && point.y <= height
+        };
+
+        let totals = particles.numbers_within(radii.0, radii.1)
+            .map(|number| {
+                let is_prime = particles.is_prime(number);
+                let mut totals = Totals { annulus_numbers: 1, annulus_primes: is_prime as u64, ..Totals::default() };
+                if !configs.draws(number) || !in_window(number) {
+                    return totals;
+                }
+
+                // A prime is at the end of one gap and the start of the next
+                let before = particles.previous_prime(number);
+                let after = particles.next_prime(number);
+                totals.largest_gap = if is_prime {
+                    widest(before.map(|before| (before, number)), after.map(|after| (number, after)))
+                } else {
+                    before.and_then(|before| Some((before, after?)))
+                };
+
+                if configs.shows_number(particles, number, is_prime) {
+                    totals.numbers = 1;
+                    totals.primes = is_prime as u64;
+                    totals.largest_prime = if is_prime { Some(number) } else { None };
+                }
+                totals
+            })
+            .reduce(Totals::default, Totals::merge);
+
+        let range = Dataset::range_within(particles, radii.0, radii.1);
+        let mertens = if configs.coloring == Coloring::Moebius {
+            particles.factors().mertens(range.clone())
+        } else {
+            None
+        };
+
+        // Drawn whatever the display mode, like the markers
+        let overlays = overlays.iter()
+            .map(|overlay| {
+                let numbers: Vec<u64> = overlay.numbers(*range.start(), range.end().saturating_add(1))
+                    .into_par_iter()
+                    .filter(|&number| configs.draws(number) && in_window(number))
+                    .collect();
+                OverlayCount {
+                    overlay: overlay.clone(),
+                    numbers: numbers.len() as u64,
+                    primes: numbers.iter().filter(|&&number| particles.is_prime(number)).count() as u64,
+                    largest: numbers.last().cloned(),
+                }
+            })
+            .collect();
+
+        Stats {
+            numbers: totals.numbers,
+            primes: totals.primes,
+            radii,
+            density: totals.annulus_primes as f64 / totals.annulus_numbers.max(1) as f64,
+            largest_prime: totals.largest_prime,
+            largest_gap: totals.largest_gap,
+            mertens,
+            overlays,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let gap = match self.largest_gap {
+            Some((before, after)) => { format!("{} ({} to {})", grouped(after - before), grouped(before), grouped(after)) }
+            None => { String::from("none") }
+        };
+        let lines = [
+            format!("Visible numbers: {}", grouped(self.numbers)),
+            format!("Visible primes: {}", grouped(self.primes)),
+            format!(
+                "Prime density: {:.2}% at radii {} to {}",
+                self.density * 100.0, grouped(self.radii.0 as u64), grouped(self.radii.1 as u64),
+            ),
+            format!("Largest prime: {}", self.largest_prime.map_or_else(|| String::from("none"), grouped)),
+            format!("Largest gap: {}", gap),
+        ];
+        let mut text = lines.join("\n");
+        if let Some(mertens) = self.mertens {
+            text.push_str(&format!(
+                "\nMertens: M({}) = {}, from {} to {} at these radii",
+                grouped(mertens.last), mertens.value, mertens.min, mertens.max,
+            ));
+        }
+        for count in &self.overlays {
+            let label = count.overlay.plural();
+            let mut first = label.chars();
+            let capitalized: String = first.next().into_iter().flat_map(char::to_uppercase).chain(first).collect();
+            let largest = count.largest.map_or_else(|| String::from("none"), grouped);
+            text.push_str(&format!(
+                "\n{}: {} ({} prime), largest {}",
+                capitalized, grouped(count.numbers), grouped(count.primes), largest,
+            ));
+        }
+        text
+    }
+}
+
+// With commas between the thousands, like 50,000,000
+pub fn grouped(number: u64) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use coffee::graphics::{Color, Sprite};
+
+use polar_oxides::dataset::Points;
+use polar_oxides::view::{Configs, Settings};
+
+use crate::sprites;
+
+// Zoomed out, composites can run into the millions, and so would the circles. Past this many,
+// only every so many of them are kept.
+pub const DEFAULT_MAX_COMPOSITES: usize = 200_000;
+
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("svg"))
+}
+
+// Every `n`th of `sprites`, `n` being just big enough to keep no more than `max` of them
+pub fn decimated(sprites: Vec<Sprite>, max: usize) -> Vec<Sprite> {
+    if sprites.len() <= max {
+        return sprites;
+    }
+    if max == 0 {
+        return Vec::new();
+    }
+    let step = (sprites.len() + max - 1) / max;
+    sprites.into_iter().step_by(step).collect()
+}
+
+// `sprites::frame_sprites`, with the composites cut down to `max_composites`
+pub fn frame_sprites(
+    points: &Points,
+    configs: Configs,
+    settings: Settings,
+    width: f32,
+    height: f32,
+    max_composites: usize,
+) -> Vec<Sprite> {
+    let composites = sprites::frame_sprites_of(points, configs, settings, width, height, false);
+    let mut frame_sprites = decimated(composites, max_composites);
+    frame_sprites.extend(sprites::frame_sprites_of(points, configs, settings, width, height, true));
+    frame_sprites
+}
+
+// The same sprites `screenshot::render` draws, as circles in a `width`x`height` picture. Each run of
+// sprites with the same color goes in a group that has the fill, written as it goes so a big
+// picture never has to be in memory as text. Returns how many circles were written.
+pub fn write(path: &Path, width: u32, height: u32, background: Color, atlas: &[Color], sprites: &[Sprite])
+    -> io::Result<usize>
+{
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(
+        file,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        width, height,
+    )?;
+    writeln!(file, r#"<rect width="100%" height="100%" {}/>"#, fill(background))?;
+
+    let mut circles = 0;
+    let mut group_source = None;
+    for sprite in sprites {
+        let color = match atlas.get(sprite.source.x as usize) {
+            Some(&color) => { color }
+            None => { continue; }
+        };
+        if group_source != Some(sprite.source.x) {
+            if group_source.is_some() {
+                writeln!(file, "</g>")?;
+            }
+            writeln!(file, "<g {}>", fill(color))?;
+            group_source = Some(sprite.source.x);
+        }
+
+        // Sprites are positioned by their corner, circles by their center
+        let radius = sprite.scale.0 / 2.0;
+        writeln!(
+            file,
+            r#"<circle cx="{}" cy="{}" r="{}"/>"#,
+            sprite.position.x + radius, sprite.position.y + radius, radius,
+        )?;
+        circles += 1;
+    }
+    if group_source.is_some() {
+        writeln!(file, "</g>")?;
+    }
+    writeln!(file, "</svg>")?;
+    file.flush()?;
+    Ok(circles)
+}
+
+fn fill(color: Color) -> String {
+    let [red, green, blue, alpha] = color.to_rgba();
+    if alpha == 255 {
+        format!(r##"fill="#{:02x}{:02x}{:02x}""##, red, green, blue)
+    } else {
+        format!(r##"fill="#{:02x}{:02x}{:02x}" fill-opacity="{}""##, red, green, blue, color.a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use coffee::graphics::{Point, Rectangle};
+
+    #[test]
+    fn decimation_keeps_no_more_than_asked_for() {
+        let sprites: Vec<Sprite> = (0..10)
+            .map(|i| Sprite {
+                source: Rectangle { x: i, y: 0, width: 1, height: 1 },
+                position: Point::new(0.0, 0.0),
+                scale: (1.0, 1.0),
+            })
+            .collect();
+        let kept = |max: usize| {
+            decimated(sprites.clone(), max).iter().map(|sprite| sprite.source.x).collect::<Vec<_>>()
+        };
+        assert_eq!(kept(10), (0..10).collect::<Vec<_>>());
+        assert_eq!(kept(4), vec![0, 3, 6, 9]);
+        assert_eq!(kept(1), vec![0]);
+        assert_eq!(kept(0), Vec::<u16>::new());
+    }
+}
@@ -0,0 +1,1404 @@
+//! Where the camera is and how the spiral looks, the math to go between the spiral and the
+//! window, and saving and sharing all of it
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use coffee::graphics::{Color, Point};
+use serde::{Deserialize, Serialize};
+
+use crate::classes::Classification;
+use crate::digits;
+use crate::palette::{self, CustomPalette, Marker, Palette, PolarOxideColors, Theme};
+use crate::particles::Particles;
+
+/// Which numbers get drawn
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DisplayMode {
+    All,
+    PrimesOnly,
+    CompositesOnly,
+}
+
+impl DisplayMode {
+    pub fn next(self) -> DisplayMode {
+        match self {
+            DisplayMode::All => { DisplayMode::PrimesOnly }
+            DisplayMode::PrimesOnly => { DisplayMode::CompositesOnly }
+            DisplayMode::CompositesOnly => { DisplayMode::All }
+        }
+    }
+
+    pub fn shows(self, is_prime: bool) -> bool {
+        match self {
+            DisplayMode::All => { true }
+            DisplayMode::PrimesOnly => { is_prime }
+            DisplayMode::CompositesOnly => { !is_prime }
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DisplayMode::All => { "All numbers" }
+            DisplayMode::PrimesOnly => { "Primes only" }
+            DisplayMode::CompositesOnly => { "Composites only" }
+        }
+    }
+}
+
+/// What the numbers are colored by, the residues and the gaps aside
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Coloring {
+    /// The palette's colors for the primes and the composites, the classification's for the primes
+    /// it picks out
+    Kinds,
+    /// How many prime factors each number has counted with multiplicity, Ω(n), see
+    /// `Palette::omega_source`
+    Omega,
+    /// The smallest prime factor of each composite, see `Palette::smallest_factor_source`, the
+    /// primes keeping their colors
+    SmallestFactor,
+    /// The Möbius function μ(n) of each number, see `Factors::moebius`
+    Moebius,
+    /// The semiprimes, products of two primes, picked out from the other composites, the squares
+    /// of primes in a shade of their own. The primes keep their colors.
+    Semiprimes,
+    /// The digital root of each number in `Configs::digit_base`, see `DigitalRoots`, in the
+    /// residues' hues
+    DigitalRoot,
+    /// The same for the primes only, the composites keeping their color
+    PrimeDigitalRoot,
+}
+
+impl Default for Coloring {
+    fn default() -> Coloring {
+        Coloring::Kinds
+    }
+}
+
+impl Coloring {
+    pub const ALL: [Coloring; 7] = [
+        Coloring::Kinds,
+        Coloring::Omega,
+        Coloring::SmallestFactor,
+        Coloring::Moebius,
+        Coloring::Semiprimes,
+        Coloring::DigitalRoot,
+        Coloring::PrimeDigitalRoot,
+    ];
+
+    pub fn next(self) -> Coloring {
+        let index = Self::ALL.iter().position(|&coloring| coloring == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// What `--color-by` takes
+    pub fn name(self) -> &'static str {
+        match self {
+            Coloring::Kinds => { "kinds" }
+            Coloring::Omega => { "omega" }
+            Coloring::SmallestFactor => { "smallest-factor" }
+            Coloring::Moebius => { "mobius" }
+            Coloring::Semiprimes => { "semiprimes" }
+            Coloring::DigitalRoot => { "digital-root" }
+            Coloring::PrimeDigitalRoot => { "prime-digital-root" }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Coloring> {
+        Self::ALL.iter().cloned().find(|coloring| coloring.name() == name)
+    }
+
+    /// Whether the numbers have to be factored for it, see `Particles::factors`
+    pub fn needs_factors(self) -> bool {
+        match self {
+            Coloring::Kinds | Coloring::DigitalRoot | Coloring::PrimeDigitalRoot => { false }
+            Coloring::Omega | Coloring::SmallestFactor | Coloring::Moebius | Coloring::Semiprimes => { true }
+        }
+    }
+
+    /// Whether it goes by the digits, see `Particles::digital_roots`
+    pub fn needs_digits(self) -> bool {
+        self == Coloring::DigitalRoot || self == Coloring::PrimeDigitalRoot
+    }
+
+    /// How the HUD says it, `None` for the palette's own colors
+    pub fn title(self) -> Option<&'static str> {
+        match self {
+            Coloring::Kinds => { None }
+            Coloring::Omega => { Some("Ω(n)") }
+            Coloring::SmallestFactor => { Some("smallest factor") }
+            Coloring::Moebius => { Some("μ(n)") }
+            Coloring::Semiprimes => { Some("semiprimes") }
+            Coloring::DigitalRoot => { Some("digital root") }
+            Coloring::PrimeDigitalRoot => { Some("primes' digital root") }
+        }
+    }
+}
+
+/// A color in the legend, where it is in the atlas the points are drawn from, and what it means
+#[derive(Clone, PartialEq, Debug)]
+pub struct LegendEntry {
+    pub source: u16,
+    pub label: String,
+}
+
+impl LegendEntry {
+    /// The color of a marker, in the palette and theme of `configs`
+    pub fn marker(configs: &Configs, marker: Marker) -> LegendEntry {
+        LegendEntry {
+            source: PolarOxideColors::marker_source(marker, configs.palette, configs.theme),
+            label: String::from(marker.meaning()),
+        }
+    }
+}
+
+/// How the camera behaves, set from the command line. Unlike `Configs` these are not part of the view.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Settings {
+    /// How much each zoom level scales the view by
+    pub zoom_base: f32,
+    /// How many levels a single press of W/S moves
+    pub zoom_step: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            zoom_base: 1.02,
+            zoom_step: 1.0,
+        }
+    }
+}
+
+impl Settings {
+    pub const BASE_PIXEL_RATE: f32 = 10.0;
+
+    /// The error says what's wrong with the value, to be put after where it came from
+    pub fn with_zoom_base(self, zoom_base: f32) -> Result<Settings, String> {
+        if !(zoom_base > 1.0 && zoom_base.is_finite()) {
+            return Err(format!("has to be greater than 1, got {}", zoom_base));
+        }
+        Ok(Settings { zoom_base, ..self })
+    }
+
+    /// See `with_zoom_base`
+    pub fn with_zoom_step(self, zoom_step: f32) -> Result<Settings, String> {
+        if !(zoom_step > 0.0 && zoom_step.is_finite()) {
+            return Err(format!("has to be greater than 0, got {}", zoom_step));
+        }
+        Ok(Settings { zoom_step, ..self })
+    }
+
+    /// How many pixels a spiral unit takes at `zoom_level`. Zooming in goes towards negative levels.
+    pub fn pixel_rate(self, zoom_level: f32) -> f32 {
+        Self::BASE_PIXEL_RATE / self.zoom_base.powf(zoom_level)
+    }
+
+    /// The inverse of `pixel_rate`
+    pub fn zoom_level_for(self, pixel_rate: f32) -> f32 {
+        (Self::BASE_PIXEL_RATE / pixel_rate).ln() / self.zoom_base.ln()
+    }
+}
+
+/// Everything about what is being looked at and how: the camera and the looks of the points
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Configs {
+    pub zoom_level: f32,
+    pub display_mode: DisplayMode,
+    /// Where the camera is, in spiral units. The middle of the window shows `-offset`.
+    pub offset: (f32, f32),
+    /// The size of the points, in pixels
+    pub prime_scale: f32,
+    pub composite_scale: f32,
+    pub angle_factor: f32,
+    pub theme: Theme,
+    /// An index into `Palette::ALL`, or `Palette::CUSTOM`
+    pub palette: usize,
+    /// Read from the file given with `--palette`, which has to be given again every run
+    #[serde(skip)]
+    pub custom_palette: Option<CustomPalette>,
+    /// Numbers below this one aren't drawn
+    #[serde(default)]
+    pub min_number: u64,
+    /// Which kinds of primes get a color of their own
+    #[serde(default)]
+    pub classification: Classification,
+    /// Only the primes the classification picks out are drawn, the composites left to the display
+    /// mode. Without a classification it does nothing.
+    #[serde(default)]
+    pub highlighted_only: bool,
+    /// Every number is colored by its residue mod `modulus` instead, the classification's colors
+    /// included
+    #[serde(default)]
+    pub residues: bool,
+    /// From 2 up to `palette::MAX_MODULUS`
+    #[serde(default = "default_modulus")]
+    pub modulus: u64,
+    /// Every prime is colored by the gap to the next one instead, see `Palette::gap_source`. The
+    /// residues go first.
+    #[serde(default)]
+    pub gap_colors: bool,
+    /// What the rest of the numbers are colored by, under the residues and the gaps
+    #[serde(default)]
+    pub coloring: Coloring,
+    /// The base the digital roots are worked out in, from 2 up to `digits::MAX_DIGIT_BASE`
+    #[serde(default = "default_digit_base")]
+    pub digit_base: u32,
+    // Numbers past this one aren't drawn. How far the reveal animation got isn't worth saving, a
+    // restored view shows every number.
+    #[serde(skip, default = "u64::max_value")]
+    pub reveal_cutoff: u64,
+}
+
+const DEFAULT_MODULUS: u64 = 4;
+
+fn default_modulus() -> u64 {
+    DEFAULT_MODULUS
+}
+
+fn default_digit_base() -> u32 {
+    10
+}
+
+impl Default for Configs {
+    fn default() -> Configs {
+        Configs {
+            zoom_level: 0.0,
+            display_mode: DisplayMode::All,
+            offset: (0.0, 0.0),
+            prime_scale: 2.0,
+            composite_scale: 2.0,
+            angle_factor: 1.0,
+            theme: Theme::Dark,
+            palette: 0,
+            custom_palette: None,
+            min_number: 0,
+            classification: Classification::None,
+            highlighted_only: false,
+            residues: false,
+            modulus: DEFAULT_MODULUS,
+            gap_colors: false,
+            coloring: Coloring::Kinds,
+            digit_base: default_digit_base(),
+            reveal_cutoff: u64::max_value(),
+        }
+    }
+}
+
+impl Configs {
+    pub const MIN_ZOOM_LEVEL: f32 = -500.0;
+    pub const MAX_ZOOM_LEVEL: f32 = 1000.0;
+    pub const MIN_POINT_SCALE: f32 = 0.5;
+    pub const MAX_POINT_SCALE: f32 = 16.0;
+    pub const POINT_SCALE_STEP: f32 = 1.25;
+    pub const ANGLE_FINE_STEP: f32 = 0.001;
+    pub const ANGLE_COARSE_STEP: f32 = 0.1;
+    /// How far past the edges of the window points are still kept, in times its larger side
+    pub const FRAME_MARGIN: f32 = 1.5;
+
+    pub fn clamp_zoom_level(zoom_level: f32) -> f32 {
+        zoom_level.max(Self::MIN_ZOOM_LEVEL).min(Self::MAX_ZOOM_LEVEL)
+    }
+
+    /// Views come from files and strings made by other versions, so anything out of range is brought back
+    pub fn sanitized(mut self) -> Configs {
+        let custom = self.palette == Palette::CUSTOM && self.custom_palette.is_some();
+        if self.palette >= Palette::ALL.len() && !custom {
+            self.palette = Configs::default().palette;
+        }
+        self.zoom_level = Self::clamp_zoom_level(self.zoom_level);
+        if self.digit_base < 2 || self.digit_base > digits::MAX_DIGIT_BASE {
+            self.digit_base = default_digit_base();
+        }
+        if !Self::is_modulus(self.modulus) {
+            self.modulus = DEFAULT_MODULUS;
+        }
+        if let Classification::Palindromic(base) = self.classification {
+            if !Classification::BASES.contains(&base) {
+                self.classification = Classification::Palindromic(10);
+            }
+        }
+        self
+    }
+
+    pub fn palette_name(&self) -> &'static str {
+        match Palette::ALL.get(self.palette) {
+            Some(palette) => { palette.name }
+            None => { CustomPalette::NAME }
+        }
+    }
+
+    pub fn background(&self) -> Color {
+        match self.custom_palette {
+            Some(custom) if self.palette == Palette::CUSTOM => { custom.background }
+            _ => { self.theme.background() }
+        }
+    }
+
+    /// Text, which has to stand out from the background
+    pub fn foreground(&self) -> Color {
+        match self.custom_palette {
+            Some(custom) if self.palette == Palette::CUSTOM => { custom.foreground() }
+            _ => { self.theme.foreground() }
+        }
+    }
+
+    /// Plots drawn over the points, in a color of their own
+    pub fn curve_color(&self) -> Color {
+        match self.custom_palette {
+            Some(custom) if self.palette == Palette::CUSTOM => { custom.highlight }
+            _ => { PolarOxideColors::AMBER }
+        }
+    }
+
+    /// What the colors of the points mean, for each kind of number the display mode shows, with
+    /// the classes of primes picked out right after the primes, or instead of them when they're the
+    /// only ones drawn. The primes' shades when they're colored by their gaps are in `gap_legend`.
+    pub fn legend(&self) -> Vec<LegendEntry> {
+        if self.residues {
+            return self.residue_legend();
+        }
+        match self.coloring {
+            Coloring::Omega => { return self.omega_legend(); }
+            Coloring::Moebius => { return self.moebius_legend(); }
+            Coloring::DigitalRoot | Coloring::PrimeDigitalRoot => { return self.digital_root_legend(); }
+            Coloring::Kinds | Coloring::SmallestFactor | Coloring::Semiprimes => {}
+        }
+        let mut legend = Vec::new();
+        if self.display_mode.shows(true) && !self.gap_colors {
+            if !self.only_highlighted_primes() {
+                legend.push(LegendEntry {
+                    source: Palette::source(self.palette, self.theme, true),
+                    label: String::from("prime"),
+                });
+            }
+            legend.extend(self.classification.classes().into_iter().map(|class| LegendEntry {
+                source: Palette::class_source(self.palette, self.theme, class),
+                label: self.classification.meaning(class),
+            }));
+        }
+        if !self.display_mode.shows(false) {
+            return legend;
+        }
+        if self.coloring == Coloring::SmallestFactor {
+            legend.extend(palette::SMALLEST_FACTOR_CLASSES.iter().map(|&(factor, factors)| LegendEntry {
+                source: Palette::smallest_factor_source(self.theme, factor),
+                label: format!("composite, smallest factor {}", factors),
+            }));
+            return legend;
+        }
+        if self.coloring == Coloring::Semiprimes {
+            for &(square, label) in &[(false, "semiprime, p·q"), (true, "square of a prime, p²")] {
+                legend.push(LegendEntry {
+                    source: Palette::semiprime_source(self.theme, square),
+                    label: String::from(label),
+                });
+            }
+        }
+        legend.push(LegendEntry {
+            source: Palette::source(self.palette, self.theme, false),
+            label: String::from("composite"),
+        });
+        legend
+    }
+
+    // With the residues coloring the numbers, a color for each residue of each kind of number shown
+    fn residue_legend(&self) -> Vec<LegendEntry> {
+        let mut legend = Vec::new();
+        for &(is_prime, kind) in &[(true, "prime"), (false, "composite")] {
+            if self.display_mode.shows(is_prime) {
+                legend.extend((0..self.modulus).map(|residue| LegendEntry {
+                    source: Palette::residue_source(self.theme, is_prime, residue),
+                    label: format!("{} ≡ {} (mod {})", kind, residue, self.modulus),
+                }));
+            }
+        }
+        legend
+    }
+
+    // With the numbers colored by their digital roots, a color for each root of each kind of number
+    // shown, the composites' own one when only the primes are colored
+    fn digital_root_legend(&self) -> Vec<LegendEntry> {
+        let in_base = if self.digit_base == 10 { String::new() } else { format!(" in base {}", self.digit_base) };
+        let mut legend = Vec::new();
+        for &(is_prime, kind) in &[(true, "prime"), (false, "composite")] {
+            // The gaps' shades cover the primes' roots up
+            if !self.display_mode.shows(is_prime) || (is_prime && self.gap_colors) {
+                continue;
+            }
+            if is_prime || self.coloring == Coloring::DigitalRoot {
+                let roots = 1..self.digit_base as u8;
+                legend.extend(roots.map(|root| LegendEntry {
+                    source: Palette::digital_root_source(self.theme, is_prime, root),
+                    label: format!("{}, digital root {}{}", kind, root, in_base),
+                }));
+            } else {
+                legend.push(LegendEntry {
+                    source: Palette::source(self.palette, self.theme, false),
+                    label: String::from(kind),
+                });
+            }
+        }
+        legend
+    }
+
+    // With the numbers colored by how many prime factors they have, the primes' color and the
+    // composites' ones, as far as they're shown
+    fn omega_legend(&self) -> Vec<LegendEntry> {
+        let mut legend = Vec::new();
+        if self.display_mode.shows(true) && !self.gap_colors {
+            legend.push(LegendEntry {
+                source: Palette::omega_source(self.theme, 1),
+                label: String::from("prime, Ω = 1"),
+            });
+        }
+        if self.display_mode.shows(false) {
+            let most = palette::OMEGA_SHADES as u8;
+            legend.extend((2..=most).map(|omega| LegendEntry {
+                source: Palette::omega_source(self.theme, omega),
+                label: if omega < most { format!("Ω = {}", omega) } else { format!("Ω ≥ {}", omega) },
+            }));
+        }
+        legend
+    }
+
+    // With the numbers colored by μ, the primes' color and the rest's, as far as they're shown
+    fn moebius_legend(&self) -> Vec<LegendEntry> {
+        let mut legend = Vec::new();
+        if self.display_mode.shows(true) && !self.gap_colors {
+            legend.push(LegendEntry {
+                source: Palette::moebius_source(self.theme, -1),
+                label: String::from("prime, μ = -1"),
+            });
+        }
+        if self.display_mode.shows(false) {
+            let values = [(1, "μ = 1"), (-1, "μ = -1"), (0, "μ = 0, a square divides it")];
+            legend.extend(values.iter().map(|&(mu, label)| LegendEntry {
+                source: Palette::moebius_source(self.theme, mu),
+                label: String::from(label),
+            }));
+        }
+        legend
+    }
+
+    /// With the primes colored by the gaps after them, the shades of the smallest, the middle and
+    /// the widest of those gaps, for a biggest gap of `max_gap`. Goes before `legend`.
+    pub fn gap_legend(&self, max_gap: u64) -> Vec<LegendEntry> {
+        if !self.gap_colors || self.residues || !self.display_mode.shows(true) {
+            return Vec::new();
+        }
+        let widest = Palette::widest_shaded_gap(max_gap);
+        let or_more = if max_gap > widest { " or more" } else { "" };
+        let gaps = [(2, ""), ((2 + widest) / 4 * 2, ""), (widest, or_more)];
+        gaps.iter()
+            .map(|&(gap, or_more)| LegendEntry {
+                source: Palette::gap_source(self.theme, gap, max_gap),
+                label: format!("prime, then a gap of {}{}", gap, or_more),
+            })
+            .collect()
+    }
+
+    /// Whether the numbers can be colored by their residues mod `modulus`
+    pub fn is_modulus(modulus: u64) -> bool {
+        modulus >= 2 && modulus <= palette::MAX_MODULUS
+    }
+
+    /// Where a point of the spiral ends up in a window of the given size
+    pub fn screen_position(&self, settings: Settings, position: Point, width: f32, height: f32) -> Point {
+        let pixel_rate = settings.pixel_rate(self.zoom_level);
+        Point::new(
+            (position.x + self.offset.0) * pixel_rate + width / 2.0,
+            (position.y + self.offset.1) * pixel_rate + height / 2.0,
+        )
+    }
+
+    /// The inverse of `screen_position`: which point of the spiral is under a given pixel
+    pub fn spiral_position(&self, settings: Settings, point: Point, width: f32, height: f32) -> Point {
+        let pixel_rate = settings.pixel_rate(self.zoom_level);
+        Point::new(
+            (point.x - width / 2.0) / pixel_rate - self.offset.0,
+            (point.y - height / 2.0) / pixel_rate - self.offset.1,
+        )
+    }
+
+    /// How far from the middle of a window of the given size points are kept, in pixels along each
+    /// axis: the window itself plus `FRAME_MARGIN` times its larger side past every edge, so the
+    /// camera can move a bit before points go missing
+    pub fn kept_half_extents(width: f32, height: f32) -> (f32, f32) {
+        let margin = width.max(height) * Self::FRAME_MARGIN;
+        (width / 2.0 + margin, height / 2.0 + margin)
+    }
+
+    /// Whether a point of the spiral, `point_size` pixels wide, is worth drawing in a window of the
+    /// given size. Anything that touches the area of `kept_half_extents` is.
+    pub fn is_in_frame(&self, settings: Settings, position: Point, point_size: f32, width: f32, height: f32) -> bool {
+        let pixel_rate = settings.pixel_rate(self.zoom_level);
+        let x = (position.x + self.offset.0) * pixel_rate;
+        let y = (position.y + self.offset.1) * pixel_rate;
+        let (half_width, half_height) = Self::kept_half_extents(width, height);
+        let radius = point_size / 2.0;
+        x.abs() <= half_width + radius && y.abs() <= half_height + radius
+    }
+
+    /// How near and how far from the origin a point can be and still pass `is_in_frame`, so numbers
+    /// that can't be in frame are skipped before working out where they are
+    pub fn radii_in_frame(&self, settings: Settings, point_size: f32, width: f32, height: f32) -> (f32, f32) {
+        let (half_width, half_height) = Self::kept_half_extents(width, height);
+        let radius = point_size / 2.0;
+        self.radii_around_middle(settings, half_width + radius, half_height + radius)
+    }
+
+    /// The same for the window itself, without any margin
+    pub fn radii_in_window(&self, settings: Settings, width: f32, height: f32) -> (f32, f32) {
+        self.radii_around_middle(settings, width / 2.0, height / 2.0)
+    }
+
+    // For a rectangle around the middle of the window, given in pixels
+    fn radii_around_middle(&self, settings: Settings, half_width: f32, half_height: f32) -> (f32, f32) {
+        let pixel_rate = settings.pixel_rate(self.zoom_level);
+        let (half_width, half_height) = (half_width / pixel_rate, half_height / pixel_rate);
+
+        // The area is a rectangle around the middle of the window, which shows minus the offset. By
+        // symmetry it's as far from the origin as the same rectangle around `|offset|`.
+        let (x, y) = (self.offset.0.abs(), self.offset.1.abs());
+        let nearest = (x - half_width).max(0.0).hypot((y - half_height).max(0.0));
+        let farthest = (x + half_width).hypot(y + half_height);
+        (nearest, farthest)
+    }
+
+    /// Whether `number` is among the ones drawn, between `min_number` and how far the reveal got
+    pub fn draws(&self, number: u64) -> bool {
+        number >= self.min_number && number <= self.reveal_cutoff
+    }
+
+    /// Whether `number`, a prime or not as `is_prime` says, is drawn: it's among the ones `draws`
+    /// takes, the display mode shows it, and as a prime it's kept by `keeps_prime`
+    pub fn shows_number(&self, particles: &Particles, number: u64, is_prime: bool) -> bool {
+        self.draws(number) && self.display_mode.shows(is_prime) && (!is_prime || self.keeps_prime(particles, number))
+    }
+
+    /// Whether the prime `number` is drawn, given `highlighted_only`
+    pub fn keeps_prime(&self, particles: &Particles, number: u64) -> bool {
+        !self.only_highlighted_primes() || self.classification.class_of(particles, number).is_some()
+    }
+
+    fn only_highlighted_primes(&self) -> bool {
+        self.highlighted_only && self.classification != Classification::None
+    }
+
+    /// Puts the camera back where it started, without touching what is being drawn
+    pub fn reset_view(&mut self) {
+        let defaults = Configs::default();
+        self.zoom_level = defaults.zoom_level;
+        self.offset = defaults.offset;
+        self.reset_point_scale();
+    }
+
+    pub fn reset_point_scale(&mut self) {
+        let defaults = Configs::default();
+        self.prime_scale = defaults.prime_scale;
+        self.composite_scale = defaults.composite_scale;
+    }
+
+    /// Grows or shrinks the points by `factor`, or only the primes if `primes_only` is set
+    pub fn scale_points(&mut self, factor: f32, primes_only: bool) {
+        let clamp = |scale: f32| scale.max(Self::MIN_POINT_SCALE).min(Self::MAX_POINT_SCALE);
+        let (prime_scale, composite_scale) = (self.prime_scale * factor, self.composite_scale * factor);
+        self.prime_scale = clamp(prime_scale);
+        if !primes_only {
+            self.composite_scale = clamp(composite_scale);
+        }
+    }
+}
+
+/// Where we were looking and how, so it can be picked up again in a later run or somewhere else
+#[derive(Serialize, Deserialize)]
+pub struct SavedView {
+    pub max_number: u64,
+    pub configs: Configs,
+}
+
+impl SavedView {
+    const FILE_NAME: &'static str = "view.toml";
+    // Bumped whenever the layout of the view string changes, older strings keep being decoded
+    // the way they were written
+    const STRING_VERSION: u8 = 1;
+
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = config_path(Self::FILE_NAME)?;
+        let contents = toml::to_string(self).map_err(|error| error.to_string())?;
+        write_config(&path, contents)?;
+        Ok(path)
+    }
+
+    pub fn load() -> Result<SavedView, String> {
+        let path = config_path(Self::FILE_NAME)?;
+        let contents = fs::read_to_string(&path)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        toml::from_str(&contents).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+}
+
+// View strings are the version followed by the max number, zoom level, offset, display mode,
+// palette and angle factor, little endian, in URL-safe base64 so they survive being pasted around
+impl SavedView {
+    pub fn encode(&self) -> String {
+        let configs = &self.configs;
+        let mut bytes = vec![Self::STRING_VERSION];
+        bytes.extend_from_slice(&self.max_number.to_le_bytes());
+        bytes.extend_from_slice(&configs.zoom_level.to_bits().to_le_bytes());
+        bytes.extend_from_slice(&configs.offset.0.to_bits().to_le_bytes());
+        bytes.extend_from_slice(&configs.offset.1.to_bits().to_le_bytes());
+        bytes.push(match configs.display_mode {
+            DisplayMode::All => { 0 }
+            DisplayMode::PrimesOnly => { 1 }
+            DisplayMode::CompositesOnly => { 2 }
+        });
+        bytes.push(configs.palette as u8);
+        bytes.extend_from_slice(&configs.angle_factor.to_bits().to_le_bytes());
+        base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    /// Whatever the string doesn't have, like the theme or the size of the points, is taken from `base`
+    pub fn decode(text: &str, base: Configs) -> Result<SavedView, String> {
+        let bytes = base64::decode_config(text.trim(), base64::URL_SAFE_NO_PAD)
+            .map_err(|_| String::from("not a view string"))?;
+        match bytes.split_first() {
+            Some((&1, rest)) => { Self::decode_v1(rest, base) }
+            Some((version, _)) => { Err(format!("unknown view string version {}", version)) }
+            None => { Err(String::from("the view string is empty")) }
+        }
+    }
+
+    fn decode_v1(bytes: &[u8], base: Configs) -> Result<SavedView, String> {
+        if bytes.len() != 26 {
+            return Err(String::from("the view string is cut short or has extra data"));
+        }
+
+        let u64_at = |i: usize| {
+            let mut buffer = [0; 8];
+            buffer.copy_from_slice(&bytes[i..i + 8]);
+            u64::from_le_bytes(buffer)
+        };
+        let f32_at = |i: usize| {
+            let mut buffer = [0; 4];
+            buffer.copy_from_slice(&bytes[i..i + 4]);
+            f32::from_bits(u32::from_le_bytes(buffer))
+        };
+
+        let max_number = u64_at(0);
+        let (zoom_level, offset_x, offset_y, angle_factor) = (f32_at(8), f32_at(12), f32_at(16), f32_at(22));
+        if max_number < 2 {
+            return Err(format!("the view string has {} numbers", max_number));
+        }
+        if ![zoom_level, offset_x, offset_y, angle_factor].iter().all(|value| value.is_finite()) {
+            return Err(String::from("the view string has invalid numbers in it"));
+        }
+
+        let display_mode = match bytes[20] {
+            0 => { DisplayMode::All }
+            1 => { DisplayMode::PrimesOnly }
+            2 => { DisplayMode::CompositesOnly }
+            other => { return Err(format!("unknown display mode {} in the view string", other)); }
+        };
+
+        Ok(SavedView {
+            max_number,
+            configs: Configs {
+                zoom_level,
+                offset: (offset_x, offset_y),
+                display_mode,
+                palette: usize::from(bytes[21]),
+                angle_factor,
+                ..base
+            },
+        })
+    }
+}
+
+/// How things were left when the app was last closed, picked up again on the next launch. Unlike
+/// `SavedView` it's saved without being asked, and what it holds changes between versions: keys it
+/// doesn't have keep their defaults and keys it doesn't know about are ignored.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastSession {
+    pub zoom_level: f32,
+    pub offset: (f32, f32),
+    pub display_mode: DisplayMode,
+    pub palette: usize,
+    pub prime_scale: f32,
+    pub composite_scale: f32,
+    /// The size of the window when it wasn't fullscreen, in pixels
+    pub window_size: Option<(u32, u32)>,
+    pub fullscreen: bool,
+}
+
+impl Default for LastSession {
+    fn default() -> LastSession {
+        LastSession::new(&Configs::default(), None, false)
+    }
+}
+
+impl LastSession {
+    const FILE_NAME: &'static str = "session.toml";
+
+    pub fn new(configs: &Configs, window_size: Option<(u32, u32)>, fullscreen: bool) -> LastSession {
+        LastSession {
+            zoom_level: configs.zoom_level,
+            offset: configs.offset,
+            display_mode: configs.display_mode,
+            palette: configs.palette,
+            prime_scale: configs.prime_scale,
+            composite_scale: configs.composite_scale,
+            window_size,
+            fullscreen,
+        }
+    }
+
+    /// The configs with what was left of the session put back, anything out of range brought back in
+    pub fn restore(&self, configs: Configs) -> Configs {
+        let clamp_scale = |scale: f32| {
+            if scale.is_finite() { scale.max(Configs::MIN_POINT_SCALE).min(Configs::MAX_POINT_SCALE) } else { 2.0 }
+        };
+        let offset = if self.offset.0.is_finite() && self.offset.1.is_finite() { self.offset } else { (0.0, 0.0) };
+        let zoom_level = if self.zoom_level.is_finite() { self.zoom_level } else { configs.zoom_level };
+        Configs {
+            zoom_level,
+            offset,
+            display_mode: self.display_mode,
+            palette: self.palette,
+            prime_scale: clamp_scale(self.prime_scale),
+            composite_scale: clamp_scale(self.composite_scale),
+            ..configs
+        }.sanitized()
+    }
+
+    pub fn parse(text: &str) -> Result<LastSession, String> {
+        toml::from_str(text).map_err(|error| error.to_string())
+    }
+
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = config_path(Self::FILE_NAME)?;
+        let contents = toml::to_string(self).map_err(|error| error.to_string())?;
+        write_config(&path, contents)?;
+        Ok(path)
+    }
+
+    /// `None` the first time around. A file that doesn't parse is moved out of the way to
+    /// session.toml.bak, and reported as an error to be started fresh from.
+    pub fn load() -> Result<Option<LastSession>, String> {
+        load_config(Self::FILE_NAME, Self::parse)
+    }
+}
+
+/// The looks kept in one of the slots on the number keys
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Preset {
+    /// An index into `Palette::ALL`, or `Palette::CUSTOM`
+    pub palette: usize,
+    pub theme: Theme,
+    pub prime_scale: f32,
+    pub composite_scale: f32,
+}
+
+impl Preset {
+    pub fn new(configs: &Configs) -> Preset {
+        Preset {
+            palette: configs.palette,
+            theme: configs.theme,
+            prime_scale: configs.prime_scale,
+            composite_scale: configs.composite_scale,
+        }
+    }
+
+    /// The configs drawn with these looks. The files are edited by hand, so anything out of range
+    /// is brought back in, and a custom palette that wasn't given this run falls back to the default.
+    pub fn apply(&self, configs: Configs) -> Configs {
+        let clamp_scale = |scale: f32, default: f32| {
+            if scale.is_finite() { scale.max(Configs::MIN_POINT_SCALE).min(Configs::MAX_POINT_SCALE) } else { default }
+        };
+        let defaults = Configs::default();
+        Configs {
+            palette: self.palette,
+            theme: self.theme,
+            prime_scale: clamp_scale(self.prime_scale, defaults.prime_scale),
+            composite_scale: clamp_scale(self.composite_scale, defaults.composite_scale),
+            ..configs
+        }.sanitized()
+    }
+}
+
+/// The slots behind keys 1 to 9. Slots that were never saved over hold a built-in preset.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Presets {
+    /// In slot order, only as many as were saved
+    slots: Vec<Preset>,
+}
+
+impl Presets {
+    pub const COUNT: usize = 9;
+    const FILE_NAME: &'static str = "presets.toml";
+
+    /// The preset in `slot`, counting from 0. Panics past `COUNT`.
+    pub fn get(&self, slot: usize) -> Preset {
+        assert!(slot < Self::COUNT, "there are only {} preset slots", Self::COUNT);
+        self.slots.get(slot).copied().unwrap_or_else(|| Self::built_in(slot))
+    }
+
+    /// Saves over `slot`, the ones before it keeping whatever they held
+    pub fn set(&mut self, slot: usize, preset: Preset) {
+        assert!(slot < Self::COUNT, "there are only {} preset slots", Self::COUNT);
+        while self.slots.len() <= slot {
+            let next = Self::built_in(self.slots.len());
+            self.slots.push(next);
+        }
+        self.slots[slot] = preset;
+    }
+
+    // Every palette on the dark theme, then a few other combinations worth having at hand
+    fn built_in(slot: usize) -> Preset {
+        let defaults = Configs::default();
+        let preset = |palette: usize, theme: Theme, prime_scale: f32, composite_scale: f32| {
+            Preset { palette, theme, prime_scale, composite_scale }
+        };
+        match slot {
+            0..=5 => { preset(slot, Theme::Dark, defaults.prime_scale, defaults.composite_scale) }
+            6 => { preset(0, Theme::Light, defaults.prime_scale, defaults.composite_scale) }
+            7 => { preset(2, Theme::Light, 4.0, 1.0) }
+            _ => { preset(3, Theme::Dark, 3.0, 1.0) }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Presets, String> {
+        let presets: Presets = toml::from_str(text).map_err(|error| error.to_string())?;
+        if presets.slots.len() > Self::COUNT {
+            return Err(format!("there are only {} preset slots, got {}", Self::COUNT, presets.slots.len()));
+        }
+        Ok(presets)
+    }
+
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = config_path(Self::FILE_NAME)?;
+        let contents = toml::to_string(self).map_err(|error| error.to_string())?;
+        write_config(&path, contents)?;
+        Ok(path)
+    }
+
+    /// The built-in presets the first time around. A file that doesn't parse is moved out of the way
+    /// to presets.toml.bak, like the session.
+    pub fn load() -> Result<Presets, String> {
+        load_config(Self::FILE_NAME, Self::parse).map(Option::unwrap_or_default)
+    }
+}
+
+// e.g. ~/.config/polar-oxides/view.toml on Linux or %APPDATA%\polar-oxides\view.toml on Windows
+fn config_path(file_name: &str) -> Result<PathBuf, String> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| String::from("couldn't find the config directory"))?;
+    Ok(config_dir.join("polar-oxides").join(file_name))
+}
+
+/// A view worth coming back to, under a name
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    /// A view string, see `SavedView::encode`, which keeps working across versions
+    pub view: String,
+}
+
+impl Bookmark {
+    /// Whatever the view string doesn't have is taken from `base`, see `SavedView::decode`
+    pub fn view(&self, base: Configs) -> Result<SavedView, String> {
+        SavedView::decode(&self.view, base).map_err(|error| format!("bookmark `{}`: {}", self.name, error))
+    }
+}
+
+/// The bookmarked views, in the order they were added
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bookmarks {
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    const FILE_NAME: &'static str = "bookmarks.toml";
+
+    pub fn list(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    /// Bookmarks `view` under `name`, in place of any bookmark with the same name. Returns where in
+    /// the list it went.
+    pub fn add(&mut self, name: &str, view: &SavedView) -> usize {
+        let bookmark = Bookmark { name: String::from(name), view: view.encode() };
+        match self.bookmarks.iter().position(|bookmark| bookmark.name == name) {
+            Some(index) => {
+                self.bookmarks[index] = bookmark;
+                index
+            }
+            None => {
+                self.bookmarks.push(bookmark);
+                self.bookmarks.len() - 1
+            }
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Bookmark> {
+        if index < self.bookmarks.len() { Some(self.bookmarks.remove(index)) } else { None }
+    }
+
+    pub fn parse(text: &str) -> Result<Bookmarks, String> {
+        toml::from_str(text).map_err(|error| error.to_string())
+    }
+
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = config_path(Self::FILE_NAME)?;
+        let contents = toml::to_string(self).map_err(|error| error.to_string())?;
+        write_config(&path, contents)?;
+        Ok(path)
+    }
+
+    /// Empty the first time around. A file that doesn't parse is moved out of the way to
+    /// bookmarks.toml.bak, like the session.
+    pub fn load() -> Result<Bookmarks, String> {
+        load_config(Self::FILE_NAME, Self::parse).map(Option::unwrap_or_default)
+    }
+}
+
+// `None` if the file isn't there. One that doesn't parse is renamed to end in .bak, so the next run
+// starts fresh instead of failing the same way.
+fn load_config<T>(file_name: &str, parse: fn(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    let path = config_path(file_name)?;
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => { contents }
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => { return Ok(None); }
+        Err(error) => { return Err(format!("{}: {}", path.display(), error)); }
+    };
+
+    parse(&contents).map(Some).map_err(|error| {
+        let backup = path.with_extension("toml.bak");
+        match fs::rename(&path, &backup) {
+            Ok(()) => { format!("{}: {}, moved it to {}", path.display(), error, backup.display()) }
+            Err(rename_error) => {
+                format!("{}: {}, and couldn't move it out of the way: {}", path.display(), error, rename_error)
+            }
+        }
+    })
+}
+
+fn write_config(path: &Path, contents: String) -> Result<(), String> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)
+            .map_err(|error| format!("{}: {}", directory.display(), error))?;
+    }
+    fs::write(path, contents).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::classes::Class;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() <= 1e-4 * b.abs().max(1.0), "{} isn't {}", a, b);
+    }
+
+    #[test]
+    fn zoom_levels_are_clamped() {
+        assert_close(Configs::clamp_zoom_level(0.0), 0.0);
+        assert_close(Configs::clamp_zoom_level(-10_000.0), Configs::MIN_ZOOM_LEVEL);
+        assert_close(Configs::clamp_zoom_level(10_000.0), Configs::MAX_ZOOM_LEVEL);
+
+        let configs = Configs { zoom_level: 5_000.0, palette: 99, ..Configs::default() }.sanitized();
+        assert_close(configs.zoom_level, Configs::MAX_ZOOM_LEVEL);
+        assert_eq!(configs.palette, Configs::default().palette);
+    }
+
+    #[test]
+    fn the_legend_follows_the_display_mode_and_the_palette() {
+        let configs = Configs { palette: 2, theme: Theme::Light, ..Configs::default() };
+        let labels = |configs: &Configs| configs.legend().into_iter().map(|entry| entry.label).collect::<Vec<_>>();
+        assert_eq!(labels(&configs), vec!["prime", "composite"]);
+        assert_eq!(configs.legend()[1].source, Palette::source(2, Theme::Light, false));
+
+        let primes_only = Configs { display_mode: DisplayMode::PrimesOnly, ..configs };
+        assert_eq!(labels(&primes_only), vec!["prime"]);
+        assert_eq!(primes_only.legend()[0].source, Palette::source(2, Theme::Light, true));
+        let composites_only = Configs { display_mode: DisplayMode::CompositesOnly, ..configs };
+        assert_eq!(labels(&composites_only), vec!["composite"]);
+
+        let twins = Configs { classification: Classification::from_name("twins").unwrap(), ..configs };
+        assert_eq!(labels(&twins), vec!["prime", "twin prime", "composite"]);
+        assert_eq!(twins.legend()[1].source, Palette::class_source(2, Theme::Light, Class::Twin));
+        assert_eq!(labels(&Configs { display_mode: DisplayMode::CompositesOnly, ..twins }), vec!["composite"]);
+        let only_twins = Configs { highlighted_only: true, ..twins };
+        assert_eq!(labels(&only_twins), vec!["twin prime", "composite"]);
+        assert_eq!(labels(&Configs { highlighted_only: true, ..configs }), vec!["prime", "composite"]);
+        let pairs = Configs { classification: Classification::from_name("sexy,cousins").unwrap(), ..configs };
+        assert_eq!(labels(&pairs), vec!["prime", "cousin prime", "sexy prime, not cousin", "composite"]);
+        assert_eq!(pairs.legend()[2].source, Palette::class_source(2, Theme::Light, Class::Sexy));
+
+        let residues = Configs { residues: true, modulus: 3, ..twins };
+        assert_eq!(labels(&Configs { display_mode: DisplayMode::PrimesOnly, ..residues }), vec![
+            "prime ≡ 0 (mod 3)",
+            "prime ≡ 1 (mod 3)",
+            "prime ≡ 2 (mod 3)",
+        ]);
+        assert_eq!(residues.legend().len(), 6);
+        assert_eq!(residues.legend()[4].source, Palette::residue_source(Theme::Light, false, 1));
+
+        let gaps = Configs { gap_colors: true, ..twins };
+        assert_eq!(labels(&gaps), vec!["composite"]);
+        let gap_labels = |max_gap: u64| {
+            gaps.gap_legend(max_gap).into_iter().map(|entry| entry.label).collect::<Vec<_>>()
+        };
+        assert_eq!(gap_labels(36), vec![
+            "prime, then a gap of 2",
+            "prime, then a gap of 18",
+            "prime, then a gap of 36",
+        ]);
+        assert_eq!(gap_labels(154)[2], "prime, then a gap of 100 or more");
+        assert_eq!(gaps.gap_legend(36)[2].source, Palette::gap_source(Theme::Light, 36, 36));
+        assert!(residues.gap_legend(36).is_empty() && twins.gap_legend(36).is_empty());
+
+        let search = LegendEntry::marker(&configs, Marker::Search);
+        assert_eq!(search.source, PolarOxideColors::marker_source(Marker::Search, 2, Theme::Light));
+        assert_eq!(search.label, Marker::Search.meaning());
+    }
+
+    #[test]
+    fn the_legend_counts_prime_factors() {
+        let twins = Configs { classification: Classification::from_name("twins").unwrap(), ..Configs::default() };
+        let omega = Configs { coloring: Coloring::Omega, theme: Theme::Light, ..twins };
+        let labels = |configs: &Configs| configs.legend().into_iter().map(|entry| entry.label).collect::<Vec<_>>();
+        assert_eq!(labels(&omega), vec!["prime, Ω = 1", "Ω = 2", "Ω = 3", "Ω = 4", "Ω ≥ 5"]);
+        assert_eq!(omega.legend()[1].source, Palette::omega_source(Theme::Light, 2));
+        assert_eq!(labels(&Configs { display_mode: DisplayMode::PrimesOnly, ..omega }), vec!["prime, Ω = 1"]);
+        // The primes' colors when the gaps or the residues cover them up
+        assert_eq!(labels(&Configs { gap_colors: true, ..omega }).len(), 4);
+        assert_eq!(labels(&Configs { residues: true, ..omega }).len(), 8);
+        assert_eq!(Coloring::PrimeDigitalRoot.next(), Coloring::Kinds);
+
+        let smallest = Configs { coloring: Coloring::SmallestFactor, ..omega };
+        assert_eq!(labels(&smallest)[..3].to_vec(), vec!["prime", "twin prime", "composite, smallest factor 2"]);
+        assert_eq!(labels(&smallest)[7], "composite, smallest factor 37 or more");
+        assert_eq!(smallest.legend()[6].source, Palette::smallest_factor_source(Theme::Light, 31));
+        assert_eq!(labels(&Configs { display_mode: DisplayMode::PrimesOnly, ..smallest }).len(), 2);
+
+        let moebius = Configs { coloring: Coloring::Moebius, ..omega };
+        assert_eq!(labels(&moebius), vec!["prime, μ = -1", "μ = 1", "μ = -1", "μ = 0, a square divides it"]);
+        assert_eq!(moebius.legend()[3].source, Palette::moebius_source(Theme::Light, 0));
+        assert_eq!(labels(&Configs { display_mode: DisplayMode::CompositesOnly, ..moebius }).len(), 3);
+
+        let semiprimes = Configs { coloring: Coloring::Semiprimes, ..omega };
+        assert_eq!(labels(&semiprimes), vec![
+            "prime",
+            "twin prime",
+            "semiprime, p·q",
+            "square of a prime, p²",
+            "composite",
+        ]);
+        assert_eq!(semiprimes.legend()[3].source, Palette::semiprime_source(Theme::Light, true));
+        let primes_only = Configs { display_mode: DisplayMode::PrimesOnly, ..semiprimes };
+        assert_eq!(labels(&primes_only), vec!["prime", "twin prime"]);
+    }
+
+    #[test]
+    fn the_legend_has_a_color_for_each_digital_root() {
+        let primes_only = Configs { display_mode: DisplayMode::PrimesOnly, ..Configs::default() };
+        let roots = Configs { coloring: Coloring::DigitalRoot, ..primes_only };
+        let labels = |configs: &Configs| configs.legend().into_iter().map(|entry| entry.label).collect::<Vec<_>>();
+        assert_eq!(labels(&roots).len(), 9);
+        assert_eq!(labels(&roots)[8], "prime, digital root 9");
+        assert_eq!(roots.legend()[2].source, Palette::digital_root_source(Theme::Dark, true, 3));
+        assert_eq!(labels(&Configs { display_mode: DisplayMode::All, ..roots }).len(), 18);
+
+        let all = Configs { display_mode: DisplayMode::All, ..roots };
+        let base_7 = Configs { coloring: Coloring::PrimeDigitalRoot, digit_base: 7, ..all };
+        assert_eq!(labels(&base_7)[..2].to_vec(), vec![
+            "prime, digital root 1 in base 7",
+            "prime, digital root 2 in base 7",
+        ]);
+        assert_eq!(labels(&base_7)[6], "composite");
+        assert_eq!(labels(&base_7).len(), 7);
+        assert_eq!(Configs { digit_base: 40, ..base_7 }.sanitized().digit_base, 10);
+        for &modulus in &[0, 1, palette::MAX_MODULUS + 1] {
+            assert_eq!(Configs { modulus, ..base_7 }.sanitized().modulus, DEFAULT_MODULUS, "mod {}", modulus);
+        }
+        assert_eq!(Configs { modulus: palette::MAX_MODULUS, ..base_7 }.sanitized().modulus, palette::MAX_MODULUS);
+    }
+
+    #[test]
+    fn last_sessions_survive_other_versions() {
+        let configs = Configs { zoom_level: 120.0, offset: (-3.5, 8.0), palette: 2, prime_scale: 4.0, ..Configs::default() };
+        let session = LastSession::new(&configs, Some((1920, 1080)), true);
+        let text = toml::to_string(&session).unwrap();
+        assert_eq!(LastSession::parse(&text), Ok(session));
+        let restored = session.restore(Configs::default());
+        assert_eq!(restored, Configs { reveal_cutoff: restored.reveal_cutoff, ..configs });
+
+        // Keys from a newer version are ignored, ones from an older version keep their defaults
+        let session = LastSession::parse("zoom_level = 50.0\nrotation = 3\n").unwrap();
+        assert_close(session.zoom_level, 50.0);
+        assert_eq!(session.window_size, None);
+        assert_eq!(session.display_mode, DisplayMode::All);
+
+        assert!(LastSession::parse("zoom_level = \"far\"").is_err());
+        assert!(LastSession::parse("\u{0}garbage[[").is_err());
+
+        // Anything out of range is brought back in
+        let session = LastSession { palette: 42, prime_scale: 1e9, zoom_level: -1e9, ..LastSession::default() };
+        let restored = session.restore(Configs::default());
+        assert_eq!(restored.palette, Configs::default().palette);
+        assert_close(restored.prime_scale, Configs::MAX_POINT_SCALE);
+        assert_close(restored.zoom_level, Configs::MIN_ZOOM_LEVEL);
+    }
+
+    #[test]
+    fn presets_keep_what_was_saved_over_the_built_in_ones() {
+        let mut presets = Presets::default();
+        let built_in = presets.get(4);
+        let configs = Configs { palette: 1, theme: Theme::Light, prime_scale: 6.0, ..Configs::default() };
+        presets.set(2, Preset::new(&configs));
+
+        let parsed = Presets::parse(&toml::to_string(&presets).unwrap()).unwrap();
+        assert_eq!(parsed.get(2), Preset::new(&configs));
+        assert_eq!(parsed.get(4), built_in);
+        let applied = parsed.get(2).apply(Configs { zoom_level: 30.0, ..Configs::default() });
+        assert_eq!(applied, Configs { zoom_level: 30.0, ..configs });
+
+        assert_eq!(Presets::parse(""), Ok(Presets::default()));
+        let slot = "[[slots]]\npalette = 0\ntheme = \"Dark\"\nprime_scale = 2.0\ncomposite_scale = 2.0\n";
+        assert!(Presets::parse(&slot.repeat(Presets::COUNT)).is_ok());
+        assert!(Presets::parse(&slot.repeat(Presets::COUNT + 1)).is_err());
+
+        // A hand edited file can hold anything
+        let preset = Preset { palette: Palette::CUSTOM, prime_scale: -1.0, composite_scale: std::f32::NAN, ..built_in };
+        let applied = preset.apply(Configs::default());
+        assert_eq!(applied.palette, Configs::default().palette);
+        assert_close(applied.prime_scale, Configs::MIN_POINT_SCALE);
+        assert_close(applied.composite_scale, Configs::default().composite_scale);
+    }
+
+    #[test]
+    fn zoom_settings_are_checked() {
+        let settings = Settings::default().with_zoom_base(1.05).and_then(|settings| settings.with_zoom_step(2.0));
+        let settings = settings.unwrap();
+        assert_close(settings.zoom_base, 1.05);
+        assert_close(settings.zoom_step, 2.0);
+        for &zoom_base in &[1.0, 0.5, std::f32::INFINITY, std::f32::NAN] {
+            assert!(Settings::default().with_zoom_base(zoom_base).is_err(), "{}", zoom_base);
+        }
+        for &zoom_step in &[0.0, -1.0, std::f32::NAN] {
+            assert!(Settings::default().with_zoom_step(zoom_step).is_err(), "{}", zoom_step);
+        }
+    }
+
+    #[test]
+    fn drawn_numbers_are_between_the_min_and_the_reveal() {
+        let configs = Configs { min_number: 100, reveal_cutoff: 200, ..Configs::default() };
+        assert!(!configs.draws(99));
+        assert!(configs.draws(100));
+        assert!(configs.draws(200));
+        assert!(!configs.draws(201));
+        assert!(Configs::default().draws(0), "everything is drawn by default");
+    }
+
+    #[test]
+    fn only_highlighted_primes_are_kept_with_a_classification() {
+        let particles = Particles::find(100);
+        let configs = Configs { highlighted_only: true, ..Configs::default() };
+        assert!(configs.keeps_prime(&particles, 23), "nothing is highlighted");
+        let palindromic = Configs { classification: Classification::Palindromic(10), ..configs };
+        assert!(palindromic.keeps_prime(&particles, 11));
+        assert!(!palindromic.keeps_prime(&particles, 23));
+        assert!(palindromic.shows_number(&particles, 11, true));
+        assert!(!palindromic.shows_number(&particles, 23, true));
+        assert!(palindromic.shows_number(&particles, 24, false));
+        assert!(!Configs { display_mode: DisplayMode::PrimesOnly, ..palindromic }.shows_number(&particles, 24, false));
+        assert!(!Configs { reveal_cutoff: 10, ..palindromic }.shows_number(&particles, 11, true));
+
+        // Base 0 would divide by zero and base 1 never run out of digits
+        for &base in &[0, 1, 37] {
+            let configs = Configs { classification: Classification::Palindromic(base), ..configs }.sanitized();
+            assert_eq!(configs.classification, Classification::Palindromic(10), "base {}", base);
+        }
+        let base_2 = Configs { classification: Classification::Palindromic(2), ..configs };
+        assert_eq!(base_2.sanitized().classification, base_2.classification);
+    }
+
+    #[test]
+    fn pixel_rate_and_zoom_level_are_inverses() {
+        let settings = Settings::default();
+        assert_close(settings.pixel_rate(0.0), Settings::BASE_PIXEL_RATE);
+        assert!(settings.pixel_rate(-10.0) > settings.pixel_rate(10.0), "negative levels are zoomed in");
+        for &zoom_level in &[-300.0, -1.5, 0.0, 42.0, 900.0] {
+            let round_trip = settings.zoom_level_for(settings.pixel_rate(zoom_level));
+            assert!((round_trip - zoom_level).abs() < 1e-2, "{} came back as {}", zoom_level, round_trip);
+        }
+    }
+
+    #[test]
+    fn screen_and_spiral_positions_are_inverses() {
+        let settings = Settings::default();
+        let configs = Configs { zoom_level: 37.0, offset: (-120.5, 33.25), ..Configs::default() };
+        for &(x, y) in &[(0.0, 0.0), (640.0, 400.0), (1279.0, 12.5)] {
+            let pixel = Point::new(x, y);
+            let back = configs.screen_position(settings, configs.spiral_position(settings, pixel, 1280.0, 800.0), 1280.0, 800.0);
+            assert!((back - pixel).norm() < 1e-2, "{:?} came back as {:?}", pixel, back);
+        }
+
+        // The middle of the window shows `-offset`
+        let middle = configs.screen_position(settings, Point::new(120.5, -33.25), 1280.0, 800.0);
+        assert!((middle - Point::new(640.0, 400.0)).norm() < 1e-3);
+    }
+
+    #[test]
+    fn culling_keeps_the_window_and_a_margin_around_it() {
+        let settings = Settings::default();
+        let configs = Configs::default();
+        let pixel_rate = settings.pixel_rate(configs.zoom_level);
+        let at_pixels = |x: f32, y: f32| Point::new(x / pixel_rate, y / pixel_rate);
+
+        // 640 + 1.5 * 1280 sideways, 400 + 1.5 * 1280 up and down
+        assert!(configs.is_in_frame(settings, at_pixels(0.0, 0.0), 0.0, 1280.0, 800.0));
+        assert!(configs.is_in_frame(settings, at_pixels(2_559.0, -2_319.0), 0.0, 1280.0, 800.0));
+        assert!(!configs.is_in_frame(settings, at_pixels(2_561.0, 0.0), 0.0, 1280.0, 800.0));
+        assert!(!configs.is_in_frame(settings, at_pixels(0.0, -2_321.0), 0.0, 1280.0, 800.0));
+
+        // Half of a point past the edge still shows
+        assert!(configs.is_in_frame(settings, at_pixels(2_563.0, 0.0), 8.0, 1280.0, 800.0));
+        assert!(!configs.is_in_frame(settings, at_pixels(2_565.0, 0.0), 8.0, 1280.0, 800.0));
+
+        // A tall window keeps more up and down
+        assert!(configs.is_in_frame(settings, at_pixels(0.0, 2_559.0), 0.0, 800.0, 1280.0));
+        assert!(!configs.is_in_frame(settings, at_pixels(2_321.0, 0.0), 0.0, 800.0, 1280.0));
+
+        // Panning moves what's kept along with the camera
+        let panned = Configs { offset: (-3_000.0 / pixel_rate, 0.0), ..configs };
+        assert!(panned.is_in_frame(settings, at_pixels(3_000.0, 0.0), 0.0, 1280.0, 800.0));
+        assert!(!panned.is_in_frame(settings, at_pixels(0.0, 0.0), 0.0, 1280.0, 800.0));
+    }
+
+    #[test]
+    fn culling_keeps_the_middle_of_the_spiral_at_any_zoom() {
+        let settings = Settings::default();
+        for &zoom_level in &[Configs::MIN_ZOOM_LEVEL, -100.0, 0.0, 300.0, Configs::MAX_ZOOM_LEVEL] {
+            let configs = Configs { zoom_level, ..Configs::default() };
+            for &x in &[0.0, 1.0, -0.5, 20.0] {
+                let position = Point::new(x, 0.0);
+                // Plus the radius of the point
+                let expected = x.abs() * settings.pixel_rate(zoom_level) <= 2_561.0;
+                assert_eq!(
+                    configs.is_in_frame(settings, position, 2.0, 1280.0, 800.0), expected,
+                    "{:?} at zoom level {}", position, zoom_level,
+                );
+            }
+        }
+
+        // Zoomed all the way in, the first numbers are far apart but the origin is still there
+        let configs = Configs { zoom_level: Configs::MIN_ZOOM_LEVEL, ..Configs::default() };
+        assert!(configs.is_in_frame(settings, Point::new(0.0, 0.0), 2.0, 1280.0, 800.0));
+        assert!(configs.is_in_frame(settings, Point::new(1e-3, -1e-3), 2.0, 1280.0, 800.0));
+    }
+
+    #[test]
+    fn points_in_frame_are_within_the_radii_in_frame() {
+        let settings = Settings::default();
+        for &offset in &[(0.0, 0.0), (-250.0, 40.0), (3_000.0, -7_000.0)] {
+            let configs = Configs { offset, zoom_level: -20.0, ..Configs::default() };
+            let (inner, outer) = configs.radii_in_frame(settings, 4.0, 1280.0, 800.0);
+            assert!(inner <= outer);
+
+            for x in (-10_000..10_000).step_by(97) {
+                for y in (-10_000..10_000).step_by(89) {
+                    let position = Point::new(x as f32, y as f32);
+                    let radius = position.coords.norm();
+                    if configs.is_in_frame(settings, position, 4.0, 1280.0, 800.0) {
+                        assert!(radius >= inner && radius <= outer, "{:?} is in frame at radius {}", position, radius);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn radii_in_frame_hug_the_frame() {
+        let settings = Settings::default();
+        // 2560 by 2320 pixels each way, 10 pixels per unit
+        let far = Configs { offset: (-1_000_000.0, 0.0), ..Configs::default() };
+        let (inner, outer) = far.radii_in_frame(settings, 0.0, 1280.0, 800.0);
+        assert!((inner - 999_744.0).abs() < 1.0, "{}", inner);
+        assert!((outer - 1_000_256.0).abs() < 1.0, "{}", outer);
+        // 64 by 40 units for the window alone
+        let (inner, outer) = far.radii_in_window(settings, 1280.0, 800.0);
+        assert!((inner - 999_936.0).abs() < 1.0, "{}", inner);
+        assert!((outer - 1_000_064.0).abs() < 1.0, "{}", outer);
+
+        // Around the origin, nothing is too near
+        let (inner, outer) = Configs::default().radii_in_frame(settings, 0.0, 1280.0, 800.0);
+        assert!(inner.abs() < 1e-6);
+        assert!((outer - 256.0f32.hypot(232.0)).abs() < 1e-2, "{}", outer);
+    }
+
+    #[test]
+    fn view_strings_round_trip() {
+        let view = SavedView {
+            max_number: 1_000_000,
+            configs: Configs {
+                zoom_level: 150.0,
+                offset: (-320.5, 812.25),
+                display_mode: DisplayMode::PrimesOnly,
+                palette: 1,
+                angle_factor: 0.5,
+                ..Configs::default()
+            },
+        };
+        let decoded = SavedView::decode(&view.encode(), Configs::default()).unwrap();
+        assert_eq!(decoded.max_number, view.max_number);
+        assert_eq!(decoded.configs, view.configs);
+    }
+
+    #[test]
+    fn bookmarks_are_replaced_by_name() {
+        let view = |zoom_level: f32| {
+            SavedView { max_number: 2000, configs: Configs { zoom_level, ..Configs::default() } }
+        };
+        let mut bookmarks = Bookmarks::default();
+        assert_eq!(bookmarks.add("diagonals", &view(10.0)), 0);
+        assert_eq!(bookmarks.add("arms", &view(20.0)), 1);
+        assert_eq!(bookmarks.add("diagonals", &view(30.0)), 0);
+        assert_eq!(bookmarks.list().len(), 2);
+
+        let parsed = Bookmarks::parse(&toml::to_string(&bookmarks).unwrap()).unwrap();
+        assert_eq!(parsed, bookmarks);
+        let diagonals = parsed.list()[0].view(Configs::default()).unwrap();
+        assert_eq!(diagonals.max_number, 2000);
+        assert_close(diagonals.configs.zoom_level, 30.0);
+
+        assert_eq!(bookmarks.remove(0).map(|bookmark| bookmark.name), Some(String::from("diagonals")));
+        assert_eq!(bookmarks.remove(5), None);
+        assert_eq!(bookmarks.list()[0].name, "arms");
+
+        let broken = Bookmark { name: String::from("broken"), view: String::from("nope") };
+        assert!(broken.view(Configs::default()).err().map_or(false, |error| error.contains("broken")));
+    }
+
+    #[test]
+    fn bad_view_strings_are_errors() {
+        assert!(SavedView::decode("", Configs::default()).is_err());
+        assert!(SavedView::decode("not base64 at all!", Configs::default()).is_err());
+        // Cut short
+        let encoded = SavedView { max_number: 100, configs: Configs::default() }.encode();
+        assert!(SavedView::decode(&encoded[..encoded.len() - 4], Configs::default()).is_err());
+        // From a newer version
+        let mut bytes = base64::decode_config(&encoded, base64::URL_SAFE_NO_PAD).unwrap();
+        bytes[0] = 200;
+        let newer = base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD);
+        assert!(SavedView::decode(&newer, Configs::default()).is_err());
+    }
+}
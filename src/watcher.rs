@@ -0,0 +1,101 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+// How long a file has to be left alone before its change comes through, so that an editor saving in
+// a few writes makes it one change
+const DELAY: Duration = Duration::from_millis(300);
+
+// Looks for changes to a few files, told about them by the system. It's the directories they're in
+// that are watched, since editors often save by writing another file and renaming it over the old
+// one, which a watch on the file itself wouldn't see past. Dropping this stops the watching.
+pub struct Watcher {
+    // Kept around only so that the watching goes on until this is dropped
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<DebouncedEvent>,
+    // The files as they were given, with where they really are, which is what changes come in as
+    paths: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Watcher {
+    pub fn start(paths: Vec<PathBuf>) -> Result<Watcher, String> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::watcher(sender, DELAY).map_err(|error| error.to_string())?;
+        let mut watched: Vec<PathBuf> = Vec::new();
+        let mut resolved = Vec::new();
+        for path in paths {
+            let real = resolve(&path).map_err(|error| format!("couldn't watch {}: {}", path.display(), error))?;
+            let directory = real.parent().map(Path::to_path_buf).unwrap_or_default();
+            if !watched.contains(&directory) {
+                watcher.watch(&directory, RecursiveMode::NonRecursive)
+                    .map_err(|error| format!("couldn't watch {}: {}", directory.display(), error))?;
+                watched.push(directory);
+            }
+            resolved.push((path, real));
+        }
+        Ok(Watcher { _watcher: watcher, receiver, paths: resolved })
+    }
+
+    // The files that changed since the last call, each once. Never blocks.
+    pub fn poll(&self) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = Vec::new();
+        for event in self.receiver.try_iter() {
+            // A file that went away, as some editors make it do while saving, isn't a change yet
+            let real = match event {
+                DebouncedEvent::Create(path) | DebouncedEvent::Write(path) | DebouncedEvent::Chmod(path) => { path }
+                DebouncedEvent::Rename(_, to) => { to }
+                _ => { continue; }
+            };
+            let given = self.paths.iter().find(|(_, path)| *path == real).map(|(given, _)| given);
+            if let Some(given) = given {
+                if !changed.contains(given) {
+                    changed.push(given.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+// Where `path` really is, to compare with the paths changes come in as. It's its directory that's
+// looked up rather than the file, which may be a link, since it's the one in the directory that's saved.
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?;
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => { parent.to_path_buf() }
+        _ => { PathBuf::from(".") }
+    };
+    Ok(directory.canonicalize()?.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::thread;
+
+    #[test]
+    fn sees_a_file_saved_over_by_renaming() {
+        let directory = std::env::temp_dir().join(format!("polar-oxides-watcher-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("polar-oxides.toml");
+        fs::write(&path, "zoom_base = 2.0\n").unwrap();
+
+        let watcher = Watcher::start(vec![path.clone()]).unwrap();
+        assert!(watcher.poll().is_empty());
+        let saved = directory.join(".polar-oxides.toml.swp");
+        fs::write(&saved, "zoom_base = 3.0\n").unwrap();
+        fs::rename(&saved, &path).unwrap();
+        fs::write(directory.join("elsewhere.toml"), "").unwrap();
+        thread::sleep(DELAY * 4);
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        drop(watcher);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}